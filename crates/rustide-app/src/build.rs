@@ -0,0 +1,215 @@
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::Sender;
+
+use crate::config::BuildConfig;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildMessage {
+    // One line of stdout or stderr, in the order the reader threads saw them, without its line
+    // ending or ANSI escapes.
+    Line(String),
+    // The exit code, or `None` when the process was killed by a signal.
+    Finished(Option<i32>),
+    // The command could not be started or waited for; no `Finished` follows.
+    Failed(String),
+}
+
+// Where the `[build]` command runs: `working_dir` resolved against `project_root`, or the
+// project root when it's unset.
+pub fn working_dir(config: &BuildConfig, project_root: Option<&Path>) -> Option<PathBuf> {
+    match (&config.working_dir, project_root) {
+        (Some(dir), Some(root)) => Some(root.join(dir)),
+        (Some(dir), None) => Some(dir.clone()),
+        (None, root) => root.map(Path::to_path_buf),
+    }
+}
+
+// Runs the `[build]` command on a background thread, in `working_dir`.
+pub fn start(config: &BuildConfig, project_root: Option<&Path>, tx: Sender<BuildMessage>) {
+    let mut command = Command::new(&config.command);
+    command
+        .args(&config.args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(dir) = working_dir(config, project_root) {
+        command.current_dir(dir);
+    }
+    let program = config.command.clone();
+    std::thread::spawn(move || {
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                let _ = tx.send(BuildMessage::Failed(format!(
+                    "Failed to run {program}: {e}"
+                )));
+                return;
+            }
+        };
+        let stderr = child.stderr.take().map(|stderr| {
+            let tx = tx.clone();
+            std::thread::spawn(move || send_lines(stderr, &tx))
+        });
+        if let Some(stdout) = child.stdout.take() {
+            send_lines(stdout, &tx);
+        }
+        if let Some(handle) = stderr {
+            if handle.join().is_err() {
+                tracing::warn!("Build output thread panicked");
+            }
+        }
+        let message = match child.wait() {
+            Ok(status) => BuildMessage::Finished(status.code()),
+            Err(e) => BuildMessage::Failed(format!("Failed to wait for {program}: {e}")),
+        };
+        let _ = tx.send(message);
+    });
+}
+
+fn send_lines(reader: impl Read, tx: &Sender<BuildMessage>) {
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&buf);
+                let line = line.trim_end_matches(['\r', '\n']);
+                if tx.send(BuildMessage::Line(strip_ansi(line))).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// `line` without ANSI escape sequences: CSI sequences such as colors and cursor moves, OSC
+/// sequences such as hyperlinks and titles, and two-byte escapes. Other control chars except
+/// tabs are dropped too.
+pub fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            if !c.is_control() || c == '\t' {
+                out.push(c);
+            }
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameter and intermediate bytes, then one final byte in `@..=~`.
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: ends at BEL or at `ESC \`.
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\u{7}' {
+                        break;
+                    }
+                    if c == '\u{1b}' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// An error or warning reported by the build, at a 1-based line and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    /// The level and text, e.g. `error[E0308]: mismatched types`.
+    pub message: String,
+}
+
+/// Parses a one-line diagnostic, `path:line:col: level: message`, as printed by GCC and Clang
+/// and by rustc with `--error-format=short`. Only errors and warnings are returned; notes and
+/// help lines are not.
+pub fn parse_rustc_error(line: &str) -> Option<(PathBuf, usize, usize, String)> {
+    // The path may itself contain colons (`C:\src\main.c`), so try each `: ` in turn.
+    line.match_indices(": ").find_map(|(at, _)| {
+        let (path, line_number, column) = parse_location(&line[..at])?;
+        let message = &line[at + 2..];
+        is_error_or_warning(message).then(|| (path, line_number, column, message.to_string()))
+    })
+}
+
+/// Collects [`Diagnostic`]s from build output one line at a time.
+///
+/// Besides the one-line form of [`parse_rustc_error`], this understands rustc's default
+/// output, where the location follows the message on a later line:
+///
+/// ```text
+/// error[E0308]: mismatched types
+///  --> src/main.rs:4:18
+/// ```
+#[derive(Debug, Default)]
+pub struct DiagnosticParser {
+    // The last unindented `error: …` or `warning: …` line, waiting for its `-->` location.
+    header: Option<String>,
+}
+
+impl DiagnosticParser {
+    pub fn push(&mut self, line: &str) -> Option<Diagnostic> {
+        if let Some((path, line, column, message)) = parse_rustc_error(line) {
+            self.header = None;
+            return Some(Diagnostic {
+                path,
+                line,
+                column,
+                message,
+            });
+        }
+        if is_error_or_warning(line) {
+            self.header = Some(line.trim_end().to_string());
+            return None;
+        }
+        let location = line.trim_start().strip_prefix("--> ")?;
+        // Only the first location belongs to the message; later ones are related spans.
+        let message = self.header.take()?;
+        let (path, line, column) = parse_location(location.trim_end())?;
+        Some(Diagnostic {
+            path,
+            line,
+            column,
+            message,
+        })
+    }
+}
+
+// `path:line:col` with a non-empty path and 1-based numbers.
+fn parse_location(text: &str) -> Option<(PathBuf, usize, usize)> {
+    let mut parts = text.rsplitn(3, ':');
+    let column = parts.next()?.parse().ok().filter(|&n| n > 0)?;
+    let line = parts.next()?.parse().ok().filter(|&n| n > 0)?;
+    let path = parts.next().filter(|path| !path.trim().is_empty())?;
+    Some((PathBuf::from(path), line, column))
+}
+
+// `error: …`, `error[E0308]: …`, `warning: …` or GCC's `fatal error: …`.
+fn is_error_or_warning(message: &str) -> bool {
+    ["error", "warning", "fatal error"].iter().any(|level| {
+        let Some(rest) = message.strip_prefix(level) else {
+            return false;
+        };
+        let rest = match rest.strip_prefix('[') {
+            Some(code) => code.split_once(']').map_or("", |(_, rest)| rest),
+            None => rest,
+        };
+        rest.starts_with(": ")
+    })
+}
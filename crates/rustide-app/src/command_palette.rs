@@ -0,0 +1,267 @@
+use eframe::egui;
+
+use crate::config::KeyboardConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    OpenFile,
+    OpenFolder,
+    SaveAll,
+    GoToLine,
+    Find,
+    ToggleFind,
+    ToggleReplace,
+    ShowSearchPanel,
+    ShowProjectPanel,
+    ShowOutlinePanel,
+    ShowProblemsPanel,
+    RunBuild,
+    NavigateBack,
+    NavigateForward,
+    Undo,
+    Redo,
+    SelectAll,
+    DuplicateLine,
+}
+
+impl Action {
+    pub const ALL: &'static [Action] = &[
+        Action::OpenFile,
+        Action::OpenFolder,
+        Action::SaveAll,
+        Action::GoToLine,
+        Action::Find,
+        Action::ToggleFind,
+        Action::ToggleReplace,
+        Action::ShowSearchPanel,
+        Action::ShowProjectPanel,
+        Action::ShowOutlinePanel,
+        Action::ShowProblemsPanel,
+        Action::RunBuild,
+        Action::NavigateBack,
+        Action::NavigateForward,
+        Action::Undo,
+        Action::Redo,
+        Action::SelectAll,
+        Action::DuplicateLine,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::OpenFile => "Open File…",
+            Self::OpenFolder => "Open Folder…",
+            Self::SaveAll => "Save All",
+            Self::GoToLine => "Go to Line…",
+            Self::Find => "Find",
+            Self::ToggleFind => "Toggle Find",
+            Self::ToggleReplace => "Toggle Replace",
+            Self::ShowSearchPanel => "Show Search Panel",
+            Self::ShowProjectPanel => "Show Project Panel",
+            Self::ShowOutlinePanel => "Show Outline",
+            Self::ShowProblemsPanel => "Show Problems",
+            Self::RunBuild => "Run Build",
+            Self::NavigateBack => "Navigate Back",
+            Self::NavigateForward => "Navigate Forward",
+            Self::Undo => "Undo",
+            Self::Redo => "Redo",
+            Self::SelectAll => "Select All",
+            Self::DuplicateLine => "Duplicate Line",
+        }
+    }
+
+    /// The app-level shortcut `global_action_for_input` handles. These can't be rebound;
+    /// `None` for actions without one and for the editor commands `[keybindings]` covers.
+    pub fn fixed_shortcut(self) -> Option<&'static str> {
+        match self {
+            Self::OpenFile => Some("Ctrl+O"),
+            Self::OpenFolder => Some("Ctrl+Shift+O"),
+            Self::SaveAll => Some("Ctrl+Alt+S"),
+            Self::GoToLine => Some("Ctrl+G"),
+            Self::Find => Some("Ctrl+F"),
+            Self::ToggleReplace => Some("Ctrl+H"),
+            Self::ShowSearchPanel => Some("Ctrl+Shift+F"),
+            Self::ShowProjectPanel => Some("Ctrl+Shift+E"),
+            Self::RunBuild => Some("Ctrl+Shift+B"),
+            Self::NavigateBack => Some("Alt+Left"),
+            Self::NavigateForward => Some("Alt+Right"),
+            Self::ToggleFind | Self::ShowOutlinePanel | Self::ShowProblemsPanel => None,
+            Self::Undo | Self::Redo | Self::SelectAll | Self::DuplicateLine => None,
+        }
+    }
+
+    /// The shortcut shown next to the label, or "" for none. Editor commands `[keybindings]`
+    /// can rebind are read from `keys`.
+    pub fn shortcut(self, keys: &KeyboardConfig) -> String {
+        // The first of `actions` that is bound.
+        let bound = |actions: &[&str]| {
+            actions
+                .iter()
+                .find_map(|action| keys.bindings.get(*action))
+                .map(ToString::to_string)
+                .unwrap_or_default()
+        };
+        match self {
+            Self::Undo => bound(&["undo"]),
+            Self::Redo => bound(&["redo", "redo_alt"]),
+            Self::SelectAll => bound(&["select_all"]),
+            Self::DuplicateLine => bound(&["duplicate_line"]),
+            _ => self.fixed_shortcut().unwrap_or_default().to_string(),
+        }
+    }
+}
+
+// App-level shortcuts dispatched from `update`, except while the palette is open; editor-level
+// ones (undo, select all, …) stay in `editor_view::handle_key` so they only fire while the
+// editor has focus.
+pub fn global_action_for_input(input: &egui::InputState) -> Option<Action> {
+    let mods = input.modifiers;
+    let pressed = |key: egui::Key| input.key_pressed(key);
+    if mods.command && pressed(egui::Key::O) {
+        return Some(if mods.shift {
+            Action::OpenFolder
+        } else {
+            Action::OpenFile
+        });
+    }
+    if mods.command && mods.alt && pressed(egui::Key::S) {
+        return Some(Action::SaveAll);
+    }
+    if mods.command && !mods.shift && pressed(egui::Key::G) {
+        return Some(Action::GoToLine);
+    }
+    if mods.command && pressed(egui::Key::F) {
+        return Some(if mods.shift {
+            Action::ShowSearchPanel
+        } else {
+            Action::Find
+        });
+    }
+    if mods.command && !mods.shift && pressed(egui::Key::H) {
+        return Some(Action::ToggleReplace);
+    }
+    if mods.command && mods.shift && pressed(egui::Key::E) {
+        return Some(Action::ShowProjectPanel);
+    }
+    if mods.command && mods.shift && pressed(egui::Key::B) {
+        return Some(Action::RunBuild);
+    }
+    if mods.alt && !mods.command && pressed(egui::Key::ArrowLeft) {
+        return Some(Action::NavigateBack);
+    }
+    if mods.alt && !mods.command && pressed(egui::Key::ArrowRight) {
+        return Some(Action::NavigateForward);
+    }
+    None
+}
+
+pub fn filter_actions(query: &str) -> Vec<Action> {
+    let query = query.trim().to_lowercase();
+    Action::ALL
+        .iter()
+        .copied()
+        .filter(|action| query.is_empty() || action.label().to_lowercase().contains(&query))
+        .collect()
+}
+
+#[derive(Debug, Default)]
+pub struct CommandPalette {
+    pub open: bool,
+    query: String,
+    selected: usize,
+    request_focus: bool,
+}
+
+impl CommandPalette {
+    pub fn toggle(&mut self) {
+        if self.open {
+            self.open = false;
+            return;
+        }
+        self.open = true;
+        self.query.clear();
+        self.selected = 0;
+        self.request_focus = true;
+    }
+
+    // Returns the action picked this frame (if any); the caller owns dispatch.
+    pub fn show(&mut self, ctx: &egui::Context, keys: &KeyboardConfig) -> Option<Action> {
+        if !self.open {
+            return None;
+        }
+
+        let matches = filter_actions(&self.query);
+        self.selected = self.selected.min(matches.len().saturating_sub(1));
+
+        let (escape, enter, up, down) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::Escape),
+                i.key_pressed(egui::Key::Enter),
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowDown),
+            )
+        });
+        if escape {
+            self.open = false;
+            return None;
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if down && self.selected + 1 < matches.len() {
+            self.selected += 1;
+        }
+
+        let mut picked: Option<Action> = None;
+        egui::Window::new("Command Palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .fixed_size(egui::vec2(420.0, 0.0))
+            .show(ctx, |ui| {
+                let resp = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text("Type a command")
+                        .desired_width(f32::INFINITY),
+                );
+                if self.request_focus {
+                    resp.request_focus();
+                    self.request_focus = false;
+                }
+                if resp.changed() {
+                    self.selected = 0;
+                }
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .max_height(320.0)
+                    .show(ui, |ui| {
+                        if matches.is_empty() {
+                            ui.weak("No matching commands");
+                        }
+                        for (idx, action) in matches.iter().enumerate() {
+                            let resp = ui.horizontal(|ui| {
+                                let resp =
+                                    ui.selectable_label(idx == self.selected, action.label());
+                                ui.with_layout(
+                                    egui::Layout::right_to_left(egui::Align::Center),
+                                    |ui| ui.weak(action.shortcut(keys)),
+                                );
+                                resp
+                            });
+                            if resp.inner.clicked() {
+                                picked = Some(*action);
+                            }
+                        }
+                    });
+            });
+
+        if enter {
+            picked = picked.or_else(|| matches.get(self.selected).copied());
+        }
+        if picked.is_some() {
+            self.open = false;
+        }
+        picked
+    }
+}
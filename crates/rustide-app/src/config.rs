@@ -1,5 +1,11 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+use eframe::egui;
+use rustide_syntax::LanguageId;
+
+use crate::command_palette::Action;
+
 #[derive(Debug, Clone, Default)]
 pub struct AppConfig {
     pub window: WindowConfig,
@@ -7,6 +13,11 @@ pub struct AppConfig {
     pub ui: UiConfig,
     pub startup: StartupConfig,
     pub layout: LayoutConfig,
+    pub telemetry: TelemetryConfig,
+    pub editor: EditorSettings,
+    pub project: ProjectConfig,
+    pub build: BuildConfig,
+    pub keybindings: KeyboardConfig,
 }
 
 #[derive(Debug, Clone)]
@@ -33,16 +44,107 @@ impl Default for WindowConfig {
 #[derive(Debug, Clone)]
 pub struct FileConfig {
     pub encoding: rustide_editor::TextEncodingHint,
+    /// Copy the file on disk to `<stem>.bak` before each save, replacing the previous backup.
+    pub backup_on_save: bool,
+    /// Strip trailing spaces and tabs from every file on save.
+    pub trim_trailing_whitespace: bool,
+    /// End every non-empty file with exactly one newline on save, dropping extra blank lines
+    /// at the end.
+    pub insert_final_newline: bool,
+    /// Line ending every loaded file is saved with; `None` keeps the one detected in the
+    /// file. A `.editorconfig` `end_of_line` still takes priority.
+    pub default_line_ending: Option<rustide_editor::LineEnding>,
+    /// Encodings for files under particular directories, as `(path prefix, encoding)`; the
+    /// longest matching prefix wins over `encoding`.
+    pub per_path_encoding: Vec<(PathBuf, rustide_editor::TextEncodingHint)>,
 }
 
 impl Default for FileConfig {
     fn default() -> Self {
         Self {
             encoding: rustide_editor::TextEncodingHint::Auto,
+            backup_on_save: false,
+            trim_trailing_whitespace: false,
+            insert_final_newline: false,
+            default_line_ending: None,
+            per_path_encoding: Vec::new(),
         }
     }
 }
 
+impl FileConfig {
+    /// The encoding hint to load `path` with: that of the longest `per_path_encoding` prefix
+    /// containing it, else `encoding`. Prefixes match whole path components.
+    pub fn encoding_for(&self, path: &Path) -> rustide_editor::TextEncodingHint {
+        self.per_path_encoding
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.components().count())
+            .map_or(self.encoding, |&(_, encoding)| encoding)
+    }
+
+    /// The line ending `doc` must be saved with: its `.editorconfig` `end_of_line`, else
+    /// [`FileConfig::default_line_ending`]. `None` saves the buffer's line endings as they are.
+    pub fn line_ending_for(
+        &self,
+        doc: &rustide_editor::Document,
+    ) -> Option<rustide_editor::LineEnding> {
+        doc.editor_config
+            .and_then(|config| config.end_of_line)
+            .or(self.default_line_ending)
+    }
+
+    /// The save-time cleanup for a file with `settings`: the `[file]` switches where they are
+    /// enabled for all files, else the `[editor]` / `.editorconfig` values.
+    pub fn save_cleanup(&self, settings: EditorConfig) -> SaveCleanup {
+        SaveCleanup {
+            trim_trailing_whitespace: settings.trim_trailing_whitespace
+                || self.trim_trailing_whitespace,
+            ensure_final_newline: settings.insert_final_newline || self.insert_final_newline,
+            single_final_newline: self.insert_final_newline,
+        }
+    }
+}
+
+/// What a save does to the text written to disk, from [`FileConfig::save_cleanup`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SaveCleanup {
+    pub trim_trailing_whitespace: bool,
+    /// Add a newline to a non-empty file that doesn't end with one.
+    pub ensure_final_newline: bool,
+    /// Also drop extra blank lines at the end, so exactly one newline is left. Only `[file]`
+    /// asks for this; `.editorconfig` and `[editor]` leave blank lines alone.
+    pub single_final_newline: bool,
+}
+
+impl SaveCleanup {
+    // Only the text written to disk is cleaned up; the buffer keeps what was typed so an
+    // autosave doesn't pull whitespace out from under the caret.
+    pub fn prepare_for_save(&self, text: String) -> String {
+        let mut text = text;
+        if self.trim_trailing_whitespace {
+            let mut trimmed = String::with_capacity(text.len());
+            for line in text.split_inclusive('\n') {
+                let body = line.trim_end_matches(['\n', '\r']);
+                trimmed.push_str(body.trim_end_matches([' ', '\t']));
+                trimmed.push_str(&line[body.len()..]);
+            }
+            text = trimmed;
+        }
+        if text.is_empty() {
+            return text;
+        }
+        let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+        if self.single_final_newline {
+            text.truncate(text.trim_end_matches(['\n', '\r']).len());
+            text.push_str(newline);
+        } else if self.ensure_final_newline && !text.ends_with('\n') {
+            text.push_str(newline);
+        }
+        text
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct UiConfig {
     pub font_file: Option<PathBuf>,
@@ -50,7 +152,40 @@ pub struct UiConfig {
     pub monospace_size: f32,
     pub monospace_style: MonospaceStyle,
     pub theme: crate::theme::ThemeId,
+    // Read when `theme=custom`; see `theme::parse_custom_theme` for the format.
+    pub custom_theme_path: Option<PathBuf>,
     pub minimap_width: f32,
+    pub search_context_lines: usize,
+    pub word_wrap: bool,
+    // Columns of the vertical guides drawn in the editor, e.g. `ruler_columns=80,120`.
+    pub ruler_columns: Vec<u32>,
+    pub highlight_current_line: bool,
+    pub show_whitespace: ShowWhitespace,
+    pub highlight_occurrences: bool,
+    pub cursor_blink: bool,
+    // How long the caret stays shown, and then hidden, in each blink.
+    pub cursor_blink_ms: u32,
+    pub cursor_style: CursorStyle,
+    // Lines kept between the caret and the top or bottom edge of the editor when the caret
+    // moves; 0 turns this off.
+    pub scroll_margin: u32,
+    // Animate jumps to search results and other targets instead of moving there at once.
+    pub smooth_scroll: bool,
+    // Emoji before project tree entries; off falls back to `[RS]`-style tags for fonts
+    // without those glyphs.
+    pub show_file_icons: bool,
+    pub autosave_enabled: bool,
+    // Minimum time between autosaves of the active document.
+    pub autosave_interval_secs: u32,
+}
+
+pub const AUTOSAVE_INTERVAL_SECS: std::ops::RangeInclusive<u32> = 1..=300;
+pub const MAX_SCROLL_MARGIN: u32 = 20;
+
+impl UiConfig {
+    pub fn autosave_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(u64::from(self.autosave_interval_secs))
+    }
 }
 
 impl Default for UiConfig {
@@ -61,7 +196,22 @@ impl Default for UiConfig {
             monospace_size: 14.0,
             monospace_style: MonospaceStyle::Regular,
             theme: crate::theme::ThemeId::Dark,
+            custom_theme_path: None,
             minimap_width: 80.0,
+            search_context_lines: 0,
+            word_wrap: false,
+            ruler_columns: Vec::new(),
+            highlight_current_line: true,
+            show_whitespace: ShowWhitespace::None,
+            highlight_occurrences: true,
+            cursor_blink: true,
+            cursor_blink_ms: 530,
+            cursor_style: CursorStyle::Line,
+            scroll_margin: 3,
+            smooth_scroll: true,
+            show_file_icons: true,
+            autosave_enabled: true,
+            autosave_interval_secs: 2,
         }
     }
 }
@@ -100,6 +250,70 @@ impl std::fmt::Display for MonospaceStyle {
     }
 }
 
+/// Where spaces and tabs are drawn as `·` and `→`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShowWhitespace {
+    #[default]
+    None,
+    Selection,
+    All,
+}
+
+impl std::str::FromStr for ShowWhitespace {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "none" | "off" => Ok(Self::None),
+            "selection" => Ok(Self::Selection),
+            "all" => Ok(Self::All),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for ShowWhitespace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => f.write_str("none"),
+            Self::Selection => f.write_str("selection"),
+            Self::All => f.write_str("all"),
+        }
+    }
+}
+
+/// Shape of the editor caret.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CursorStyle {
+    #[default]
+    Line,
+    Block,
+    Underline,
+}
+
+impl std::str::FromStr for CursorStyle {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "line" | "bar" => Ok(Self::Line),
+            "block" => Ok(Self::Block),
+            "underline" => Ok(Self::Underline),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for CursorStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Line => f.write_str("line"),
+            Self::Block => f.write_str("block"),
+            Self::Underline => f.write_str("underline"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum MonospaceFont {
     #[default]
@@ -165,9 +379,22 @@ impl AppConfig {
                 "ui" => apply_ui_kv(&mut cfg.ui, &key, value),
                 "startup" => apply_startup_kv(&mut cfg.startup, &key, value),
                 "layout" => apply_layout_kv(&mut cfg.layout, &key, value),
+                "telemetry" => apply_telemetry_kv(&mut cfg.telemetry, &key, value),
+                "project" => apply_project_kv(&mut cfg.project, &key, value),
+                "build" => apply_build_kv(&mut cfg.build, &key, value),
+                "keybindings" => cfg.keybindings.apply_kv(&key, value),
+                "editor" => apply_editor_kv(&mut cfg.editor.defaults, &key, value),
+                name if name.starts_with("editor.") => {
+                    // Language sections start from whatever `[editor]` set above them.
+                    let language = name["editor.".len()..].trim().to_string();
+                    let defaults = cfg.editor.defaults;
+                    let editor = cfg.editor.per_language.entry(language).or_insert(defaults);
+                    apply_editor_kv(editor, &key, value);
+                }
                 _ => {}
             }
         }
+        cfg.keybindings.drop_shared_bindings();
 
         cfg
     }
@@ -176,14 +403,7 @@ impl AppConfig {
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        let tmp = path.with_extension("tmp");
-        std::fs::write(&tmp, self.to_string())?;
-        // `rename` won't overwrite on Windows, so remove the old file first.
-        if path.exists() {
-            let _ = std::fs::remove_file(path);
-        }
-        std::fs::rename(tmp, path)?;
-        Ok(())
+        rustide_editor::write_atomically(path, self.to_string().as_bytes())
     }
 }
 
@@ -206,6 +426,24 @@ impl std::fmt::Display for AppConfig {
 
         writeln!(f, "[file]")?;
         writeln!(f, "encoding={}", self.file.encoding)?;
+        writeln!(f, "backup_on_save={}", self.file.backup_on_save)?;
+        writeln!(
+            f,
+            "trim_trailing_whitespace={}",
+            self.file.trim_trailing_whitespace
+        )?;
+        writeln!(f, "insert_final_newline={}", self.file.insert_final_newline)?;
+        writeln!(
+            f,
+            "default_line_ending={}",
+            self.file
+                .default_line_ending
+                .map_or("auto", |ending| ending.name())
+                .to_ascii_lowercase()
+        )?;
+        for (i, (prefix, encoding)) in self.file.per_path_encoding.iter().enumerate() {
+            writeln!(f, "encoding_path_{i}={}:{encoding}", prefix.display())?;
+        }
         writeln!(f)?;
 
         writeln!(f, "[ui]")?;
@@ -216,7 +454,33 @@ impl std::fmt::Display for AppConfig {
         writeln!(f, "monospace_size={}", self.ui.monospace_size)?;
         writeln!(f, "monospace_style={}", self.ui.monospace_style)?;
         writeln!(f, "theme={}", self.ui.theme)?;
+        if let Some(path) = &self.ui.custom_theme_path {
+            writeln!(f, "custom_theme_path={}", path.display())?;
+        }
         writeln!(f, "minimap_width={}", self.ui.minimap_width)?;
+        writeln!(f, "search_context_lines={}", self.ui.search_context_lines)?;
+        writeln!(f, "word_wrap={}", self.ui.word_wrap)?;
+        let rulers: Vec<String> = self.ui.ruler_columns.iter().map(u32::to_string).collect();
+        writeln!(f, "ruler_columns={}", rulers.join(","))?;
+        writeln!(
+            f,
+            "highlight_current_line={}",
+            self.ui.highlight_current_line
+        )?;
+        writeln!(f, "show_whitespace={}", self.ui.show_whitespace)?;
+        writeln!(f, "highlight_occurrences={}", self.ui.highlight_occurrences)?;
+        writeln!(f, "cursor_blink={}", self.ui.cursor_blink)?;
+        writeln!(f, "cursor_blink_ms={}", self.ui.cursor_blink_ms)?;
+        writeln!(f, "cursor_style={}", self.ui.cursor_style)?;
+        writeln!(f, "scroll_margin={}", self.ui.scroll_margin)?;
+        writeln!(f, "smooth_scroll={}", self.ui.smooth_scroll)?;
+        writeln!(f, "show_file_icons={}", self.ui.show_file_icons)?;
+        writeln!(f, "autosave_enabled={}", self.ui.autosave_enabled)?;
+        writeln!(
+            f,
+            "autosave_interval_secs={}",
+            self.ui.autosave_interval_secs
+        )?;
         writeln!(f)?;
 
         writeln!(f, "[startup]")?;
@@ -232,6 +496,46 @@ impl std::fmt::Display for AppConfig {
         if let Some(json) = &self.layout.dock_layout_json {
             writeln!(f, "dock_layout_json={json}")?;
         }
+        writeln!(f)?;
+
+        writeln!(f, "[telemetry]")?;
+        writeln!(f, "enabled={}", self.telemetry.enabled)?;
+        writeln!(f)?;
+
+        writeln!(f, "[project]")?;
+        writeln!(f, "max_tree_depth={}", self.project.max_tree_depth)?;
+        writeln!(
+            f,
+            "max_entries_per_dir={}",
+            self.project.max_entries_per_dir
+        )?;
+        writeln!(f)?;
+
+        writeln!(f, "[build]")?;
+        writeln!(f, "command={}", self.build.command)?;
+        writeln!(f, "args={}", self.build.args.join(" "))?;
+        if let Some(dir) = &self.build.working_dir {
+            writeln!(f, "working_dir={}", dir.display())?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "[keybindings]")?;
+        // Unbound actions are written as `none` so they don't fall back to the default.
+        for &(action, _) in DEFAULT_KEYBINDINGS {
+            match self.keybindings.bindings.get(action) {
+                Some(binding) => writeln!(f, "{action}={binding}")?,
+                None => writeln!(f, "{action}=none")?,
+            }
+        }
+        writeln!(f)?;
+
+        write_editor_section(f, "editor", &self.editor.defaults)?;
+        let mut languages: Vec<_> = self.editor.per_language.iter().collect();
+        languages.sort_by(|a, b| a.0.cmp(b.0));
+        for (language, editor) in languages {
+            writeln!(f)?;
+            write_editor_section(f, &format!("editor.{language}"), editor)?;
+        }
 
         Ok(())
     }
@@ -262,6 +566,46 @@ fn apply_file_kv(file: &mut FileConfig, key: &str, value: &str) {
             file.encoding = enc;
         }
     }
+    if key == "backup_on_save" {
+        if let Some(v) = parse_bool(value) {
+            file.backup_on_save = v;
+        }
+    }
+    if key == "trim_trailing_whitespace" {
+        if let Some(v) = parse_bool(value) {
+            file.trim_trailing_whitespace = v;
+        }
+    }
+    if key == "insert_final_newline" {
+        if let Some(v) = parse_bool(value) {
+            file.insert_final_newline = v;
+        }
+    }
+    if key == "default_line_ending" {
+        if value.eq_ignore_ascii_case("auto") {
+            file.default_line_ending = None;
+        } else if let Some(&ending) = rustide_editor::LineEnding::ALL
+            .iter()
+            .find(|ending| ending.name().eq_ignore_ascii_case(value))
+        {
+            file.default_line_ending = Some(ending);
+        }
+    }
+    let is_encoding_path = key
+        .strip_prefix("encoding_path_")
+        .is_some_and(|n| n.parse::<usize>().is_ok());
+    if is_encoding_path {
+        // Split at the last colon: encoding names have none, Windows paths do.
+        if let Some((prefix, encoding)) = value.rsplit_once(':') {
+            let prefix = prefix.trim().trim_matches('"');
+            if let Ok(encoding) = encoding.trim().parse::<rustide_editor::TextEncodingHint>() {
+                if !prefix.is_empty() {
+                    file.per_path_encoding
+                        .push((PathBuf::from(prefix), encoding));
+                }
+            }
+        }
+    }
 }
 
 fn apply_ui_kv(ui: &mut UiConfig, key: &str, value: &str) {
@@ -271,6 +615,12 @@ fn apply_ui_kv(ui: &mut UiConfig, key: &str, value: &str) {
             ui.font_file = Some(PathBuf::from(trimmed));
         }
     }
+    if key == "custom_theme_path" {
+        let trimmed = value.trim().trim_matches('"');
+        if !trimmed.is_empty() {
+            ui.custom_theme_path = Some(PathBuf::from(trimmed));
+        }
+    }
     if key == "monospace_font" {
         if let Ok(v) = value.parse::<MonospaceFont>() {
             ui.monospace_font = v;
@@ -296,6 +646,84 @@ fn apply_ui_kv(ui: &mut UiConfig, key: &str, value: &str) {
             ui.minimap_width = v.clamp(40.0, 220.0);
         }
     }
+    if key == "search_context_lines" {
+        if let Ok(v) = value.parse::<usize>() {
+            ui.search_context_lines = v.min(10);
+        }
+    }
+    if key == "word_wrap" {
+        if let Some(v) = parse_bool(value) {
+            ui.word_wrap = v;
+        }
+    }
+    if key == "ruler_columns" {
+        let mut columns: Vec<u32> = value
+            .split(',')
+            .filter_map(|column| column.trim().parse::<u32>().ok())
+            .filter(|&column| column > 0)
+            .collect();
+        columns.sort_unstable();
+        columns.dedup();
+        ui.ruler_columns = columns;
+    }
+    if key == "highlight_current_line" {
+        if let Some(v) = parse_bool(value) {
+            ui.highlight_current_line = v;
+        }
+    }
+    if key == "show_whitespace" {
+        if let Ok(v) = value.parse::<ShowWhitespace>() {
+            ui.show_whitespace = v;
+        }
+    }
+    if key == "highlight_occurrences" {
+        if let Some(v) = parse_bool(value) {
+            ui.highlight_occurrences = v;
+        }
+    }
+    if key == "autosave_enabled" {
+        if let Some(v) = parse_bool(value) {
+            ui.autosave_enabled = v;
+        }
+    }
+    if key == "autosave_interval_secs" {
+        if let Ok(v) = value.parse::<u32>() {
+            ui.autosave_interval_secs = v.clamp(
+                *AUTOSAVE_INTERVAL_SECS.start(),
+                *AUTOSAVE_INTERVAL_SECS.end(),
+            );
+        }
+    }
+    if key == "show_file_icons" {
+        if let Some(v) = parse_bool(value) {
+            ui.show_file_icons = v;
+        }
+    }
+    if key == "cursor_blink" {
+        if let Some(v) = parse_bool(value) {
+            ui.cursor_blink = v;
+        }
+    }
+    if key == "cursor_blink_ms" {
+        if let Ok(v) = value.parse::<u32>() {
+            ui.cursor_blink_ms = v.clamp(100, 5000);
+        }
+    }
+    if key == "scroll_margin" {
+        if let Ok(v) = value.parse::<u32>() {
+            ui.scroll_margin = v.min(MAX_SCROLL_MARGIN);
+        }
+    }
+    if key == "smooth_scroll" {
+        if let Some(v) = parse_bool(value) {
+            ui.smooth_scroll = v;
+        }
+    }
+    if key == "cursor_style" {
+        if let Ok(v) = value.parse::<CursorStyle>() {
+            ui.cursor_style = v;
+        }
+    }
 }
 
 fn apply_startup_kv(startup: &mut StartupConfig, key: &str, value: &str) {
@@ -340,6 +768,8 @@ pub enum LeftTool {
     #[default]
     Project,
     Search,
+    Outline,
+    Problems,
     None,
 }
 
@@ -350,6 +780,8 @@ impl std::str::FromStr for LeftTool {
         match s.trim().to_ascii_lowercase().as_str() {
             "project" => Ok(Self::Project),
             "search" => Ok(Self::Search),
+            "outline" => Ok(Self::Outline),
+            "problems" => Ok(Self::Problems),
             "none" | "off" | "hidden" => Ok(Self::None),
             _ => Err(()),
         }
@@ -361,6 +793,8 @@ impl std::fmt::Display for LeftTool {
         match self {
             Self::Project => f.write_str("project"),
             Self::Search => f.write_str("search"),
+            Self::Outline => f.write_str("outline"),
+            Self::Problems => f.write_str("problems"),
             Self::None => f.write_str("none"),
         }
     }
@@ -404,6 +838,393 @@ fn apply_layout_kv(layout: &mut LayoutConfig, key: &str, value: &str) {
     }
 }
 
+// Opt-in local crash reporting; reports are written next to the config file and never uploaded.
+#[derive(Debug, Clone, Default)]
+pub struct TelemetryConfig {
+    pub enabled: bool,
+}
+
+fn apply_telemetry_kv(telemetry: &mut TelemetryConfig, key: &str, value: &str) {
+    if key == "enabled" {
+        if let Some(v) = parse_bool(value) {
+            telemetry.enabled = v;
+        }
+    }
+}
+
+// Limits on the project tree so huge repositories (nested `node_modules`, `target`) load
+// quickly; see `rustide_project::TreeOptions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProjectConfig {
+    pub max_tree_depth: usize,
+    pub max_entries_per_dir: usize,
+}
+
+impl Default for ProjectConfig {
+    fn default() -> Self {
+        Self {
+            max_tree_depth: 32,
+            max_entries_per_dir: 10_000,
+        }
+    }
+}
+
+impl ProjectConfig {
+    pub fn tree_options(&self) -> rustide_project::TreeOptions {
+        rustide_project::TreeOptions {
+            max_depth: self.max_tree_depth,
+            max_entries_per_dir: self.max_entries_per_dir,
+        }
+    }
+}
+
+fn apply_project_kv(project: &mut ProjectConfig, key: &str, value: &str) {
+    match key {
+        "max_tree_depth" => {
+            if let Ok(v) = value.parse::<usize>() {
+                project.max_tree_depth = v.max(1);
+            }
+        }
+        "max_entries_per_dir" => {
+            if let Ok(v) = value.parse::<usize>() {
+                project.max_entries_per_dir = v.max(1);
+            }
+        }
+        _ => {}
+    }
+}
+
+// The command "Run Build" starts, e.g. `command=cargo` and `args=build --release`. Arguments are
+// separated by whitespace; `working_dir` is relative to the project root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildConfig {
+    pub command: String,
+    pub args: Vec<String>,
+    pub working_dir: Option<PathBuf>,
+}
+
+impl Default for BuildConfig {
+    fn default() -> Self {
+        Self {
+            command: "cargo".to_string(),
+            args: vec!["build".to_string()],
+            working_dir: None,
+        }
+    }
+}
+
+fn apply_build_kv(build: &mut BuildConfig, key: &str, value: &str) {
+    match key {
+        "command" => build.command = value.to_string(),
+        "args" => build.args = value.split_whitespace().map(str::to_string).collect(),
+        "working_dir" => {
+            let trimmed = value.trim_matches('"');
+            build.working_dir = (!trimmed.is_empty()).then(|| PathBuf::from(trimmed));
+        }
+        _ => {}
+    }
+}
+
+// Editor commands `[keybindings]` can rebind, with their default shortcuts. App commands
+// (open, find, build, …), cursor movement and plain editing keys stay fixed, and a binding
+// can't take one of their shortcuts.
+const DEFAULT_KEYBINDINGS: &[(&str, &str)] = &[
+    ("undo", "Ctrl+Z"),
+    ("redo", "Ctrl+Shift+Z"),
+    ("redo_alt", "Ctrl+Y"),
+    ("select_all", "Ctrl+A"),
+    ("duplicate_line", "Ctrl+Shift+D"),
+    ("delete_word_backward", "Ctrl+Backspace"),
+    ("delete_word_forward", "Ctrl+Delete"),
+    ("indent", "Ctrl+]"),
+    ("dedent", "Ctrl+["),
+];
+
+// Fixed shortcuts besides `Action::fixed_shortcut`: the command palette, word movement,
+// the clipboard and editor zoom.
+const FIXED_SHORTCUTS: &[&str] = &[
+    "Ctrl+Shift+P",
+    "Ctrl+Left",
+    "Ctrl+Right",
+    "Ctrl+Shift+Left",
+    "Ctrl+Shift+Right",
+    "Ctrl+C",
+    "Ctrl+X",
+    "Ctrl+V",
+    "Ctrl+Plus",
+    "Ctrl+Equals",
+    "Ctrl+Minus",
+    "Ctrl+0",
+];
+
+/// Whether `binding` is a fixed shortcut, which `[keybindings]` can't take.
+fn is_fixed_shortcut(binding: KeyBinding) -> bool {
+    Action::ALL
+        .iter()
+        .filter_map(|action| action.fixed_shortcut())
+        .chain(FIXED_SHORTCUTS.iter().copied())
+        .any(|shortcut| shortcut.parse() == Ok(binding))
+}
+
+/// A key with the modifiers that must be held for it, written like `Ctrl+Shift+Z`. `Ctrl`
+/// means Cmd on macOS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub key: egui::Key,
+    pub modifiers: egui::Modifiers,
+}
+
+impl KeyBinding {
+    /// Whether pressing `key` with exactly `modifiers` triggers this binding.
+    pub fn matches(&self, key: egui::Key, modifiers: egui::Modifiers) -> bool {
+        self.key == key && modifiers.matches_exact(self.modifiers)
+    }
+}
+
+impl std::str::FromStr for KeyBinding {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let key = parts.pop().ok_or(())?;
+        let key = egui::Key::from_name(key)
+            .or_else(|| egui::Key::from_name(&key.to_ascii_uppercase()))
+            .ok_or(())?;
+        let mut modifiers = egui::Modifiers::NONE;
+        for part in parts {
+            modifiers |= match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" | "cmd" | "command" => egui::Modifiers::COMMAND,
+                "shift" => egui::Modifiers::SHIFT,
+                "alt" | "option" => egui::Modifiers::ALT,
+                _ => return Err(()),
+            };
+        }
+        Ok(Self { key, modifiers })
+    }
+}
+
+impl std::fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (held, name) in [
+            (self.modifiers.command, "Ctrl+"),
+            (self.modifiers.alt, "Alt+"),
+            (self.modifiers.shift, "Shift+"),
+        ] {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+        f.write_str(self.key.name())
+    }
+}
+
+/// Shortcuts of the editor commands, by action name (`undo`, `select_all`, …). An action
+/// without an entry has no shortcut.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyboardConfig {
+    pub bindings: HashMap<String, KeyBinding>,
+}
+
+impl Default for KeyboardConfig {
+    fn default() -> Self {
+        Self::defaults()
+    }
+}
+
+impl KeyboardConfig {
+    pub fn defaults() -> Self {
+        let bindings = DEFAULT_KEYBINDINGS
+            .iter()
+            .map(|&(action, binding)| {
+                let binding = binding.parse().expect("default keybindings parse");
+                (action.to_string(), binding)
+            })
+            .collect();
+        Self { bindings }
+    }
+
+    /// Whether `key` with `modifiers` is the shortcut of `action`.
+    pub fn matches(&self, action: &str, key: egui::Key, modifiers: egui::Modifiers) -> bool {
+        self.bindings
+            .get(action)
+            .is_some_and(|binding| binding.matches(key, modifiers))
+    }
+
+    // `action=Ctrl+U` rebinds an action; `action=none` removes its shortcut.
+    fn apply_kv(&mut self, action: &str, value: &str) {
+        if !DEFAULT_KEYBINDINGS.iter().any(|&(name, _)| name == action) {
+            tracing::warn!("Unknown action in [keybindings]: {action}");
+            return;
+        }
+        if value.is_empty() || value.eq_ignore_ascii_case("none") {
+            self.bindings.remove(action);
+            return;
+        }
+        match value.parse::<KeyBinding>() {
+            Ok(binding) if is_fixed_shortcut(binding) => {
+                tracing::warn!("Key binding for {action} is a fixed shortcut: {value}");
+            }
+            Ok(binding) => {
+                self.bindings.insert(action.to_string(), binding);
+            }
+            Err(()) => tracing::warn!("Invalid key binding for {action}: {value}"),
+        }
+    }
+
+    // Run once `[keybindings]` is read. Of the actions sharing a shortcut, the first one in
+    // `DEFAULT_KEYBINDINGS` keeps it, as `handle_key` checks them in that order; the others
+    // lose theirs.
+    fn drop_shared_bindings(&mut self) {
+        let mut taken: Vec<(KeyBinding, &str)> = Vec::new();
+        for &(action, _) in DEFAULT_KEYBINDINGS {
+            let Some(&binding) = self.bindings.get(action) else {
+                continue;
+            };
+            match taken.iter().find(|(taken, _)| *taken == binding) {
+                Some((_, owner)) => {
+                    tracing::warn!("Key binding for {action} is already {owner}'s: {binding}");
+                    self.bindings.remove(action);
+                }
+                None => taken.push((binding, action)),
+            }
+        }
+    }
+}
+
+/// Indentation and save-time cleanup for a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EditorConfig {
+    pub tab_width: u8,
+    pub use_tabs: bool,
+    pub trim_trailing_whitespace: bool,
+    pub insert_final_newline: bool,
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        Self {
+            tab_width: 4,
+            use_tabs: false,
+            trim_trailing_whitespace: false,
+            insert_final_newline: false,
+        }
+    }
+}
+
+impl EditorConfig {
+    /// One indentation level: a tab, or `tab_width` spaces.
+    pub fn indent_unit(&self) -> String {
+        if self.use_tabs {
+            "\t".to_string()
+        } else {
+            " ".repeat(usize::from(self.tab_width.max(1)))
+        }
+    }
+}
+
+/// `[editor]` defaults plus `[editor.<language>]` overrides, keyed by [`language_key`].
+#[derive(Debug, Clone, Default)]
+pub struct EditorSettings {
+    pub defaults: EditorConfig,
+    pub per_language: HashMap<String, EditorConfig>,
+}
+
+impl EditorSettings {
+    /// Settings for a file in `language`. Properties set by the file's `.editorconfig` take
+    /// priority over the IDE config.
+    pub fn resolve(
+        &self,
+        language: LanguageId,
+        editorconfig: Option<&rustide_editor::EditorConfig>,
+    ) -> EditorConfig {
+        let mut editor = self
+            .per_language
+            .get(language_key(language))
+            .copied()
+            .unwrap_or(self.defaults);
+        let Some(overrides) = editorconfig else {
+            return editor;
+        };
+        if let Some(style) = overrides.indent_style {
+            editor.use_tabs = style == rustide_editor::IndentStyle::Tab;
+        }
+        // `tab_width` is both the indent width and the tab stop; tab indentation cares about
+        // the latter.
+        let width = if editor.use_tabs {
+            overrides.tab_columns()
+        } else {
+            overrides.indent_columns()
+        };
+        if let Some(width) = width {
+            editor.tab_width = width;
+        }
+        if let Some(trim) = overrides.trim_trailing_whitespace {
+            editor.trim_trailing_whitespace = trim;
+        }
+        if let Some(insert) = overrides.insert_final_newline {
+            editor.insert_final_newline = insert;
+        }
+        editor
+    }
+}
+
+/// Section key for a language, e.g. `python` in `[editor.python]`.
+pub fn language_key(language: LanguageId) -> &'static str {
+    match language {
+        LanguageId::Rust => "rust",
+        LanguageId::JavaScript => "javascript",
+        LanguageId::TypeScript => "typescript",
+        LanguageId::Cpp => "cpp",
+        LanguageId::Python => "python",
+        LanguageId::Hlsl => "hlsl",
+        LanguageId::Json => "json",
+        LanguageId::Toml => "toml",
+        LanguageId::Yaml => "yaml",
+        LanguageId::Go => "go",
+        LanguageId::Lua => "lua",
+        LanguageId::Xml => "xml",
+        LanguageId::Html => "html",
+        LanguageId::Shell => "shell",
+        LanguageId::Markdown => "markdown",
+        LanguageId::PlainText => "plaintext",
+    }
+}
+
+fn apply_editor_kv(editor: &mut EditorConfig, key: &str, value: &str) {
+    match key {
+        "tab_width" => {
+            if let Ok(v) = value.parse::<u8>() {
+                editor.tab_width = v.clamp(1, 16);
+            }
+        }
+        "use_tabs" => editor.use_tabs = parse_bool(value).unwrap_or(editor.use_tabs),
+        "trim_trailing_whitespace" => {
+            editor.trim_trailing_whitespace =
+                parse_bool(value).unwrap_or(editor.trim_trailing_whitespace);
+        }
+        "insert_final_newline" => {
+            editor.insert_final_newline = parse_bool(value).unwrap_or(editor.insert_final_newline);
+        }
+        _ => {}
+    }
+}
+
+fn write_editor_section(
+    f: &mut std::fmt::Formatter<'_>,
+    name: &str,
+    editor: &EditorConfig,
+) -> std::fmt::Result {
+    writeln!(f, "[{name}]")?;
+    writeln!(f, "tab_width={}", editor.tab_width)?;
+    writeln!(f, "use_tabs={}", editor.use_tabs)?;
+    writeln!(
+        f,
+        "trim_trailing_whitespace={}",
+        editor.trim_trailing_whitespace
+    )?;
+    writeln!(f, "insert_final_newline={}", editor.insert_final_newline)
+}
+
 pub fn default_config_path() -> PathBuf {
     // `RUSTIDE_CONFIG` overrides the default, which is under the per-user config dir.
     if let Some(p) = std::env::var_os("RUSTIDE_CONFIG") {
@@ -0,0 +1,116 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use eframe::egui;
+
+// Crash reports stay on disk next to the config; nothing leaves the machine unless the user
+// copies the report out explicitly.
+pub fn crash_report_path(config_path: &Path) -> PathBuf {
+    config_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("crash_report.txt")
+}
+
+pub fn install_panic_hook(report_path: PathBuf) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = write_report(&report_path, info);
+        default_hook(info);
+    }));
+}
+
+fn write_report(path: &Path, info: &std::panic::PanicHookInfo<'_>) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let thread = std::thread::current();
+    let backtrace = std::backtrace::Backtrace::force_capture();
+
+    let mut file = std::fs::File::create(path)?;
+    writeln!(file, "RustIDE {} crash report", env!("CARGO_PKG_VERSION"))?;
+    writeln!(file, "time (unix): {timestamp}")?;
+    writeln!(
+        file,
+        "os: {} ({})",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )?;
+    writeln!(file, "thread: {}", thread.name().unwrap_or("<unnamed>"))?;
+    writeln!(file, "{info}")?;
+    writeln!(file)?;
+    writeln!(file, "{backtrace}")?;
+    Ok(())
+}
+
+pub fn load_pending(report_path: &Path) -> Option<String> {
+    std::fs::read_to_string(report_path)
+        .ok()
+        .filter(|text| !text.trim().is_empty())
+}
+
+pub struct CrashReportPrompt {
+    path: PathBuf,
+    report: String,
+    expanded: bool,
+}
+
+impl CrashReportPrompt {
+    pub fn new(path: PathBuf, report: String) -> Self {
+        Self {
+            path,
+            report,
+            expanded: false,
+        }
+    }
+
+    // Returns `false` once the user dismisses the prompt (the report file is removed then).
+    pub fn show(&mut self, ctx: &egui::Context) -> bool {
+        let mut keep_open = true;
+        egui::Window::new("RustIDE closed unexpectedly")
+            .collapsible(false)
+            .resizable(true)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label("A crash report from the previous session was saved locally.");
+                ui.label(self.path.display().to_string());
+                ui.horizontal(|ui| {
+                    let toggle = if self.expanded {
+                        "Hide Report"
+                    } else {
+                        "Show Report"
+                    };
+                    if ui.button(toggle).clicked() {
+                        self.expanded = !self.expanded;
+                    }
+                    if ui.button("Copy to Clipboard").clicked() {
+                        ctx.copy_text(self.report.clone());
+                    }
+                    if ui.button("Dismiss").clicked() {
+                        keep_open = false;
+                    }
+                });
+                if self.expanded {
+                    ui.separator();
+                    egui::ScrollArea::both().max_height(360.0).show(ui, |ui| {
+                        ui.monospace(&self.report);
+                    });
+                }
+            });
+
+        if !keep_open {
+            if let Err(err) = std::fs::remove_file(&self.path) {
+                tracing::warn!(
+                    "Failed to remove crash report {}: {err}",
+                    self.path.display()
+                );
+            }
+        }
+        keep_open
+    }
+}
@@ -1,16 +1,44 @@
-use std::ops::Range;
+use std::collections::HashSet;
+use std::ops::{Range, RangeInclusive};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use eframe::egui;
 use egui::epaint::text::cursor::CCursor;
 use egui::{Align2, Color32, FontId, Rect, Stroke, TextStyle};
-use rustide_syntax::SyntaxState;
+use rustide_editor::{char_to_byte_index, find_first_from, wrap_line, Generation};
+use rustide_syntax::{LanguageId, SyntaxState};
 use tree_sitter::{InputEdit, Point};
 
+use crate::config::{CursorStyle, KeyboardConfig, ShowWhitespace};
+
 #[derive(Debug, Default, Clone, Copy)]
 struct DragState {
     anchor: Option<usize>,
 }
 
+// Word-wrapped rows with the `(text version, wrap columns, hidden line ranges)` they were
+// built for.
+type WrapCache = (
+    (Generation, usize, Vec<RangeInclusive<usize>>),
+    Arc<Vec<(usize, Range<usize>)>>,
+);
+
+// `fold_lines` with the `(parse generation, text version)` it was built for.
+type FoldCache = ((Option<u64>, Generation), Arc<Vec<(usize, usize, usize)>>);
+
+// `visible_lines` with the `(text version, hidden line ranges)` it was built for.
+type VisibleCache = ((Generation, Vec<RangeInclusive<usize>>), Arc<Vec<usize>>);
+
+// An animated jump to a `scroll_to_char` target; `offset` is where the animation has got to.
+#[derive(Debug, Default, Clone, Copy)]
+struct SmoothScroll {
+    offset: f32,
+    scroll_target: Option<f32>,
+    // Pixels per second, set when the jump starts so it takes `SMOOTH_SCROLL_SECONDS`.
+    scroll_velocity: f32,
+}
+
 #[derive(Debug, Default, Clone, Copy)]
 struct ClickState {
     last_time: f64,
@@ -32,9 +60,15 @@ pub fn show_editor(
     editor: &mut rustide_editor::Editor,
     max_line_chars: usize,
     syntax: &mut Option<SyntaxState>,
+    folded: &mut HashSet<usize>,
+    indent_unit: &str,
+    caret_blink: &mut CaretBlink,
     theme: &crate::theme::Theme,
     ui_cfg: &mut crate::config::UiConfig,
+    configured_font_size: f32,
     scroll_to_char: &mut Option<usize>,
+    search_matches: &[usize],
+    keys: &KeyboardConfig,
 ) -> EditorScrollMetrics {
     let available = ui.available_size();
     let mut out_metrics = EditorScrollMetrics::default();
@@ -48,9 +82,18 @@ pub fn show_editor(
         }
 
         if response.has_focus() {
-            handle_input(ctx, editor, syntax);
+            handle_input(ctx, editor, syntax, folded, indent_unit, keys);
             ctx.request_repaint();
         }
+        let zoom_id = ui.make_persistent_id("font_zoom");
+        handle_font_zoom(
+            ctx,
+            ui_cfg,
+            configured_font_size,
+            zoom_id,
+            ui.rect_contains_pointer(rect),
+            response.has_focus(),
+        );
 
         let resizable_minimap_width = ui_cfg.minimap_width.clamp(40.0, 220.0);
         let minimap_width = resizable_minimap_width.min(rect.width() * 0.4).max(0.0);
@@ -95,14 +138,180 @@ pub fn show_editor(
                 .height()
         });
         let row_height = base_row_height.max(cjk_row_height);
-        let total_rows = editor.rope().len_lines();
 
-        let desired_scroll_y = if let Some(char_idx) = *scroll_to_char {
-            let len = editor.rope().len_chars();
-            let clamped = char_idx.min(len);
-            let line = editor.rope().char_to_line(clamped);
+        if let Some(syntax) = syntax.as_mut() {
+            let _ = syntax.ensure_parsed(editor.rope());
+        }
+        let fold_gutter = if syntax
+            .as_ref()
+            .is_some_and(|s| !matches!(s.language(), LanguageId::PlainText | LanguageId::Markdown))
+        {
+            font_char_width(ui, &font_id)
+        } else {
+            0.0
+        };
+        let char_width = font_char_width(ui, &font_id);
+        let line_digits = editor.rope().len_lines().max(1).to_string().len();
+        let gutter_width = fold_gutter + line_digits as f32 * char_width + 12.0;
+        // Leave room for the vertical scroll bar so wrapped rows never need horizontal scrolling.
+        let wrap_columns = ui_cfg.word_wrap.then(|| {
+            let text_width = main_rect.width() - gutter_width - ui.spacing().scroll.bar_width - 8.0;
+            (text_width / char_width).floor().max(1.0) as usize
+        });
+        editor.set_wrap_columns(wrap_columns);
+        // Like the wrapped rows below, the fold lines and the visible lines are kept until the
+        // parse, the text or the folds change.
+        let fold_cache_id = ui.make_persistent_id("fold_lines");
+        let fold_key = (
+            syntax.as_ref().map(SyntaxState::generation),
+            editor.version(),
+        );
+        let folds = match ctx.data(|d| d.get_temp::<FoldCache>(fold_cache_id)) {
+            Some((key, folds)) if key == fold_key => folds,
+            _ => {
+                let ranges = syntax.as_ref().map_or(&[][..], |s| s.fold_ranges());
+                let folds = Arc::new(fold_lines(editor.rope(), ranges));
+                ctx.data_mut(|d| d.insert_temp(fold_cache_id, (fold_key, folds.clone())));
+                folds
+            }
+        };
+        let fold_at = |start: &usize| {
+            folds
+                .binary_search_by_key(start, |&(fold_start, _, _)| fold_start)
+                .ok()
+                .map(|i| folds[i])
+        };
+        // Forget folds that were edited away, and open any fold hiding the caret or a jump target.
+        let rope = editor.rope();
+        let cursor_line = rope.char_to_line(editor.selection().cursor);
+        let target_line = scroll_to_char.map(|c| rope.char_to_line(c.min(rope.len_chars())));
+        folded.retain(|start| {
+            fold_at(start).is_some_and(|(_, first, last)| {
+                ![Some(cursor_line), target_line]
+                    .into_iter()
+                    .flatten()
+                    .any(|line| (first + 1..=last).contains(&line))
+            })
+        });
+        let mut hidden: Vec<RangeInclusive<usize>> = folded
+            .iter()
+            .filter_map(fold_at)
+            .map(|(_, first, last)| first + 1..=last)
+            .collect();
+        hidden.sort_by_key(|range| *range.start());
+        let visible_cache_id = ui.make_persistent_id("visible_lines");
+        let visible = (!hidden.is_empty()).then(|| {
+            let key = (editor.version(), hidden.clone());
+            match ctx.data(|d| d.get_temp::<VisibleCache>(visible_cache_id)) {
+                Some((cached_key, lines)) if cached_key == key => lines,
+                _ => {
+                    let lines = Arc::new(visible_lines(rope.len_lines(), hidden.iter().cloned()));
+                    ctx.data_mut(|d| d.insert_temp(visible_cache_id, (key, lines.clone())));
+                    lines
+                }
+            }
+        });
+        let line_at_row = |row: usize| visible.as_ref().map_or(row, |lines| lines[row]);
+        let row_of_line = |line: usize| {
+            visible
+                .as_ref()
+                .map_or(line, |lines| lines.partition_point(|&l| l < line))
+        };
+        let line_rows = visible
+            .as_ref()
+            .map_or(rope.len_lines(), |lines| lines.len());
+        // With word wrap a row is one wrapped segment `(line, char range within the line)`;
+        // otherwise rows are whole lines. Wrapping every line is too slow to redo each frame on
+        // long files, so the rows are kept until the text, the width or the folds change.
+        let wrap_cache_id = ui.make_persistent_id("wrapped_rows");
+        let wrapped_rows = wrap_columns.map(|columns| {
+            let key = (editor.version(), columns, hidden.clone());
+            let cached = ctx.data(|d| d.get_temp::<WrapCache>(wrap_cache_id));
+            if let Some((_, rows)) = cached.filter(|(cached_key, _)| *cached_key == key) {
+                return rows;
+            }
+            let rows: Arc<Vec<(usize, Range<usize>)>> = Arc::new(
+                (0..line_rows)
+                    .map(line_at_row)
+                    .flat_map(|line| {
+                        let (text, len) = rope_line_without_newline(rope, line);
+                        let starts = wrap_line(&text, columns);
+                        let ends: Vec<usize> =
+                            starts.iter().skip(1).copied().chain([len]).collect();
+                        starts
+                            .into_iter()
+                            .zip(ends)
+                            .map(move |(start, end)| (line, start..end))
+                    })
+                    .collect(),
+            );
+            ctx.data_mut(|d| d.insert_temp(wrap_cache_id, (key, rows.clone())));
+            rows
+        });
+        let row_segment = |row: usize| match &wrapped_rows {
+            Some(rows) => rows[row].clone(),
+            None => (line_at_row(row), 0..usize::MAX),
+        };
+        let total_rows = wrapped_rows.as_ref().map_or(line_rows, |rows| rows.len());
+
+        let row_of_char = |char_idx: usize| {
+            let clamped = char_idx.min(rope.len_chars());
+            let line = rope.char_to_line(clamped);
+            match &wrapped_rows {
+                Some(rows) => {
+                    let col = clamped - rope.line_to_char(line);
+                    rows.partition_point(|(l, segment)| (*l, segment.start) <= (line, col))
+                        .saturating_sub(1)
+                }
+                None => row_of_line(line),
+            }
+        };
+        // The caret and scroll offset as of the end of last frame. The margin is only enforced
+        // after the keyboard moved the caret or changed the text, so wheel scrolling and
+        // clicks near the edges don't pull the view along.
+        let caret_id = ui.make_persistent_id("scroll_margin_caret");
+        let offset_id = ui.make_persistent_id("scroll_offset");
+        // Offset set by a drag-select that left the view last frame.
+        let drag_scroll_id = ui.make_persistent_id("drag_scroll");
+        let drag_scroll = ctx.data_mut(|d| d.remove_temp::<f32>(drag_scroll_id));
+        let caret_moved = ctx.data(|d| d.get_temp::<(usize, Generation)>(caret_id))
+            != Some((editor.selection().cursor, editor.version()));
+
+        let jump_y = scroll_to_char.map(|char_idx| {
             let center = main_rect.height() * 0.5;
-            Some((line as f32 * row_height - center).max(0.0))
+            (row_of_char(char_idx) as f32 * row_height - center).max(0.0)
+        });
+        // Jumps animate from the offset of last frame. The animation stops when anything else
+        // (the wheel, the scrollbar, the end of the content) left the view elsewhere.
+        let smooth_id = ui.make_persistent_id("smooth_scroll");
+        let last_offset = ctx.data(|d| d.get_temp::<f32>(offset_id)).unwrap_or(0.0);
+        let mut smooth = ctx
+            .data(|d| d.get_temp::<SmoothScroll>(smooth_id))
+            .unwrap_or_default();
+        if (smooth.offset - last_offset).abs() > 0.5 {
+            smooth.scroll_target = None;
+        }
+        if let (Some(target), true) = (jump_y, ui_cfg.smooth_scroll) {
+            smooth = SmoothScroll {
+                offset: last_offset,
+                scroll_target: Some(target),
+                scroll_velocity: (target - last_offset).abs() / SMOOTH_SCROLL_SECONDS,
+            };
+        }
+
+        let desired_scroll_y = if let (Some(y), false) = (jump_y, ui_cfg.smooth_scroll) {
+            Some(y)
+        } else if let Some(target) = smooth.scroll_target {
+            let dt = ui.input(|i| i.stable_dt);
+            smooth.offset = smooth_scroll_step(smooth.offset, target, smooth.scroll_velocity, dt);
+            if smooth.offset == target {
+                smooth.scroll_target = None;
+            } else {
+                ctx.request_repaint();
+            }
+            Some(smooth.offset)
+        } else if drag_scroll.is_some() {
+            drag_scroll
         } else if minimap_enabled
             && (minimap_response.dragged()
                 || (minimap_response.clicked() && minimap_response.hovered()))
@@ -114,28 +323,31 @@ pub fn show_editor(
                 let center = main_rect.height() * 0.5;
                 (target_row * row_height - center).max(0.0)
             })
+        } else if caret_moved {
+            let offset_y = ctx.data(|d| d.get_temp::<f32>(offset_id)).unwrap_or(0.0);
+            scroll_for_margin(
+                row_of_char(editor.selection().cursor),
+                offset_y,
+                main_rect.height(),
+                row_height,
+                ui_cfg.scroll_margin as usize,
+            )
         } else {
             None
         };
 
         let mut metrics = EditorScrollMetrics::default();
         let mut pointer_moved_cursor = false;
+        let mut fold_toggled = false;
+        let mut menu_action: Option<TextMenuAction> = None;
 
         ui.scope_builder(egui::UiBuilder::new().max_rect(main_rect), |ui| {
-            let char_width = ui.fonts_mut(|fonts| {
-                fonts
-                    .layout_no_wrap("W".to_owned(), font_id.clone(), Color32::PLACEHOLDER)
-                    .rect
-                    .width()
-            });
-            let line_digits = total_rows.max(1).to_string().len();
-            let gutter_width = line_digits as f32 * char_width + 12.0;
-            let desired_width =
-                (gutter_width + max_line_chars as f32 * char_width + 8.0).max(ui.available_width());
-
-            if let Some(syntax) = syntax.as_mut() {
-                let _ = syntax.ensure_parsed(editor.rope());
-            }
+            // `max_line_chars` only sizes the horizontal scroll range, which wrapping removes.
+            let desired_width = if wrap_columns.is_some() {
+                ui.available_width()
+            } else {
+                (gutter_width + max_line_chars as f32 * char_width + 8.0).max(ui.available_width())
+            };
 
             let mut scroll_area = egui::ScrollArea::both()
                 .auto_shrink([false, false])
@@ -157,12 +369,57 @@ pub fn show_editor(
                     let selection = editor.selection().range();
                     let cursor = editor.selection().cursor;
                     let has_focus = response.has_focus();
+                    let caret_shown = caret_blink.is_visible(
+                        editor,
+                        Instant::now(),
+                        ui_cfg.cursor_blink.then_some(ui_cfg.cursor_blink_ms),
+                    );
+                    if has_focus && ui_cfg.cursor_blink {
+                        ctx.request_repaint_after(Duration::from_millis(u64::from(
+                            ui_cfg.cursor_blink_ms,
+                        )));
+                    }
+                    // The word under the caret and its char range; other occurrences get a fill.
+                    let occurrence_word = (ui_cfg.highlight_occurrences && selection.is_empty())
+                        .then(|| word_under_cursor(editor.rope(), cursor))
+                        .flatten()
+                        .map(|range| (editor.rope().slice(range.clone()).to_string(), range));
+                    let mut occurrences_left = MAX_OCCURRENCE_HIGHLIGHTS;
+                    let whitespace = WhitespaceGlyphs::measure(
+                        ui,
+                        ui_cfg.show_whitespace,
+                        theme.whitespace_color,
+                        &font_id,
+                    );
+                    let current_line =
+                        highlighted_line(editor, has_focus && ui_cfg.highlight_current_line);
+                    let guide_columns = indent_columns(indent_unit);
+                    let guide_step = guide_columns as f32 * char_width;
+                    let cursor_line = editor
+                        .rope()
+                        .char_to_line(cursor.min(editor.rope().len_chars()));
+                    let active_guide =
+                        indent_depth(editor.rope().line(cursor_line).chars(), guide_columns);
+
+                    // Painted before the rows so text draws over them.
+                    let text_left = ui.max_rect().left() + gutter_width;
+                    let clip = ui.clip_rect();
+                    for &column in &ui_cfg.ruler_columns {
+                        let x = text_left + ruler_offset(column, char_width);
+                        ui.painter()
+                            .vline(x, clip.y_range(), Stroke::new(1.0, theme.ruler_color));
+                    }
 
-                    for line_index in row_range {
-                        let line_start = editor.rope().line_to_char(line_index);
+                    for row in row_range {
+                        let (line_index, segment) = row_segment(row);
+                        let first_segment = segment.start == 0;
+                        let RowText {
+                            start: line_start,
+                            text: line_text,
+                            len: line_len_chars,
+                            max_local,
+                        } = row_text(editor.rope(), line_index, segment);
                         let line_start_byte = editor.rope().char_to_byte(line_start);
-                        let (line_text, line_len_chars) =
-                            rope_line_without_newline(editor.rope(), line_index);
                         let line_end_char =
                             (line_start + line_len_chars).min(editor.rope().len_chars());
                         let line_end_byte = editor.rope().char_to_byte(line_end_char);
@@ -178,6 +435,25 @@ pub fn show_editor(
                             egui::vec2(ui.available_width(), row_height),
                             egui::Sense::click_and_drag(),
                         );
+                        if current_line == Some(line_index) {
+                            ui.painter()
+                                .rect_filled(row_rect, 0.0, theme.current_line_bg);
+                        }
+                        // Wrapped continuation rows keep their line's guides.
+                        let depth =
+                            indent_depth(editor.rope().line(line_index).chars(), guide_columns);
+                        for level in 1..=depth {
+                            let color = if level == active_guide {
+                                theme.active_indent_guide
+                            } else {
+                                theme.indent_guide
+                            };
+                            ui.painter().vline(
+                                row_rect.min.x + gutter_width + level as f32 * guide_step,
+                                row_rect.y_range(),
+                                Stroke::new(1.0, color),
+                            );
+                        }
 
                         let local_selection =
                             selection_on_line(&selection, line_start, line_len_chars);
@@ -192,9 +468,11 @@ pub fn show_editor(
                                 selection: local_selection,
                                 font_id: &font_id,
                                 selection_bg: ui.visuals().selection.bg_fill,
+                                selection_fg: theme.selection_fg,
                                 highlight_spans: &highlight_spans,
                                 line_start_byte,
                                 syntax_colors: &theme.syntax,
+                                whitespace,
                             },
                         );
 
@@ -202,46 +480,169 @@ pub fn show_editor(
                         let y_offset = ((row_height - galley.rect.height()).max(0.0) * 0.5).round();
                         let text_origin = row_rect.min + egui::vec2(gutter_width, y_offset);
 
-                        let line_number =
-                            format!("{:>width$}", line_index + 1, width = line_digits);
-                        ui.painter().text(
-                            egui::pos2(
-                                row_rect.min.x + gutter_width - 4.0,
-                                row_rect.min.y + y_offset,
-                            ),
-                            Align2::RIGHT_TOP,
-                            line_number,
-                            font_id.clone(),
-                            ui.visuals().weak_text_color(),
-                        );
+                        if first_segment {
+                            let line_number =
+                                format!("{:>width$}", line_index + 1, width = line_digits);
+                            ui.painter().text(
+                                egui::pos2(
+                                    row_rect.min.x + gutter_width - 4.0,
+                                    row_rect.min.y + y_offset,
+                                ),
+                                Align2::RIGHT_TOP,
+                                line_number,
+                                font_id.clone(),
+                                ui.visuals().weak_text_color(),
+                            );
+                        }
+                        if let Some((word, word_range)) = &occurrence_word {
+                            for local in word_occurrences(&line_text, word, occurrences_left) {
+                                let start = line_start + local;
+                                if start == word_range.start {
+                                    continue;
+                                }
+                                occurrences_left -= 1;
+                                let x_at = |local: usize| {
+                                    text_origin.x
+                                        + galley.pos_from_cursor(CCursor::new(local)).min.x
+                                };
+                                let rect = Rect::from_x_y_ranges(
+                                    x_at(local)..=x_at(local + word_range.len()),
+                                    row_rect.y_range(),
+                                );
+                                ui.painter().rect_filled(rect, 2.0, theme.occurrence_bg);
+                            }
+                        }
                         ui.painter()
                             .galley(text_origin, galley.clone(), ui.visuals().text_color());
 
+                        let error_spans = syntax
+                            .as_ref()
+                            .map(|s| s.error_spans_in(line_start_byte..line_end_byte))
+                            .unwrap_or_default();
+                        for span in error_spans {
+                            let range = &span.byte_range;
+                            let on_line = if range.is_empty() {
+                                (line_start_byte..=line_end_byte).contains(&range.start)
+                            } else {
+                                range.start < line_end_byte && line_start_byte < range.end
+                            };
+                            if !on_line {
+                                continue;
+                            }
+                            let local_char = |byte: usize| {
+                                let byte = byte.clamp(line_start_byte, line_end_byte);
+                                editor.rope().byte_to_char(byte) - line_start
+                            };
+                            let x_at = |local: usize| {
+                                text_origin.x + galley.pos_from_cursor(CCursor::new(local)).min.x
+                            };
+                            let start = x_at(local_char(range.start));
+                            // Missing tokens are zero-width; mark one cell where they belong.
+                            let end = if range.is_empty() {
+                                start + char_width
+                            } else {
+                                x_at(local_char(range.end))
+                            };
+                            let y = text_origin.y + galley.rect.height() - 1.0;
+                            paint_squiggle(ui.painter(), start..end, y, theme.syntax.error);
+                        }
+
                         if has_focus {
                             if let Some(local_cursor) =
-                                cursor_on_line(cursor, line_start, line_len_chars)
+                                cursor_on_line(cursor, line_start, max_local)
                             {
                                 let caret_rect = galley.pos_from_cursor(CCursor::new(local_cursor));
-                                paint_caret(ui, text_origin, caret_rect);
+                                // The char's advance, or one cell past the end of the line.
+                                let next_x =
+                                    galley.pos_from_cursor(CCursor::new(local_cursor + 1)).min.x;
+                                let cell_width = if next_x > caret_rect.min.x {
+                                    next_x - caret_rect.min.x
+                                } else {
+                                    char_width
+                                };
+                                if caret_shown {
+                                    paint_caret(
+                                        ui,
+                                        caret_shape(
+                                            ui_cfg.cursor_style,
+                                            caret_rect.translate(text_origin.to_vec2()),
+                                            cell_width,
+                                        ),
+                                        ui_cfg.cursor_style,
+                                        line_text.chars().nth(local_cursor),
+                                        &font_id,
+                                        theme,
+                                    );
+                                }
                             }
                         }
 
-                        if row_response.clicked() {
+                        let fold = folds
+                            .binary_search_by_key(&line_index, |&(_, first, _)| first)
+                            .ok()
+                            .filter(|_| first_segment)
+                            .map(|i| folds[i]);
+                        if let Some((fold_start, _, _)) = fold {
+                            paint_fold_marker(
+                                ui,
+                                row_rect,
+                                fold_gutter,
+                                folded.contains(&fold_start),
+                            );
+                        }
+
+                        let in_fold_gutter = row_response
+                            .interact_pointer_pos()
+                            .is_some_and(|pos| pos.x < row_rect.min.x + fold_gutter);
+                        if let (true, true, Some((fold_start, first, last))) =
+                            (row_response.clicked(), in_fold_gutter, fold)
+                        {
+                            if !folded.remove(&fold_start) {
+                                folded.insert(fold_start);
+                                // Keep the caret visible, or the fold would reopen next frame.
+                                if (first + 1..=last).contains(&cursor_line) {
+                                    editor
+                                        .set_cursor(editor.rope().byte_to_char(fold_start), false);
+                                }
+                            }
+                            fold_toggled = true;
+                            ctx.request_repaint();
+                        } else if row_response.clicked() {
                             if let Some(pointer_pos) = row_response.interact_pointer_pos() {
                                 let local = galley.cursor_from_pos(pointer_pos - text_origin).index;
                                 let extend = ui.input(|i| i.modifiers.shift);
                                 editor.set_cursor(
-                                    (line_start + local).min(line_start + line_len_chars),
+                                    (line_start + local).min(line_start + max_local),
                                     extend,
                                 );
                                 pointer_moved_cursor = true;
                             }
                         }
 
+                        if row_response.secondary_clicked() {
+                            response.request_focus();
+                            if let Some(pointer_pos) = row_response.interact_pointer_pos() {
+                                let local = galley.cursor_from_pos(pointer_pos - text_origin).index;
+                                let pos = (line_start + local).min(line_start + max_local);
+                                // Right-clicking inside the selection keeps it so Cut/Copy apply.
+                                if selection.is_empty()
+                                    || !(selection.start..=selection.end).contains(&pos)
+                                {
+                                    editor.set_cursor(pos, false);
+                                    pointer_moved_cursor = true;
+                                }
+                            }
+                        }
+                        row_response.context_menu(|ui| {
+                            if let Some(action) = text_context_menu(ui, !selection.is_empty()) {
+                                menu_action = Some(action);
+                            }
+                        });
+
                         if row_response.drag_started() {
                             if let Some(pointer_pos) = row_response.interact_pointer_pos() {
                                 let local = galley.cursor_from_pos(pointer_pos - text_origin).index;
-                                let anchor = (line_start + local).min(line_start + line_len_chars);
+                                let anchor = (line_start + local).min(line_start + max_local);
                                 editor.set_cursor(anchor, false);
                                 pointer_moved_cursor = true;
                                 ctx.data_mut(|d| {
@@ -266,7 +667,7 @@ pub fn show_editor(
                                 0.0
                             };
                             let local = galley.cursor_from_pos(pointer_pos - text_origin).index;
-                            let target = (line_start + local).min(line_start + line_len_chars);
+                            let target = (line_start + local).min(line_start + max_local);
                             match drag_target {
                                 None => drag_target = Some((y_dist, target)),
                                 Some((best_dist, _)) if y_dist < best_dist => {
@@ -289,9 +690,41 @@ pub fn show_editor(
                 });
 
             metrics.offset_y = scroll_output.state.offset.y;
+            ctx.data_mut(|d| {
+                d.insert_temp(offset_id, metrics.offset_y);
+                d.insert_temp(smooth_id, smooth);
+            });
             metrics.content_h = scroll_output.content_size.y;
             metrics.viewport_h = scroll_output.inner_rect.height();
 
+            // Keep scrolling while a drag-select holds the pointer above or below the view; next
+            // frame the drag extends the selection to the nearest of the rows scrolled in.
+            let (pointer_pos, pointer_down, dt) = ui.input(|i| {
+                (
+                    i.pointer.interact_pos(),
+                    i.pointer.primary_down(),
+                    i.stable_dt,
+                )
+            });
+            let dragging = ctx
+                .data(|d| d.get_temp::<DragState>(drag_id).and_then(|s| s.anchor))
+                .is_some();
+            if let (Some(pointer_pos), true, true) = (pointer_pos, pointer_down, dragging) {
+                let inner = scroll_output.inner_rect;
+                let max_offset = (metrics.content_h - metrics.viewport_h).max(0.0);
+                if let Some(offset) = drag_scroll_offset(
+                    pointer_pos.y,
+                    inner.y_range(),
+                    metrics.offset_y,
+                    max_offset,
+                    row_height,
+                    dt,
+                ) {
+                    ctx.data_mut(|d| d.insert_temp(drag_scroll_id, offset));
+                    ctx.request_repaint();
+                }
+            }
+
             let (pointer_pos, pointer_clicked, extend) = ui.input(|i| {
                 (
                     i.pointer.interact_pos(),
@@ -306,36 +739,41 @@ pub fn show_editor(
                 }
             }
 
-            if let (Some(pointer_pos), true) = (pointer_pos, pointer_clicked) {
+            // The char under a point in the view, and the line it's on.
+            let char_at = |ui: &egui::Ui, rope: &ropey::Rope, pointer_pos: egui::Pos2| {
+                let row_height_with_spacing = row_height + ui.spacing().item_spacing.y;
+                let content_pos =
+                    pointer_pos - scroll_output.inner_rect.min + scroll_output.state.offset;
+                let row = (content_pos.y / row_height_with_spacing).floor().max(0.0) as usize;
+                let row = row.min(total_rows.saturating_sub(1));
+                let (line_index, segment) = row_segment(row);
+                let RowText {
+                    start: line_start,
+                    text: line_text,
+                    max_local,
+                    ..
+                } = row_text(rope, line_index, segment);
+
+                let local_x = (content_pos.x - gutter_width).max(0.0);
+                let galley = ui.fonts_mut(|fonts| {
+                    fonts.layout_no_wrap(line_text, font_id.clone(), Color32::PLACEHOLDER)
+                });
+                let y_offset = ((row_height - galley.rect.height()).max(0.0) * 0.5).round();
+                let local_y = (content_pos.y - row as f32 * row_height_with_spacing)
+                    .clamp(0.0, row_height)
+                    - y_offset;
+                let local_y = local_y.max(0.0);
+                let local = galley.cursor_from_pos(egui::vec2(local_x, local_y)).index;
+                ((line_start + local).min(line_start + max_local), line_index)
+            };
+
+            // A click on a context-menu entry must not also place the caret under the menu.
+            if let (Some(pointer_pos), true, None, false) =
+                (pointer_pos, pointer_clicked, menu_action, fold_toggled)
+            {
                 if scroll_output.inner_rect.contains(pointer_pos) {
                     response.request_focus();
-
-                    let row_height_with_spacing = row_height + ui.spacing().item_spacing.y;
-                    let content_pos =
-                        pointer_pos - scroll_output.inner_rect.min + scroll_output.state.offset;
-                    let line_index =
-                        (content_pos.y / row_height_with_spacing).floor().max(0.0) as usize;
-                    let line_index = line_index.min(total_rows.saturating_sub(1));
-
-                    let line_start = editor.rope().line_to_char(line_index);
-                    let (line_text, line_len_chars) =
-                        rope_line_without_newline(editor.rope(), line_index);
-
-                    let local_x = (content_pos.x - gutter_width).max(0.0);
-                    let galley = ui.fonts_mut(|fonts| {
-                        fonts.layout_no_wrap(
-                            line_text.clone(),
-                            font_id.clone(),
-                            Color32::PLACEHOLDER,
-                        )
-                    });
-                    let y_offset = ((row_height - galley.rect.height()).max(0.0) * 0.5).round();
-                    let local_y = (content_pos.y - line_index as f32 * row_height_with_spacing)
-                        .clamp(0.0, row_height)
-                        - y_offset;
-                    let local_y = local_y.max(0.0);
-                    let local = galley.cursor_from_pos(egui::vec2(local_x, local_y)).index;
-                    let clicked_pos = (line_start + local).min(line_start + line_len_chars);
+                    let (clicked_pos, line_index) = char_at(ui, editor.rope(), pointer_pos);
 
                     let click_id = ui.make_persistent_id("click_state");
                     let now = ui.input(|i| i.time);
@@ -359,6 +797,31 @@ pub fn show_editor(
                 }
             }
 
+            if PRIMARY_SELECTION_PASTE {
+                // The stored text is only cloned out on the frame of a middle click.
+                let rect = scroll_output.inner_rect;
+                let paste =
+                    ui.input(|i| middle_click_pos(&i.events, rect))
+                        .and_then(|pointer_pos| {
+                            let text =
+                                ctx.data(|d| d.get_temp::<String>(primary_selection_id()))?;
+                            (!text.is_empty()).then_some((pointer_pos, text))
+                        });
+                if let Some((pointer_pos, text)) = paste {
+                    response.request_focus();
+                    let (clicked_pos, _) = char_at(ui, editor.rope(), pointer_pos);
+                    editor.set_cursor(clicked_pos, false);
+                    editor.insert_text(&text);
+                    drain_syntax_edits(editor, syntax, folded);
+                    pointer_moved_cursor = true;
+                }
+            }
+
+            if let Some(action) = menu_action {
+                response.request_focus();
+                apply_text_menu_action(ctx, editor, syntax, folded, action);
+            }
+
             metrics.cursor_moved_by_pointer = pointer_moved_cursor;
         });
 
@@ -366,14 +829,32 @@ pub fn show_editor(
             paint_minimap(
                 ui,
                 editor,
-                total_rows,
+                syntax,
+                line_rows,
                 minimap_rect,
                 minimap_response,
                 metrics,
-                &theme.minimap,
+                theme,
+                search_matches,
             );
         }
 
+        ctx.data_mut(|d| {
+            d.insert_temp(caret_id, (editor.selection().cursor, editor.version()));
+        });
+        // Only copy the selected text out when the selection or the text changed.
+        let primary_key_id = ui.make_persistent_id("primary_selection_key");
+        let selection_key = (editor.selection(), editor.version());
+        if PRIMARY_SELECTION_PASTE
+            && !editor.selection().is_empty()
+            && ctx.data(|d| d.get_temp(primary_key_id)) != Some(selection_key)
+        {
+            let text = editor.selected_text();
+            ctx.data_mut(|d| {
+                d.insert_temp(primary_key_id, selection_key);
+                d.insert_temp(primary_selection_id(), text);
+            });
+        }
         out_metrics = metrics;
     });
 
@@ -385,10 +866,87 @@ pub fn show_editor(
     out_metrics
 }
 
+// Middle-click pastes the text last selected in any editor, as X11 and Wayland desktops do.
+// egui can't read the system's primary selection, so this one is kept inside the app.
+const PRIMARY_SELECTION_PASTE: bool = cfg!(all(unix, not(target_os = "macos")));
+
+fn primary_selection_id() -> egui::Id {
+    egui::Id::new("rustide_primary_selection")
+}
+
+/// Where the middle button was pressed inside `rect` this frame, if it was.
+pub(crate) fn middle_click_pos(events: &[egui::Event], rect: egui::Rect) -> Option<egui::Pos2> {
+    events.iter().find_map(|event| match *event {
+        egui::Event::PointerButton {
+            pos,
+            button: egui::PointerButton::Middle,
+            pressed: true,
+            ..
+        } if rect.contains(pos) => Some(pos),
+        _ => None,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextMenuAction {
+    Cut,
+    Copy,
+    Paste,
+    SelectWord,
+    SelectAll,
+}
+
+fn text_context_menu(ui: &mut egui::Ui, has_selection: bool) -> Option<TextMenuAction> {
+    let mut action = None;
+    let mut item = |ui: &mut egui::Ui, enabled: bool, label: &str, value: TextMenuAction| {
+        if ui.add_enabled(enabled, egui::Button::new(label)).clicked() {
+            action = Some(value);
+            ui.close();
+        }
+    };
+    item(ui, has_selection, "Cut", TextMenuAction::Cut);
+    item(ui, has_selection, "Copy", TextMenuAction::Copy);
+    item(ui, true, "Paste", TextMenuAction::Paste);
+    ui.separator();
+    item(ui, true, "Select Word", TextMenuAction::SelectWord);
+    item(ui, true, "Select All", TextMenuAction::SelectAll);
+    action
+}
+
+fn apply_text_menu_action(
+    ctx: &egui::Context,
+    editor: &mut rustide_editor::Editor,
+    syntax: &mut Option<SyntaxState>,
+    folded: &mut HashSet<usize>,
+    action: TextMenuAction,
+) {
+    match action {
+        TextMenuAction::Cut | TextMenuAction::Copy => {
+            let text = editor.selected_text();
+            if text.is_empty() {
+                return;
+            }
+            ctx.copy_text(text);
+            if action == TextMenuAction::Cut {
+                editor.insert_text("");
+                drain_syntax_edits(editor, syntax, folded);
+            }
+        }
+        // egui cannot read the clipboard synchronously; the platform answers with an
+        // `Event::Paste` next frame, which `handle_input` inserts like Ctrl+V.
+        TextMenuAction::Paste => ctx.send_viewport_cmd(egui::ViewportCommand::RequestPaste),
+        TextMenuAction::SelectWord => select_word(editor, editor.selection().cursor),
+        TextMenuAction::SelectAll => editor.select_all(),
+    }
+}
+
 fn handle_input(
     ctx: &egui::Context,
     editor: &mut rustide_editor::Editor,
     syntax: &mut Option<SyntaxState>,
+    folded: &mut HashSet<usize>,
+    indent_unit: &str,
+    keys: &KeyboardConfig,
 ) {
     let events = ctx.input(|i| i.events.clone());
     for event in events {
@@ -404,16 +962,16 @@ fn handle_input(
                 if !text.is_empty() {
                     ctx.copy_text(text);
                     editor.insert_text("");
-                    drain_syntax_edits(editor, syntax);
+                    drain_syntax_edits(editor, syntax, folded);
                 }
             }
             egui::Event::Paste(text) => {
                 editor.insert_text(&text);
-                drain_syntax_edits(editor, syntax);
+                drain_syntax_edits(editor, syntax, folded);
             }
             egui::Event::Text(text) => {
                 editor.insert_text(&text);
-                drain_syntax_edits(editor, syntax);
+                drain_syntax_edits(editor, syntax, folded);
             }
             egui::Event::Key {
                 key,
@@ -421,8 +979,8 @@ fn handle_input(
                 modifiers,
                 ..
             } => {
-                if handle_key(editor, key, modifiers) {
-                    drain_syntax_edits(editor, syntax);
+                if handle_key(editor, key, modifiers, indent_unit, keys) {
+                    drain_syntax_edits(editor, syntax, folded);
                     // keep going: multiple keys can be pressed in one frame
                 }
             }
@@ -431,27 +989,203 @@ fn handle_input(
     }
 }
 
-fn handle_key(
+pub(crate) const FONT_SIZE_RANGE: RangeInclusive<f32> = 8.0..=48.0;
+
+// Ctrl+wheel over the editor and Ctrl+Plus / Ctrl+Minus while it has focus change
+// `monospace_size` one point per step, and Ctrl+0 puts back `configured_font_size`; the app
+// re-applies fonts when it sees the new size. Fractional (smooth) wheel deltas are kept under
+// `zoom_id` until they add up to a step.
+fn handle_font_zoom(
+    ctx: &egui::Context,
+    ui_cfg: &mut crate::config::UiConfig,
+    configured_font_size: f32,
+    zoom_id: egui::Id,
+    hovered: bool,
+    focused: bool,
+) {
+    let events = ctx.input(|i| i.events.clone());
+    let line_height = ctx.options(|o| o.input_options.line_scroll_speed.max(1.0));
+    let mut pending = ctx.data(|d| d.get_temp::<f32>(zoom_id).unwrap_or(0.0));
+    let mut steps = 0;
+    for event in events {
+        match event {
+            egui::Event::MouseWheel {
+                unit,
+                delta,
+                modifiers,
+            } if hovered && modifiers.command => {
+                let lines = match unit {
+                    egui::MouseWheelUnit::Point => delta.y / line_height,
+                    egui::MouseWheelUnit::Line | egui::MouseWheelUnit::Page => delta.y,
+                };
+                steps += zoom_steps(&mut pending, lines);
+            }
+            egui::Event::Key {
+                key,
+                pressed: true,
+                modifiers,
+                ..
+            } if focused && modifiers.command => match key {
+                egui::Key::Plus | egui::Key::Equals => steps += 1,
+                egui::Key::Minus => steps -= 1,
+                egui::Key::Num0 => {
+                    ui_cfg.monospace_size = configured_font_size;
+                    pending = 0.0;
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    ctx.data_mut(|d| d.insert_temp(zoom_id, pending));
+    if steps != 0 {
+        ui_cfg.monospace_size = (ui_cfg.monospace_size + steps as f32)
+            .round()
+            .clamp(*FONT_SIZE_RANGE.start(), *FONT_SIZE_RANGE.end());
+    }
+}
+
+/// The scroll offset that keeps `margin` rows between the caret's row and the top and bottom
+/// edges of a `viewport_h` tall view, or `None` if `offset_y` already does. The margin
+/// shrinks to fit views too short for it; 0 disables this.
+pub(crate) fn scroll_for_margin(
+    cursor_row: usize,
+    offset_y: f32,
+    viewport_h: f32,
+    row_height: f32,
+    margin: usize,
+) -> Option<f32> {
+    if margin == 0 || row_height <= 0.0 {
+        return None;
+    }
+    let visible_rows = (viewport_h / row_height).floor() as usize;
+    let margin = margin.min(visible_rows.saturating_sub(1) / 2) as f32 * row_height;
+    let top = cursor_row as f32 * row_height;
+    let min_offset = top + row_height + margin - viewport_h;
+    let max_offset = (top - margin).max(0.0);
+    if offset_y > max_offset {
+        Some(max_offset)
+    } else if offset_y < min_offset {
+        Some(min_offset)
+    } else {
+        None
+    }
+}
+
+/// Moves a smooth scroll from `offset` toward `target` by `velocity` pixels per second over
+/// `dt` seconds, without overshooting.
+pub(crate) fn smooth_scroll_step(offset: f32, target: f32, velocity: f32, dt: f32) -> f32 {
+    let remaining = target - offset;
+    let step = dt * velocity;
+    if step >= remaining.abs() {
+        target
+    } else {
+        offset + step.copysign(remaining)
+    }
+}
+
+// How long a smooth jump to a `scroll_to_char` target takes.
+const SMOOTH_SCROLL_SECONDS: f32 = 0.15;
+
+/// The scroll offset for the next frame while a drag-select holds the pointer at
+/// `pointer_y`, outside `view` (the visible y range); `None` inside it or once the end is
+/// reached. The speed, in rows per second, grows with the pointer's distance from the view.
+pub(crate) fn drag_scroll_offset(
+    pointer_y: f32,
+    view: egui::Rangef,
+    offset_y: f32,
+    max_offset: f32,
+    row_height: f32,
+    dt: f32,
+) -> Option<f32> {
+    let distance = if pointer_y < view.min {
+        pointer_y - view.min
+    } else if pointer_y > view.max {
+        pointer_y - view.max
+    } else {
+        return None;
+    };
+    let rows_per_second = (DRAG_SCROLL_BASE_SPEED + distance.abs() / row_height.max(1.0) * 4.0)
+        .min(DRAG_SCROLL_MAX_SPEED);
+    let step = rows_per_second * row_height * dt;
+    let offset = (offset_y + step.copysign(distance)).clamp(0.0, max_offset.max(0.0));
+    (offset != offset_y).then_some(offset)
+}
+
+// Rows per second a drag-select scrolls just past the edge of the view, and at most.
+const DRAG_SCROLL_BASE_SPEED: f32 = 8.0;
+const DRAG_SCROLL_MAX_SPEED: f32 = 200.0;
+
+/// Adds a wheel delta (in lines, positive to zoom in) to `pending` and takes out the whole
+/// steps, leaving the fraction for later events.
+pub(crate) fn zoom_steps(pending: &mut f32, lines: f32) -> i32 {
+    *pending += lines;
+    let steps = pending.trunc();
+    *pending -= steps;
+    steps as i32
+}
+
+pub(crate) fn handle_key(
     editor: &mut rustide_editor::Editor,
     key: egui::Key,
     modifiers: egui::Modifiers,
+    indent_unit: &str,
+    keys: &KeyboardConfig,
 ) -> bool {
     let command = modifiers.command;
     let extend = modifiers.shift;
+    let bound = |action| keys.matches(action, key, modifiers);
+
+    if bound("undo") {
+        return editor.undo();
+    }
+    if bound("redo") || bound("redo_alt") {
+        return editor.redo();
+    }
+    if bound("select_all") {
+        editor.select_all();
+        return true;
+    }
+    if bound("duplicate_line") {
+        editor.duplicate_line();
+        return true;
+    }
+    if bound("delete_word_backward") {
+        editor.delete_word_backward();
+        return true;
+    }
+    if bound("delete_word_forward") {
+        editor.delete_word_forward();
+        return true;
+    }
+    if bound("indent") {
+        editor.indent_selection(indent_unit);
+        return true;
+    }
+    if bound("dedent") {
+        editor.dedent_selection(indent_unit);
+        return true;
+    }
 
     if command {
         match key {
-            egui::Key::Z if extend => return editor.redo(),
-            egui::Key::Z => return editor.undo(),
-            egui::Key::Y => return editor.redo(),
-            egui::Key::A => {
-                editor.select_all();
+            egui::Key::ArrowLeft => {
+                editor.move_word_left(extend);
+                return true;
+            }
+            egui::Key::ArrowRight => {
+                editor.move_word_right(extend);
                 return true;
             }
             _ => {}
         }
     }
 
+    if modifiers.alt && matches!(key, egui::Key::ArrowLeft | egui::Key::ArrowRight) {
+        // Alt+Left/Right is app-level navigation (back/forward).
+        return false;
+    }
+
     match key {
         egui::Key::ArrowLeft => {
             editor.move_left(extend);
@@ -486,11 +1220,11 @@ fn handle_key(
             true
         }
         egui::Key::Enter => {
-            editor.insert_newline_auto_indent();
+            editor.insert_newline_auto_indent(indent_unit);
             true
         }
         egui::Key::Tab => {
-            editor.insert_text("    ");
+            editor.insert_text(indent_unit);
             true
         }
         _ => false,
@@ -510,6 +1244,39 @@ fn rope_line_without_newline(rope: &ropey::Rope, line_index: usize) -> (String,
     (text, len_chars)
 }
 
+// One editor row: a whole line, or one segment of a wrapped line.
+struct RowText {
+    // Char index of the row's first char in the rope.
+    start: usize,
+    text: String,
+    len: usize,
+    // Furthest caret offset in the row. A wrapped row ends where the next one starts, and that
+    // position belongs to the next row.
+    max_local: usize,
+}
+
+fn row_text(rope: &ropey::Rope, line_index: usize, segment: Range<usize>) -> RowText {
+    let (text, len_chars) = rope_line_without_newline(rope, line_index);
+    let start = segment.start.min(len_chars);
+    let end = segment.end.min(len_chars);
+    let text = if start == 0 && end == len_chars {
+        text
+    } else {
+        text[char_to_byte_index(&text, start)..char_to_byte_index(&text, end)].to_string()
+    };
+    let len = end - start;
+    RowText {
+        start: rope.line_to_char(line_index) + start,
+        text,
+        len,
+        max_local: if end == len_chars {
+            len
+        } else {
+            len.saturating_sub(1)
+        },
+    }
+}
+
 fn cursor_on_line(cursor: usize, line_start: usize, line_len_chars: usize) -> Option<usize> {
     if cursor < line_start {
         return None;
@@ -540,23 +1307,65 @@ fn selection_on_line(
     }
 }
 
-struct StyledLineArgs<'a> {
-    selection: Option<Range<usize>>,
-    font_id: &'a FontId,
-    selection_bg: Color32,
-    highlight_spans: &'a [rustide_syntax::HighlightSpan],
-    line_start_byte: usize,
-    syntax_colors: &'a crate::theme::SyntaxColors,
+pub(crate) struct StyledLineArgs<'a> {
+    pub selection: Option<Range<usize>>,
+    pub font_id: &'a FontId,
+    pub selection_bg: Color32,
+    // `Color32::PLACEHOLDER` keeps the syntax colors on the selection.
+    pub selection_fg: Color32,
+    pub highlight_spans: &'a [rustide_syntax::HighlightSpan],
+    pub line_start_byte: usize,
+    pub syntax_colors: &'a crate::theme::SyntaxColors,
+    pub whitespace: WhitespaceGlyphs,
+}
+
+/// How `append_styled_line` draws spaces and tabs.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct WhitespaceGlyphs {
+    pub mode: ShowWhitespace,
+    pub color: Color32,
+    // Spacing added after `→` so it advances as far as the tab it stands for; every glyph
+    // keeps its char's width, so caret and click positions are unaffected.
+    pub tab_padding: f32,
+}
+
+impl WhitespaceGlyphs {
+    fn measure(ui: &egui::Ui, mode: ShowWhitespace, color: Color32, font_id: &FontId) -> Self {
+        let tab_padding = if mode == ShowWhitespace::None {
+            0.0
+        } else {
+            ui.fonts_mut(|fonts| {
+                let mut width = |text: &str| {
+                    fonts
+                        .layout_no_wrap(text.to_owned(), font_id.clone(), Color32::PLACEHOLDER)
+                        .rect
+                        .width()
+                };
+                (width("\t") - width("\u{2192}")).max(0.0)
+            })
+        };
+        Self {
+            mode,
+            color,
+            tab_padding,
+        }
+    }
 }
 
-fn append_styled_line(job: &mut egui::text::LayoutJob, line: &str, args: StyledLineArgs<'_>) {
+pub(crate) fn append_styled_line(
+    job: &mut egui::text::LayoutJob,
+    line: &str,
+    args: StyledLineArgs<'_>,
+) {
     let StyledLineArgs {
         selection,
         font_id,
         selection_bg,
+        selection_fg,
         highlight_spans,
         line_start_byte,
         syntax_colors,
+        whitespace,
     } = args;
     let selection_bytes = selection.map(|sel| {
         let start = char_to_byte_index(line, sel.start);
@@ -602,7 +1411,11 @@ fn append_styled_line(job: &mut egui::text::LayoutJob, line: &str, args: StyledL
 
         let fmt = egui::TextFormat {
             font_id: font_id.clone(),
-            color: fg,
+            color: if selected && selection_fg != Color32::PLACEHOLDER {
+                selection_fg
+            } else {
+                fg
+            },
             background: if selected {
                 selection_bg
             } else {
@@ -610,20 +1423,87 @@ fn append_styled_line(job: &mut egui::text::LayoutJob, line: &str, args: StyledL
             },
             ..Default::default()
         };
-        job.append(&line[start..end], 0.0, fmt);
+        let decorate = match whitespace.mode {
+            ShowWhitespace::None => false,
+            ShowWhitespace::Selection => selected,
+            ShowWhitespace::All => true,
+        };
+        if decorate {
+            append_with_whitespace_glyphs(job, &line[start..end], fmt, whitespace);
+        } else {
+            job.append(&line[start..end], 0.0, fmt);
+        }
+    }
+}
+
+// Appends `text`, replacing each space with `·` and each tab with `→` in the whitespace color.
+fn append_with_whitespace_glyphs(
+    job: &mut egui::text::LayoutJob,
+    text: &str,
+    fmt: egui::TextFormat,
+    whitespace: WhitespaceGlyphs,
+) {
+    let mut run_start = 0;
+    for (i, c) in text.char_indices() {
+        let (glyph, extra_letter_spacing) = match c {
+            ' ' => ("\u{00B7}", 0.0),
+            '\t' => ("\u{2192}", whitespace.tab_padding),
+            _ => continue,
+        };
+        if run_start < i {
+            job.append(&text[run_start..i], 0.0, fmt.clone());
+        }
+        job.append(
+            glyph,
+            0.0,
+            egui::TextFormat {
+                color: whitespace.color,
+                extra_letter_spacing,
+                ..fmt.clone()
+            },
+        );
+        run_start = i + c.len_utf8();
+    }
+    if run_start < text.len() {
+        job.append(&text[run_start..], 0.0, fmt);
     }
 }
 
-fn drain_syntax_edits(editor: &mut rustide_editor::Editor, syntax: &mut Option<SyntaxState>) {
+/// Hands the editor's pending edits to the parser and moves the `folded` start bytes with them.
+pub fn drain_syntax_edits(
+    editor: &mut rustide_editor::Editor,
+    syntax: &mut Option<SyntaxState>,
+    folded: &mut HashSet<usize>,
+) {
+    let edits = editor.take_last_edits();
+    for edit in &edits {
+        shift_folds(folded, edit);
+    }
     let Some(syntax) = syntax.as_mut() else {
-        let _ = editor.take_last_edit();
         return;
     };
-    while let Some(edit) = editor.take_last_edit() {
+    for edit in edits {
         syntax.queue_edit(to_input_edit(edit));
     }
 }
 
+/// Keeps fold start bytes on the same text across `edit`: starts before it stay, starts after
+/// it move by the change in length, and starts inside the replaced bytes are dropped.
+pub(crate) fn shift_folds(folded: &mut HashSet<usize>, edit: &rustide_editor::EditorEdit) {
+    *folded = folded
+        .iter()
+        .filter_map(|&start| {
+            if start < edit.start_byte {
+                Some(start)
+            } else if start >= edit.old_end_byte {
+                Some(start - edit.old_end_byte + edit.new_end_byte)
+            } else {
+                None
+            }
+        })
+        .collect();
+}
+
 fn to_input_edit(edit: rustide_editor::EditorEdit) -> InputEdit {
     InputEdit {
         start_byte: edit.start_byte,
@@ -644,15 +1524,19 @@ fn to_input_edit(edit: rustide_editor::EditorEdit) -> InputEdit {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn paint_minimap(
     ui: &egui::Ui,
     editor: &rustide_editor::Editor,
+    syntax: &mut Option<SyntaxState>,
     total_rows: usize,
     rect: Rect,
     response: egui::Response,
     metrics: EditorScrollMetrics,
-    colors: &crate::theme::MinimapColors,
+    theme: &crate::theme::Theme,
+    search_matches: &[usize],
 ) {
+    let colors = &theme.minimap;
     let EditorScrollMetrics {
         offset_y,
         content_h,
@@ -680,18 +1564,32 @@ fn paint_minimap(
         egui::StrokeKind::Inside,
     );
 
-    let font_size = 6.0_f32.min(rect.height().max(4.0)).max(4.0);
+    let (font_size, max_chars) = minimap_font_metrics(rect.width());
     let step_y = font_size;
-    let approx_char_w = font_size * 0.6;
-    let max_chars = if approx_char_w <= f32::EPSILON {
-        16
-    } else {
-        (((rect.width() - 4.0).max(0.0)) / approx_char_w)
-            .floor()
-            .clamp(8.0, 120.0) as usize
-    };
 
     let rope = editor.rope();
+    let row_y = |line_index: usize| {
+        let t = if total_rows <= 1 {
+            0.0
+        } else {
+            line_index as f32 / (total_rows.saturating_sub(1) as f32)
+        };
+        rect.top() + t * rect.height()
+    };
+
+    let selection = editor.selection().range();
+    if !selection.is_empty() {
+        let first_line = rope.char_to_line(selection.start.min(rope.len_chars()));
+        let last_line = rope.char_to_line(selection.end.min(rope.len_chars()));
+        let top = row_y(first_line);
+        let bottom = (row_y(last_line) + step_y).max(top + 2.0);
+        painter.rect_filled(
+            Rect::from_x_y_ranges(rect.x_range(), top..=bottom),
+            0.0,
+            colors.selection_fill,
+        );
+    }
+
     let sample_count = (rect.height() / step_y).ceil().max(1.0) as usize;
     for i in 0..sample_count {
         let y = rect.top() + i as f32 * step_y;
@@ -711,13 +1609,24 @@ fn paint_minimap(
         if snippet.is_empty() {
             continue;
         }
-        painter.text(
-            egui::pos2(rect.left() + 2.0, y),
-            Align2::LEFT_TOP,
-            snippet,
-            FontId::monospace(font_size),
-            colors.text,
+        // Spans are only queried for sampled lines, so cost follows the minimap's height.
+        let line_start_byte = rope.line_to_byte(line_index);
+        let highlight_spans = syntax
+            .as_mut()
+            .and_then(|s| {
+                s.highlight_spans(rope, line_start_byte..line_start_byte + snippet.len())
+                    .ok()
+            })
+            .unwrap_or_default();
+        let job = minimap_line_job(
+            &snippet,
+            &highlight_spans,
+            line_start_byte,
+            font_size,
+            theme,
         );
+        let galley = ui.fonts_mut(|fonts| fonts.layout_job(job));
+        painter.galley(egui::pos2(rect.left() + 2.0, y), galley, colors.text);
     }
 
     let content_h = content_h.max(1.0);
@@ -744,19 +1653,108 @@ fn paint_minimap(
         egui::StrokeKind::Inside,
     );
 
-    let cursor_line = editor.rope().char_to_line(editor.selection().cursor);
-    let t = if total_rows <= 1 {
+    // Short ticks at the right edge, at most one per pixel row.
+    let line_height = if total_rows <= 1 {
         0.0
     } else {
-        cursor_line as f32 / (total_rows.saturating_sub(1) as f32)
+        rect.height() / (total_rows - 1) as f32
     };
-    let y = rect.top() + t * rect.height();
+    for line in minimap_match_lines(rope, search_matches, line_height) {
+        let y = row_y(line);
+        painter.rect_filled(
+            Rect::from_x_y_ranges(rect.right() - 6.0..=rect.right() - 1.0, y..=y + 2.0),
+            0.0,
+            colors.search_match,
+        );
+    }
+
+    let cursor_line = editor.rope().char_to_line(editor.selection().cursor);
+    let y = row_y(cursor_line);
     painter.line_segment(
         [egui::pos2(rect.left(), y), egui::pos2(rect.right(), y)],
         Stroke::new(1.0, colors.caret_marker),
     );
 }
 
+/// The lines that get a minimap tick for `search_matches` (sorted start chars), with line `l`
+/// drawn `l * line_height` pixels down: the first match's line in each pixel row. The matches
+/// left in a row are skipped with a binary search, so the cost follows the minimap's height
+/// rather than the number of matches.
+pub(crate) fn minimap_match_lines(
+    rope: &ropey::Rope,
+    search_matches: &[usize],
+    line_height: f32,
+) -> Vec<usize> {
+    let mut lines = Vec::new();
+    let mut next = 0;
+    while let Some(&pos) = search_matches.get(next) {
+        let line = rope.char_to_line(pos.min(rope.len_chars()));
+        lines.push(line);
+        if line_height <= 0.0 {
+            break;
+        }
+        let next_row = (line as f32 * line_height).floor() + 1.0;
+        let next_line = ((next_row / line_height).ceil() as usize).max(line + 1);
+        if next_line >= rope.len_lines() {
+            break;
+        }
+        let next_char = rope.line_to_char(next_line);
+        next = search_matches.partition_point(|&p| p < next_char);
+    }
+    lines
+}
+
+// The minimap font size at the default minimap width. The font scales with the width so a
+// wide minimap stays readable instead of leaving most of its width empty.
+const MINIMAP_BASE_FONT_SIZE: f32 = 6.0;
+const MINIMAP_BASE_WIDTH: f32 = 80.0;
+
+/// Returns the minimap font size and how many characters of each line fit at that size.
+pub(crate) fn minimap_font_metrics(width: f32) -> (f32, usize) {
+    let font_size = (width * MINIMAP_BASE_FONT_SIZE / MINIMAP_BASE_WIDTH).clamp(3.0, 10.0);
+    let approx_char_w = font_size * 0.6;
+    let max_chars = (((width - 4.0).max(0.0)) / approx_char_w)
+        .floor()
+        .clamp(8.0, 120.0) as usize;
+    (font_size, max_chars)
+}
+
+/// One sampled minimap line colored by its highlight spans; unhighlighted text keeps the
+/// minimap's own text color.
+pub(crate) fn minimap_line_job(
+    snippet: &str,
+    highlight_spans: &[rustide_syntax::HighlightSpan],
+    line_start_byte: usize,
+    font_size: f32,
+    theme: &crate::theme::Theme,
+) -> egui::text::LayoutJob {
+    let syntax_colors = crate::theme::SyntaxColors {
+        fallback: theme.minimap.text,
+        ..theme.syntax
+    };
+    let mut job = egui::text::LayoutJob::default();
+    job.wrap.max_width = f32::INFINITY;
+    append_styled_line(
+        &mut job,
+        snippet,
+        StyledLineArgs {
+            selection: None,
+            font_id: &FontId::monospace(font_size),
+            selection_bg: Color32::TRANSPARENT,
+            selection_fg: Color32::PLACEHOLDER,
+            highlight_spans,
+            line_start_byte,
+            syntax_colors: &syntax_colors,
+            whitespace: WhitespaceGlyphs {
+                mode: ShowWhitespace::None,
+                color: Color32::TRANSPARENT,
+                tab_padding: 0.0,
+            },
+        },
+    );
+    job
+}
+
 fn rope_line_snippet(rope: &ropey::Rope, line_index: usize, max_chars: usize) -> String {
     let slice = rope.line(line_index);
     let mut out = String::new();
@@ -780,19 +1778,33 @@ fn select_word(editor: &mut rustide_editor::Editor, pos: usize) {
         return;
     }
     let pos = pos.min(rope.len_chars().saturating_sub(1));
+    match word_range_at(rope, pos) {
+        Some(range) => editor.select_range(range),
+        None => editor.set_cursor(pos, false),
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// The run of word chars, or of other non-whitespace chars, containing `pos`; `None` on
+// whitespace or past the end.
+fn word_range_at(rope: &ropey::Rope, pos: usize) -> Option<Range<usize>> {
+    if pos >= rope.len_chars() {
+        return None;
+    }
     let ch = rope.char(pos);
     if ch.is_whitespace() {
-        editor.set_cursor(pos, false);
-        return;
+        return None;
     }
 
-    let is_word = |c: char| c.is_alphanumeric() || c == '_';
-    let target_is_word = is_word(ch);
+    let target_is_word = is_word_char(ch);
 
     let mut start = pos;
     while start > 0 {
         let c = rope.char(start - 1);
-        if c.is_whitespace() || is_word(c) != target_is_word {
+        if c.is_whitespace() || is_word_char(c) != target_is_word {
             break;
         }
         start -= 1;
@@ -801,13 +1813,48 @@ fn select_word(editor: &mut rustide_editor::Editor, pos: usize) {
     let mut end = pos + 1;
     while end < rope.len_chars() {
         let c = rope.char(end);
-        if c.is_whitespace() || is_word(c) != target_is_word {
+        if c.is_whitespace() || is_word_char(c) != target_is_word {
             break;
         }
         end += 1;
     }
 
-    editor.select_range(start..end);
+    Some(start..end)
+}
+
+// Cap on occurrence highlights per frame so a common token doesn't flood the painter.
+const MAX_OCCURRENCE_HIGHLIGHTS: usize = 500;
+
+/// The identifier touching the caret: the word at `cursor`, or the one just before it when
+/// the caret sits at a word's end. Punctuation runs are not highlighted.
+pub(crate) fn word_under_cursor(rope: &ropey::Rope, cursor: usize) -> Option<Range<usize>> {
+    [Some(cursor), cursor.checked_sub(1)]
+        .into_iter()
+        .flatten()
+        .filter(|&pos| pos < rope.len_chars() && is_word_char(rope.char(pos)))
+        .find_map(|pos| word_range_at(rope, pos))
+}
+
+/// Char offsets of whole-word occurrences of `word` in `line`, at most `limit` of them.
+pub(crate) fn word_occurrences(line: &str, word: &str, limit: usize) -> Vec<usize> {
+    let word_len = word.chars().count();
+    let mut found = Vec::new();
+    let mut from = 0;
+    while found.len() < limit {
+        let Some(at) = find_first_from(line, word, from) else {
+            break;
+        };
+        let before = at
+            .checked_sub(1)
+            .and_then(|i| line.chars().nth(i))
+            .is_some_and(is_word_char);
+        let after = line.chars().nth(at + word_len).is_some_and(is_word_char);
+        if !before && !after {
+            found.push(at);
+        }
+        from = at + 1;
+    }
+    found
 }
 
 fn select_line(editor: &mut rustide_editor::Editor, line_index: usize) {
@@ -825,22 +1872,206 @@ fn select_line(editor: &mut rustide_editor::Editor, line_index: usize) {
     editor.select_range(start..(start + len));
 }
 
-fn char_to_byte_index(text: &str, char_index: usize) -> usize {
-    if char_index == 0 {
-        return 0;
-    }
-    match text.char_indices().nth(char_index) {
-        Some((byte, _)) => byte,
-        None => text.len(),
+/// The caret's line, which gets the current-line background, or `None` when the highlight is
+/// off (disabled, or the editor is not focused).
+pub(crate) fn highlighted_line(editor: &rustide_editor::Editor, enabled: bool) -> Option<usize> {
+    enabled.then(|| {
+        let rope = editor.rope();
+        rope.char_to_line(editor.selection().cursor.min(rope.len_chars()))
+    })
+}
+
+// egui lays a tab out as four space advances whatever the document's tab width.
+const TAB_COLUMNS: usize = 4;
+
+/// Columns one indentation level spans on screen.
+pub(crate) fn indent_columns(indent_unit: &str) -> usize {
+    indent_unit
+        .chars()
+        .map(|c| if c == '\t' { TAB_COLUMNS } else { 1 })
+        .sum::<usize>()
+        .max(1)
+}
+
+/// Whole indentation levels in a line's leading whitespace, for the indent guides.
+pub(crate) fn indent_depth(line: impl IntoIterator<Item = char>, indent_columns: usize) -> usize {
+    let columns: usize = line
+        .into_iter()
+        .map_while(|c| match c {
+            ' ' => Some(1),
+            '\t' => Some(TAB_COLUMNS),
+            _ => None,
+        })
+        .sum();
+    columns / indent_columns.max(1)
+}
+
+/// Horizontal distance from the start of the text to the ruler at `column`.
+pub(crate) fn ruler_offset(column: u32, char_width: f32) -> f32 {
+    column as f32 * char_width
+}
+
+fn font_char_width(ui: &egui::Ui, font_id: &FontId) -> f32 {
+    ui.fonts_mut(|fonts| {
+        fonts
+            .layout_no_wrap("W".to_owned(), font_id.clone(), Color32::PLACEHOLDER)
+            .rect
+            .width()
+    })
+}
+
+// `(start byte, first line, last line)` of each fold range. The first line stays visible
+// when folded; a range ending right after a newline does not hide the following line.
+fn fold_lines(rope: &ropey::Rope, ranges: &[Range<usize>]) -> Vec<(usize, usize, usize)> {
+    ranges
+        .iter()
+        .map(|range| {
+            let first = rope.byte_to_line(range.start);
+            let last = rope.byte_to_line(range.end.saturating_sub(1).max(range.start));
+            (range.start, first, last)
+        })
+        .filter(|&(_, first, last)| last > first)
+        .collect()
+}
+
+/// Line indices that remain on screen once the `hidden` line ranges are collapsed.
+pub(crate) fn visible_lines(
+    line_count: usize,
+    hidden: impl IntoIterator<Item = RangeInclusive<usize>>,
+) -> Vec<usize> {
+    let mut is_hidden = vec![false; line_count];
+    for range in hidden {
+        let end = (*range.end() + 1).min(line_count);
+        for flag in is_hidden.iter_mut().take(end).skip(*range.start()) {
+            *flag = true;
+        }
     }
+    (0..line_count).filter(|&line| !is_hidden[line]).collect()
 }
 
-fn paint_caret(ui: &egui::Ui, text_origin: egui::Pos2, caret_rect: Rect) {
-    let caret_color = ui.visuals().text_color();
-    let stroke = Stroke::new(2.0, caret_color);
-    let x = text_origin.x + caret_rect.min.x;
-    let top = text_origin.y + caret_rect.min.y;
-    let bottom = text_origin.y + caret_rect.max.y;
+fn paint_fold_marker(ui: &egui::Ui, row_rect: Rect, gutter: f32, folded: bool) {
+    let center = egui::pos2(row_rect.min.x + gutter * 0.5, row_rect.center().y);
+    let size = (gutter * 0.3).min(row_rect.height() * 0.3);
+    let points = if folded {
+        // Pointing right: the body is hidden.
+        vec![
+            center + egui::vec2(-size * 0.5, -size),
+            center + egui::vec2(size * 0.7, 0.0),
+            center + egui::vec2(-size * 0.5, size),
+        ]
+    } else {
+        vec![
+            center + egui::vec2(-size, -size * 0.5),
+            center + egui::vec2(size, -size * 0.5),
+            center + egui::vec2(0.0, size * 0.7),
+        ]
+    };
+    let color = ui.visuals().weak_text_color();
     ui.painter()
-        .line_segment([egui::pos2(x, top), egui::pos2(x, bottom)], stroke);
+        .add(egui::Shape::convex_polygon(points, color, Stroke::NONE));
+}
+
+// Approximates a wave with short zig-zag segments, like a spell-check underline.
+fn paint_squiggle(painter: &egui::Painter, x_range: Range<f32>, y: f32, color: Color32) {
+    const STEP: f32 = 2.0;
+    const AMPLITUDE: f32 = 1.5;
+
+    let mut points = vec![egui::pos2(x_range.start, y)];
+    let mut x = x_range.start;
+    let mut up = true;
+    while x < x_range.end {
+        x = (x + STEP).min(x_range.end);
+        let dy = if up { -AMPLITUDE } else { AMPLITUDE };
+        points.push(egui::pos2(x, y + dy));
+        up = !up;
+    }
+    if points.len() > 1 {
+        painter.add(egui::Shape::line(points, Stroke::new(1.0, color)));
+    }
+}
+
+/// Blink phase of one document's caret. The caret restarts shown after every edit or move so
+/// it never disappears while the user is typing.
+#[derive(Debug, Clone, Copy)]
+pub struct CaretBlink {
+    last_activity: Instant,
+    cursor: usize,
+    version: Generation,
+}
+
+impl CaretBlink {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            last_activity: now,
+            cursor: 0,
+            version: Generation::default(),
+        }
+    }
+
+    /// Whether the caret is drawn at `now`; `blink_ms` is `None` when blinking is off.
+    pub(crate) fn is_visible(
+        &mut self,
+        editor: &rustide_editor::Editor,
+        now: Instant,
+        blink_ms: Option<u32>,
+    ) -> bool {
+        let (cursor, version) = (editor.selection().cursor, editor.version());
+        if cursor != self.cursor || version != self.version {
+            self.cursor = cursor;
+            self.version = version;
+            self.last_activity = now;
+        }
+        let Some(blink_ms) = blink_ms else {
+            return true;
+        };
+        // One period is `blink_ms` shown followed by `blink_ms` hidden.
+        let period = 2.0 * f64::from(blink_ms.max(1)) / 1000.0;
+        let elapsed = now.saturating_duration_since(self.last_activity);
+        (elapsed.as_secs_f64() / period) % 1.0 < 0.5
+    }
+}
+
+const CARET_THICKNESS: f32 = 2.0;
+
+/// The area `paint_caret` fills for `style`, given the galley's zero-width caret rect (in
+/// screen space) and the width of the char cell it sits in.
+pub(crate) fn caret_shape(style: CursorStyle, caret: Rect, cell_width: f32) -> Rect {
+    let x = caret.min.x;
+    match style {
+        CursorStyle::Line => Rect::from_x_y_ranges(
+            x - CARET_THICKNESS * 0.5..=x + CARET_THICKNESS * 0.5,
+            caret.y_range(),
+        ),
+        CursorStyle::Block => Rect::from_x_y_ranges(x..=x + cell_width, caret.y_range()),
+        CursorStyle::Underline => Rect::from_x_y_ranges(
+            x..=x + cell_width,
+            caret.max.y - CARET_THICKNESS..=caret.max.y,
+        ),
+    }
+}
+
+// A block caret redraws the char it covers (`under`) so the text stays readable.
+fn paint_caret(
+    ui: &egui::Ui,
+    shape: Rect,
+    style: CursorStyle,
+    under: Option<char>,
+    font_id: &FontId,
+    theme: &crate::theme::Theme,
+) {
+    let painter = ui.painter();
+    if style != CursorStyle::Block {
+        painter.rect_filled(shape, 0.0, ui.visuals().text_cursor.stroke.color);
+        return;
+    }
+    painter.rect_filled(shape, 0.0, theme.cursor_bg);
+    if let Some(ch) = under.filter(|c| !c.is_whitespace()) {
+        painter.text(
+            shape.min,
+            Align2::LEFT_TOP,
+            ch,
+            font_id.clone(),
+            theme.cursor_fg,
+        );
+    }
 }
@@ -0,0 +1,167 @@
+use std::path::PathBuf;
+
+use eframe::egui;
+
+use crate::config;
+
+#[derive(Clone)]
+pub struct FontState {
+    base_definitions: egui::FontDefinitions,
+    user_font_name: Option<String>,
+    consolas_font_name: Option<String>,
+    consolas_bold_font_name: Option<String>,
+    consolas_italic_font_name: Option<String>,
+    consolas_bold_italic_font_name: Option<String>,
+    simhei_font_name: Option<String>,
+}
+
+/// Loads the optional user font plus the Windows system fonts (Consolas, SimHei) once;
+/// [`apply_font_families`] then picks among them whenever the font settings change.
+pub fn build_font_state(cfg: &config::UiConfig) -> FontState {
+    let mut defs = egui::FontDefinitions::default();
+
+    let user_font_name = cfg.font_file.as_ref().and_then(|path| {
+        let data = std::fs::read(path).ok()?;
+        let name = format!("rustide_user_font:{}", path.display());
+        defs.font_data
+            .insert(name.clone(), egui::FontData::from_owned(data).into());
+        Some(name)
+    });
+
+    let mut consolas_font_name = None;
+    let mut consolas_bold_font_name = None;
+    let mut consolas_italic_font_name = None;
+    let mut consolas_bold_italic_font_name = None;
+    let mut simhei_font_name = None;
+    if cfg!(windows) {
+        if let Some(windir) = std::env::var_os("WINDIR") {
+            let fonts_dir = PathBuf::from(windir).join("Fonts");
+
+            let consolas_path = fonts_dir.join("consola.ttf");
+            if let Ok(data) = std::fs::read(&consolas_path) {
+                let name = "rustide_font:consolas".to_string();
+                defs.font_data
+                    .insert(name.clone(), egui::FontData::from_owned(data).into());
+                consolas_font_name = Some(name);
+            }
+
+            let consolas_bold_path = fonts_dir.join("consolab.ttf");
+            if let Ok(data) = std::fs::read(&consolas_bold_path) {
+                let name = "rustide_font:consolas_bold".to_string();
+                defs.font_data
+                    .insert(name.clone(), egui::FontData::from_owned(data).into());
+                consolas_bold_font_name = Some(name);
+            }
+
+            let consolas_italic_path = fonts_dir.join("consolai.ttf");
+            if let Ok(data) = std::fs::read(&consolas_italic_path) {
+                let name = "rustide_font:consolas_italic".to_string();
+                defs.font_data
+                    .insert(name.clone(), egui::FontData::from_owned(data).into());
+                consolas_italic_font_name = Some(name);
+            }
+
+            let consolas_bold_italic_path = fonts_dir.join("consolaz.ttf");
+            if let Ok(data) = std::fs::read(&consolas_bold_italic_path) {
+                let name = "rustide_font:consolas_bold_italic".to_string();
+                defs.font_data
+                    .insert(name.clone(), egui::FontData::from_owned(data).into());
+                consolas_bold_italic_font_name = Some(name);
+            }
+
+            let simhei_path = fonts_dir.join("simhei.ttf");
+            if let Ok(data) = std::fs::read(&simhei_path) {
+                let name = "rustide_font:simhei".to_string();
+                defs.font_data
+                    .insert(name.clone(), egui::FontData::from_owned(data).into());
+                simhei_font_name = Some(name);
+            }
+        }
+    }
+
+    FontState {
+        base_definitions: defs,
+        user_font_name,
+        consolas_font_name,
+        consolas_bold_font_name,
+        consolas_italic_font_name,
+        consolas_bold_italic_font_name,
+        simhei_font_name,
+    }
+}
+
+pub fn apply_font_families(ctx: &egui::Context, fonts: &FontState, cfg: &config::UiConfig) {
+    let mut defs = fonts.base_definitions.clone();
+
+    let mut monospace = Vec::<String>::new();
+    if let Some(name) = &fonts.user_font_name {
+        monospace.push(name.clone());
+    }
+
+    let (primary_regular, primary_bold, primary_italic, primary_bold_italic, secondary_regular) =
+        match cfg.monospace_font {
+            config::MonospaceFont::Consolas => (
+                &fonts.consolas_font_name,
+                &fonts.consolas_bold_font_name,
+                &fonts.consolas_italic_font_name,
+                &fonts.consolas_bold_italic_font_name,
+                &fonts.simhei_font_name,
+            ),
+            config::MonospaceFont::SimHei => (
+                &fonts.simhei_font_name,
+                &fonts.simhei_font_name,
+                &fonts.simhei_font_name,
+                &fonts.simhei_font_name,
+                &fonts.consolas_font_name,
+            ),
+        };
+
+    let primary = match cfg.monospace_style {
+        config::MonospaceStyle::Regular => primary_regular,
+        config::MonospaceStyle::Bold => primary_bold,
+        config::MonospaceStyle::Italic => primary_italic,
+        config::MonospaceStyle::BoldItalic => primary_bold_italic,
+    };
+
+    if let Some(name) = primary {
+        monospace.push(name.clone());
+    }
+    if let Some(name) = secondary_regular {
+        monospace.push(name.clone());
+    }
+
+    if let Some(family) = defs.families.get_mut(&egui::FontFamily::Monospace) {
+        for name in monospace.into_iter().rev() {
+            family.insert(0, name);
+        }
+    }
+
+    if let Some(family) = defs.families.get_mut(&egui::FontFamily::Proportional) {
+        // Prefer the configured editor font for UI as well, but keep egui defaults as fallbacks.
+        let existing = family.clone();
+        family.clear();
+
+        let mut preferred: Vec<String> = Vec::new();
+        if let Some(name) = &fonts.user_font_name {
+            preferred.push(name.clone());
+        }
+        if let Some(name) = primary_regular {
+            preferred.push(name.clone());
+        }
+        if let Some(name) = secondary_regular {
+            preferred.push(name.clone());
+        }
+        for name in preferred {
+            if !family.contains(&name) {
+                family.push(name);
+            }
+        }
+        for name in existing {
+            if !family.contains(&name) {
+                family.push(name);
+            }
+        }
+    }
+
+    ctx.set_fonts(defs);
+}
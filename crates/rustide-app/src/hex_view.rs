@@ -0,0 +1,114 @@
+//! Read-only hex dump for files opened with "Open as Hex".
+
+use std::fmt::Write as _;
+use std::io::Read;
+
+use eframe::egui;
+
+use crate::theme::Theme;
+
+pub const BYTES_PER_ROW: usize = 16;
+
+/// The most of a file the dump loads.
+pub const MAX_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Reads at most `limit` bytes from `reader`; the flag is set when there was more.
+pub fn read_capped(reader: impl Read, limit: u64) -> std::io::Result<(Vec<u8>, bool)> {
+    let mut bytes = Vec::new();
+    reader
+        .take(limit.saturating_add(1))
+        .read_to_end(&mut bytes)?;
+    let truncated = bytes.len() as u64 > limit;
+    bytes.truncate(limit as usize);
+    Ok((bytes, truncated))
+}
+
+/// One row of the dump, as drawn: `address | hex bytes | ASCII`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HexRow {
+    /// Offset of the row's first byte, as eight hex digits.
+    pub address: String,
+    /// Lowercase byte pairs separated by spaces, with an extra space after the eighth byte.
+    /// A short last row is padded so the ASCII column stays aligned.
+    pub hex: String,
+    /// Printable ASCII as itself, everything else as `.`.
+    pub ascii: String,
+}
+
+impl HexRow {
+    /// Overwrites this row with row `row` of `bytes`, reusing its buffers. Returns `false`,
+    /// leaving the row empty, past the end.
+    pub fn fill(&mut self, bytes: &[u8], row: usize) -> bool {
+        self.address.clear();
+        self.hex.clear();
+        self.ascii.clear();
+        let Some(start) = row.checked_mul(BYTES_PER_ROW) else {
+            return false;
+        };
+        let Some(chunk) = bytes
+            .get(start..)
+            .and_then(|rest| rest.chunks(BYTES_PER_ROW).next())
+        else {
+            return false;
+        };
+        // Writing to a `String` can't fail.
+        let _ = write!(self.address, "{start:08x}");
+        for i in 0..BYTES_PER_ROW {
+            if i > 0 {
+                self.hex.push(' ');
+            }
+            if i == BYTES_PER_ROW / 2 {
+                self.hex.push(' ');
+            }
+            match chunk.get(i) {
+                Some(byte) => {
+                    let _ = write!(self.hex, "{byte:02x}");
+                }
+                None => self.hex.push_str("  "),
+            }
+        }
+        self.ascii.extend(chunk.iter().map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                char::from(b)
+            } else {
+                '.'
+            }
+        }));
+        true
+    }
+}
+
+pub fn row_count(bytes: &[u8]) -> usize {
+    bytes.len().div_ceil(BYTES_PER_ROW)
+}
+
+/// Draws the dump, laying out only the visible rows. The columns borrow the theme's comment,
+/// number and string colors.
+pub fn show(ui: &mut egui::Ui, bytes: &[u8], theme: &Theme) {
+    let font = egui::TextStyle::Monospace.resolve(ui.style());
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+    let colors = [
+        theme.syntax.comment,
+        theme.syntax.number,
+        theme.syntax.string,
+    ];
+    egui::ScrollArea::both()
+        .auto_shrink([false, false])
+        .id_salt("rustide_hex_view")
+        .show_rows(ui, row_height, row_count(bytes), |ui, rows| {
+            let mut row = HexRow::default();
+            for index in rows {
+                if !row.fill(bytes, index) {
+                    break;
+                }
+                let mut job = egui::text::LayoutJob::default();
+                for (text, color) in [&row.address, &row.hex, &row.ascii].into_iter().zip(colors) {
+                    if !job.text.is_empty() {
+                        job.append("  ", 0.0, egui::TextFormat::simple(font.clone(), color));
+                    }
+                    job.append(text, 0.0, egui::TextFormat::simple(font.clone(), color));
+                }
+                ui.add(egui::Label::new(job).extend());
+            }
+        });
+}
@@ -0,0 +1,34 @@
+// UI strings keyed by message ID so they can be translated later without touching callers.
+// Only English exists today; unknown languages fall back to it.
+
+pub const DEFAULT_LANG: &str = "en";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageId {
+    // Dock tab titles.
+    Editor,
+    Project,
+    Search,
+    Outline,
+    Problems,
+    Output,
+}
+
+pub fn localize(msg: MessageId, lang: &str) -> &'static str {
+    match lang {
+        "en" => english(msg),
+        // No other translations yet.
+        _ => english(msg),
+    }
+}
+
+fn english(msg: MessageId) -> &'static str {
+    match msg {
+        MessageId::Editor => "Editor",
+        MessageId::Project => "Project",
+        MessageId::Search => "Search",
+        MessageId::Outline => "Outline",
+        MessageId::Problems => "Problems",
+        MessageId::Output => "Output",
+    }
+}
@@ -1,42 +1,77 @@
 #![cfg_attr(windows, windows_subsystem = "windows")]
 
 use std::{
+    collections::{HashSet, VecDeque},
+    ops::Range,
     path::{Path, PathBuf},
     sync::mpsc::{self, Receiver, Sender},
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 
 use eframe::egui;
 use egui_commonmark::{CommonMarkCache, CommonMarkViewer};
 use egui_dock::{DockArea, DockState, Style, TabViewer};
 use rfd::FileDialog;
-use rustide_syntax::{LanguageId, SyntaxState};
+use rustide_editor::{
+    char_to_byte_index, find_first_from, find_last_before, find_normalized_line, DocumentPath,
+    Generation,
+};
+use rustide_syntax::{LanguageId, SymbolKind, SyntaxState};
 use serde::{Deserialize, Serialize};
 
+mod build;
+mod command_palette;
 mod config;
+mod crash_report;
 mod editor_view;
+mod font;
+mod hex_view;
+mod i18n;
+mod panes;
 mod project;
 mod theme;
 
+#[cfg(test)]
+mod tests;
+
 #[derive(Debug, Clone)]
 struct LoadRequest {
     tab_id: usize,
     path: PathBuf,
     encoding: rustide_editor::TextEncodingHint,
+    // Read the raw bytes for the hex view instead of decoding a document.
+    hex: bool,
 }
 
 #[derive(Debug)]
 enum LoadMessage {
     Loaded {
         tab_id: usize,
-        result: Result<rustide_editor::Document, String>,
+        result: Result<rustide_editor::Document, rustide_editor::EditorError>,
+        // Read before the file, so a write during the load still looks newer afterwards.
+        modified: Option<SystemTime>,
+    },
+    LoadedBytes {
+        tab_id: usize,
+        // The bytes, and whether the file was longer than `hex_view::MAX_BYTES`.
+        result: Result<(Vec<u8>, bool), String>,
+        modified: Option<SystemTime>,
     },
 }
 
+// Minimap markers from a `collect_find_all_positions` scan; `request` tells stale scans apart.
+#[derive(Debug)]
+struct FindAllMessage {
+    request: u64,
+    source: (usize, Generation),
+    positions: Vec<usize>,
+}
+
 #[derive(Debug)]
 enum DialogMessage {
     FilePicked(Option<PathBuf>),
     FolderPicked(Option<PathBuf>),
+    DeleteConfirmed(PathBuf),
 }
 
 struct SaveRequest {
@@ -44,40 +79,75 @@ struct SaveRequest {
     path: PathBuf,
     encoding: rustide_editor::TextEncoding,
     text: String,
-    version: u64,
+    version: Generation,
+    backup: bool,
+    cleanup: config::SaveCleanup,
 }
 
 enum SaveMessage {
     Saved {
         tab_id: usize,
-        version: u64,
+        version: Generation,
+        modified: Option<SystemTime>,
     },
     Failed {
         tab_id: usize,
-        version: u64,
+        version: Generation,
         error: String,
     },
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum DocumentMode {
+    #[default]
+    Text,
+    // Read-only dump of `OpenDocument::raw_bytes`; the buffer stays empty and is never saved.
+    Hex,
+}
+
 struct OpenDocument {
     doc: rustide_editor::Document,
+    mode: DocumentMode,
+    raw_bytes: Option<Vec<u8>>,
     syntax: Option<SyntaxState>,
     markdown: Option<MarkdownState>,
+    // Chosen in the header; takes priority over detection from the path and first line.
+    language_override: Option<LanguageId>,
+    // Resolved from `[editor]`, `[editor.<language>]` and the document's `.editorconfig` for the
+    // current language.
+    settings: config::EditorConfig,
     scroll_to_char: Option<usize>,
+    // Start bytes of the folded `SyntaxState::fold_ranges`.
+    folded: HashSet<usize>,
     nav_back: Vec<NavLocation>,
     nav_forward: Vec<NavLocation>,
     pending_jump: Option<(usize, usize)>, // (line_index, column_chars)
     pinned: bool,
-    last_saved_version: u64,
-    pending_save_version: Option<u64>,
+    last_saved_version: Generation,
+    pending_save_version: Option<Generation>,
     last_save_request: Option<Instant>,
+    caret_blink: editor_view::CaretBlink,
+    // Modified time of the file after our last load or save; a watcher event with the same
+    // time is our own write.
+    disk_modified: Option<SystemTime>,
+    // Set when the file changed on disk while this document had unsaved edits; shows the
+    // reload banner and pauses autosave.
+    file_changed_on_disk: bool,
+}
+
+// What to do with a document whose file the watcher reports as changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiskChange {
+    Ignore,
+    Reload,
+    AskUser,
 }
 
 struct MarkdownState {
     preview_enabled: bool,
     cache: CommonMarkCache,
     cached_text: String,
-    cached_version: u64,
+    cached_version: Generation,
     preview_content_h: f32,
     preview_viewport_h: f32,
 }
@@ -89,48 +159,43 @@ struct NavLocation {
 
 impl OpenDocument {
     fn empty() -> Self {
+        let doc = rustide_editor::Document::empty();
+        let last_saved_version = doc.editor.version();
         Self {
-            doc: rustide_editor::Document::empty(),
+            doc,
+            mode: DocumentMode::Text,
+            raw_bytes: None,
             syntax: None,
             markdown: None,
+            language_override: None,
+            settings: config::EditorConfig::default(),
             scroll_to_char: None,
+            folded: HashSet::new(),
             nav_back: Vec::new(),
             nav_forward: Vec::new(),
             pending_jump: None,
             pinned: false,
-            last_saved_version: 0,
+            last_saved_version,
             pending_save_version: None,
             last_save_request: None,
+            caret_blink: editor_view::CaretBlink::new(Instant::now()),
+            disk_modified: None,
+            file_changed_on_disk: false,
         }
     }
 
-    fn from_loaded(doc: rustide_editor::Document) -> Self {
-        let language = LanguageId::from_path(doc.path.as_deref());
-        let mut syntax = SyntaxState::new(language).ok();
-        if let Some(s) = syntax.as_mut() {
-            if let Err(err) = s.set_text(doc.editor.rope()) {
-                tracing::warn!("Failed to initialize syntax: {err}");
-                syntax = None;
-            }
-        }
-        let markdown = if language == LanguageId::Markdown {
-            Some(MarkdownState {
-                preview_enabled: true,
-                cache: CommonMarkCache::default(),
-                cached_text: doc.editor.rope().to_string(),
-                cached_version: doc.editor.version(),
-                preview_content_h: 0.0,
-                preview_viewport_h: 0.0,
-            })
-        } else {
-            None
-        };
+    fn from_loaded(doc: rustide_editor::Document, language_override: Option<LanguageId>) -> Self {
         let last_saved_version = doc.editor.version();
-        Self {
+        let mut open = Self {
             doc,
-            syntax,
-            markdown,
+            mode: DocumentMode::Text,
+            raw_bytes: None,
+            syntax: None,
+            markdown: None,
+            language_override,
+            settings: config::EditorConfig::default(),
             scroll_to_char: None,
+            folded: HashSet::new(),
             nav_back: Vec::new(),
             nav_forward: Vec::new(),
             pending_jump: None,
@@ -138,33 +203,128 @@ impl OpenDocument {
             last_saved_version,
             pending_save_version: None,
             last_save_request: None,
+            caret_blink: editor_view::CaretBlink::new(Instant::now()),
+            disk_modified: None,
+            file_changed_on_disk: false,
+        };
+        open.apply_language();
+        open
+    }
+
+    fn language(&self) -> LanguageId {
+        if let Some(language) = self.language_override {
+            return language;
+        }
+        let first_line = self
+            .doc
+            .editor
+            .rope()
+            .lines()
+            .next()
+            .map(|line| line.to_string())
+            .unwrap_or_default();
+        LanguageId::detect(self.doc.path.as_deref(), &first_line)
+    }
+
+    // `modified` is the file's modified time now. While a save is in flight the change is
+    // most likely that save, and its result records the new time.
+    fn on_disk_change(&self, modified: Option<SystemTime>) -> DiskChange {
+        if self.pending_save_version.is_some()
+            || (modified.is_some() && modified == self.disk_modified)
+        {
+            DiskChange::Ignore
+        } else if self.doc.editor.version() == self.last_saved_version {
+            DiskChange::Reload
+        } else {
+            DiskChange::AskUser
+        }
+    }
+
+    fn set_language_override(&mut self, language_override: Option<LanguageId>) {
+        let previous = std::mem::replace(&mut self.language_override, language_override);
+        if !self.apply_language() {
+            // Keep naming the language the syntax state still has.
+            self.language_override = previous;
+        }
+    }
+
+    // (Re)builds the syntax tree and the Markdown preview state for `self.language()`. Returns
+    // `false`, leaving the tree, folds and preview as they were, if the new tree can't be built.
+    fn apply_language(&mut self) -> bool {
+        let language = self.language();
+        let rope = self.doc.editor.rope();
+        // `reset` leaves the current state in place if the new one can't be built.
+        let result = match &mut self.syntax {
+            Some(syntax) => syntax.reset(language, rope),
+            None => SyntaxState::new(language)
+                .and_then(|mut syntax| syntax.set_text(rope).map(|()| syntax))
+                .map(|syntax| self.syntax = Some(syntax)),
+        };
+        if let Err(err) = result {
+            tracing::warn!("Failed to initialize syntax: {err}");
+            return false;
+        }
+        self.folded.clear();
+
+        if language != LanguageId::Markdown {
+            self.markdown = None;
+        } else if self.markdown.is_none() {
+            self.markdown = Some(MarkdownState {
+                preview_enabled: true,
+                cache: CommonMarkCache::default(),
+                cached_text: rope.to_string(),
+                cached_version: self.doc.editor.version(),
+                preview_content_h: 0.0,
+                preview_viewport_h: 0.0,
+            });
         }
+        true
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 enum DockTab {
-    Editor,
+    // An editor pane; 0 is the one that is always there, the others come from "Open to the
+    // Side".
+    Editor(usize),
     Project,
     Search,
+    Outline,
+    Problems,
+    Output,
 }
 
 impl DockTab {
-    fn title(&self) -> &'static str {
+    fn message_id(&self) -> i18n::MessageId {
         match self {
-            Self::Editor => "Editor",
-            Self::Project => "Project",
-            Self::Search => "Search",
+            Self::Editor(_) => i18n::MessageId::Editor,
+            Self::Project => i18n::MessageId::Project,
+            Self::Search => i18n::MessageId::Search,
+            Self::Outline => i18n::MessageId::Outline,
+            Self::Problems => i18n::MessageId::Problems,
+            Self::Output => i18n::MessageId::Output,
         }
     }
-}
 
-const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(2);
+    fn title(&self) -> &'static str {
+        i18n::localize(self.message_id(), i18n::DEFAULT_LANG)
+    }
+}
 
 struct RustideApp {
     documents: Vec<OpenDocument>,
+    // The focused editor pane's document.
     active_doc: usize,
+    // Each `DockTab::Editor` pane's tabs, and which pane has the focus.
+    panes: panes::EditorPanes,
+    // A document to show in another pane, once the dock isn't being drawn.
+    side_doc_request: Option<usize>,
+    // Panes that lost their last tab while drawn, to take out of the dock afterwards.
+    emptied_panes: Vec<usize>,
     status: String,
+    // Set when the last file opened was refused as binary; offers "Open as Hex" next to the
+    // status.
+    binary_file: Option<PathBuf>,
     load_tx: Sender<LoadRequest>,
     load_rx: Receiver<LoadMessage>,
     save_tx: Sender<SaveRequest>,
@@ -173,14 +333,30 @@ struct RustideApp {
     dialog_rx: Receiver<DialogMessage>,
     config: config::AppConfig,
     config_path: PathBuf,
-    font_state: FontState,
+    // `monospace_size` as loaded or set in Settings; Ctrl+0 undoes editor zoom back to it.
+    configured_font_size: f32,
+    font_state: font::FontState,
     theme: theme::Theme,
     last_applied_ui: Option<(config::MonospaceFont, f32, theme::ThemeId, f32)>,
+    // Polls the custom theme file while `theme=custom` has a path; no thread runs otherwise.
+    custom_theme_watcher: Option<CustomThemeWatcher>,
     dock_state: DockState<DockTab>,
 
     project_root: Option<PathBuf>,
     project_root_input: String,
     project_tree: Option<rustide_project::TreeNode>,
+    // The fully walked tree from `ListingUpdated`, kept only while the filter has text. The
+    // filter searches it, since `project_tree` only has the directories expanded so far.
+    project_listing: Option<rustide_project::TreeNode>,
+    // Directories asked for with `LoadChildren` and not answered yet.
+    project_pending_loads: HashSet<PathBuf>,
+    project_filter: String,
+    // `filter_tree` result for the query it was built with; cleared whenever the tree or the
+    // listing changes.
+    project_filtered: Option<(String, Option<rustide_project::TreeNode>)>,
+    project_name_prompt: Option<NamePrompt>,
+    // "N files, M KB" for the listing, as counted by the project worker.
+    project_stats: String,
     project_status: String,
     project_worker: Option<project::ProjectWorker>,
     project_rx: Receiver<project::ProjectMessage>,
@@ -188,19 +364,64 @@ struct RustideApp {
 
     search_query: String,
     search_case_sensitive: bool,
+    search_regex: bool,
+    search_whole_word: bool,
+    search_file_glob: String,
     search_request_focus: bool,
     search_results: Vec<project::SearchMatch>,
     search_status: String,
     search_worker: Option<project::SearchWorker>,
     search_rx: Receiver<project::SearchMessage>,
     search_tx: Sender<project::SearchMessage>,
+    search_replacement: String,
+    replace_confirm_open: bool,
+    replace_worker: Option<project::ReplaceWorker>,
+    replace_rx: Receiver<project::ReplaceMessage>,
+    replace_tx: Sender<project::ReplaceMessage>,
     active_search_id: u64,
 
+    // Lines from the last "Run Build", oldest first; capped at `MAX_BUILD_OUTPUT_LINES`.
+    build_output: VecDeque<String>,
+    build_status: String,
+    build_running: bool,
+    // Bumped by each "Run Build", so the Problems rows know when `build_diagnostics` changed.
+    build_runs: u64,
+    // Errors and warnings parsed from `build_output`; the Problems tab lists them once the
+    // build finishes. Paths are resolved against `build_dir`, where the command ran.
+    build_diagnostics: Vec<build::Diagnostic>,
+    build_parser: build::DiagnosticParser,
+    build_dir: Option<PathBuf>,
+    // The Problems tab's rows, rebuilt only when a document's parse or the build changes.
+    problem_rows: ProblemRows,
+    build_rx: Receiver<build::BuildMessage>,
+    build_tx: Sender<build::BuildMessage>,
+
     find_open: bool,
     find_request_focus: bool,
     find_query: String,
     find_case_sensitive: bool,
+    find_regex: bool,
+    find_whole_word: bool,
+    replace_open: bool,
+    replace_query: String,
     find_status: String,
+    // Start chars of every find-bar match in `find_all_source`'s document, drawn as minimap
+    // ticks; the source is `(document index, version)` when they were collected.
+    find_all_positions: Vec<usize>,
+    find_all_source: Option<(usize, Generation)>,
+    // The scan in flight, collected off the UI thread. Only the answer to the latest request
+    // is kept; clearing the markers bumps `find_all_requests` too.
+    find_all_pending: Option<(usize, Generation)>,
+    find_all_requests: u64,
+    find_all_tx: Sender<FindAllMessage>,
+    find_all_rx: Receiver<FindAllMessage>,
+
+    command_palette: command_palette::CommandPalette,
+    goto_line_open: bool,
+    goto_line_request_focus: bool,
+    goto_line_input: String,
+
+    crash_prompt: Option<crash_report::CrashReportPrompt>,
 }
 
 impl RustideApp {
@@ -208,7 +429,7 @@ impl RustideApp {
         initial_path: Option<PathBuf>,
         config: config::AppConfig,
         config_path: PathBuf,
-        font_state: FontState,
+        font_state: font::FontState,
     ) -> Self {
         let (load_tx, request_rx) = mpsc::channel::<LoadRequest>();
         let (result_tx, load_rx) = mpsc::channel::<LoadMessage>();
@@ -217,25 +438,48 @@ impl RustideApp {
         let (dialog_tx, dialog_rx) = mpsc::channel::<DialogMessage>();
         let (project_tx, project_rx) = mpsc::channel::<project::ProjectMessage>();
         let (search_tx, search_rx) = mpsc::channel::<project::SearchMessage>();
+        let (replace_tx, replace_rx) = mpsc::channel::<project::ReplaceMessage>();
+        let (build_tx, build_rx) = mpsc::channel::<build::BuildMessage>();
+        let (find_all_tx, find_all_rx) = mpsc::channel::<FindAllMessage>();
 
         std::thread::spawn(move || {
             while let Ok(req) = request_rx.recv() {
-                let loaded = rustide_editor::Document::load_with_encoding(&req.path, req.encoding)
-                    .map_err(|e| e.to_string());
+                let modified = file_modified(Some(&req.path));
+                if req.hex {
+                    let _ = result_tx.send(LoadMessage::LoadedBytes {
+                        tab_id: req.tab_id,
+                        result: std::fs::File::open(&req.path)
+                            .and_then(|file| hex_view::read_capped(file, hex_view::MAX_BYTES))
+                            .map_err(|e| e.to_string()),
+                        modified,
+                    });
+                    continue;
+                }
+                let loaded = rustide_editor::Document::load_with_encoding(&req.path, req.encoding);
                 let _ = result_tx.send(LoadMessage::Loaded {
                     tab_id: req.tab_id,
                     result: loaded,
+                    modified,
                 });
             }
         });
 
         std::thread::spawn(move || {
             while let Ok(req) = save_rx_req.recv() {
-                let bytes = rustide_editor::encode_text(&req.text, req.encoding);
-                let message = match std::fs::write(&req.path, bytes) {
+                let text = req.cleanup.prepare_for_save(req.text);
+                let bytes = rustide_editor::encode_text(&text, req.encoding);
+                let result = if req.backup {
+                    rustide_editor::write_backup(&req.path)
+                } else {
+                    Ok(())
+                };
+                let result =
+                    result.and_then(|()| rustide_editor::write_atomically(&req.path, &bytes));
+                let message = match result {
                     Ok(()) => SaveMessage::Saved {
                         tab_id: req.tab_id,
                         version: req.version,
+                        modified: file_modified(Some(&req.path)),
                     },
                     Err(err) => SaveMessage::Failed {
                         tab_id: req.tab_id,
@@ -247,29 +491,47 @@ impl RustideApp {
             }
         });
 
-        let theme = theme::build_theme(config.ui.theme);
+        let theme = theme::resolve_theme(config.ui.theme, config.ui.custom_theme_path.as_deref());
+        let custom_theme_watcher = custom_theme_path(&config.ui).map(CustomThemeWatcher::spawn);
         let dock_state = load_or_default_dock_state(&config);
+        let crash_prompt = {
+            let report_path = crash_report::crash_report_path(&config_path);
+            crash_report::load_pending(&report_path)
+                .map(|report| crash_report::CrashReportPrompt::new(report_path, report))
+        };
         let mut app = Self {
             documents: Vec::new(),
             active_doc: 0,
+            panes: panes::EditorPanes::default(),
+            side_doc_request: None,
+            emptied_panes: Vec::new(),
             status: "Drop a file into the window, or pass a file path as the first CLI argument."
                 .to_string(),
+            binary_file: None,
             load_tx,
             load_rx,
             save_tx,
             save_rx,
             dialog_tx,
             dialog_rx,
+            configured_font_size: config.ui.monospace_size,
             config,
             config_path,
             font_state,
             theme,
             last_applied_ui: None,
+            custom_theme_watcher,
             dock_state,
 
             project_root: None,
             project_root_input: String::new(),
             project_tree: None,
+            project_listing: None,
+            project_pending_loads: HashSet::new(),
+            project_filter: String::new(),
+            project_filtered: None,
+            project_name_prompt: None,
+            project_stats: String::new(),
             project_status: "Drop a folder to open a project.".to_string(),
             project_worker: None,
             project_rx,
@@ -277,19 +539,55 @@ impl RustideApp {
 
             search_query: String::new(),
             search_case_sensitive: false,
+            search_regex: false,
+            search_whole_word: false,
+            search_file_glob: "*.rs".to_string(),
             search_request_focus: false,
             search_results: Vec::new(),
             search_status: String::new(),
             search_worker: None,
             search_rx,
             search_tx,
+            search_replacement: String::new(),
+            replace_confirm_open: false,
+            replace_worker: None,
+            replace_rx,
+            replace_tx,
             active_search_id: 0,
 
+            build_output: VecDeque::new(),
+            build_status: String::new(),
+            build_running: false,
+            build_runs: 0,
+            build_diagnostics: Vec::new(),
+            build_parser: build::DiagnosticParser::default(),
+            build_dir: None,
+            problem_rows: ProblemRows::default(),
+            build_rx,
+            build_tx,
+
             find_open: false,
             find_request_focus: false,
             find_query: String::new(),
             find_case_sensitive: false,
+            find_regex: false,
+            find_whole_word: false,
+            replace_open: false,
+            replace_query: String::new(),
             find_status: String::new(),
+            find_all_positions: Vec::new(),
+            find_all_source: None,
+            find_all_pending: None,
+            find_all_requests: 0,
+            find_all_tx,
+            find_all_rx,
+
+            command_palette: command_palette::CommandPalette::default(),
+            goto_line_open: false,
+            goto_line_request_focus: false,
+            goto_line_input: String::new(),
+
+            crash_prompt,
         };
 
         if let Some(path) = initial_path {
@@ -323,6 +621,78 @@ impl RustideApp {
         &mut self.documents[self.active_doc]
     }
 
+    // Lists `active_doc` in the focused pane's tab strip, unless no document is open.
+    fn sync_panes(&mut self) {
+        if self.active_doc < self.documents.len() {
+            self.panes.sync(self.active_doc);
+        }
+    }
+
+    // Makes `pane` the focused editor pane; `active_doc` follows it.
+    fn focus_pane(&mut self, pane: usize) {
+        self.sync_panes();
+        self.panes.focus_pane(pane, &mut self.active_doc);
+        self.sync_panes();
+    }
+
+    // Takes the tabs `close` picks off the focused pane. The documents no other pane lists are
+    // closed with them, and a pane left without tabs is closed unless it's the last one.
+    fn close_tabs(&mut self, mut close: impl FnMut(usize, &OpenDocument) -> bool) {
+        self.sync_panes();
+        let pane = self.panes.focused();
+        let documents = &self.documents;
+        if self
+            .panes
+            .close_tabs(&mut self.active_doc, |idx| close(idx, &documents[idx]))
+        {
+            self.emptied_panes.push(pane);
+        }
+        self.close_unlisted_documents();
+    }
+
+    // Closes the documents no pane lists any more. Every pane keeps showing the same document.
+    fn close_unlisted_documents(&mut self) {
+        self.sync_panes();
+        let kept: Vec<bool> = (0..self.documents.len())
+            .map(|idx| self.panes.lists(idx))
+            .collect();
+        if kept.iter().all(|&kept| kept) {
+            return;
+        }
+        let mut flags = kept.iter().copied();
+        self.documents.retain(|_| flags.next().unwrap_or(true));
+        let last = self.documents.len().saturating_sub(1);
+        self.active_doc = index_after_removal(&kept, self.active_doc).min(last);
+        self.panes.retain_documents(&kept);
+    }
+
+    // Shows document `idx` in an editor pane other than the focused one, splitting the focused
+    // pane in two when it's the only one, and focuses it. Must not run while the dock is drawn.
+    fn open_to_side(&mut self, idx: usize) {
+        self.sync_panes();
+        let pane = match self.panes.side_pane() {
+            Some(pane) => pane,
+            None => {
+                let pane = (0..)
+                    .find(|&pane| self.dock_state.find_tab(&DockTab::Editor(pane)).is_none())
+                    .expect("a free pane id");
+                let focused = DockTab::Editor(self.panes.focused());
+                let node = match self.dock_state.find_tab(&focused) {
+                    Some((surface, node, _)) if surface == egui_dock::SurfaceIndex::main() => node,
+                    _ => egui_dock::NodeIndex::root(),
+                };
+                self.dock_state.main_surface_mut().split_right(
+                    node,
+                    0.5,
+                    vec![DockTab::Editor(pane)],
+                );
+                pane
+            }
+        };
+        self.panes.open_to_side(pane, idx, &mut self.active_doc);
+        self.focus_tab(DockTab::Editor(pane));
+    }
+
     fn open_in_new_tab(&mut self, path: PathBuf, jump: Option<(usize, usize)>) {
         if let Some(existing) = self
             .documents
@@ -352,6 +722,32 @@ impl RustideApp {
         self.queue_load(self.active_doc, path);
     }
 
+    fn open_hex_view(&mut self, path: PathBuf) {
+        if let Some(idx) = self
+            .documents
+            .iter()
+            .position(|d| d.mode == DocumentMode::Hex && d.doc.path.as_ref() == Some(&path))
+        {
+            self.active_doc = idx;
+            return;
+        }
+        let mut doc = OpenDocument::empty();
+        doc.doc.path = Some(path.clone());
+        doc.mode = DocumentMode::Hex;
+        // Take over the blank tab a refused text load leaves behind.
+        let blank = self
+            .documents
+            .get(self.active_doc)
+            .is_some_and(|d| d.doc.path.is_none() && d.doc.editor.rope().len_chars() == 0);
+        if blank {
+            self.documents[self.active_doc] = doc;
+        } else {
+            self.documents.push(doc);
+            self.active_doc = self.documents.len() - 1;
+        }
+        self.queue_load(self.active_doc, path);
+    }
+
     fn open_file_dialog(&mut self) {
         let tx = self.dialog_tx.clone();
         let start_dir = self
@@ -386,10 +782,25 @@ impl RustideApp {
     }
 
     fn queue_load(&mut self, tab_id: usize, path: PathBuf) {
+        let encoding = self.config.file.encoding_for(&path);
+        self.queue_load_with_encoding(tab_id, path, encoding);
+    }
+
+    fn queue_load_with_encoding(
+        &mut self,
+        tab_id: usize,
+        path: PathBuf,
+        encoding: rustide_editor::TextEncodingHint,
+    ) {
+        let hex = self
+            .documents
+            .get(tab_id)
+            .is_some_and(|doc| doc.mode == DocumentMode::Hex);
         let req = LoadRequest {
             tab_id,
             path,
-            encoding: self.config.file.encoding,
+            encoding,
+            hex,
         };
         match self.load_tx.send(req) {
             Ok(()) => self.status = "Loading…".to_string(),
@@ -403,21 +814,73 @@ impl RustideApp {
                 LoadMessage::Loaded {
                     tab_id,
                     result: Ok(doc),
+                    modified,
                 } => {
                     self.status.clear();
+                    self.binary_file = None;
                     if let Some(p) = doc.path.clone() {
                         self.config.startup.last_file = Some(p);
                     }
                     if let Some(slot) = self.documents.get_mut(tab_id) {
                         let pending_jump = slot.pending_jump.take();
-                        *slot = OpenDocument::from_loaded(doc);
+                        *slot = OpenDocument::from_loaded(doc, slot.language_override);
+                        slot.disk_modified = modified;
+                        if let Some(ending) = self.config.file.line_ending_for(&slot.doc) {
+                            slot.doc.set_line_ending(ending);
+                        }
+                        slot.settings = self
+                            .config
+                            .editor
+                            .resolve(slot.language(), slot.doc.editor_config.as_ref());
                         slot.pending_jump = pending_jump;
                         self.apply_pending_jump(tab_id);
                     }
                 }
                 LoadMessage::Loaded {
-                    tab_id: _,
-                    result: Err(err),
+                    tab_id,
+                    result: Err(rustide_editor::EditorError::BinaryFile { path }),
+                    ..
+                } => {
+                    // Don't leave an empty placeholder tab behind that a save would write over
+                    // the file with.
+                    if let Some(slot) = self.documents.get_mut(tab_id) {
+                        if slot.doc.path.as_ref() == Some(&path)
+                            && slot.doc.editor.rope().len_chars() == 0
+                        {
+                            *slot = OpenDocument::empty();
+                        }
+                    }
+                    self.status = format!(
+                        "{} is a binary file and can't be opened as text.",
+                        path.display()
+                    );
+                    self.binary_file = Some(path);
+                }
+                LoadMessage::Loaded {
+                    result: Err(err), ..
+                } => {
+                    self.status = format!("Load failed: {err}");
+                }
+                LoadMessage::LoadedBytes {
+                    tab_id,
+                    result: Ok((bytes, truncated)),
+                    modified,
+                } => {
+                    self.status = if truncated {
+                        format!(
+                            "Showing the first {} MB of the file",
+                            hex_view::MAX_BYTES / (1024 * 1024)
+                        )
+                    } else {
+                        String::new()
+                    };
+                    if let Some(slot) = self.documents.get_mut(tab_id) {
+                        slot.raw_bytes = Some(bytes);
+                        slot.disk_modified = modified;
+                    }
+                }
+                LoadMessage::LoadedBytes {
+                    result: Err(err), ..
                 } => {
                     self.status = format!("Load failed: {err}");
                 }
@@ -434,6 +897,9 @@ impl RustideApp {
                 DialogMessage::FolderPicked(Some(path)) => {
                     self.set_project_root(path);
                 }
+                DialogMessage::DeleteConfirmed(path) => {
+                    self.run_file_op(project::FileOp::Delete(path));
+                }
                 DialogMessage::FilePicked(None) | DialogMessage::FolderPicked(None) => {}
             }
         }
@@ -442,17 +908,16 @@ impl RustideApp {
     fn poll_save_results(&mut self) {
         while let Ok(msg) = self.save_rx.try_recv() {
             match msg {
-                SaveMessage::Saved { tab_id, version } => {
+                SaveMessage::Saved {
+                    tab_id,
+                    version,
+                    modified,
+                } => {
                     if let Some(doc) = self.documents.get_mut(tab_id) {
-                        let path_label = doc
-                            .doc
-                            .path
-                            .as_ref()
-                            .map(|p| p.display().to_string())
-                            .unwrap_or_else(|| "<untitled>".to_string());
                         doc.pending_save_version = None;
                         doc.last_saved_version = version;
-                        self.status = format!("Autosaved {path_label}");
+                        doc.disk_modified = modified;
+                        self.status = format!("Autosaved {}", DocumentPath(&doc.doc.path));
                     }
                 }
                 SaveMessage::Failed {
@@ -464,13 +929,10 @@ impl RustideApp {
                         if doc.pending_save_version == Some(version) {
                             doc.pending_save_version = None;
                         }
-                        let path_label = doc
-                            .doc
-                            .path
-                            .as_ref()
-                            .map(|p| p.display().to_string())
-                            .unwrap_or_else(|| "<untitled>".to_string());
-                        self.status = format!("Autosave failed for {path_label}: {error}");
+                        self.status = format!(
+                            "Autosave failed for {}: {error}",
+                            DocumentPath(&doc.doc.path)
+                        );
                     } else {
                         self.status = format!("Autosave failed: {error}");
                     }
@@ -479,61 +941,120 @@ impl RustideApp {
         }
     }
 
+    // Picks up trees finished by the per-document parse threads; stale highlights stay on screen
+    // until then, so keep repainting while a parse is running.
+    fn poll_syntax_results(&mut self, ctx: &egui::Context) {
+        for doc in &mut self.documents {
+            let Some(syntax) = doc.syntax.as_mut() else {
+                continue;
+            };
+            if syntax.poll_result().is_some() {
+                ctx.request_repaint();
+            } else if syntax.is_parsing() {
+                ctx.request_repaint_after(Duration::from_millis(16));
+            }
+        }
+    }
+
+    fn needs_save(doc: &OpenDocument) -> bool {
+        doc.doc.path.is_some()
+            && doc.pending_save_version.is_none()
+            && doc.doc.editor.version() != doc.last_saved_version
+    }
+
+    fn queue_save(&mut self, tab_id: usize) -> bool {
+        let Some(doc) = self.documents.get_mut(tab_id) else {
+            return false;
+        };
+        if doc.mode == DocumentMode::Hex {
+            return false;
+        }
+        let Some(path) = doc.doc.path.clone() else {
+            return false;
+        };
+        let version = doc.doc.editor.version();
+        let request = SaveRequest {
+            tab_id,
+            path,
+            encoding: doc.doc.encoding,
+            text: doc.doc.save_text(),
+            version,
+            backup: self.config.file.backup_on_save,
+            cleanup: self.config.file.save_cleanup(doc.settings),
+        };
+        if self.save_tx.send(request).is_err() {
+            return false;
+        }
+        doc.pending_save_version = Some(version);
+        doc.last_save_request = Some(Instant::now());
+        true
+    }
+
     fn maybe_autosave_active_doc(&mut self) {
         if self.documents.is_empty() {
             return;
         }
         let now = Instant::now();
-        let (path, encoding, version, text) = {
+        let interval = self.config.ui.autosave_interval();
+        let path = {
             let doc = self.active_document_mut();
-            if doc.doc.path.is_none() {
+            // Don't overwrite an outside change the user hasn't decided about yet.
+            if !Self::needs_save(doc) || doc.file_changed_on_disk {
                 return;
             }
-            if doc.pending_save_version.is_some() {
+            if !autosave_due(doc.last_save_request, now, interval) {
                 return;
             }
-            let version = doc.doc.editor.version();
-            if version == doc.last_saved_version {
-                return;
-            }
-            if doc
-                .last_save_request
-                .map(|instant| now.duration_since(instant) < AUTOSAVE_INTERVAL)
-                .unwrap_or(false)
-            {
-                return;
-            }
-            let path = doc.doc.path.clone().unwrap();
-            let encoding = doc.doc.encoding;
-            let text = doc.doc.editor.rope().to_string();
-            (path, encoding, version, text)
-        };
-
-        let request = SaveRequest {
-            tab_id: self.active_doc,
-            path: path.clone(),
-            encoding,
-            text,
-            version,
+            doc.doc.path.clone().unwrap_or_default()
         };
 
-        if self.save_tx.send(request).is_ok() {
-            let doc = self.active_document_mut();
-            doc.pending_save_version = Some(version);
-            doc.last_save_request = Some(now);
+        if self.queue_save(self.active_doc) {
             self.status = format!("Autosaving {}", path.display());
         } else {
             self.status = "Autosave queue failed".to_string();
         }
     }
 
+    fn save_all(&mut self) {
+        let mut queued = 0usize;
+        for tab_id in 0..self.documents.len() {
+            if Self::needs_save(&self.documents[tab_id]) && self.queue_save(tab_id) {
+                queued += 1;
+            }
+        }
+        self.status = if queued == 0 {
+            "Nothing to save".to_string()
+        } else {
+            format!("Saving {queued} file(s)…")
+        };
+    }
+
     fn poll_project(&mut self) {
         while let Ok(msg) = self.project_rx.try_recv() {
             match msg {
                 project::ProjectMessage::TreeUpdated(tree) => {
                     self.project_tree = Some(tree);
+                    self.project_tree_changed();
                     self.project_status = "Ready".to_string();
                 }
+                project::ProjectMessage::ChildrenLoaded(dir, children) => {
+                    self.project_pending_loads.remove(&dir);
+                    let tree = self.project_tree.as_mut();
+                    if tree.is_some_and(|tree| tree.set_children(&dir, children)) {
+                        self.project_tree_changed();
+                    }
+                }
+                project::ProjectMessage::ListingUpdated(listing) => {
+                    self.project_listing = Some(listing);
+                    self.project_filtered = None;
+                }
+                project::ProjectMessage::StatsUpdated(stats) => {
+                    self.project_stats = format_project_stats(stats.files, stats.size_bytes);
+                }
+                project::ProjectMessage::FileOpDone(op) => self.finish_file_op(op),
+                project::ProjectMessage::FileChanged(path, modified) => {
+                    self.file_changed_on_disk(&path, modified);
+                }
                 project::ProjectMessage::Error(e) => {
                     self.project_status = format!("Project error: {e}");
                 }
@@ -549,9 +1070,9 @@ impl RustideApp {
                     self.search_results.clear();
                     self.search_status = "Searching…".to_string();
                 }
-                project::SearchMessage::Match(id, m) => {
+                project::SearchMessage::Batch(id, matches) => {
                     if id == self.active_search_id {
-                        self.search_results.push(m);
+                        self.search_results.extend(matches);
                     }
                 }
                 project::SearchMessage::Finished(id) => {
@@ -566,6 +1087,92 @@ impl RustideApp {
                 }
             }
         }
+
+        while let Ok(msg) = self.replace_rx.try_recv() {
+            match msg {
+                project::ReplaceMessage::Replaced { path, count } => {
+                    tracing::info!("Replaced {count} match(es) in {}", path.display());
+                    self.reload_if_clean(&path);
+                }
+                project::ReplaceMessage::Failed { path, error } => {
+                    tracing::warn!("Replace failed for {}: {error}", path.display());
+                    self.search_status = format!("Replace failed: {}: {error}", path.display());
+                }
+                project::ReplaceMessage::Finished {
+                    files,
+                    replacements,
+                } => {
+                    self.replace_worker = None;
+                    if !self.search_status.starts_with("Replace failed") {
+                        self.search_status =
+                            format!("Replaced {replacements} match(es) in {files} file(s)");
+                    }
+                }
+            }
+        }
+    }
+
+    // Files rewritten on disk by project replace: reload open tabs that have no unsaved edits
+    // so they don't later overwrite the replacement; dirty tabs are left alone.
+    fn reload_if_clean(&mut self, path: &Path) {
+        for tab_id in 0..self.documents.len() {
+            let doc = &self.documents[tab_id];
+            if doc.doc.path.as_deref() == Some(path)
+                && doc.doc.editor.version() == doc.last_saved_version
+            {
+                self.queue_reload(tab_id);
+            }
+        }
+    }
+
+    // Watcher reports for files changed outside the editor: clean tabs reload silently, tabs
+    // with unsaved edits get the reload banner.
+    fn file_changed_on_disk(&mut self, path: &Path, modified: Option<SystemTime>) {
+        for tab_id in 0..self.documents.len() {
+            let doc = &mut self.documents[tab_id];
+            if doc.doc.path.as_deref() != Some(path) {
+                continue;
+            }
+            match doc.on_disk_change(modified) {
+                DiskChange::Ignore => {}
+                DiskChange::Reload => self.queue_reload(tab_id),
+                DiskChange::AskUser => {
+                    doc.disk_modified = modified;
+                    doc.file_changed_on_disk = true;
+                }
+            }
+        }
+    }
+
+    // Reloads a tab from its file, keeping the cursor's line and column.
+    fn queue_reload(&mut self, tab_id: usize) {
+        let encoding = self
+            .documents
+            .get(tab_id)
+            .and_then(|doc| doc.doc.path.as_deref())
+            .map_or(self.config.file.encoding, |path| {
+                self.config.file.encoding_for(path)
+            });
+        self.queue_reload_with_encoding(tab_id, encoding);
+    }
+
+    // Loads the tab's file again, keeping the caret's line and column; unsaved edits are lost.
+    fn queue_reload_with_encoding(
+        &mut self,
+        tab_id: usize,
+        encoding: rustide_editor::TextEncodingHint,
+    ) {
+        let Some(doc) = self.documents.get_mut(tab_id) else {
+            return;
+        };
+        let Some(path) = doc.doc.path.clone() else {
+            return;
+        };
+        let rope = doc.doc.editor.rope();
+        let cursor = doc.doc.editor.selection().cursor.min(rope.len_chars());
+        let line = rope.char_to_line(cursor);
+        doc.pending_jump = Some((line, cursor - rope.line_to_char(line)));
+        self.queue_load_with_encoding(tab_id, path, encoding);
     }
 
     fn apply_pending_jump(&mut self, tab_id: usize) {
@@ -639,12 +1246,25 @@ impl RustideApp {
         doc.scroll_to_char = Some(next.cursor);
     }
 
+    // Drops state derived from `project_tree`.
+    fn project_tree_changed(&mut self) {
+        self.project_filtered = None;
+    }
+
     fn set_project_root(&mut self, root: PathBuf) {
         self.project_root_input = root.display().to_string();
         self.project_root = Some(root.clone());
         self.project_tree = None;
+        self.project_listing = None;
+        self.project_stats.clear();
+        self.project_pending_loads.clear();
+        self.project_tree_changed();
         self.project_status = "Loading…".to_string();
-        self.project_worker = Some(project::ProjectWorker::start(root, self.project_tx.clone()));
+        self.project_worker = Some(project::ProjectWorker::start(
+            root,
+            self.config.project.tree_options(),
+            self.project_tx.clone(),
+        ));
     }
 
     fn start_search(&mut self) {
@@ -654,25 +1274,98 @@ impl RustideApp {
         };
         self.active_search_id = self.active_search_id.wrapping_add(1);
         let request_id = self.active_search_id;
-        if let Some(worker) = &self.search_worker {
+        if let Some(worker) = self.search_worker.take() {
             worker.cancel();
         }
+        let options = project::SearchOptions {
+            query: self.search_query.clone(),
+            case_sensitive: self.search_case_sensitive,
+            use_regex: self.search_regex,
+            whole_word: self.search_whole_word,
+            context_lines: self.config.ui.search_context_lines,
+            file_glob: Some(self.search_file_glob.trim().to_string()).filter(|g| !g.is_empty()),
+        };
         self.search_worker = Some(project::SearchWorker::start(
             root,
-            self.search_query.clone(),
-            self.search_case_sensitive,
+            options,
             self.config.file.encoding,
             request_id,
             self.search_tx.clone(),
         ));
     }
 
+    fn start_replace_in_project(&mut self) {
+        let Some(root) = self.project_root.clone() else {
+            self.search_status = "No project root".to_string();
+            return;
+        };
+        if let Some(worker) = &self.replace_worker {
+            worker.cancel();
+        }
+        let options = project::SearchOptions {
+            query: self.search_query.clone(),
+            case_sensitive: self.search_case_sensitive,
+            use_regex: self.search_regex,
+            whole_word: self.search_whole_word,
+            context_lines: self.config.ui.search_context_lines,
+            file_glob: Some(self.search_file_glob.trim().to_string()).filter(|g| !g.is_empty()),
+        };
+        self.search_status = "Replacing…".to_string();
+        self.replace_worker = Some(project::ReplaceWorker::start(
+            root,
+            options,
+            self.search_replacement.clone(),
+            self.config.file.encoding,
+            self.replace_tx.clone(),
+        ));
+    }
+
+    fn ui_replace_confirm(&mut self, ctx: &egui::Context) {
+        if !self.replace_confirm_open {
+            return;
+        }
+        let mut files: Vec<&Path> = self
+            .search_results
+            .iter()
+            .map(|m| m.path.as_path())
+            .collect();
+        files.dedup();
+        let message = format!(
+            "Replace every match of \"{}\" with \"{}\" in the project?\nThe last search matched {} line(s) in {} file(s). Files are rewritten on disk and this cannot be undone.",
+            self.search_query, self.search_replacement, self.search_results.len(), files.len()
+        );
+
+        let mut confirmed = false;
+        let mut canceled = ctx.input(|i| i.key_pressed(egui::Key::Escape));
+        egui::Window::new("Replace in Project")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label(message);
+                ui.horizontal(|ui| {
+                    if ui.button("Replace All").clicked() {
+                        confirmed = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        canceled = true;
+                    }
+                });
+            });
+
+        if confirmed {
+            self.replace_confirm_open = false;
+            self.start_replace_in_project();
+        } else if canceled {
+            self.replace_confirm_open = false;
+        }
+    }
+
     fn cancel_search(&mut self) {
         self.active_search_id = self.active_search_id.wrapping_add(1);
-        if let Some(worker) = &self.search_worker {
+        if let Some(worker) = self.search_worker.take() {
             worker.cancel();
         }
-        self.search_worker = None;
         self.search_status = "Canceled".to_string();
     }
 
@@ -718,23 +1411,240 @@ impl RustideApp {
             }
         });
         ui.label(&self.project_status);
+        if !self.project_stats.is_empty() {
+            ui.weak(&self.project_stats);
+        }
         ui.separator();
 
+        ui.horizontal(|ui| {
+            ui.label("Filter");
+            let resp = ui.text_edit_singleline(&mut self.project_filter);
+            if resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                self.project_filter.clear();
+            }
+        });
+        let filter = self.project_filter.trim().to_string();
+        if let Some(worker) = self.project_worker.as_mut() {
+            worker.want_listing(!filter.is_empty());
+        }
+        if filter.is_empty() {
+            self.project_listing = None;
+        }
+        if !filter.is_empty()
+            && self
+                .project_filtered
+                .as_ref()
+                .is_none_or(|(query, _)| *query != filter)
+        {
+            // Until the first listing arrives, only the loaded part of the tree can be searched.
+            let searched = self.project_listing.as_ref().or(self.project_tree.as_ref());
+            self.project_filtered = searched.map(|tree| {
+                let filtered = rustide_project::filter_tree(tree, &filter);
+                (filter.clone(), filtered)
+            });
+        }
+
+        let mut load_requests = Vec::new();
         egui::ScrollArea::vertical().show(ui, |ui| {
-            if let Some(tree) = &self.project_tree {
+            let (tree, expand_all) = if filter.is_empty() {
+                (self.project_tree.as_ref(), false)
+            } else {
+                let filtered = self.project_filtered.as_ref().and_then(|(_, t)| t.as_ref());
+                if filtered.is_none() && self.project_tree.is_some() {
+                    ui.weak("No matching files");
+                }
+                (filtered, true)
+            };
+            if let Some(tree) = tree {
                 let root_path = self.project_root.as_deref().unwrap_or(tree.path.as_path());
-                if let Some(path) = show_tree(ui, tree, root_path) {
-                    self.open_in_new_tab(path, None);
+                let show_icons = self.config.ui.show_file_icons;
+                let options = TreeViewOptions {
+                    show_icons,
+                    expand_all,
+                };
+                if let Some(action) = show_tree(ui, tree, root_path, options, &mut load_requests) {
+                    self.run_tree_action(ui.ctx(), action);
                 }
             }
         });
+        for dir in load_requests {
+            if let Some(worker) = &self.project_worker {
+                if self.project_pending_loads.insert(dir.clone()) {
+                    worker.load_children(dir);
+                }
+            }
+        }
     }
 
-    fn ui_search_panel(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Search");
-        if self.project_root.is_none() {
-            ui.label("Open a project root first.");
-            return;
+    fn run_tree_action(&mut self, ctx: &egui::Context, action: TreeAction) {
+        match action {
+            TreeAction::Open(path) => self.open_in_new_tab(path, None),
+            TreeAction::OpenToSide(path) => {
+                // Open the file without showing it in the focused pane.
+                let focused_doc = self.active_doc;
+                self.open_in_new_tab(path, None);
+                self.side_doc_request = Some(self.active_doc);
+                self.active_doc = focused_doc;
+            }
+            TreeAction::OpenAsRoot(dir) => self.set_project_root(dir),
+            TreeAction::CopyPath(path) => ctx.copy_text(path.display().to_string()),
+            TreeAction::Reveal(path) => self.run_file_op(project::FileOp::Reveal(path)),
+            TreeAction::Delete(path) => self.confirm_delete(path),
+            TreeAction::Rename(path) => self.open_name_prompt(NamePromptKind::Rename, path),
+            TreeAction::NewFile(dir) => self.open_name_prompt(NamePromptKind::NewFile, dir),
+            TreeAction::NewFolder(dir) => self.open_name_prompt(NamePromptKind::NewFolder, dir),
+        }
+    }
+
+    fn open_name_prompt(&mut self, kind: NamePromptKind, target: PathBuf) {
+        let name = match kind {
+            NamePromptKind::Rename => target
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            NamePromptKind::NewFile | NamePromptKind::NewFolder => String::new(),
+        };
+        self.project_name_prompt = Some(NamePrompt {
+            kind,
+            target,
+            name,
+            error: None,
+            request_focus: true,
+        });
+    }
+
+    // The native dialog blocks, so it runs on its own thread like the file pickers.
+    fn confirm_delete(&mut self, path: PathBuf) {
+        let tx = self.dialog_tx.clone();
+        std::thread::spawn(move || {
+            let what = if path.is_dir() {
+                "folder and everything in it"
+            } else {
+                "file"
+            };
+            let answer = rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Warning)
+                .set_title("Delete")
+                .set_description(format!(
+                    "Delete this {what}?\n{}\nThis cannot be undone.",
+                    path.display()
+                ))
+                .set_buttons(rfd::MessageButtons::YesNo)
+                .show();
+            if matches!(answer, rfd::MessageDialogResult::Yes) {
+                let _ = tx.send(DialogMessage::DeleteConfirmed(path));
+            }
+        });
+    }
+
+    fn run_file_op(&mut self, op: project::FileOp) {
+        match &self.project_worker {
+            Some(worker) => worker.file_op(op),
+            None => self.project_status = "Open a project root first.".to_string(),
+        }
+    }
+
+    fn finish_file_op(&mut self, op: project::FileOp) {
+        match op {
+            project::FileOp::Rename { from, to } => {
+                // Keep open tabs pointing at the file, including files under a renamed folder.
+                for doc in &mut self.documents {
+                    let Some(rest) = doc
+                        .doc
+                        .path
+                        .as_ref()
+                        .and_then(|p| p.strip_prefix(&from).ok())
+                    else {
+                        continue;
+                    };
+                    let path = if rest.as_os_str().is_empty() {
+                        to.clone()
+                    } else {
+                        to.join(rest)
+                    };
+                    let language = doc.language();
+                    doc.doc.path = Some(path);
+                    if doc.language() != language {
+                        doc.apply_language();
+                    }
+                }
+                self.project_status = format!("Renamed to {}", to.display());
+            }
+            project::FileOp::Delete(path) => {
+                self.project_status = format!("Deleted {}", path.display());
+            }
+            project::FileOp::CreateFile(path) => {
+                self.project_status = format!("Created {}", path.display());
+                self.open_in_new_tab(path, None);
+            }
+            project::FileOp::CreateDir(path) => {
+                self.project_status = format!("Created {}", path.display());
+            }
+            project::FileOp::Reveal(_) => {}
+        }
+    }
+
+    fn ui_name_prompt(&mut self, ctx: &egui::Context) {
+        let Some(prompt) = self.project_name_prompt.as_mut() else {
+            return;
+        };
+        let mut submit = false;
+        let mut canceled = ctx.input(|i| i.key_pressed(egui::Key::Escape));
+        egui::Window::new(prompt.kind.title())
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 48.0))
+            .show(ctx, |ui| {
+                ui.label(format!("In {}", prompt.parent().display()));
+                let resp = ui.text_edit_singleline(&mut prompt.name);
+                if prompt.request_focus {
+                    resp.request_focus();
+                    prompt.request_focus = false;
+                }
+                submit = resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if let Some(error) = &prompt.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.horizontal(|ui| {
+                    submit |= ui.button("OK").clicked();
+                    canceled |= ui.button("Cancel").clicked();
+                });
+            });
+
+        if canceled {
+            self.project_name_prompt = None;
+            return;
+        }
+        if !submit {
+            return;
+        }
+        let name = match validate_entry_name(&prompt.name) {
+            Ok(name) => name.to_string(),
+            Err(error) => {
+                prompt.error = Some(error);
+                prompt.request_focus = true;
+                return;
+            }
+        };
+        let op = match prompt.kind {
+            NamePromptKind::Rename => project::FileOp::Rename {
+                from: prompt.target.clone(),
+                to: prompt.target.with_file_name(&name),
+            },
+            NamePromptKind::NewFile => project::FileOp::CreateFile(prompt.target.join(&name)),
+            NamePromptKind::NewFolder => project::FileOp::CreateDir(prompt.target.join(&name)),
+        };
+        self.project_name_prompt = None;
+        if !matches!(&op, project::FileOp::Rename { from, to } if from == to) {
+            self.run_file_op(op);
+        }
+    }
+
+    fn ui_search_panel(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Search");
+        if self.project_root.is_none() {
+            ui.label("Open a project root first.");
+            return;
         }
 
         ui.horizontal(|ui| {
@@ -757,7 +1667,39 @@ impl RustideApp {
                 self.cancel_search();
             }
         });
-        ui.checkbox(&mut self.search_case_sensitive, "Case sensitive");
+        ui.horizontal(|ui| {
+            ui.label("Files:");
+            let resp = ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.search_file_glob)
+                        .hint_text("*.rs, src/**/*.hlsl, or empty for all")
+                        .desired_width(f32::INFINITY),
+                )
+                .on_hover_text(
+                    "Matched against the file name; patterns containing '/' match the path \
+                 relative to the project root",
+                );
+            if resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                self.start_search();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.search_case_sensitive, "Case sensitive");
+            ui.checkbox(&mut self.search_regex, "Regex");
+            ui.checkbox(&mut self.search_whole_word, "Whole word");
+        });
+        ui.add(
+            egui::TextEdit::singleline(&mut self.search_replacement)
+                .hint_text("replace with")
+                .desired_width(f32::INFINITY),
+        );
+        let can_replace = !self.search_query.trim().is_empty() && self.replace_worker.is_none();
+        if ui
+            .add_enabled(can_replace, egui::Button::new("Replace All in Project"))
+            .clicked()
+        {
+            self.replace_confirm_open = true;
+        }
         ui.label(&self.search_status);
         ui.separator();
 
@@ -771,15 +1713,21 @@ impl RustideApp {
                         .and_then(|root| m.path.strip_prefix(root).ok())
                         .map(|p| p.display().to_string())
                         .unwrap_or_else(|| m.path.display().to_string());
-                    format!(
-                        "{}:{}:{}  {}",
-                        display_path,
-                        m.line_index + 1,
-                        m.column_chars + 1,
-                        m.preview.trim()
-                    )
+                    search_result_label(ui, &display_path, m)
                 };
-                if ui.selectable_label(false, label).clicked() {
+                let m = &self.search_results[idx];
+                for line in &m.context_before {
+                    ui.weak(line.trim_end());
+                }
+                let clicked = ui.selectable_label(false, label).clicked();
+                let m = &self.search_results[idx];
+                for line in &m.context_after {
+                    ui.weak(line.trim_end());
+                }
+                if !m.context_before.is_empty() || !m.context_after.is_empty() {
+                    ui.separator();
+                }
+                if clicked {
                     let m = self.search_results[idx].clone();
                     self.open_in_new_tab(m.path, Some((m.line_index, m.column_chars)));
                 }
@@ -787,6 +1735,245 @@ impl RustideApp {
         });
     }
 
+    fn ui_outline_panel(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Outline");
+        let Some(doc) = self.documents.get(self.active_doc) else {
+            ui.label("No file open.");
+            return;
+        };
+        let symbols = doc
+            .syntax
+            .as_ref()
+            .map_or(&[][..], |syntax| syntax.symbol_list(doc.doc.editor.rope()));
+        if symbols.is_empty() {
+            ui.label("No symbols in this file.");
+            return;
+        }
+        ui.separator();
+
+        let mut clicked_line = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for symbol in &symbols {
+                let tag = match symbol.kind {
+                    SymbolKind::Function => "fn",
+                    SymbolKind::Type => "type",
+                    SymbolKind::Constant => "const",
+                    SymbolKind::Variable => "var",
+                };
+                let label = format!("{tag:<5} {}", symbol.name);
+                if ui
+                    .selectable_label(false, egui::RichText::new(label).monospace())
+                    .on_hover_text(format!("Line {}", symbol.line + 1))
+                    .clicked()
+                {
+                    clicked_line = Some(symbol.line);
+                }
+            }
+        });
+        if let Some(line) = clicked_line {
+            self.record_nav_from(self.current_location());
+            let tab_id = self.active_doc;
+            self.active_document_mut().pending_jump = Some((line, 0));
+            self.apply_pending_jump(tab_id);
+            self.focus_tab(DockTab::Editor(self.panes.focused()));
+        }
+    }
+
+    fn ui_problems_panel(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Problems");
+        let build = (self.build_runs, self.build_running);
+        if !self.problem_rows.is_current(&self.documents, build) {
+            self.problem_rows = ProblemRows {
+                documents: self
+                    .documents
+                    .iter()
+                    .map(|doc| {
+                        let generation = doc.syntax.as_ref().map(SyntaxState::generation);
+                        (generation, doc.doc.path.clone())
+                    })
+                    .collect(),
+                build,
+                rows: self.problems(),
+            };
+        }
+        if self.problem_rows.rows.is_empty() {
+            ui.label("No problems in open files or the last build.");
+            return;
+        }
+        ui.separator();
+
+        let mut clicked = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (index, (_, label, _, _)) in self.problem_rows.rows.iter().enumerate() {
+                if ui
+                    .selectable_label(false, egui::RichText::new(label).monospace())
+                    .on_hover_text(label)
+                    .clicked()
+                {
+                    clicked = Some(index);
+                }
+            }
+        });
+        if let Some((target, _, line, column)) =
+            clicked.map(|index| self.problem_rows.rows[index].clone())
+        {
+            self.record_nav_from(self.current_location());
+            match target {
+                ProblemTarget::File(path) => self.open_in_new_tab(path, Some((line, column))),
+                ProblemTarget::Tab(tab_id) => {
+                    self.active_doc = tab_id;
+                    self.documents[tab_id].pending_jump = Some((line, column));
+                    self.apply_pending_jump(tab_id);
+                }
+            }
+            self.focus_tab(DockTab::Editor(self.panes.focused()));
+        }
+    }
+
+    // Rows are `(target, label, line, column)` with a zero-based line and column, built from
+    // each document's last parse and the last finished build.
+    fn problems(&self) -> Vec<(ProblemTarget, String, usize, usize)> {
+        let mut problems = Vec::new();
+        for (tab_id, doc) in self.documents.iter().enumerate() {
+            let Some(syntax) = doc.syntax.as_ref() else {
+                continue;
+            };
+            let file_name = DocumentPath(&doc.doc.path).file_name();
+            for (line, column, message) in
+                syntax_problems(doc.doc.editor.rope(), syntax.error_spans())
+            {
+                let target = match &doc.doc.path {
+                    Some(path) => ProblemTarget::File(path.clone()),
+                    None => ProblemTarget::Tab(tab_id),
+                };
+                let label = format!("{file_name}:{}:{}  {message}", line + 1, column + 1);
+                problems.push((target, label, line, column));
+            }
+        }
+        // Build diagnostics from the last finished "Run Build".
+        if !self.build_running {
+            for diagnostic in &self.build_diagnostics {
+                let label = format!(
+                    "{}:{}:{}  {}",
+                    DocumentPath(&Some(diagnostic.path.clone())).file_name(),
+                    diagnostic.line,
+                    diagnostic.column,
+                    diagnostic.message
+                );
+                problems.push((
+                    ProblemTarget::File(diagnostic.path.clone()),
+                    label,
+                    diagnostic.line - 1,
+                    diagnostic.column - 1,
+                ));
+            }
+        }
+        problems
+    }
+
+    fn run_build(&mut self) {
+        self.focus_tab(DockTab::Output);
+        if self.build_running {
+            return;
+        }
+        if self.config.build.command.trim().is_empty() {
+            self.build_status = "Set a command under [build] in the config first.".to_string();
+            return;
+        }
+        self.build_output.clear();
+        self.build_diagnostics.clear();
+        self.build_runs += 1;
+        self.build_parser = build::DiagnosticParser::default();
+        self.build_dir = build::working_dir(&self.config.build, self.project_root.as_deref());
+        self.build_running = true;
+        self.build_status = format!(
+            "Running {} {}…",
+            self.config.build.command,
+            self.config.build.args.join(" ")
+        );
+        build::start(
+            &self.config.build,
+            self.project_root.as_deref(),
+            self.build_tx.clone(),
+        );
+    }
+
+    fn poll_build(&mut self, ctx: &egui::Context) {
+        while let Ok(msg) = self.build_rx.try_recv() {
+            match msg {
+                build::BuildMessage::Line(line) => {
+                    if let Some(mut diagnostic) = self.build_parser.push(&line) {
+                        // Compilers print paths relative to where they ran.
+                        if let Some(dir) = &self.build_dir {
+                            diagnostic.path = dir.join(&diagnostic.path);
+                        }
+                        self.build_diagnostics.push(diagnostic);
+                    }
+                    if self.build_output.len() >= MAX_BUILD_OUTPUT_LINES {
+                        self.build_output.pop_front();
+                    }
+                    self.build_output.push_back(line);
+                }
+                build::BuildMessage::Finished(code) => {
+                    self.build_running = false;
+                    self.build_status = match code {
+                        Some(code) => format!("Exited with code {code}"),
+                        None => "Terminated by a signal".to_string(),
+                    };
+                    if !self.build_diagnostics.is_empty() {
+                        self.build_status += &format!(
+                            ", {} problem(s) in the Problems tab",
+                            self.build_diagnostics.len()
+                        );
+                    }
+                }
+                build::BuildMessage::Failed(e) => {
+                    self.build_running = false;
+                    self.build_status = e;
+                }
+            }
+        }
+        if self.build_running {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+    }
+
+    fn ui_output_panel(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!self.build_running, egui::Button::new("Run Build"))
+                .on_hover_text("Ctrl+Shift+B")
+                .clicked()
+            {
+                self.run_build();
+            }
+            if ui.button("Clear").clicked() {
+                self.build_output.clear();
+                if !self.build_running {
+                    self.build_status.clear();
+                }
+            }
+            if !self.build_status.is_empty() {
+                ui.label(&self.build_status);
+            }
+        });
+        ui.separator();
+
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        egui::ScrollArea::both()
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show_rows(ui, row_height, self.build_output.len(), |ui, rows| {
+                for line in self.build_output.range(rows) {
+                    ui.add(
+                        egui::Label::new(egui::RichText::new(line).monospace())
+                            .extend()
+                            .selectable(true),
+                    );
+                }
+            });
+    }
+
     fn find_next(&mut self, backwards: bool) {
         let needle_raw = self.find_query.trim();
         if needle_raw.is_empty() {
@@ -798,6 +1985,11 @@ impl RustideApp {
             return;
         }
 
+        if self.find_regex || self.find_whole_word {
+            self.find_next_with_matcher(backwards);
+            return;
+        }
+
         let rope = self.active_document().doc.editor.rope();
         let cursor = self
             .active_document()
@@ -840,6 +2032,7 @@ impl RustideApp {
                     doc.doc.editor.select_range(start..end);
                     doc.scroll_to_char = Some(start);
                     self.find_status = format!("Found at {}:{}", line_index + 1, pos + 1);
+                    self.collect_find_all_positions();
                     return;
                 }
             }
@@ -862,6 +2055,7 @@ impl RustideApp {
                     doc.doc.editor.select_range(start..end);
                     doc.scroll_to_char = Some(start);
                     self.find_status = format!("Found at {}:{}", line_index + 1, pos + 1);
+                    self.collect_find_all_positions();
                     return;
                 }
             }
@@ -869,6 +2063,183 @@ impl RustideApp {
 
         self.find_status = "No matches".to_string();
     }
+
+    fn find_matcher(&self) -> Result<project::LineMatcher, String> {
+        project::LineMatcher::new(
+            self.find_query.trim(),
+            self.find_case_sensitive,
+            self.find_regex,
+        )
+        .map(|matcher| matcher.whole_word(self.find_whole_word))
+        .map_err(|e| format!("Invalid regex: {e}"))
+    }
+
+    fn find_next_with_matcher(&mut self, backwards: bool) {
+        let matcher = match self.find_matcher() {
+            Ok(matcher) => matcher,
+            Err(status) => {
+                self.find_status = status;
+                return;
+            }
+        };
+
+        let rope = self.active_document().doc.editor.rope();
+        let cursor = self
+            .active_document()
+            .doc
+            .editor
+            .selection()
+            .cursor
+            .min(rope.len_chars());
+        let total_lines = rope.len_lines().max(1);
+        let start_line = rope.char_to_line(cursor);
+        let start_col = cursor.saturating_sub(rope.line_to_char(start_line));
+
+        let mut found: Option<(usize, usize, usize)> = None; // (line_index, col, len_chars)
+        for step in 0..total_lines {
+            let line_index = if backwards {
+                (start_line + total_lines - step) % total_lines
+            } else {
+                (start_line + step) % total_lines
+            };
+            let (text, line_len_chars) = find_normalized_line(rope.line(line_index), true);
+            let matches = matches_in_line(&matcher, &text);
+            let on_start_line = step == 0 && line_index == start_line;
+            let hit = if backwards {
+                let limit = if on_start_line {
+                    start_col
+                } else {
+                    line_len_chars + 1
+                };
+                matches.into_iter().rev().find(|(col, _)| *col < limit)
+            } else {
+                let from = if on_start_line { start_col + 1 } else { 0 };
+                matches.into_iter().find(|(col, _)| *col >= from)
+            };
+            if let Some((col, len)) = hit {
+                found = Some((line_index, col, len));
+                break;
+            }
+        }
+
+        let Some((line_index, col, len)) = found else {
+            self.find_status = "No matches".to_string();
+            return;
+        };
+        let start = rope.line_to_char(line_index) + col;
+        self.record_nav_from(self.current_location());
+        let doc = self.active_document_mut();
+        doc.doc.editor.select_range(start..start + len);
+        doc.scroll_to_char = Some(start);
+        self.find_status = format!("Found at {}:{}", line_index + 1, col + 1);
+        self.collect_find_all_positions();
+    }
+
+    // Run after a successful find so the minimap can mark every match. The whole document is
+    // scanned on its own thread; Find Next over the same version and query keeps the markers.
+    fn collect_find_all_positions(&mut self) {
+        let Ok(matcher) = self.find_matcher() else {
+            self.clear_find_all_positions();
+            return;
+        };
+        let editor = &self.active_document().doc.editor;
+        let source = (self.active_doc, editor.version());
+        if self.find_all_source == Some(source) || self.find_all_pending == Some(source) {
+            return;
+        }
+        let rope = editor.rope().clone();
+        self.find_all_requests += 1;
+        self.find_all_pending = Some(source);
+        let request = self.find_all_requests;
+        let tx = self.find_all_tx.clone();
+        std::thread::spawn(move || {
+            let positions = document_match_ranges(&rope, &matcher)
+                .into_iter()
+                .map(|range| range.start)
+                .collect();
+            let _ = tx.send(FindAllMessage {
+                request,
+                source,
+                positions,
+            });
+        });
+    }
+
+    fn poll_find_all(&mut self, ctx: &egui::Context) {
+        while let Ok(msg) = self.find_all_rx.try_recv() {
+            if msg.request == self.find_all_requests {
+                self.find_all_positions = msg.positions;
+                self.find_all_source = Some(msg.source);
+                self.find_all_pending = None;
+            }
+        }
+        if self.find_all_pending.is_some() {
+            ctx.request_repaint_after(Duration::from_millis(16));
+        }
+    }
+
+    fn clear_find_all_positions(&mut self) {
+        self.find_all_positions.clear();
+        self.find_all_source = None;
+        self.find_all_pending = None;
+        self.find_all_requests += 1;
+    }
+
+    // Shared by replace/replace-all: the find bar's matcher, or `None` after reporting why in
+    // `find_status`.
+    fn replace_matcher(&mut self) -> Option<project::LineMatcher> {
+        if self.find_query.trim().is_empty() {
+            self.find_status = "Empty query".to_string();
+            return None;
+        }
+        if self.documents.is_empty() {
+            self.find_status = "No file open".to_string();
+            return None;
+        }
+        match self.find_matcher() {
+            Ok(matcher) => Some(matcher),
+            Err(status) => {
+                self.find_status = status;
+                None
+            }
+        }
+    }
+
+    fn replace_next(&mut self) {
+        let Some(matcher) = self.replace_matcher() else {
+            return;
+        };
+        let replacement = self.replace_query.clone();
+        let mut replaced = None;
+        self.edit_active_document(|editor| {
+            replaced = replace_next_match(editor, &matcher, &replacement);
+        });
+        let Some(start) = replaced else {
+            self.find_status = "No matches".to_string();
+            return;
+        };
+        let rope = self.active_document().doc.editor.rope();
+        let line_index = rope.char_to_line(start);
+        let col = start - rope.line_to_char(line_index);
+        self.find_status = format!("Replaced at {}:{}", line_index + 1, col + 1);
+    }
+
+    fn replace_all(&mut self) -> usize {
+        let Some(matcher) = self.replace_matcher() else {
+            return 0;
+        };
+        let replacement = self.replace_query.clone();
+        let mut count = 0;
+        self.edit_active_document(|editor| {
+            count = replace_all_matches(editor, &matcher, &replacement);
+        });
+        self.find_status = if count == 0 {
+            "No matches".to_string()
+        } else {
+            format!("Replaced {count} occurrence(s)")
+        };
+        count
+    }
 }
 
 impl eframe::App for RustideApp {
@@ -879,6 +2250,9 @@ impl eframe::App for RustideApp {
         self.ensure_ui_applied(ctx);
         self.poll_project();
         self.poll_save_results();
+        self.poll_syntax_results(ctx);
+        self.poll_build(ctx);
+        self.poll_find_all(ctx);
 
         let dropped_files = ctx.input(|i| i.raw.dropped_files.clone());
         if let Some(path) = dropped_files.into_iter().filter_map(|f| f.path).next() {
@@ -889,17 +2263,14 @@ impl eframe::App for RustideApp {
             }
         }
 
-        if ctx.input(|i| i.modifiers.command && i.key_pressed(egui::Key::O)) {
-            if ctx.input(|i| i.modifiers.shift) {
-                self.open_folder_dialog();
-            } else {
-                self.open_file_dialog();
-            }
+        if ctx.input(|i| i.modifiers.command && i.modifiers.shift && i.key_pressed(egui::Key::P)) {
+            self.command_palette.toggle();
         }
-        if ctx.input(|i| i.modifiers.command && i.modifiers.shift && i.key_pressed(egui::Key::F)) {
-            self.focus_tab(DockTab::Search);
-            self.search_request_focus = true;
-            self.config.layout.left_tool = config::LeftTool::Search;
+        // The palette's query box keeps the keys typed into it, e.g. Alt+Left.
+        if !self.command_palette.open {
+            if let Some(action) = ctx.input(command_palette::global_action_for_input) {
+                self.run_action(action);
+            }
         }
 
         egui::TopBottomPanel::top("top")
@@ -922,9 +2293,19 @@ impl eframe::App for RustideApp {
                     if !self.status.is_empty() {
                         ui.label(&self.status);
                     }
+                    if self.binary_file.is_some() && ui.button("Open as Hex").clicked() {
+                        if let Some(path) = self.binary_file.take() {
+                            self.open_hex_view(path);
+                        }
+                    }
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                         ui.add_space(8.0);
                         self.ui_font_controls(ui, ctx);
+                        ui.separator();
+                        self.ui_language_mode(ui);
+                        ui.separator();
+                        self.ui_line_ending(ui);
+                        self.ui_encoding(ui);
                     });
                 });
             });
@@ -938,13 +2319,16 @@ impl eframe::App for RustideApp {
                 ui.vertical_centered(|ui| {
                     ui.add_space(6.0);
                     if ui.button("P").on_hover_text("Project").clicked() {
-                        self.focus_tab(DockTab::Project);
-                        self.config.layout.left_tool = config::LeftTool::Project;
+                        self.run_action(command_palette::Action::ShowProjectPanel);
                     }
                     if ui.button("S").on_hover_text("Search").clicked() {
-                        self.focus_tab(DockTab::Search);
-                        self.search_request_focus = true;
-                        self.config.layout.left_tool = config::LeftTool::Search;
+                        self.run_action(command_palette::Action::ShowSearchPanel);
+                    }
+                    if ui.button("O").on_hover_text("Outline").clicked() {
+                        self.run_action(command_palette::Action::ShowOutlinePanel);
+                    }
+                    if ui.button("!").on_hover_text("Problems").clicked() {
+                        self.run_action(command_palette::Action::ShowProblemsPanel);
                     }
                 });
             });
@@ -958,8 +2342,39 @@ impl eframe::App for RustideApp {
                 .show_inside(ui, &mut RustideTabViewer { app: self, ctx });
             self.dock_state = dock_state;
         });
+        for pane in self.emptied_panes.drain(..) {
+            if let Some(tab) = self.dock_state.find_tab(&DockTab::Editor(pane)) {
+                self.dock_state.remove_tab(tab);
+            }
+        }
+        if let Some(idx) = self.side_doc_request.take() {
+            self.open_to_side(idx);
+        }
+        // Forget closed editor panes, and the documents only they listed; closing the focused
+        // one moves the focus to the first.
+        let dock_state = &self.dock_state;
+        if self.panes.retain_panes(
+            |pane| dock_state.find_tab(&DockTab::Editor(pane)).is_some(),
+            &mut self.active_doc,
+        ) {
+            self.close_unlisted_documents();
+        }
+
+        if let Some(action) = self.command_palette.show(ctx, &self.config.keybindings) {
+            self.run_action(action);
+        }
+        self.ui_goto_line(ctx);
+        self.ui_name_prompt(ctx);
+        self.ui_replace_confirm(ctx);
+        if let Some(prompt) = self.crash_prompt.as_mut() {
+            if !prompt.show(ctx) {
+                self.crash_prompt = None;
+            }
+        }
 
-        self.maybe_autosave_active_doc();
+        if self.config.ui.autosave_enabled {
+            self.maybe_autosave_active_doc();
+        }
         self.persist_dock_layout();
     }
 }
@@ -969,369 +2384,481 @@ struct RustideTabViewer<'a> {
     ctx: &'a egui::Context,
 }
 
-impl TabViewer for RustideTabViewer<'_> {
-    type Tab = DockTab;
-
-    fn title(&mut self, tab: &mut Self::Tab) -> egui::WidgetText {
-        tab.title().into()
-    }
+impl RustideTabViewer<'_> {
+    // One editor pane: the tab strip, the header and the editor of the document `pane` shows.
+    // Only the focused pane shows the find bar. Actions that work on the focused pane, like
+    // closing tabs or going back, focus `pane` first.
+    fn ui_editor(&mut self, ui: &mut egui::Ui, pane: usize) {
+        let focused = pane == self.app.panes.focused();
+        if focused && self.app.find_open && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.app.find_open = false;
+            self.app.replace_open = false;
+            self.app.find_request_focus = false;
+            self.app.clear_find_all_positions();
+        }
 
-    fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Self::Tab) {
-        match tab {
-            DockTab::Editor => {
-                if ui.input(|i| {
-                    i.modifiers.command && !i.modifiers.shift && i.key_pressed(egui::Key::F)
-                }) {
-                    self.app.find_open = true;
-                    self.app.find_request_focus = true;
-                }
-                if self.app.find_open && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
-                    self.app.find_open = false;
+        if focused && self.app.find_open {
+            ui.horizontal(|ui| {
+                ui.label("Find");
+                let resp = ui.text_edit_singleline(&mut self.app.find_query);
+                if resp.changed() {
+                    self.app.clear_find_all_positions();
+                }
+                if self.app.find_request_focus {
+                    resp.request_focus();
                     self.app.find_request_focus = false;
                 }
-
-                if self.app.find_open {
-                    ui.horizontal(|ui| {
-                        ui.label("Find");
-                        let resp = ui.text_edit_singleline(&mut self.app.find_query);
-                        if self.app.find_request_focus {
-                            resp.request_focus();
-                            self.app.find_request_focus = false;
-                        }
-                        if resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-                            let backwards = ui.input(|i| i.modifiers.shift);
-                            self.app.find_next(backwards);
-                        }
-                        if ui.button("Prev").clicked() {
-                            self.app.find_next(true);
-                        }
-                        if ui.button("Next").clicked() {
-                            self.app.find_next(false);
-                        }
-                        ui.checkbox(&mut self.app.find_case_sensitive, "Aa");
-                        if ui.button("X").clicked() {
-                            self.app.find_open = false;
-                        }
-                        ui.label(&self.app.find_status);
-                    });
-                    ui.separator();
+                if resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    let backwards = ui.input(|i| i.modifiers.shift);
+                    self.app.find_next(backwards);
                 }
-
-                if self.app.documents.is_empty() {
-                    ui.vertical_centered(|ui| {
-                        ui.add_space(20.0);
-                        ui.label("No file open.");
-                        if ui.button("Open File…").clicked() {
-                            self.app.open_file_dialog();
-                        }
-                    });
-                    return;
+                if ui.button("Prev").clicked() {
+                    self.app.find_next(true);
                 }
-
-                let path_label = self
-                    .app
-                    .active_document()
-                    .doc
-                    .path
-                    .as_ref()
-                    .map(|p| p.display().to_string())
-                    .unwrap_or_else(|| "<no file>".to_string());
-
-                // Tab strip (VSCode-like: one window, multiple open files)
-                let mut close_tab: Option<usize> = None;
-                let mut action_pin: Option<(usize, bool)> = None;
-                let mut action_close_others: Option<usize> = None;
-                let mut action_close_right: Option<usize> = None;
-                let mut action_close_all = false;
-                let mut action_close_all_but_pinned = false;
+                if ui.button("Next").clicked() {
+                    self.app.find_next(false);
+                }
+                let options_changed = [
+                    ui.checkbox(&mut self.app.find_case_sensitive, "Aa")
+                        .on_hover_text("Match case"),
+                    ui.checkbox(&mut self.app.find_regex, ".*")
+                        .on_hover_text("Use regular expression"),
+                    ui.checkbox(&mut self.app.find_whole_word, "ab")
+                        .on_hover_text("Match whole word"),
+                ]
+                .iter()
+                .any(egui::Response::changed);
+                if options_changed {
+                    self.app.clear_find_all_positions();
+                }
+                if ui.button("X").clicked() {
+                    self.app.find_open = false;
+                    self.app.clear_find_all_positions();
+                }
+                ui.label(&self.app.find_status);
+            });
+            if self.app.replace_open {
                 ui.horizontal(|ui| {
-                    ui.spacing_mut().item_spacing.x = 6.0;
-                    for (idx, doc) in self.app.documents.iter().enumerate() {
-                        let title = doc
-                            .doc
-                            .path
-                            .as_ref()
-                            .and_then(|p| p.file_name())
-                            .map(|s| s.to_string_lossy().to_string())
-                            .unwrap_or_else(|| "Untitled".to_string());
-                        let selected = idx == self.app.active_doc;
-                        let label = if doc.pinned {
-                            format!("{title} 📌")
-                        } else {
-                            title
-                        };
-                        let resp = ui.selectable_label(selected, label);
-                        if resp.clicked() {
-                            self.app.active_doc = idx;
-                        }
-                        resp.context_menu(|ui| {
-                            let mut pinned = doc.pinned;
-                            if ui.checkbox(&mut pinned, "Pin").changed() {
-                                action_pin = Some((idx, pinned));
-                                ui.close();
-                            }
-                            ui.separator();
-                            if ui.button("Close Others").clicked() {
-                                action_close_others = Some(idx);
-                                ui.close();
-                            }
-                            if ui.button("Close Tabs to the Right").clicked() {
-                                action_close_right = Some(idx);
-                                ui.close();
-                            }
-                            ui.separator();
-                            if ui.button("Close All").clicked() {
-                                action_close_all = true;
-                                ui.close();
-                            }
-                            if ui.button("Close All But Pinned").clicked() {
-                                action_close_all_but_pinned = true;
-                                ui.close();
-                            }
-                        });
-
-                        let can_close = !doc.pinned;
-                        if ui
-                            .add_enabled(can_close, egui::Button::new("x"))
-                            .on_hover_text(if can_close {
-                                "Close"
-                            } else {
-                                "Pinned tabs can't be closed"
-                            })
-                            .clicked()
-                        {
-                            close_tab = Some(idx);
-                        }
+                    ui.label("Replace");
+                    let resp = ui.text_edit_singleline(&mut self.app.replace_query);
+                    if resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        self.app.replace_next();
+                    }
+                    if ui.button("Replace").clicked() {
+                        self.app.replace_next();
+                    }
+                    if ui.button("Replace All").clicked() {
+                        self.app.replace_all();
                     }
                 });
-                ui.separator();
-
-                let header_h = ui
-                    .spacing()
-                    .interact_size
-                    .y
-                    .max(ui.text_style_height(&egui::TextStyle::Body));
-                let (header_rect, _) = ui.allocate_exact_size(
-                    egui::vec2(ui.available_width(), header_h),
-                    egui::Sense::hover(),
-                );
-                let mut go_back = false;
-                let mut go_forward = false;
-                let mut header_ui = ui.new_child(
-                    egui::UiBuilder::new()
-                        .max_rect(header_rect)
-                        .layout(egui::Layout::left_to_right(egui::Align::Center)),
-                );
-                header_ui.scope(|ui| {
-                    let font_id = egui::TextStyle::Body.resolve(ui.style());
-                    ui.painter().text(
-                        header_rect.center(),
-                        egui::Align2::CENTER_CENTER,
-                        path_label,
-                        font_id,
-                        ui.visuals().text_color(),
-                    );
+            }
+            ui.separator();
+        }
 
-                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        go_forward |= ui
-                            .add_enabled(
-                                !self.app.active_document().nav_forward.is_empty(),
-                                egui::Button::new("->"),
-                            )
-                            .on_hover_text("Forward")
-                            .clicked();
-                        go_back |= ui
-                            .add_enabled(
-                                !self.app.active_document().nav_back.is_empty(),
-                                egui::Button::new("<-"),
-                            )
-                            .on_hover_text("Back")
-                            .clicked();
-                    });
-                });
-                if go_back {
-                    self.app.navigate_back();
+        if self.app.documents.is_empty() {
+            ui.vertical_centered(|ui| {
+                ui.add_space(20.0);
+                ui.label("No file open.");
+                if ui.button("Open File…").clicked() {
+                    self.app.open_file_dialog();
+                }
+            });
+            return;
+        }
+
+        let last = self.app.documents.len() - 1;
+        let doc_idx = self.app.panes.shown(pane, self.app.active_doc.min(last));
+        let path_label = DocumentPath(&self.app.documents[doc_idx].doc.path).to_string();
+
+        // Tab strip (VSCode-like: one window, multiple open files).
+        let mut show_tab: Option<usize> = None;
+        let mut close_tab: Option<usize> = None;
+        let mut action_pin: Option<(usize, bool)> = None;
+        let mut action_close_others: Option<usize> = None;
+        let mut action_close_right: Option<usize> = None;
+        let mut action_close_all = false;
+        let mut action_close_all_but_pinned = false;
+        let mut action_open_to_side: Option<usize> = None;
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 6.0;
+            for idx in self.app.panes.tabs(pane).to_vec() {
+                let doc = &self.app.documents[idx];
+                let title = DocumentPath(&doc.doc.path).file_name().into_owned();
+                let selected = idx == doc_idx;
+                let label = if doc.pinned {
+                    format!("{title} 📌")
+                } else {
+                    title
+                };
+                let resp = ui.selectable_label(selected, label);
+                if resp.clicked() {
+                    show_tab = Some(idx);
                 }
-                if go_forward {
-                    self.app.navigate_forward();
+                resp.context_menu(|ui| {
+                    let mut pinned = doc.pinned;
+                    if ui.checkbox(&mut pinned, "Pin").changed() {
+                        action_pin = Some((idx, pinned));
+                        ui.close();
+                    }
+                    if ui.button("Open to the Side").clicked() {
+                        action_open_to_side = Some(idx);
+                        ui.close();
+                    }
+                    ui.separator();
+                    if ui.button("Close Others").clicked() {
+                        action_close_others = Some(idx);
+                        ui.close();
+                    }
+                    if ui.button("Close Tabs to the Right").clicked() {
+                        action_close_right = Some(idx);
+                        ui.close();
+                    }
+                    ui.separator();
+                    if ui.button("Close All").clicked() {
+                        action_close_all = true;
+                        ui.close();
+                    }
+                    if ui.button("Close All But Pinned").clicked() {
+                        action_close_all_but_pinned = true;
+                        ui.close();
+                    }
+                });
+
+                let can_close = !doc.pinned;
+                if ui
+                    .add_enabled(can_close, egui::Button::new("x"))
+                    .on_hover_text(if can_close {
+                        "Close"
+                    } else {
+                        "Pinned tabs can't be closed"
+                    })
+                    .clicked()
+                {
+                    close_tab = Some(idx);
                 }
-                ui.separator();
+            }
+        });
+        ui.separator();
+        let tab_action = show_tab.is_some()
+            || close_tab.is_some()
+            || action_close_others.is_some()
+            || action_close_right.is_some()
+            || action_close_all
+            || action_close_all_but_pinned;
+        if tab_action && !focused {
+            self.app.focus_pane(pane);
+        }
+        if let Some(idx) = show_tab {
+            self.app.active_doc = idx;
+        }
+
+        let header_h = ui
+            .spacing()
+            .interact_size
+            .y
+            .max(ui.text_style_height(&egui::TextStyle::Body));
+        let (header_rect, _) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), header_h),
+            egui::Sense::hover(),
+        );
+        let mut go_back = false;
+        let mut go_forward = false;
+        let mut header_ui = ui.new_child(
+            egui::UiBuilder::new()
+                .max_rect(header_rect)
+                .layout(egui::Layout::left_to_right(egui::Align::Center)),
+        );
+        header_ui.scope(|ui| {
+            let font_id = egui::TextStyle::Body.resolve(ui.style());
+            ui.painter().text(
+                header_rect.center(),
+                egui::Align2::CENTER_CENTER,
+                path_label,
+                font_id,
+                ui.visuals().text_color(),
+            );
 
-                if let Some(active) = self.app.documents.get_mut(self.app.active_doc) {
-                    let cursor_before = active.doc.editor.selection().cursor;
-                    let mut editor_metrics: Option<editor_view::EditorScrollMetrics> = None;
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                go_forward |= ui
+                    .add_enabled(
+                        !self.app.documents[doc_idx].nav_forward.is_empty(),
+                        egui::Button::new("->"),
+                    )
+                    .on_hover_text("Forward")
+                    .clicked();
+                go_back |= ui
+                    .add_enabled(
+                        !self.app.documents[doc_idx].nav_back.is_empty(),
+                        egui::Button::new("<-"),
+                    )
+                    .on_hover_text("Back")
+                    .clicked();
+            });
+        });
+        if (go_back || go_forward) && self.app.panes.focused() != pane {
+            self.app.focus_pane(pane);
+        }
+        if go_back {
+            self.app.navigate_back();
+        }
+        if go_forward {
+            self.app.navigate_forward();
+        }
+        ui.separator();
+
+        if self.app.documents[doc_idx].file_changed_on_disk {
+            let mut reload = false;
+            let mut keep_mine = false;
+            ui.horizontal(|ui| {
+                ui.colored_label(ui.visuals().warn_fg_color, "File changed on disk.");
+                reload = ui.button("Reload").clicked();
+                keep_mine = ui
+                    .button("Keep mine")
+                    .on_hover_text("Keep your edits; the next save overwrites the file")
+                    .clicked();
+            });
+            ui.separator();
+            if reload {
+                self.app.queue_reload(doc_idx);
+            } else if keep_mine {
+                self.app.documents[doc_idx].file_changed_on_disk = false;
+            }
+        }
+
+        let mut moved_from: Option<usize> = None;
+        if let Some(active) = self.app.documents.get_mut(doc_idx) {
+            let cursor_before = active.doc.editor.selection().cursor;
+            let mut editor_metrics: Option<editor_view::EditorScrollMetrics> = None;
+
+            {
+                let theme = &self.app.theme;
+                let ui_cfg = &mut self.app.config.ui;
+                let font_size = self.app.configured_font_size;
+                let keys = &self.app.config.keybindings;
+                let scroll_to = &mut active.scroll_to_char;
+                let folded = &mut active.folded;
+                let caret_blink = &mut active.caret_blink;
+                let indent_unit = active.settings.indent_unit();
+                // Markers only apply to the document and version they were collected
+                // from; an edit hides them until the next find.
+                let search_matches: &[usize] = if self.app.find_open
+                    && self.app.find_all_source == Some((doc_idx, active.doc.editor.version()))
+                {
+                    &self.app.find_all_positions
+                } else {
+                    &[]
+                };
+
+                if active.mode == DocumentMode::Hex {
+                    hex_view::show(ui, active.raw_bytes.as_deref().unwrap_or_default(), theme);
+                } else if let Some(md) = active.markdown.as_mut() {
+                    let current_version = active.doc.editor.version();
+                    if md.cached_version != current_version {
+                        md.cached_text = active.doc.editor.rope().to_string();
+                        md.cached_version = current_version;
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut md.preview_enabled, "Preview");
+                    });
+                    ui.separator();
 
-                    {
-                        let theme = &self.app.theme;
-                        let ui_cfg = &mut self.app.config.ui;
-                        let scroll_to = &mut active.scroll_to_char;
-
-                        if let Some(md) = active.markdown.as_mut() {
-                            let current_version = active.doc.editor.version();
-                            if md.cached_version != current_version {
-                                md.cached_text = active.doc.editor.rope().to_string();
-                                md.cached_version = current_version;
-                            }
-
-                            ui.horizontal(|ui| {
-                                ui.checkbox(&mut md.preview_enabled, "Preview");
-                            });
-                            ui.separator();
-
-                            if md.preview_enabled {
-                                ui.columns(2, |cols| {
-                                    editor_metrics = Some(editor_view::show_editor(
-                                        &mut cols[0],
-                                        self.ctx,
-                                        &mut active.doc.editor,
-                                        active.doc.max_line_chars,
-                                        &mut active.syntax,
-                                        theme,
-                                        ui_cfg,
-                                        scroll_to,
-                                    ));
-                                    let editor_metrics = editor_metrics.unwrap();
-
-                                    cols[1].heading("Markdown");
-                                    cols[1].separator();
-
-                                    let src_max_scroll = (editor_metrics.content_h
-                                        - editor_metrics.viewport_h)
-                                        .max(1.0);
-                                    let t =
-                                        (editor_metrics.offset_y / src_max_scroll).clamp(0.0, 1.0);
-                                    let preview_max_scroll =
-                                        (md.preview_content_h - md.preview_viewport_h).max(0.0);
-                                    let desired_preview_offset = t * preview_max_scroll;
-
-                                    let preview_output = egui::ScrollArea::vertical()
-                                        .auto_shrink([false, false])
-                                        .id_salt("rustide_md_preview_scroll")
-                                        .vertical_scroll_offset(desired_preview_offset)
-                                        .show(&mut cols[1], |ui| {
-                                            CommonMarkViewer::new().show(
-                                                ui,
-                                                &mut md.cache,
-                                                &md.cached_text,
-                                            );
-                                        });
-                                    md.preview_content_h = preview_output.content_size.y;
-                                    md.preview_viewport_h = preview_output.inner_rect.height();
-                                });
-                            } else {
-                                editor_metrics = Some(editor_view::show_editor(
-                                    ui,
-                                    self.ctx,
-                                    &mut active.doc.editor,
-                                    active.doc.max_line_chars,
-                                    &mut active.syntax,
-                                    theme,
-                                    ui_cfg,
-                                    scroll_to,
-                                ));
-                            }
-                        } else {
+                    if md.preview_enabled {
+                        ui.columns(2, |cols| {
                             editor_metrics = Some(editor_view::show_editor(
-                                ui,
+                                &mut cols[0],
                                 self.ctx,
                                 &mut active.doc.editor,
                                 active.doc.max_line_chars,
                                 &mut active.syntax,
+                                folded,
+                                &indent_unit,
+                                caret_blink,
                                 theme,
                                 ui_cfg,
+                                font_size,
                                 scroll_to,
+                                search_matches,
+                                keys,
                             ));
-                        }
-                    }
-
-                    if let Some(metrics) = editor_metrics {
-                        if metrics.cursor_moved_by_pointer {
-                            let cursor_after = active.doc.editor.selection().cursor;
-                            if cursor_after != cursor_before {
-                                self.app.record_nav_from(NavLocation {
-                                    cursor: cursor_before,
+                            let editor_metrics = editor_metrics.unwrap();
+
+                            cols[1].heading("Markdown");
+                            cols[1].separator();
+
+                            let src_max_scroll =
+                                (editor_metrics.content_h - editor_metrics.viewport_h).max(1.0);
+                            let t = (editor_metrics.offset_y / src_max_scroll).clamp(0.0, 1.0);
+                            let preview_max_scroll =
+                                (md.preview_content_h - md.preview_viewport_h).max(0.0);
+                            let desired_preview_offset = t * preview_max_scroll;
+
+                            let preview_output = egui::ScrollArea::vertical()
+                                .auto_shrink([false, false])
+                                .id_salt("rustide_md_preview_scroll")
+                                .vertical_scroll_offset(desired_preview_offset)
+                                .show(&mut cols[1], |ui| {
+                                    CommonMarkViewer::new().show(
+                                        ui,
+                                        &mut md.cache,
+                                        &md.cached_text,
+                                    );
                                 });
-                            }
-                        }
-                    }
-                }
-
-                if let Some((idx, pinned)) = action_pin {
-                    if let Some(doc) = self.app.documents.get_mut(idx) {
-                        doc.pinned = pinned;
-                    }
-                }
-                if action_close_all {
-                    self.app.documents.clear();
-                    self.app.active_doc = 0;
-                    return;
-                }
-                if action_close_all_but_pinned {
-                    self.app.documents.retain(|d| d.pinned);
-                    self.app.active_doc = self.app.active_doc.min(self.app.documents.len().saturating_sub(1));
-                    if self.app.documents.is_empty() {
-                        return;
-                    }
-                }
-                if let Some(idx) = action_close_others {
-                    if self.app.documents.len() > 1 {
-                        let keep_path = self.app.documents[idx].doc.path.clone();
-                        self.app.documents.retain(|d| {
-                            d.pinned
-                                || d.doc.path == keep_path
-                                || (d.doc.path.is_none() && keep_path.is_none())
+                            md.preview_content_h = preview_output.content_size.y;
+                            md.preview_viewport_h = preview_output.inner_rect.height();
                         });
-                        self.app.active_doc = self
-                            .app
-                            .documents
-                            .iter()
-                            .position(|d| d.doc.path == keep_path)
-                            .unwrap_or(0);
+                    } else {
+                        editor_metrics = Some(editor_view::show_editor(
+                            ui,
+                            self.ctx,
+                            &mut active.doc.editor,
+                            active.doc.max_line_chars,
+                            &mut active.syntax,
+                            folded,
+                            &indent_unit,
+                            caret_blink,
+                            theme,
+                            ui_cfg,
+                            font_size,
+                            scroll_to,
+                            search_matches,
+                            keys,
+                        ));
                     }
+                } else {
+                    editor_metrics = Some(editor_view::show_editor(
+                        ui,
+                        self.ctx,
+                        &mut active.doc.editor,
+                        active.doc.max_line_chars,
+                        &mut active.syntax,
+                        folded,
+                        &indent_unit,
+                        caret_blink,
+                        theme,
+                        ui_cfg,
+                        font_size,
+                        scroll_to,
+                        search_matches,
+                        keys,
+                    ));
                 }
-                if let Some(idx) = action_close_right {
-                    if self.app.documents.len() > 1 && idx + 1 < self.app.documents.len() {
-                        // Remove from the right side, but keep pinned tabs.
-                        let mut i = self.app.documents.len();
-                        while i > idx + 1 {
-                            i -= 1;
-                            if !self.app.documents[i].pinned {
-                                self.app.documents.remove(i);
-                            }
-                        }
-                        self.app.active_doc = self.app.active_doc.min(self.app.documents.len() - 1);
+            }
+
+            if let Some(metrics) = editor_metrics {
+                if metrics.cursor_moved_by_pointer {
+                    let cursor_after = active.doc.editor.selection().cursor;
+                    if cursor_after != cursor_before {
+                        moved_from = Some(cursor_before);
                     }
                 }
-                if let Some(idx) = close_tab {
-                    if self.app.documents.len() > 1 {
-                        self.app.documents.remove(idx);
-                        self.app.active_doc = self.app.active_doc.min(self.app.documents.len() - 1);
-                    } else {
-                        // Keep at least one tab.
-                        self.app.documents[0] = OpenDocument::empty();
-                        self.app.active_doc = 0;
-                    }
+            }
+        }
+        if let Some(cursor) = moved_from {
+            if self.app.panes.focused() != pane {
+                self.app.focus_pane(pane);
+            }
+            self.app.record_nav_from(NavLocation { cursor });
+        }
+
+        if let Some((idx, pinned)) = action_pin {
+            if let Some(doc) = self.app.documents.get_mut(idx) {
+                doc.pinned = pinned;
+            }
+        }
+        if let Some(idx) = action_open_to_side {
+            self.app.side_doc_request = Some(idx);
+        }
+        if action_close_all {
+            self.app.close_tabs(|_, _| true);
+            return;
+        }
+        if action_close_all_but_pinned {
+            self.app.close_tabs(|_, d| !d.pinned);
+            if !self.app.panes.contains(pane) {
+                return;
+            }
+        }
+        if let Some(idx) = action_close_others {
+            self.app.active_doc = idx;
+            self.app.close_tabs(|i, d| i != idx && !d.pinned);
+        }
+        if let Some(idx) = action_close_right {
+            // Keep pinned tabs.
+            let tabs = self.app.panes.tabs(pane);
+            let right = tabs[tabs
+                .iter()
+                .position(|&i| i == idx)
+                .map_or(tabs.len(), |pos| pos + 1)..]
+                .to_vec();
+            self.app.close_tabs(|i, d| right.contains(&i) && !d.pinned);
+        }
+        if let Some(idx) = close_tab {
+            if self.app.panes.tabs(pane).len() > 1 || self.app.panes.pane_count() > 1 {
+                self.app.close_tabs(|i, _| i == idx);
+            } else {
+                // Keep at least one tab.
+                self.app.documents[idx] = OpenDocument::empty();
+            }
+        }
+    }
+}
+
+impl TabViewer for RustideTabViewer<'_> {
+    type Tab = DockTab;
+
+    fn title(&mut self, tab: &mut Self::Tab) -> egui::WidgetText {
+        tab.title().into()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Self::Tab) {
+        match tab {
+            DockTab::Editor(pane) => {
+                let pane = *pane;
+                if ui.input(|i| i.pointer.any_pressed()) && ui.rect_contains_pointer(ui.max_rect())
+                {
+                    self.app.focus_pane(pane);
                 }
+                self.ui_editor(ui, pane);
             }
             DockTab::Project => self.app.ui_project_panel(ui),
             DockTab::Search => self.app.ui_search_panel(ui),
+            DockTab::Outline => self.app.ui_outline_panel(ui),
+            DockTab::Problems => self.app.ui_problems_panel(ui),
+            DockTab::Output => self.app.ui_output_panel(ui),
         }
     }
 
-    fn is_closeable(&self, _tab: &Self::Tab) -> bool {
-        false
+    // Titles repeat across editor panes, so they can't be the ids.
+    fn id(&mut self, tab: &mut Self::Tab) -> egui::Id {
+        egui::Id::new(*tab)
+    }
+
+    fn is_closeable(&self, tab: &Self::Tab) -> bool {
+        // The last editor pane stays.
+        matches!(tab, DockTab::Editor(_)) && self.app.panes.pane_count() > 1
     }
 }
 
 fn default_dock_state() -> DockState<DockTab> {
-    let mut dock_state = DockState::new(vec![DockTab::Editor]);
+    let mut dock_state = DockState::new(vec![DockTab::Editor(0)]);
     let root = egui_dock::NodeIndex::root();
-    let [left, _] = dock_state.main_surface_mut().split_left(
+    let [editor, _] = dock_state.main_surface_mut().split_left(
         root,
         0.25,
-        vec![DockTab::Project, DockTab::Search],
+        vec![
+            DockTab::Project,
+            DockTab::Search,
+            DockTab::Outline,
+            DockTab::Problems,
+        ],
     );
-    dock_state.main_surface_mut().set_focused_node(left);
+    // Splitting moves the editor tabs into a new child node, returned first.
+    let [editor, _] =
+        dock_state
+            .main_surface_mut()
+            .split_below(editor, 0.75, vec![DockTab::Output]);
+    dock_state.main_surface_mut().set_focused_node(editor);
     dock_state
 }
 
@@ -1352,80 +2879,444 @@ impl RustideApp {
         }
     }
 
+    fn run_action(&mut self, action: command_palette::Action) {
+        use command_palette::Action;
+        match action {
+            Action::OpenFile => self.open_file_dialog(),
+            Action::OpenFolder => self.open_folder_dialog(),
+            Action::SaveAll => self.save_all(),
+            Action::GoToLine => {
+                if !self.documents.is_empty() {
+                    self.goto_line_open = true;
+                    self.goto_line_request_focus = true;
+                    self.goto_line_input.clear();
+                }
+            }
+            // Ctrl+F focuses the find bar even when it's already open.
+            Action::Find => {
+                self.find_open = true;
+                self.find_request_focus = true;
+                self.focus_tab(DockTab::Editor(self.panes.focused()));
+            }
+            Action::ToggleFind => {
+                self.find_open = !self.find_open;
+                self.replace_open = false;
+                self.find_request_focus = self.find_open;
+                if self.find_open {
+                    self.focus_tab(DockTab::Editor(self.panes.focused()));
+                } else {
+                    self.clear_find_all_positions();
+                }
+            }
+            Action::ToggleReplace => {
+                // Ctrl+H opens find + replace together; pressing it again hides just the
+                // replace row.
+                let open = !(self.find_open && self.replace_open);
+                self.find_open = open || self.find_open;
+                self.replace_open = open;
+                self.find_request_focus = open;
+                if open {
+                    self.focus_tab(DockTab::Editor(self.panes.focused()));
+                }
+            }
+            Action::ShowSearchPanel => {
+                self.focus_tab(DockTab::Search);
+                self.search_request_focus = true;
+                self.config.layout.left_tool = config::LeftTool::Search;
+            }
+            Action::ShowProjectPanel => {
+                self.focus_tab(DockTab::Project);
+                self.config.layout.left_tool = config::LeftTool::Project;
+            }
+            Action::ShowOutlinePanel => {
+                self.focus_tab(DockTab::Outline);
+                self.config.layout.left_tool = config::LeftTool::Outline;
+            }
+            Action::ShowProblemsPanel => {
+                self.focus_tab(DockTab::Problems);
+                self.config.layout.left_tool = config::LeftTool::Problems;
+            }
+            Action::RunBuild => self.run_build(),
+            Action::NavigateBack => self.navigate_back(),
+            Action::NavigateForward => self.navigate_forward(),
+            Action::Undo => self.edit_active_document(|editor| {
+                editor.undo();
+            }),
+            Action::Redo => self.edit_active_document(|editor| {
+                editor.redo();
+            }),
+            Action::SelectAll => self.edit_active_document(|editor| editor.select_all()),
+            Action::DuplicateLine => self.edit_active_document(|editor| editor.duplicate_line()),
+        }
+    }
+
+    fn edit_active_document(&mut self, edit: impl FnOnce(&mut rustide_editor::Editor)) {
+        if self.documents.is_empty() {
+            return;
+        }
+        let doc = self.active_document_mut();
+        edit(&mut doc.doc.editor);
+        editor_view::drain_syntax_edits(&mut doc.doc.editor, &mut doc.syntax, &mut doc.folded);
+        doc.scroll_to_char = Some(doc.doc.editor.selection().cursor);
+    }
+
+    fn ui_goto_line(&mut self, ctx: &egui::Context) {
+        if !self.goto_line_open {
+            return;
+        }
+        if self.documents.is_empty() || ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.goto_line_open = false;
+            return;
+        }
+
+        let total_lines = self.active_document().doc.editor.rope().len_lines();
+        let mut submit = false;
+        egui::Window::new("Go to Line")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 48.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Line (1-{total_lines}), optionally :column"));
+                    let resp = ui.text_edit_singleline(&mut self.goto_line_input);
+                    if self.goto_line_request_focus {
+                        resp.request_focus();
+                        self.goto_line_request_focus = false;
+                    }
+                    submit = resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                });
+            });
+
+        if !submit {
+            return;
+        }
+        self.goto_line_open = false;
+        let Some(jump) = parse_line_col(&self.goto_line_input) else {
+            self.status = format!("Invalid line: {}", self.goto_line_input.trim());
+            return;
+        };
+        self.record_nav_from(self.current_location());
+        let tab_id = self.active_doc;
+        self.active_document_mut().pending_jump = Some(jump);
+        self.apply_pending_jump(tab_id);
+    }
+
+    // Tabs missing from a layout saved by an older version are added to the focused leaf.
     fn focus_tab(&mut self, tab: DockTab) {
         if let Some((surface, node, index)) = self.dock_state.find_tab(&tab) {
             self.dock_state.set_active_tab((surface, node, index));
             self.dock_state
                 .set_focused_node_and_surface((surface, node));
+        } else {
+            self.dock_state.push_to_focused_leaf(tab);
         }
     }
 }
 
-fn find_normalized_line(line: ropey::RopeSlice<'_>, case_sensitive: bool) -> (String, usize) {
-    let mut text = line.to_string();
-    if text.ends_with('\n') {
-        text.pop();
-        if text.ends_with('\r') {
-            text.pop();
-        }
+// Where a Problems row jumps to: a file, or an untitled tab, which has no path to open.
+#[derive(Clone)]
+enum ProblemTarget {
+    File(PathBuf),
+    Tab(usize),
+}
+
+// The Problems rows and what they were built from: each document's parse generation and path
+// (labels use the file name, and rows jump by tab index), and the build run.
+#[derive(Default)]
+struct ProblemRows {
+    documents: Vec<(Option<u64>, Option<PathBuf>)>,
+    build: (u64, bool),
+    rows: Vec<(ProblemTarget, String, usize, usize)>,
+}
+
+impl ProblemRows {
+    fn is_current(&self, documents: &[OpenDocument], build: (u64, bool)) -> bool {
+        self.build == build
+            && self.documents.len() == documents.len()
+            && self
+                .documents
+                .iter()
+                .zip(documents)
+                .all(|((generation, path), doc)| {
+                    *generation == doc.syntax.as_ref().map(SyntaxState::generation)
+                        && *path == doc.doc.path
+                })
     }
-    let len_chars = text.chars().count();
-    let hay = if case_sensitive {
-        text
-    } else {
-        text.to_ascii_lowercase()
+}
+
+// Zero-based `(line, column in chars)` and a short description for each syntax error span.
+fn syntax_problems(
+    rope: &ropey::Rope,
+    spans: &[rustide_syntax::HighlightSpan],
+) -> Vec<(usize, usize, &'static str)> {
+    spans
+        .iter()
+        .map(|span| {
+            let char_idx = rope.byte_to_char(span.byte_range.start.min(rope.len_bytes()));
+            let line = rope.char_to_line(char_idx);
+            let message = if span.byte_range.is_empty() {
+                "Missing token"
+            } else {
+                "Syntax error"
+            };
+            (line, char_idx - rope.line_to_char(line), message)
+        })
+        .collect()
+}
+
+// `path:line:col  preview` with the matched range highlighted in the preview.
+fn search_result_label(
+    ui: &egui::Ui,
+    display_path: &str,
+    m: &project::SearchMatch,
+) -> egui::text::LayoutJob {
+    let font_id = egui::TextStyle::Body.resolve(ui.style());
+    let plain = egui::TextFormat::simple(font_id.clone(), ui.visuals().text_color());
+    let highlight = egui::TextFormat {
+        background: ui.visuals().selection.bg_fill,
+        ..egui::TextFormat::simple(font_id, ui.visuals().strong_text_color())
+    };
+
+    let preview = &m.preview;
+    let byte_at = |col: usize| char_to_byte_index(preview, col);
+    let indent = preview.len() - preview.trim_start().len();
+    let start = byte_at(m.column_chars).max(indent);
+    let end = byte_at(m.match_end_column_chars).max(start);
+
+    let mut job = egui::text::LayoutJob::default();
+    job.append(
+        &format!(
+            "{}:{}:{}  ",
+            display_path,
+            m.line_index + 1,
+            m.column_chars + 1
+        ),
+        0.0,
+        plain.clone(),
+    );
+    job.append(&preview[indent..start], 0.0, plain.clone());
+    job.append(&preview[start..end], 0.0, highlight);
+    job.append(preview[end..].trim_end(), 0.0, plain);
+    job
+}
+
+// Parses `line` or `line:column` (1-based) into a zero-based `(line_index, column_chars)` jump.
+fn parse_line_col(input: &str) -> Option<(usize, usize)> {
+    let mut parts = input.trim().splitn(2, ':');
+    let line = parts.next()?.trim().parse::<usize>().ok()?;
+    let column = match parts.next() {
+        Some(col) => col.trim().parse::<usize>().ok()?,
+        None => 1,
     };
-    (hay, len_chars)
+    Some((line.saturating_sub(1), column.saturating_sub(1)))
 }
 
-fn find_first_from(hay: &str, needle: &str, from_char: usize) -> Option<usize> {
-    if needle.is_empty() {
-        return None;
+// Non-empty matches in `text` as `(start_char, len_chars)` pairs.
+fn matches_in_line(matcher: &project::LineMatcher, text: &str) -> Vec<(usize, usize)> {
+    matcher
+        .find_all(text)
+        .into_iter()
+        .map(|range| {
+            let start = text[..range.start].chars().count();
+            (start, text[range].chars().count())
+        })
+        .collect()
+}
+
+// Char ranges of every match in `rope`; matches never span lines.
+fn document_match_ranges(rope: &ropey::Rope, matcher: &project::LineMatcher) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    for line_index in 0..rope.len_lines() {
+        let (text, _) = find_normalized_line(rope.line(line_index), true);
+        let line_start = rope.line_to_char(line_index);
+        ranges.extend(
+            matches_in_line(matcher, &text)
+                .into_iter()
+                .map(|(col, len)| line_start + col..line_start + col + len),
+        );
     }
-    let from_byte = char_to_byte_index(hay, from_char);
-    let idx = hay.get(from_byte..)?.find(needle)?;
-    let byte = from_byte + idx;
-    Some(hay[..byte].chars().count())
+    ranges
 }
 
-fn find_last_before(hay: &str, needle: &str, before_char: usize) -> Option<usize> {
-    if needle.is_empty() {
-        return None;
-    }
-    let before_byte = char_to_byte_index(hay, before_char);
-    let prefix = hay.get(..before_byte).unwrap_or(hay);
-    let mut best: Option<usize> = None;
-    let mut start = 0usize;
-    loop {
-        let Some(rest) = prefix.get(start..) else {
-            break;
-        };
-        let Some(found) = rest.find(needle) else {
-            break;
-        };
-        let at = start + found;
-        best = Some(at);
-        start = at + 1;
+// "Replace": rewrites the first match at or after the selection's start, so a match selected
+// by "Find" is the one replaced, wrapping around to the first match. Returns where the
+// replaced match started, or `None` if nothing matches.
+fn replace_next_match(
+    editor: &mut rustide_editor::Editor,
+    matcher: &project::LineMatcher,
+    replacement: &str,
+) -> Option<usize> {
+    let ranges = document_match_ranges(editor.rope(), matcher);
+    let from = editor.selection().range().start;
+    let target = ranges
+        .iter()
+        .find(|range| range.start >= from)
+        .or(ranges.first())?
+        .clone();
+    editor.select_range(target.clone());
+    editor.insert_text(replacement);
+    Some(target.start)
+}
+
+// "Replace All": every match, found before any is rewritten, in one undo step. Returns how
+// many there were.
+fn replace_all_matches(
+    editor: &mut rustide_editor::Editor,
+    matcher: &project::LineMatcher,
+    replacement: &str,
+) -> usize {
+    let ranges = document_match_ranges(editor.rope(), matcher);
+    editor.replace_ranges(&ranges, replacement)
+}
+
+// Sizes round up, so a non-empty project never shows 0 KB.
+fn format_project_stats(files: usize, bytes: u64) -> String {
+    let noun = if files == 1 { "file" } else { "files" };
+    format!("{files} {noun}, {} KB", bytes.div_ceil(1024))
+}
+
+/// Icon for a project tree file, picked by extension alone.
+fn icon_for_path(path: &Path) -> char {
+    let ext = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "rs" => '🦀',
+        "py" => '🐍',
+        "json" | "toml" | "yaml" | "yml" => '📋',
+        "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "svg" | "ico" => '🖼',
+        _ => '📄',
+    }
+}
+
+fn dir_icon(open: bool) -> char {
+    if open {
+        '📂'
+    } else {
+        '📁'
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TreeAction {
+    Open(PathBuf),
+    OpenToSide(PathBuf),
+    OpenAsRoot(PathBuf),
+    Rename(PathBuf),
+    Delete(PathBuf),
+    CopyPath(PathBuf),
+    Reveal(PathBuf),
+    NewFile(PathBuf),
+    NewFolder(PathBuf),
+}
+
+type TreeMenu = &'static [(&'static str, fn(PathBuf) -> TreeAction)];
+
+const FILE_MENU: TreeMenu = &[
+    ("Open", TreeAction::Open),
+    ("Open to the Side", TreeAction::OpenToSide),
+    ("Rename…", TreeAction::Rename),
+    ("Delete…", TreeAction::Delete),
+    ("Copy Path", TreeAction::CopyPath),
+    ("Reveal in File Manager", TreeAction::Reveal),
+];
+
+// The project root gets the same menu minus the trailing "Delete…".
+const DIR_MENU: TreeMenu = &[
+    ("Open as Project Root", TreeAction::OpenAsRoot),
+    ("New File…", TreeAction::NewFile),
+    ("New Folder…", TreeAction::NewFolder),
+    ("Delete…", TreeAction::Delete),
+];
+
+fn tree_context_menu(
+    response: &egui::Response,
+    path: &Path,
+    menu: TreeMenu,
+    action: &mut Option<TreeAction>,
+) {
+    response.context_menu(|ui| {
+        for (label, make) in menu {
+            if ui.button(*label).clicked() {
+                *action = Some(make(path.to_path_buf()));
+                ui.close();
+            }
+        }
+    });
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NamePromptKind {
+    Rename,
+    NewFile,
+    NewFolder,
+}
+
+impl NamePromptKind {
+    fn title(self) -> &'static str {
+        match self {
+            Self::Rename => "Rename",
+            Self::NewFile => "New File",
+            Self::NewFolder => "New Folder",
+        }
+    }
+}
+
+// Name input for a project tree operation. `target` is the entry being renamed, or the
+// directory a new entry goes in.
+struct NamePrompt {
+    kind: NamePromptKind,
+    target: PathBuf,
+    name: String,
+    error: Option<String>,
+    request_focus: bool,
+}
+
+impl NamePrompt {
+    fn parent(&self) -> &Path {
+        match self.kind {
+            NamePromptKind::Rename => self.target.parent().unwrap_or(&self.target),
+            NamePromptKind::NewFile | NamePromptKind::NewFolder => &self.target,
+        }
     }
-    let best_byte = best?;
-    Some(prefix[..best_byte].chars().count())
 }
 
-fn char_to_byte_index(text: &str, char_index: usize) -> usize {
-    if char_index == 0 {
-        return 0;
+// Where document `idx` ends up once the documents not `kept` are removed. A removed one maps
+// to the document after it, which may be one past the end.
+fn index_after_removal(kept: &[bool], idx: usize) -> usize {
+    kept.iter().take(idx).filter(|&&kept| kept).count()
+}
+
+// A single path component typed into the name prompt, trimmed.
+fn validate_entry_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Enter a name".to_string());
     }
-    match text.char_indices().nth(char_index) {
-        Some((byte, _)) => byte,
-        None => text.len(),
+    if name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(format!("\"{name}\" is not a valid name"));
     }
+    Ok(name)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TreeViewOptions {
+    show_icons: bool,
+    // Opens every directory, e.g. so all filter matches are visible.
+    expand_all: bool,
 }
 
+// Directories expanded before their children are listed are pushed onto `load_requests`.
 fn show_tree(
     ui: &mut egui::Ui,
     node: &rustide_project::TreeNode,
     root_path: &Path,
-) -> Option<PathBuf> {
+    options: TreeViewOptions,
+    load_requests: &mut Vec<PathBuf>,
+) -> Option<TreeAction> {
     fn file_tag(path: &Path) -> &'static str {
         let ext = path
             .extension()
@@ -1447,26 +3338,54 @@ fn show_tree(
 
     if node.is_dir {
         let id = ui.make_persistent_id(&node.path);
-        let mut clicked: Option<PathBuf> = None;
-        egui::CollapsingHeader::new(format!("[DIR] {}", node.name))
+        let default_open = node.path == root_path;
+        let title = if options.show_icons {
+            // The header stores its open state under its own id derived from `id_salt`.
+            let state_id = ui.make_persistent_id(egui::Id::new(id));
+            let open = options.expand_all
+                || egui::collapsing_header::CollapsingState::load(ui.ctx(), state_id)
+                    .map_or(default_open, |state| state.is_open());
+            format!("{} {}", dir_icon(open), node.name)
+        } else {
+            format!("[DIR] {}", node.name)
+        };
+        let mut clicked: Option<TreeAction> = None;
+        let header = egui::CollapsingHeader::new(title)
             .id_salt(id)
-            .default_open(node.path == root_path)
+            .default_open(default_open)
+            .open(options.expand_all.then_some(true))
             .show(ui, |ui| {
+                if !node.children_loaded {
+                    load_requests.push(node.path.clone());
+                    ui.weak("Loading…");
+                }
                 for child in &node.children {
                     if clicked.is_none() {
-                        clicked = show_tree(ui, child, root_path);
+                        clicked = show_tree(ui, child, root_path, options, load_requests);
                     }
                 }
-            });
+            })
+            .header_response;
+        let menu = if node.path == root_path {
+            &DIR_MENU[..DIR_MENU.len() - 1]
+        } else {
+            DIR_MENU
+        };
+        tree_context_menu(&header, &node.path, menu, &mut clicked);
         return clicked;
     }
 
-    let label = format!("{} {}", file_tag(&node.path), node.name);
-    if ui.selectable_label(false, label).clicked() {
-        return Some(node.path.clone());
-    }
-
-    None
+    let label = if options.show_icons {
+        format!("{} {}", icon_for_path(&node.path), node.name)
+    } else {
+        format!("{} {}", file_tag(&node.path), node.name)
+    };
+    let response = ui.selectable_label(false, label);
+    let mut clicked = response
+        .clicked()
+        .then(|| TreeAction::Open(node.path.clone()));
+    tree_context_menu(&response, &node.path, FILE_MENU, &mut clicked);
+    clicked
 }
 
 impl Drop for RustideApp {
@@ -1489,9 +3408,28 @@ impl RustideApp {
             ctx.pixels_per_point(),
         );
 
+        let custom_path = custom_theme_path(&self.config.ui);
+        if custom_path.as_ref() != self.custom_theme_watcher.as_ref().map(|w| &w.path) {
+            // Dropping the old watcher stops its thread.
+            self.custom_theme_watcher = custom_path.map(CustomThemeWatcher::spawn);
+        }
+        if let Some(watcher) = &self.custom_theme_watcher {
+            while let Ok(theme) = watcher.rx.try_recv() {
+                self.theme = theme;
+            }
+            ctx.request_repaint_after(CUSTOM_THEME_POLL);
+        }
+
         if self.last_applied_ui != Some(desired) {
-            self.theme = theme::build_theme(self.config.ui.theme);
-            apply_font_families(ctx, &self.font_state, &self.config.ui);
+            // The watcher thread reads the custom theme file; until it has, the previous theme
+            // stays.
+            if self.custom_theme_watcher.is_none() {
+                self.theme = theme::resolve_theme(
+                    self.config.ui.theme,
+                    self.config.ui.custom_theme_path.as_deref(),
+                );
+            }
+            font::apply_font_families(ctx, &self.font_state, &self.config.ui);
             self.last_applied_ui = Some(desired);
         }
 
@@ -1500,8 +3438,120 @@ impl RustideApp {
         apply_ui_style(ctx, &self.config.ui);
     }
 
+    fn ui_language_mode(&mut self, ui: &mut egui::Ui) {
+        let Some(doc) = self.documents.get_mut(self.active_doc) else {
+            return;
+        };
+        let detected = doc
+            .syntax
+            .as_ref()
+            .map_or(LanguageId::PlainText, SyntaxState::language);
+        let mut selected = doc.language_override;
+        ui.label("Language");
+        egui::ComboBox::from_id_salt("language_mode")
+            .selected_text(match selected {
+                Some(language) => language.display_name().to_string(),
+                None => format!("Auto ({})", detected.display_name()),
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut selected, None, "Auto-detect");
+                for &language in LanguageId::ALL {
+                    ui.selectable_value(&mut selected, Some(language), language.display_name());
+                }
+            });
+        if selected != doc.language_override {
+            doc.set_language_override(selected);
+            doc.settings = self
+                .config
+                .editor
+                .resolve(doc.language(), doc.doc.editor_config.as_ref());
+        }
+    }
+
+    fn ui_line_ending(&mut self, ui: &mut egui::Ui) {
+        let Some(doc) = self.documents.get_mut(self.active_doc) else {
+            return;
+        };
+        let mut selected = doc.doc.line_ending;
+        egui::ComboBox::from_id_salt("line_ending")
+            .selected_text(selected.name())
+            .show_ui(ui, |ui| {
+                for &ending in rustide_editor::LineEnding::ALL {
+                    ui.selectable_value(&mut selected, ending, ending.name());
+                }
+            })
+            .response
+            .on_hover_text("Line endings");
+        if selected != doc.doc.line_ending {
+            let converted = doc.doc.convert_line_endings(selected);
+            editor_view::drain_syntax_edits(&mut doc.doc.editor, &mut doc.syntax, &mut doc.folded);
+            self.status = format!(
+                "Converted {converted} line ending(s) to {}",
+                selected.name()
+            );
+        }
+    }
+
+    fn ui_encoding(&mut self, ui: &mut egui::Ui) {
+        let Some(doc) = self.documents.get(self.active_doc) else {
+            return;
+        };
+        let current = doc.doc.encoding;
+        let has_path = doc.doc.path.is_some();
+        let mut reopen = None;
+        let mut save_as = None;
+        ui.menu_button(current.to_string(), |ui| {
+            ui.add_enabled_ui(has_path, |ui| {
+                ui.menu_button("Re-open with Encoding", |ui| {
+                    for &encoding in rustide_editor::TextEncoding::ALL {
+                        if ui.button(encoding.to_string()).clicked() {
+                            reopen = Some(encoding);
+                            ui.close();
+                        }
+                    }
+                })
+                .response
+                .on_hover_text("Decode the file again; unsaved edits are discarded");
+            });
+            ui.menu_button("Set Save Encoding", |ui| {
+                for &encoding in rustide_editor::TextEncoding::ALL {
+                    if ui
+                        .radio(encoding == current, encoding.to_string())
+                        .clicked()
+                    {
+                        save_as = Some(encoding);
+                        ui.close();
+                    }
+                }
+            });
+        })
+        .response
+        .on_hover_text("File encoding");
+
+        if let Some(encoding) = reopen {
+            self.queue_reload_with_encoding(self.active_doc, encoding.into());
+            self.status = format!("Re-opening as {encoding}");
+        } else if let Some(encoding) = save_as.filter(|&encoding| encoding != current) {
+            self.active_document_mut().doc.convert_encoding(encoding);
+            self.status = if self.queue_save(self.active_doc) {
+                format!("Saving as {encoding}")
+            } else {
+                format!("Encoding set to {encoding}")
+            };
+        }
+    }
+
     fn ui_font_controls(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
         ui.checkbox(&mut self.config.startup.open_last_file, "Open last");
+        ui.checkbox(&mut self.config.ui.word_wrap, "Wrap");
+        ui.checkbox(&mut self.config.ui.autosave_enabled, "Autosave");
+        ui.add_enabled(
+            self.config.ui.autosave_enabled,
+            egui::DragValue::new(&mut self.config.ui.autosave_interval_secs)
+                .range(config::AUTOSAVE_INTERVAL_SECS)
+                .suffix(" s"),
+        )
+        .on_hover_text("Minimum time between autosaves");
         ui.separator();
 
         ui.label("Theme");
@@ -1521,6 +3571,24 @@ impl RustideApp {
                     theme::ThemeId::Monokai,
                     "monokai",
                 );
+                ui.selectable_value(
+                    &mut self.config.ui.theme,
+                    theme::ThemeId::Dracula,
+                    "dracula",
+                );
+                ui.selectable_value(&mut self.config.ui.theme, theme::ThemeId::Nord, "nord");
+                ui.selectable_value(
+                    &mut self.config.ui.theme,
+                    theme::ThemeId::CatppuccinMocha,
+                    "catppuccin-mocha",
+                );
+                ui.selectable_value(
+                    &mut self.config.ui.theme,
+                    theme::ThemeId::HighContrast,
+                    "high-contrast",
+                );
+                ui.selectable_value(&mut self.config.ui.theme, theme::ThemeId::Custom, "custom")
+                    .on_hover_text("Colors from the file set as `custom_theme_path` in [ui]");
             });
         if self.config.ui.theme != theme_before {
             self.last_applied_ui = None;
@@ -1581,172 +3649,81 @@ impl RustideApp {
 
         ui.label("Size");
         let size_before = self.config.ui.monospace_size;
-        ui.add(egui::DragValue::new(&mut self.config.ui.monospace_size).range(8.0..=48.0));
+        ui.add(
+            egui::DragValue::new(&mut self.config.ui.monospace_size)
+                .range(editor_view::FONT_SIZE_RANGE),
+        )
+        .on_hover_text("Ctrl+Wheel or Ctrl+Plus / Ctrl+Minus in the editor; Ctrl+0 resets");
         if (self.config.ui.monospace_size - size_before).abs() > f32::EPSILON {
+            self.configured_font_size = self.config.ui.monospace_size;
             self.last_applied_ui = None;
             self.ensure_ui_applied(ctx);
         }
     }
 }
 
-#[derive(Clone)]
-struct FontState {
-    base_definitions: egui::FontDefinitions,
-    user_font_name: Option<String>,
-    consolas_font_name: Option<String>,
-    consolas_bold_font_name: Option<String>,
-    consolas_italic_font_name: Option<String>,
-    consolas_bold_italic_font_name: Option<String>,
-    simhei_font_name: Option<String>,
-}
-
-fn build_font_state(cfg: &config::UiConfig) -> FontState {
-    let mut defs = egui::FontDefinitions::default();
-
-    let user_font_name = cfg.font_file.as_ref().and_then(|path| {
-        let data = std::fs::read(path).ok()?;
-        let name = format!("rustide_user_font:{}", path.display());
-        defs.font_data
-            .insert(name.clone(), egui::FontData::from_owned(data).into());
-        Some(name)
-    });
-
-    let mut consolas_font_name = None;
-    let mut consolas_bold_font_name = None;
-    let mut consolas_italic_font_name = None;
-    let mut consolas_bold_italic_font_name = None;
-    let mut simhei_font_name = None;
-    if cfg!(windows) {
-        if let Some(windir) = std::env::var_os("WINDIR") {
-            let fonts_dir = PathBuf::from(windir).join("Fonts");
-
-            let consolas_path = fonts_dir.join("consola.ttf");
-            if let Ok(data) = std::fs::read(&consolas_path) {
-                let name = "rustide_font:consolas".to_string();
-                defs.font_data
-                    .insert(name.clone(), egui::FontData::from_owned(data).into());
-                consolas_font_name = Some(name);
-            }
-
-            let consolas_bold_path = fonts_dir.join("consolab.ttf");
-            if let Ok(data) = std::fs::read(&consolas_bold_path) {
-                let name = "rustide_font:consolas_bold".to_string();
-                defs.font_data
-                    .insert(name.clone(), egui::FontData::from_owned(data).into());
-                consolas_bold_font_name = Some(name);
-            }
-
-            let consolas_italic_path = fonts_dir.join("consolai.ttf");
-            if let Ok(data) = std::fs::read(&consolas_italic_path) {
-                let name = "rustide_font:consolas_italic".to_string();
-                defs.font_data
-                    .insert(name.clone(), egui::FontData::from_owned(data).into());
-                consolas_italic_font_name = Some(name);
-            }
-
-            let consolas_bold_italic_path = fonts_dir.join("consolaz.ttf");
-            if let Ok(data) = std::fs::read(&consolas_bold_italic_path) {
-                let name = "rustide_font:consolas_bold_italic".to_string();
-                defs.font_data
-                    .insert(name.clone(), egui::FontData::from_owned(data).into());
-                consolas_bold_italic_font_name = Some(name);
-            }
-
-            let simhei_path = fonts_dir.join("simhei.ttf");
-            if let Ok(data) = std::fs::read(&simhei_path) {
-                let name = "rustide_font:simhei".to_string();
-                defs.font_data
-                    .insert(name.clone(), egui::FontData::from_owned(data).into());
-                simhei_font_name = Some(name);
-            }
-        }
-    }
+const CUSTOM_THEME_POLL: Duration = Duration::from_secs(1);
 
-    FontState {
-        base_definitions: defs,
-        user_font_name,
-        consolas_font_name,
-        consolas_bold_font_name,
-        consolas_italic_font_name,
-        consolas_bold_italic_font_name,
-        simhei_font_name,
+// The custom theme file to watch: `custom_theme_path` when `theme=custom`.
+fn custom_theme_path(cfg: &config::UiConfig) -> Option<PathBuf> {
+    if cfg.theme == theme::ThemeId::Custom {
+        cfg.custom_theme_path.clone()
+    } else {
+        None
     }
 }
 
-fn apply_font_families(ctx: &egui::Context, fonts: &FontState, cfg: &config::UiConfig) {
-    let mut defs = fonts.base_definitions.clone();
-
-    let mut monospace = Vec::<String>::new();
-    if let Some(name) = &fonts.user_font_name {
-        monospace.push(name.clone());
-    }
-
-    let (primary_regular, primary_bold, primary_italic, primary_bold_italic, secondary_regular) =
-        match cfg.monospace_font {
-            config::MonospaceFont::Consolas => (
-                &fonts.consolas_font_name,
-                &fonts.consolas_bold_font_name,
-                &fonts.consolas_italic_font_name,
-                &fonts.consolas_bold_italic_font_name,
-                &fonts.simhei_font_name,
-            ),
-            config::MonospaceFont::SimHei => (
-                &fonts.simhei_font_name,
-                &fonts.simhei_font_name,
-                &fonts.simhei_font_name,
-                &fonts.simhei_font_name,
-                &fonts.consolas_font_name,
-            ),
-        };
-
-    let primary = match cfg.monospace_style {
-        config::MonospaceStyle::Regular => primary_regular,
-        config::MonospaceStyle::Bold => primary_bold,
-        config::MonospaceStyle::Italic => primary_italic,
-        config::MonospaceStyle::BoldItalic => primary_bold_italic,
-    };
-
-    if let Some(name) = primary {
-        monospace.push(name.clone());
-    }
-    if let Some(name) = secondary_regular {
-        monospace.push(name.clone());
-    }
+// A thread that reads the custom theme file `path` once, then again whenever it changes,
+// and sends the theme back through `rx`, so it is reloaded without a restart. It stops once
+// the watcher is dropped.
+struct CustomThemeWatcher {
+    path: PathBuf,
+    rx: Receiver<theme::Theme>,
+    _stop: Sender<()>,
+}
 
-    if let Some(family) = defs.families.get_mut(&egui::FontFamily::Monospace) {
-        for name in monospace.into_iter().rev() {
-            family.insert(0, name);
+impl CustomThemeWatcher {
+    fn spawn(path: PathBuf) -> Self {
+        let (stop, stop_rx) = mpsc::channel::<()>();
+        let (theme_tx, rx) = mpsc::channel::<theme::Theme>();
+        let watched = path.clone();
+        std::thread::spawn(move || {
+            let mut stamp = None;
+            loop {
+                let modified = file_modified(Some(&watched));
+                if stamp != Some(modified) {
+                    stamp = Some(modified);
+                    let theme = theme::resolve_theme(theme::ThemeId::Custom, Some(&watched));
+                    if theme_tx.send(theme).is_err() {
+                        break;
+                    }
+                }
+                if let Err(mpsc::RecvTimeoutError::Disconnected) =
+                    stop_rx.recv_timeout(CUSTOM_THEME_POLL)
+                {
+                    break;
+                }
+            }
+        });
+        Self {
+            path,
+            rx,
+            _stop: stop,
         }
     }
+}
 
-    if let Some(family) = defs.families.get_mut(&egui::FontFamily::Proportional) {
-        // Prefer the configured editor font for UI as well, but keep egui defaults as fallbacks.
-        let existing = family.clone();
-        family.clear();
+const MAX_BUILD_OUTPUT_LINES: usize = 10_000;
 
-        let mut preferred: Vec<String> = Vec::new();
-        if let Some(name) = &fonts.user_font_name {
-            preferred.push(name.clone());
-        }
-        if let Some(name) = primary_regular {
-            preferred.push(name.clone());
-        }
-        if let Some(name) = secondary_regular {
-            preferred.push(name.clone());
-        }
-        for name in preferred {
-            if !family.contains(&name) {
-                family.push(name);
-            }
-        }
-        for name in existing {
-            if !family.contains(&name) {
-                family.push(name);
-            }
-        }
-    }
+// Whether a document last queued for saving at `last_save_request` may autosave again.
+fn autosave_due(last_save_request: Option<Instant>, now: Instant, interval: Duration) -> bool {
+    last_save_request.is_none_or(|instant| now.duration_since(instant) >= interval)
+}
 
-    ctx.set_fonts(defs);
+fn file_modified(path: Option<&Path>) -> Option<SystemTime> {
+    std::fs::metadata(path?)
+        .and_then(|meta| meta.modified())
+        .ok()
 }
 
 fn apply_ui_style(ctx: &egui::Context, cfg: &config::UiConfig) {
@@ -1765,6 +3742,9 @@ fn main() -> anyhow::Result<()> {
 
     let config_path = config::default_config_path();
     let config = config::AppConfig::load_or_default(&config_path);
+    if config.telemetry.enabled {
+        crash_report::install_panic_hook(crash_report::crash_report_path(&config_path));
+    }
 
     let initial_path = std::env::args_os().nth(1).map(PathBuf::from).or_else(|| {
         if config.startup.open_last_file {
@@ -1792,11 +3772,14 @@ fn main() -> anyhow::Result<()> {
         "RustIDE",
         native_options,
         Box::new(move |cc| {
-            let font_state = build_font_state(&config.ui);
-            let theme = theme::build_theme(config.ui.theme);
+            let font_state = font::build_font_state(&config.ui);
+            let theme =
+                theme::resolve_theme(config.ui.theme, config.ui.custom_theme_path.as_deref());
             theme::apply_theme(&cc.egui_ctx, &theme);
             apply_ui_style(&cc.egui_ctx, &config.ui);
-            apply_font_families(&cc.egui_ctx, &font_state, &config.ui);
+            font::apply_font_families(&cc.egui_ctx, &font_state, &config.ui);
+            // Ctrl+Plus / Ctrl+Minus / Ctrl+0 zoom the editor font instead of the whole UI.
+            cc.egui_ctx.options_mut(|o| o.zoom_with_keyboard = false);
             Ok(Box::new(RustideApp::new(
                 initial_path.clone(),
                 config.clone(),
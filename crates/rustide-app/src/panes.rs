@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+
+use crate::index_after_removal;
+
+/// The tab strip of every editor pane (`DockTab::Editor`): the documents it lists and the one
+/// it shows. Documents are indices into `RustideApp::documents`. A document can be listed by
+/// several panes, and is only closed once none lists it.
+///
+/// The focused pane shows `RustideApp::active_doc`, which the rest of the app sets freely, so
+/// methods that touch it take it as `active_doc`. [`Self::sync`] lists it in the focused pane;
+/// the other methods expect that to have happened.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct EditorPanes {
+    focused: usize,
+    panes: BTreeMap<usize, Pane>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Pane {
+    tabs: Vec<usize>,
+    // Stale for the focused pane until the next `sync`.
+    shown: usize,
+}
+
+impl EditorPanes {
+    pub fn focused(&self) -> usize {
+        self.focused
+    }
+
+    /// Whether `pane` has been shown a document yet; panes restored with the layout are not
+    /// tracked until then.
+    pub fn contains(&self, pane: usize) -> bool {
+        self.panes.contains_key(&pane)
+    }
+
+    pub fn pane_count(&self) -> usize {
+        self.panes.len()
+    }
+
+    /// `pane`'s tabs, in order.
+    pub fn tabs(&self, pane: usize) -> &[usize] {
+        self.panes.get(&pane).map_or(&[], |pane| &pane.tabs)
+    }
+
+    /// Whether any pane lists document `doc`.
+    pub fn lists(&self, doc: usize) -> bool {
+        self.panes.values().any(|pane| pane.tabs.contains(&doc))
+    }
+
+    /// Shows `active_doc` in the focused pane, adding it after the other tabs if it isn't
+    /// listed yet.
+    pub fn sync(&mut self, active_doc: usize) {
+        let pane = self.panes.entry(self.focused).or_default();
+        if !pane.tabs.contains(&active_doc) {
+            pane.tabs.push(active_doc);
+        }
+        pane.shown = active_doc;
+    }
+
+    /// The document `pane` shows: `active_doc` for the focused pane. A pane not tracked yet
+    /// starts on `active_doc`, listing only it, and is tracked from here on.
+    pub fn shown(&mut self, pane: usize, active_doc: usize) -> usize {
+        if pane == self.focused {
+            return active_doc;
+        }
+        self.panes
+            .entry(pane)
+            .or_insert_with(|| Pane {
+                tabs: vec![active_doc],
+                shown: active_doc,
+            })
+            .shown
+    }
+
+    /// Focuses `pane`; `active_doc` becomes the document it shows. A pane not tracked yet
+    /// keeps `active_doc` and is tracked from the next [`Self::sync`].
+    pub fn focus_pane(&mut self, pane: usize, active_doc: &mut usize) {
+        self.focused = pane;
+        if let Some(pane) = self.panes.get(&pane) {
+            *active_doc = pane.shown;
+        }
+    }
+
+    /// The pane "Open to the Side" shows documents in: the first one other than the focused
+    /// pane, or `None` when a new pane is needed.
+    pub fn side_pane(&self) -> Option<usize> {
+        self.panes
+            .keys()
+            .copied()
+            .find(|&pane| pane != self.focused)
+    }
+
+    /// Shows `doc` in `pane`, listing it there, and focuses `pane`.
+    pub fn open_to_side(&mut self, pane: usize, doc: usize, active_doc: &mut usize) {
+        self.focus_pane(pane, active_doc);
+        *active_doc = doc;
+        self.sync(doc);
+    }
+
+    /// Removes the tabs `close` picks from the focused pane. If the shown one goes, the pane
+    /// shows the tab that took its place, or else the last one.
+    ///
+    /// A pane left without tabs is dropped, with the focus moving to the first other pane,
+    /// unless it is the only one; returns whether it was dropped. Documents no pane lists any
+    /// more are left for the caller to close.
+    pub fn close_tabs(
+        &mut self,
+        active_doc: &mut usize,
+        mut close: impl FnMut(usize) -> bool,
+    ) -> bool {
+        let Some(pane) = self.panes.get_mut(&self.focused) else {
+            return false;
+        };
+        let shown_at = pane.tabs.iter().position(|&doc| doc == pane.shown);
+        let mut kept_before_shown = 0;
+        let mut kept = Vec::with_capacity(pane.tabs.len());
+        for (pos, &doc) in pane.tabs.iter().enumerate() {
+            if !close(doc) {
+                if shown_at.is_some_and(|shown_at| pos < shown_at) {
+                    kept_before_shown += 1;
+                }
+                kept.push(doc);
+            }
+        }
+
+        let shown = if kept.contains(&pane.shown) {
+            Some(pane.shown)
+        } else {
+            kept.get(kept_before_shown).or(kept.last()).copied()
+        };
+        pane.tabs = kept;
+        if let Some(shown) = shown {
+            pane.shown = shown;
+            *active_doc = shown;
+            return false;
+        }
+        if self.panes.len() == 1 {
+            return false;
+        }
+        self.panes.remove(&self.focused);
+        self.focus_first(active_doc);
+        true
+    }
+
+    /// Drops the panes `keep` rejects, e.g. ones closed in the dock, and returns whether there
+    /// were any. Losing the focused pane moves the focus to the first remaining one.
+    pub fn retain_panes(
+        &mut self,
+        mut keep: impl FnMut(usize) -> bool,
+        active_doc: &mut usize,
+    ) -> bool {
+        let count = self.panes.len();
+        self.panes.retain(|&pane, _| keep(pane));
+        if !self.panes.contains_key(&self.focused) {
+            self.focus_first(active_doc);
+        }
+        self.panes.len() < count
+    }
+
+    /// Renumbers the tabs once the documents not `kept` were removed from
+    /// `RustideApp::documents`, the way [`index_after_removal`] does.
+    pub fn retain_documents(&mut self, kept: &[bool]) {
+        let is_kept = |doc: usize| kept.get(doc).copied().unwrap_or(true);
+        for pane in self.panes.values_mut() {
+            pane.tabs = pane
+                .tabs
+                .iter()
+                .filter(|&&doc| is_kept(doc))
+                .map(|&doc| index_after_removal(kept, doc))
+                .collect();
+            pane.shown = index_after_removal(kept, pane.shown);
+        }
+    }
+
+    fn focus_first(&mut self, active_doc: &mut usize) {
+        if let Some(&pane) = self.panes.keys().next() {
+            self.focus_pane(pane, active_doc);
+        }
+    }
+}
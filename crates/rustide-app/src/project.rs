@@ -1,39 +1,294 @@
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::sync::{
     atomic::{AtomicBool, Ordering},
-    mpsc::Sender,
+    mpsc::{Receiver, Sender},
     Arc,
 };
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
-use rustide_project::{build_tree, ProjectEvent, TreeNode};
+use rustide_project::{
+    build_subtree, build_tree_lazy, build_tree_with_options, load_children, ProjectEvent, TreeNode,
+    TreeOptions,
+};
 
+// The tree is listed one level at a time: directories arrive unloaded, the UI asks for them
+// with `ProjectRequest::LoadChildren` when first expanded, and the worker answers with
+// `ChildrenLoaded`.
 #[derive(Debug, Clone)]
 pub enum ProjectMessage {
     TreeUpdated(TreeNode),
+    ChildrenLoaded(PathBuf, Vec<TreeNode>),
+    // The whole tree within the `TreeOptions` limits, so the filter also finds files in
+    // directories never expanded. Only walked while the UI asks for it with
+    // `ProjectRequest::WantListing`: once then, and again when files are created or deleted.
+    ListingUpdated(TreeNode),
+    // Totals for the whole project, sent once the worker has counted it and again whenever
+    // they change.
+    StatsUpdated(ProjectStats),
+    // Answers `ProjectRequest::FileOp`, after the `TreeUpdated` it caused.
+    FileOpDone(FileOp),
+    // A file was written or replaced on disk, possibly by another program, with its new
+    // modification time. The worker reads that so the UI thread does no file I/O for it.
+    FileChanged(PathBuf, Option<SystemTime>),
     Error(String),
 }
 
+/// Sent from the UI to the project worker.
+#[derive(Debug, Clone)]
+pub enum ProjectRequest {
+    LoadChildren(PathBuf),
+    // Answered with `FileOpDone` or `Error`.
+    FileOp(FileOp),
+    // Whether to send `ListingUpdated`; the UI wants it while the filter box has text.
+    WantListing(bool),
+}
+
+/// File count and total size of the project, within the `TreeOptions` limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ProjectStats {
+    pub files: usize,
+    pub size_bytes: u64,
+}
+
+/// The files behind [`ProjectStats`] with their sizes. The worker walks the project once per
+/// root and then keeps this up to date from watcher events, so totals cost no walk per change.
+#[derive(Debug, Default)]
+pub struct ProjectTotals {
+    files: HashMap<PathBuf, u64>,
+    dirs: HashSet<PathBuf>,
+    size_bytes: u64,
+}
+
+impl ProjectTotals {
+    pub fn count(root: &Path, options: TreeOptions) -> Self {
+        let mut totals = Self::default();
+        totals.dirs.insert(root.to_path_buf());
+        totals.add_children(&build_tree_with_options(root, options));
+        totals
+    }
+
+    pub fn stats(&self) -> ProjectStats {
+        ProjectStats {
+            files: self.files.len(),
+            size_bytes: self.size_bytes,
+        }
+    }
+
+    /// Updates the totals for a burst of watcher events; returns whether they changed.
+    ///
+    /// Created paths are found by listing their parent one level deep (and walking new
+    /// directories), so ignore rules and `options` apply as in the first walk. Changes in
+    /// directories that walk skipped are not counted.
+    pub fn apply(&mut self, root: &Path, options: TreeOptions, events: &[ProjectEvent]) -> bool {
+        let before = self.stats();
+        let mut created_in = BTreeSet::new();
+        for event in events {
+            match event {
+                ProjectEvent::Changed => {
+                    *self = Self::count(root, options);
+                    return self.stats() != before;
+                }
+                ProjectEvent::FileDeleted(path) => self.remove(path),
+                ProjectEvent::FileModified(path) => {
+                    if let Some(size) = self.files.get_mut(path) {
+                        let len = std::fs::metadata(path).map_or(*size, |meta| meta.len());
+                        self.size_bytes = self.size_bytes - *size + len;
+                        *size = len;
+                    }
+                }
+                ProjectEvent::FileCreated(path) => {
+                    if let Some(parent) = path.parent().filter(|dir| self.dirs.contains(*dir)) {
+                        created_in.insert(parent.to_path_buf());
+                    }
+                }
+                ProjectEvent::Error(_) => {}
+            }
+        }
+
+        for dir in created_in {
+            let level = dir
+                .strip_prefix(root)
+                .map_or(0, |rel| rel.components().count());
+            if level >= options.max_depth {
+                continue;
+            }
+            for child in build_subtree(&dir, 1, options.max_entries_per_dir) {
+                if !child.is_dir {
+                    self.add_file(child.path, child.size_bytes.unwrap_or(0));
+                } else if self.dirs.insert(child.path.clone()) {
+                    let below = options.max_depth - level - 1;
+                    if below > 0 {
+                        let options = TreeOptions {
+                            max_depth: below,
+                            ..options
+                        };
+                        self.add_children(&build_tree_with_options(&child.path, options));
+                    }
+                }
+            }
+        }
+        self.stats() != before
+    }
+
+    fn add_children(&mut self, node: &TreeNode) {
+        for child in &node.children {
+            if child.is_dir {
+                self.dirs.insert(child.path.clone());
+                self.add_children(child);
+            } else {
+                self.add_file(child.path.clone(), child.size_bytes.unwrap_or(0));
+            }
+        }
+    }
+
+    fn add_file(&mut self, path: PathBuf, size: u64) {
+        self.size_bytes += size;
+        if let Some(old) = self.files.insert(path, size) {
+            self.size_bytes -= old;
+        }
+    }
+
+    fn remove(&mut self, path: &Path) {
+        if let Some(size) = self.files.remove(path) {
+            self.size_bytes -= size;
+        } else if self.dirs.remove(path) {
+            self.dirs.retain(|dir| !dir.starts_with(path));
+            let size_bytes = &mut self.size_bytes;
+            self.files.retain(|file, size| {
+                let keep = !file.starts_with(path);
+                if !keep {
+                    *size_bytes -= *size;
+                }
+                keep
+            });
+        }
+    }
+}
+
+/// A change to the files on disk requested from the project tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileOp {
+    Rename { from: PathBuf, to: PathBuf },
+    Delete(PathBuf),
+    CreateFile(PathBuf),
+    CreateDir(PathBuf),
+    Reveal(PathBuf),
+}
+
+impl FileOp {
+    pub fn apply(&self) -> std::io::Result<()> {
+        match self {
+            // `rename` silently replaces an existing file on Unix.
+            Self::Rename { to, .. } if to.exists() => Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                "a file with that name already exists",
+            )),
+            Self::Rename { from, to } => std::fs::rename(from, to),
+            // `symlink_metadata` so a link to a directory removes the link, not the target.
+            Self::Delete(path) if std::fs::symlink_metadata(path)?.is_dir() => {
+                std::fs::remove_dir_all(path)
+            }
+            Self::Delete(path) => std::fs::remove_file(path),
+            Self::CreateFile(path) => std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)
+                .map(drop),
+            Self::CreateDir(path) => std::fs::create_dir(path),
+            Self::Reveal(path) => reveal_in_file_manager(path),
+        }
+    }
+
+    fn changes_tree(&self) -> bool {
+        !matches!(self, Self::Reveal(_))
+    }
+}
+
+impl std::fmt::Display for FileOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rename { from, to } => write!(f, "rename {} to {}", from.display(), to.display()),
+            Self::Delete(path) => write!(f, "delete {}", path.display()),
+            Self::CreateFile(path) => write!(f, "create {}", path.display()),
+            Self::CreateDir(path) => write!(f, "create folder {}", path.display()),
+            Self::Reveal(path) => write!(f, "reveal {}", path.display()),
+        }
+    }
+}
+
+// Selects the entry where the file manager supports it; otherwise opens its folder.
+fn reveal_in_file_manager(path: &Path) -> std::io::Result<()> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = std::process::Command::new("explorer");
+        command.arg("/select,").arg(path);
+        command
+    } else if cfg!(target_os = "macos") {
+        let mut command = std::process::Command::new("open");
+        command.arg("-R").arg(path);
+        command
+    } else {
+        let mut command = std::process::Command::new("xdg-open");
+        command.arg(path.parent().unwrap_or(path));
+        command
+    };
+    command.spawn().map(drop)
+}
+
 pub struct ProjectWorker {
     stop: Arc<AtomicBool>,
+    requests: Sender<ProjectRequest>,
+    listing_wanted: bool,
 }
 
 impl ProjectWorker {
-    pub fn start(root: PathBuf, tx: Sender<ProjectMessage>) -> Self {
+    pub fn start(root: PathBuf, options: TreeOptions, tx: Sender<ProjectMessage>) -> Self {
         let stop = Arc::new(AtomicBool::new(false));
         let stop_thread = stop.clone();
-        std::thread::spawn(move || run_worker(root, tx, stop_thread));
-        Self { stop }
+        let (requests, requests_rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || run_worker(root, options, tx, requests_rx, stop_thread));
+        Self {
+            stop,
+            requests,
+            listing_wanted: false,
+        }
+    }
+
+    pub fn load_children(&self, dir: PathBuf) {
+        let _ = self.requests.send(ProjectRequest::LoadChildren(dir));
+    }
+
+    pub fn file_op(&self, op: FileOp) {
+        let _ = self.requests.send(ProjectRequest::FileOp(op));
+    }
+
+    /// Starts or stops the full listing; cheap to call every frame, since only changes are
+    /// sent to the worker.
+    pub fn want_listing(&mut self, wanted: bool) {
+        if wanted != self.listing_wanted {
+            self.listing_wanted = wanted;
+            let _ = self.requests.send(ProjectRequest::WantListing(wanted));
+        }
     }
 }
 
 impl Drop for ProjectWorker {
     fn drop(&mut self) {
-        self.stop.store(true, Ordering::Relaxed);
+        // Release/Acquire (here and in the search/replace workers) pairs the stop request with
+        // the worker's check, so the flag and anything written before it are visible to the
+        // worker thread even on weakly ordered CPUs (ARM, RISC-V).
+        self.stop.store(true, Ordering::Release);
     }
 }
 
-fn run_worker(root: PathBuf, tx: Sender<ProjectMessage>, stop: Arc<AtomicBool>) {
+fn run_worker(
+    root: PathBuf,
+    options: TreeOptions,
+    tx: Sender<ProjectMessage>,
+    requests: Receiver<ProjectRequest>,
+    stop: Arc<AtomicBool>,
+) {
     if !root.exists() || !root.is_dir() {
         let _ = tx.send(ProjectMessage::Error(format!(
             "Project root does not exist: {}",
@@ -42,7 +297,15 @@ fn run_worker(root: PathBuf, tx: Sender<ProjectMessage>, stop: Arc<AtomicBool>)
         return;
     }
 
-    let _ = tx.send(ProjectMessage::TreeUpdated(build_tree(&root)));
+    // Directories the UI has loaded, so a refresh lists them again instead of collapsing the
+    // tree back to the first level.
+    let mut expanded = HashSet::new();
+    let _ = tx.send(ProjectMessage::TreeUpdated(build_tree_lazy(
+        &root, &expanded, options,
+    )));
+    let mut listing_wanted = false;
+    let mut totals = ProjectTotals::count(&root, options);
+    let _ = tx.send(ProjectMessage::StatsUpdated(totals.stats()));
 
     let (raw_tx, raw_rx) = std::sync::mpsc::channel::<ProjectEvent>();
     let (debounced_tx, debounced_rx) = std::sync::mpsc::channel::<ProjectEvent>();
@@ -60,18 +323,95 @@ fn run_worker(root: PathBuf, tx: Sender<ProjectMessage>, stop: Arc<AtomicBool>)
     });
 
     let mut last_refresh = Instant::now();
-    while !stop.load(Ordering::Relaxed) {
-        match debounced_rx.recv_timeout(Duration::from_millis(100)) {
-            Ok(ProjectEvent::Changed) => {
-                if last_refresh.elapsed() < Duration::from_millis(100) {
-                    continue;
+    while !stop.load(Ordering::Acquire) {
+        while let Ok(request) = requests.try_recv() {
+            match request {
+                ProjectRequest::LoadChildren(dir) => {
+                    let children = load_children(&root, &dir, &expanded, options);
+                    expanded.insert(dir.clone());
+                    let _ = tx.send(ProjectMessage::ChildrenLoaded(dir, children));
+                }
+                ProjectRequest::FileOp(op) => match op.apply() {
+                    Ok(()) => {
+                        // Refresh now rather than waiting for the watcher's debounce.
+                        if op.changes_tree() {
+                            last_refresh = Instant::now();
+                            let tree = build_tree_lazy(&root, &expanded, options);
+                            let _ = tx.send(ProjectMessage::TreeUpdated(tree));
+                            if listing_wanted {
+                                send_listing(&root, options, &tx);
+                            }
+                        }
+                        let _ = tx.send(ProjectMessage::FileOpDone(op));
+                    }
+                    Err(e) => {
+                        let _ = tx.send(ProjectMessage::Error(format!("Could not {op}: {e}")));
+                    }
+                },
+                ProjectRequest::WantListing(wanted) => {
+                    if wanted && !listing_wanted {
+                        send_listing(&root, options, &tx);
+                    }
+                    listing_wanted = wanted;
                 }
-                last_refresh = Instant::now();
-                let tree = build_tree(&root);
-                let _ = tx.send(ProjectMessage::TreeUpdated(tree));
             }
-            Ok(ProjectEvent::Error(e)) => {
-                let _ = tx.send(ProjectMessage::Error(e));
+        }
+        match debounced_rx.recv_timeout(Duration::from_millis(50)) {
+            Ok(event) => {
+                // The debouncer sends one event per path at the end of each window; take the
+                // whole burst at once.
+                let mut events = vec![event];
+                events.extend(debounced_rx.try_iter());
+                for event in &events {
+                    match event {
+                        ProjectEvent::Error(e) => {
+                            let _ = tx.send(ProjectMessage::Error(e.clone()));
+                        }
+                        ProjectEvent::FileDeleted(path) => {
+                            expanded.retain(|dir| !dir.starts_with(path));
+                        }
+                        // Editors that save by renaming a temp file over the original show up
+                        // as a create.
+                        ProjectEvent::FileModified(path) | ProjectEvent::FileCreated(path) => {
+                            let modified = crate::file_modified(Some(path));
+                            let _ = tx.send(ProjectMessage::FileChanged(path.clone(), modified));
+                        }
+                        ProjectEvent::Changed => {}
+                    }
+                }
+                if totals.apply(&root, options, &events) {
+                    let _ = tx.send(ProjectMessage::StatsUpdated(totals.stats()));
+                }
+                // Writes to existing files leave the listing as it is.
+                let listing_changed = listing_wanted
+                    && events.iter().any(|event| {
+                        !matches!(
+                            event,
+                            ProjectEvent::FileModified(_) | ProjectEvent::Error(_)
+                        )
+                    });
+                match refresh_targets(&root, &expanded, &events) {
+                    Some(dirs) => {
+                        for dir in dirs {
+                            let children = load_children(&root, &dir, &expanded, options);
+                            let _ = tx.send(ProjectMessage::ChildrenLoaded(dir, children));
+                        }
+                        if listing_changed {
+                            send_listing(&root, options, &tx);
+                        }
+                    }
+                    None => {
+                        if last_refresh.elapsed() < Duration::from_millis(100) {
+                            continue;
+                        }
+                        last_refresh = Instant::now();
+                        let tree = build_tree_lazy(&root, &expanded, options);
+                        let _ = tx.send(ProjectMessage::TreeUpdated(tree));
+                        if listing_changed {
+                            send_listing(&root, options, &tx);
+                        }
+                    }
+                }
             }
             Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
             Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
@@ -79,6 +419,57 @@ fn run_worker(root: PathBuf, tx: Sender<ProjectMessage>, stop: Arc<AtomicBool>)
     }
 }
 
+// Sent after the lazy tree rather than instead of it, so the panel shows up before the slow
+// full walk is done.
+fn send_listing(root: &Path, options: TreeOptions, tx: &Sender<ProjectMessage>) {
+    let listing = build_tree_with_options(root, options);
+    let _ = tx.send(ProjectMessage::ListingUpdated(listing));
+}
+
+// Past this many directories a full rebuild is about as cheap as reloading each one.
+const MAX_TARGETED_REFRESHES: usize = 16;
+
+/// Loaded directories to reload for a burst of watcher events, outermost only, or `None` when
+/// the whole tree should be rebuilt (an unspecific `Changed`, the root itself, or too many
+/// directories). Changes inside directories that were never loaded need no refresh, and
+/// neither do modified files, whose names and places in the tree stay the same.
+pub fn refresh_targets(
+    root: &Path,
+    expanded: &HashSet<PathBuf>,
+    events: &[ProjectEvent],
+) -> Option<BTreeSet<PathBuf>> {
+    let mut dirs = BTreeSet::new();
+    for event in events {
+        let path = match event {
+            ProjectEvent::FileCreated(path) | ProjectEvent::FileDeleted(path) => path,
+            ProjectEvent::Changed => return None,
+            ProjectEvent::FileModified(_) | ProjectEvent::Error(_) => continue,
+        };
+        if path == root {
+            return None;
+        }
+        let Some(parent) = path.parent() else {
+            continue;
+        };
+        if parent == root || expanded.contains(parent) {
+            dirs.insert(parent.to_path_buf());
+        }
+    }
+    if dirs.len() > MAX_TARGETED_REFRESHES {
+        return None;
+    }
+    let outermost = dirs
+        .iter()
+        .filter(|dir| {
+            !dirs
+                .iter()
+                .any(|other| other != *dir && dir.starts_with(other))
+        })
+        .cloned()
+        .collect();
+    Some(outermost)
+}
+
 pub struct SearchWorker {
     stop: Arc<AtomicBool>,
 }
@@ -88,22 +479,178 @@ pub struct SearchMatch {
     pub path: PathBuf,
     pub line_index: usize,
     pub column_chars: usize,
+    pub match_end_column_chars: usize,
     pub preview: String,
+    pub context_before: Vec<String>,
+    pub context_after: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
 pub enum SearchMessage {
     Started(u64),
-    Match(u64, SearchMatch),
+    Batch(u64, Vec<SearchMatch>),
     Finished(u64),
     Error(u64, String),
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    pub query: String,
+    pub case_sensitive: bool,
+    pub use_regex: bool,
+    pub whole_word: bool,
+    pub context_lines: usize,
+    pub file_glob: Option<String>,
+}
+
+// Per-line matcher shared by project search and the in-file find bar.
+#[derive(Debug, Clone)]
+pub struct LineMatcher {
+    kind: MatcherKind,
+    whole_word: bool,
+}
+
+#[derive(Debug, Clone)]
+enum MatcherKind {
+    Literal {
+        needle: String,
+        case_sensitive: bool,
+    },
+    Regex(regex::Regex),
+}
+
+impl LineMatcher {
+    pub fn new(query: &str, case_sensitive: bool, use_regex: bool) -> Result<Self, String> {
+        let kind = if use_regex {
+            let re = regex::RegexBuilder::new(query)
+                .case_insensitive(!case_sensitive)
+                .build()
+                .map_err(|e| e.to_string())?;
+            MatcherKind::Regex(re)
+        } else {
+            let needle = if case_sensitive {
+                query.to_string()
+            } else {
+                query.to_ascii_lowercase()
+            };
+            MatcherKind::Literal {
+                needle,
+                case_sensitive,
+            }
+        };
+        Ok(Self {
+            kind,
+            whole_word: false,
+        })
+    }
+
+    pub fn whole_word(mut self, whole_word: bool) -> Self {
+        self.whole_word = whole_word;
+        self
+    }
+
+    /// Byte ranges of all non-empty, non-overlapping matches in `line`.
+    pub fn find_all(&self, line: &str) -> Vec<Range<usize>> {
+        let mut matches: Vec<Range<usize>> = match &self.kind {
+            MatcherKind::Literal {
+                needle,
+                case_sensitive,
+            } => {
+                if needle.is_empty() {
+                    return Vec::new();
+                }
+                let lowered;
+                let hay = if *case_sensitive {
+                    line
+                } else {
+                    lowered = line.to_ascii_lowercase();
+                    lowered.as_str()
+                };
+                hay.match_indices(needle.as_str())
+                    .map(|(start, m)| start..start + m.len())
+                    .collect()
+            }
+            MatcherKind::Regex(re) => re
+                .find_iter(line)
+                .filter(|m| !m.is_empty())
+                .map(|m| m.range())
+                .collect(),
+        };
+        if self.whole_word {
+            matches.retain(|range| {
+                is_word_boundary(line, range.start) && is_word_boundary(line, range.end)
+            });
+        }
+        matches
+    }
+
+    pub fn find_first(&self, line: &str) -> Option<Range<usize>> {
+        self.find_all(line).into_iter().next()
+    }
+}
+
+/// Restricts project search/replace to files matching a glob.
+///
+/// The glob is matched against the file name only (`*.rs`), unless it contains a `/`, in which
+/// case it is matched against the path relative to the project root (`src/**/*.rs`). An empty
+/// pattern or `*` matches every file.
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
+    pattern: Option<glob::Pattern>,
+    match_path: bool,
+}
+
+impl FileFilter {
+    pub fn new(glob: Option<&str>) -> Result<Self, String> {
+        let glob = glob.map(str::trim).unwrap_or_default();
+        if glob.is_empty() || glob == "*" {
+            return Ok(Self::default());
+        }
+        let pattern = glob::Pattern::new(glob).map_err(|e| format!("Invalid file pattern: {e}"))?;
+        Ok(Self {
+            pattern: Some(pattern),
+            match_path: glob.contains('/'),
+        })
+    }
+
+    pub fn matches(&self, relative_path: &Path) -> bool {
+        let Some(pattern) = &self.pattern else {
+            return true;
+        };
+        if self.match_path {
+            let options = glob::MatchOptions {
+                require_literal_separator: true,
+                ..glob::MatchOptions::new()
+            };
+            return pattern.matches_path_with(relative_path, options);
+        }
+        relative_path
+            .file_name()
+            .is_some_and(|name| pattern.matches(&name.to_string_lossy()))
+    }
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+/// True when `byte_offset` does not split a run of word characters (alphanumeric or `_`).
+///
+/// CJK ideographs count as alphanumeric, so a run of CJK text has no inner boundaries.
+pub fn is_word_boundary(text: &str, byte_offset: usize) -> bool {
+    let (Some(before), Some(after)) = (text.get(..byte_offset), text.get(byte_offset..)) else {
+        return false;
+    };
+    match (before.chars().next_back(), after.chars().next()) {
+        (Some(prev), Some(next)) => !(is_word_char(prev) && is_word_char(next)),
+        _ => true,
+    }
+}
+
 impl SearchWorker {
     pub fn start(
         root: PathBuf,
-        query: String,
-        case_sensitive: bool,
+        options: SearchOptions,
         encoding_hint: rustide_editor::TextEncodingHint,
         request_id: u64,
         tx: Sender<SearchMessage>,
@@ -111,28 +658,22 @@ impl SearchWorker {
         let stop = Arc::new(AtomicBool::new(false));
         let stop_thread = stop.clone();
         std::thread::spawn(move || {
-            run_search(
-                root,
-                query,
-                case_sensitive,
-                encoding_hint,
-                request_id,
-                tx,
-                stop_thread,
-            )
+            run_search(root, options, encoding_hint, request_id, tx, stop_thread)
         });
         Self { stop }
     }
 
-    pub fn cancel(&self) {
-        self.stop.store(true, Ordering::Relaxed);
+    // Asks the search thread to stop without waiting for it. It polls `stop` per line and
+    // exits on its own; anything it sends meanwhile carries the old request id, which
+    // `poll_project` drops.
+    pub fn cancel(self) {
+        self.stop.store(true, Ordering::Release);
     }
 }
 
 fn run_search(
     root: PathBuf,
-    query: String,
-    case_sensitive: bool,
+    options: SearchOptions,
     encoding_hint: rustide_editor::TextEncodingHint,
     request_id: u64,
     tx: Sender<SearchMessage>,
@@ -146,19 +687,191 @@ fn run_search(
         let _ = tx.send(SearchMessage::Finished(request_id));
         return;
     }
-    if query.trim().is_empty() {
+    if options.query.trim().is_empty() {
         let _ = tx.send(SearchMessage::Finished(request_id));
         return;
     }
-    let needle = if case_sensitive {
-        query
-    } else {
-        query.to_ascii_lowercase()
+    // Compile once up front; the matcher is reused for every line of every file.
+    let matcher = match LineMatcher::new(&options.query, options.case_sensitive, options.use_regex)
+    {
+        Ok(matcher) => matcher.whole_word(options.whole_word),
+        Err(e) => {
+            let _ = tx.send(SearchMessage::Error(
+                request_id,
+                format!("Invalid regex: {e}"),
+            ));
+            let _ = tx.send(SearchMessage::Finished(request_id));
+            return;
+        }
+    };
+    let filter = match FileFilter::new(options.file_glob.as_deref()) {
+        Ok(filter) => filter,
+        Err(e) => {
+            let _ = tx.send(SearchMessage::Error(request_id, e));
+            let _ = tx.send(SearchMessage::Finished(request_id));
+            return;
+        }
     };
 
     let _ = tx.send(SearchMessage::Started(request_id));
 
-    for entry in ignore::WalkBuilder::new(&root)
+    // Collect and sort the file list first so results come out in a stable order no matter
+    // how the parallel work below is scheduled.
+    let mut paths = Vec::new();
+    for path in project_files(&root, &filter) {
+        if stop.load(Ordering::Acquire) {
+            break;
+        }
+        paths.push(path);
+    }
+    paths.sort();
+
+    let mut batcher = MatchBatcher::new(&tx, request_id, SEARCH_BATCH_SIZE);
+    for chunk in paths.chunks(PARALLEL_SEARCH_CHUNK) {
+        if stop.load(Ordering::Acquire) {
+            break;
+        }
+        batcher.extend(search_files(
+            chunk,
+            &matcher,
+            options.context_lines,
+            encoding_hint,
+            &stop,
+        ));
+    }
+
+    batcher.flush();
+    let _ = tx.send(SearchMessage::Finished(request_id));
+}
+
+pub const SEARCH_BATCH_SIZE: usize = 50;
+
+// Files searched in parallel per round; results are sent after each round so the UI still
+// fills in progressively.
+const PARALLEL_SEARCH_CHUNK: usize = 256;
+
+/// Searches `paths` in parallel and returns matches in `paths` order, then line order.
+pub fn search_files(
+    paths: &[PathBuf],
+    matcher: &LineMatcher,
+    context_lines: usize,
+    encoding_hint: rustide_editor::TextEncodingHint,
+    stop: &AtomicBool,
+) -> Vec<SearchMatch> {
+    use rayon::prelude::*;
+
+    paths
+        .par_iter()
+        .map(|path| {
+            if stop.load(Ordering::Acquire) {
+                return Vec::new();
+            }
+            // Binary files would only turn up as mojibake matches.
+            let Ok(bytes) = std::fs::read(path) else {
+                return Vec::new();
+            };
+            if rustide_editor::is_likely_binary(&bytes) {
+                return Vec::new();
+            }
+            let (content, _encoding) = rustide_editor::decode_bytes(&bytes, encoding_hint);
+            search_content(path, &content, matcher, context_lines, stop)
+        })
+        .flatten_iter()
+        .collect()
+}
+
+// Groups matches into `SearchMessage::Batch` so the UI drains a few large messages per frame
+// instead of one message per matching line.
+pub struct MatchBatcher<'a> {
+    tx: &'a Sender<SearchMessage>,
+    request_id: u64,
+    batch_size: usize,
+    pending: Vec<SearchMatch>,
+}
+
+impl<'a> MatchBatcher<'a> {
+    pub fn new(tx: &'a Sender<SearchMessage>, request_id: u64, batch_size: usize) -> Self {
+        let batch_size = batch_size.max(1);
+        Self {
+            tx,
+            request_id,
+            batch_size,
+            pending: Vec::with_capacity(batch_size),
+        }
+    }
+
+    pub fn extend(&mut self, matches: impl IntoIterator<Item = SearchMatch>) {
+        for m in matches {
+            self.pending.push(m);
+            if self.pending.len() >= self.batch_size {
+                self.flush();
+            }
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let batch = std::mem::replace(&mut self.pending, Vec::with_capacity(self.batch_size));
+        let _ = self.tx.send(SearchMessage::Batch(self.request_id, batch));
+    }
+}
+
+/// First match per line of `content`, with up to `context_lines` surrounding lines (like
+/// `grep -C`). Patterns are applied one line at a time, so `^`/`$` anchor at line boundaries
+/// and nothing matches across a newline.
+pub fn search_content(
+    path: &Path,
+    content: &str,
+    matcher: &LineMatcher,
+    context_lines: usize,
+    stop: &AtomicBool,
+) -> Vec<SearchMatch> {
+    content_matches(path, content, matcher, context_lines, stop).collect()
+}
+
+/// [`search_content`]'s matches, found as they are asked for. `stop` is checked before each
+/// line, so setting it ends the iterator even in the middle of a large file.
+pub fn content_matches<'a>(
+    path: &'a Path,
+    content: &'a str,
+    matcher: &'a LineMatcher,
+    context_lines: usize,
+    stop: &'a AtomicBool,
+) -> impl Iterator<Item = SearchMatch> + 'a {
+    let lines: Vec<&str> = content.lines().collect();
+    let preview_of = |line: &str| -> String { line.chars().take(200).collect() };
+    (0..lines.len())
+        .take_while(move |_| !stop.load(Ordering::Acquire))
+        .filter_map(move |line_index| {
+            let line = lines[line_index];
+            let range = matcher.find_first(line)?;
+            let column_chars = line[..range.start].chars().count();
+            let match_end_column_chars = column_chars + line[range].chars().count();
+            let before_start = line_index.saturating_sub(context_lines);
+            let after_end = (line_index + 1 + context_lines).min(lines.len());
+            Some(SearchMatch {
+                path: path.to_path_buf(),
+                line_index,
+                column_chars,
+                match_end_column_chars,
+                preview: preview_of(line),
+                context_before: lines[before_start..line_index]
+                    .iter()
+                    .map(|l| preview_of(l))
+                    .collect(),
+                context_after: lines[line_index + 1..after_end]
+                    .iter()
+                    .map(|l| preview_of(l))
+                    .collect(),
+            })
+        })
+}
+
+// Files searched/replaced under `root`, honoring .gitignore like the project tree.
+fn project_files<'a>(root: &'a Path, filter: &'a FileFilter) -> impl Iterator<Item = PathBuf> + 'a {
+    ignore::WalkBuilder::new(root)
         .hidden(false)
         .git_ignore(true)
         .git_exclude(true)
@@ -166,59 +879,147 @@ fn run_search(
         .follow_links(false)
         .build()
         .flatten()
-    {
-        if stop.load(Ordering::Relaxed) {
-            break;
-        }
-        let path = entry.path();
-        if entry
-            .file_type()
-            .map(|t| t.is_dir())
-            .unwrap_or_else(|| path.is_dir())
-        {
-            continue;
+        .filter(|entry| {
+            !entry
+                .file_type()
+                .map(|t| t.is_dir())
+                .unwrap_or_else(|| entry.path().is_dir())
+        })
+        .map(ignore::DirEntry::into_path)
+        .filter(move |path| filter.matches(path.strip_prefix(root).unwrap_or(path)))
+}
+
+#[derive(Debug, Clone)]
+pub enum ReplaceMessage {
+    Replaced { path: PathBuf, count: usize },
+    Failed { path: PathBuf, error: String },
+    Finished { files: usize, replacements: usize },
+}
+
+pub struct ReplaceWorker {
+    stop: Arc<AtomicBool>,
+}
+
+impl ReplaceWorker {
+    pub fn start(
+        root: PathBuf,
+        options: SearchOptions,
+        replacement: String,
+        encoding_hint: rustide_editor::TextEncodingHint,
+        tx: Sender<ReplaceMessage>,
+    ) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_thread = stop.clone();
+        std::thread::spawn(move || {
+            run_replace(root, options, replacement, encoding_hint, tx, stop_thread)
+        });
+        Self { stop }
+    }
+
+    pub fn cancel(&self) {
+        self.stop.store(true, Ordering::Release);
+    }
+}
+
+fn run_replace(
+    root: PathBuf,
+    options: SearchOptions,
+    replacement: String,
+    encoding_hint: rustide_editor::TextEncodingHint,
+    tx: Sender<ReplaceMessage>,
+    stop: Arc<AtomicBool>,
+) {
+    let mut files = 0;
+    let mut replacements = 0;
+    let matcher = LineMatcher::new(&options.query, options.case_sensitive, options.use_regex)
+        .map(|matcher| matcher.whole_word(options.whole_word))
+        .map_err(|e| format!("Invalid regex: {e}"))
+        .and_then(|matcher| Ok((matcher, FileFilter::new(options.file_glob.as_deref())?)));
+    let matcher = match matcher {
+        Ok(matcher) => Some(matcher),
+        Err(error) => {
+            let _ = tx.send(ReplaceMessage::Failed {
+                path: root.clone(),
+                error,
+            });
+            None
         }
+    };
 
-        let Ok(bytes) = std::fs::read(path) else {
-            continue;
-        };
-        let (content, _encoding) = rustide_editor::decode_bytes(&bytes, encoding_hint);
-        for (line_index, line) in content.lines().enumerate() {
-            if stop.load(Ordering::Relaxed) {
+    if let Some((matcher, filter)) = matcher.filter(|_| !options.query.trim().is_empty()) {
+        for path in project_files(&root, &filter) {
+            if stop.load(Ordering::Acquire) {
                 break;
             }
-            if !case_sensitive {
-                let lower = line.to_ascii_lowercase();
-                if let Some(byte_idx) = lower.find(&needle) {
-                    let column_chars = line[..byte_idx].chars().count();
-                    let preview = line.chars().take(200).collect();
-                    let _ = tx.send(SearchMessage::Match(
-                        request_id,
-                        SearchMatch {
-                            path: path.to_path_buf(),
-                            line_index,
-                            column_chars,
-                            preview,
-                        },
-                    ));
-                }
+            let Ok(bytes) = std::fs::read(&path) else {
                 continue;
-            }
-            if let Some(byte_idx) = line.find(&needle) {
-                let column_chars = line[..byte_idx].chars().count();
-                let preview = line.chars().take(200).collect();
-                let _ = tx.send(SearchMessage::Match(
-                    request_id,
-                    SearchMatch {
-                        path: path.to_path_buf(),
-                        line_index,
-                        column_chars,
-                        preview,
-                    },
-                ));
+            };
+            let Some((bytes, count)) =
+                replace_in_bytes(&bytes, &matcher, &replacement, encoding_hint)
+            else {
+                continue;
+            };
+            match rustide_editor::write_atomically(&path, &bytes) {
+                Ok(()) => {
+                    files += 1;
+                    replacements += count;
+                    let _ = tx.send(ReplaceMessage::Replaced { path, count });
+                }
+                Err(e) => {
+                    let _ = tx.send(ReplaceMessage::Failed {
+                        path,
+                        error: e.to_string(),
+                    });
+                }
             }
         }
     }
 
-    let _ = tx.send(SearchMessage::Finished(request_id));
+    let _ = tx.send(ReplaceMessage::Finished {
+        files,
+        replacements,
+    });
+}
+
+/// Replaces every match in a file's `bytes`, re-encoded in the encoding they were decoded
+/// with. Returns `None` when nothing matched, or when the file must be left alone: binary
+/// files (a NUL once decoded) and files that don't decode exactly, where re-encoding would
+/// overwrite bytes the user never touched.
+pub fn replace_in_bytes(
+    bytes: &[u8],
+    matcher: &LineMatcher,
+    replacement: &str,
+    encoding_hint: rustide_editor::TextEncodingHint,
+) -> Option<(Vec<u8>, usize)> {
+    let (content, encoding) = rustide_editor::decode_bytes(bytes, encoding_hint);
+    if content.contains('\0') || rustide_editor::encode_text(&content, encoding) != bytes {
+        return None;
+    }
+    let (replaced, count) = replace_in_text(&content, matcher, replacement);
+    if count == 0 {
+        return None;
+    }
+    Some((rustide_editor::encode_text(&replaced, encoding), count))
+}
+
+/// Applies `matcher` line by line (line endings are preserved and never matched) and returns
+/// the rewritten text with the number of replacements.
+pub fn replace_in_text(content: &str, matcher: &LineMatcher, replacement: &str) -> (String, usize) {
+    let mut out = String::with_capacity(content.len());
+    let mut count = 0;
+    for line in content.split_inclusive('\n') {
+        let body = line
+            .strip_suffix('\n')
+            .map(|l| l.strip_suffix('\r').unwrap_or(l))
+            .unwrap_or(line);
+        let mut pos = 0;
+        for range in matcher.find_all(body) {
+            out.push_str(&body[pos..range.start]);
+            out.push_str(replacement);
+            pos = range.end;
+            count += 1;
+        }
+        out.push_str(&line[pos..]);
+    }
+    (out, count)
 }
@@ -0,0 +1,2267 @@
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+
+use crate::project::{
+    is_word_boundary, replace_in_text, search_content, search_files, FileFilter, FileOp,
+    LineMatcher, MatchBatcher, ProjectMessage, ProjectWorker, SearchMatch, SearchMessage,
+    SearchOptions, SearchWorker, SEARCH_BATCH_SIZE,
+};
+
+#[test]
+fn regex_matcher_reports_match_start_with_capture_groups() {
+    let matcher = LineMatcher::new(r"fn (\w+)\((\w*)\)", true, true).unwrap();
+    let line = "pub fn render(frame) {";
+    let range = matcher.find_first(line).unwrap();
+    assert_eq!(&line[range.clone()], "fn render(frame)");
+    assert_eq!(line[..range.start].chars().count(), 4);
+}
+
+#[test]
+fn regex_matcher_handles_unicode_columns() {
+    let matcher = LineMatcher::new(r"\p{Han}+", true, true).unwrap();
+    let line = "let 名字 = \"世界\";";
+    let matches = crate::matches_in_line(&matcher, line);
+    assert_eq!(matches, vec![(4, 2), (10, 2)]);
+}
+
+#[test]
+fn regex_matcher_respects_case_flag() {
+    let insensitive = LineMatcher::new("hello", false, true).unwrap();
+    assert!(insensitive.find_first("Say HELLO").is_some());
+    let sensitive = LineMatcher::new("hello", true, true).unwrap();
+    assert!(sensitive.find_first("Say HELLO").is_none());
+}
+
+#[test]
+fn regex_matcher_rejects_invalid_pattern() {
+    let err = LineMatcher::new("foo(bar", true, true).unwrap_err();
+    assert!(!err.is_empty());
+}
+
+#[test]
+fn literal_matcher_ignores_ascii_case() {
+    let matcher = LineMatcher::new("Needle", false, false).unwrap();
+    assert_eq!(matcher.find_all("needle NEEDLE"), vec![0..6, 7..13]);
+}
+
+#[test]
+fn word_boundary_at_line_edges() {
+    assert!(is_word_boundary("foo", 0));
+    assert!(is_word_boundary("foo", 3));
+    assert!(!is_word_boundary("foo", 1));
+    assert!(is_word_boundary("", 0));
+}
+
+#[test]
+fn word_boundary_next_to_punctuation() {
+    let line = "(foo_bar).baz";
+    assert!(is_word_boundary(line, 1));
+    assert!(!is_word_boundary(line, 4));
+    assert!(is_word_boundary(line, 8));
+    assert!(is_word_boundary(line, 10));
+}
+
+#[test]
+fn word_boundary_treats_cjk_as_word_chars() {
+    let line = "名字 foo";
+    assert!(!is_word_boundary(line, "名".len()));
+    assert!(is_word_boundary(line, "名字".len()));
+    // Offsets inside a multi-byte char are never boundaries.
+    assert!(!is_word_boundary(line, 1));
+}
+
+#[test]
+fn whole_word_matcher_skips_partial_matches() {
+    let matcher = LineMatcher::new("foo", true, false)
+        .unwrap()
+        .whole_word(true);
+    assert_eq!(
+        matcher.find_all("foo foobar _foo (foo)"),
+        vec![0..3, 17..20]
+    );
+    let regex = LineMatcher::new(r"ba\w", true, true)
+        .unwrap()
+        .whole_word(true);
+    assert_eq!(regex.find_all("bar rebar baz"), vec![0..3, 10..13]);
+}
+
+#[test]
+fn replace_in_text_preserves_line_endings() {
+    let matcher = LineMatcher::new("foo", true, false).unwrap();
+    let (out, count) = replace_in_text("foo bar\r\nbaz foo foo\nfoo", &matcher, "qux");
+    assert_eq!(out, "qux bar\r\nbaz qux qux\nqux");
+    assert_eq!(count, 4);
+}
+
+#[test]
+fn replace_in_text_without_matches_is_unchanged() {
+    let matcher = LineMatcher::new(r"\d+", true, true).unwrap();
+    let (out, count) = replace_in_text("no digits\n", &matcher, "#");
+    assert_eq!(out, "no digits\n");
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn replace_next_starts_at_the_selection_and_wraps() {
+    let matcher = LineMatcher::new("foo", true, false).unwrap();
+    let mut editor = rustide_editor::Editor::from_text("foo bar foo\nfoo");
+    editor.select_range(5..5);
+
+    assert_eq!(
+        crate::replace_next_match(&mut editor, &matcher, "x"),
+        Some(8)
+    );
+    assert_eq!(editor.rope().to_string(), "foo bar x\nfoo");
+    // The caret lands after the replacement, so the next one is on the line below...
+    assert_eq!(
+        crate::replace_next_match(&mut editor, &matcher, "x"),
+        Some(10)
+    );
+    // ...and then the search wraps around to the top.
+    assert_eq!(
+        crate::replace_next_match(&mut editor, &matcher, "x"),
+        Some(0)
+    );
+    assert_eq!(editor.rope().to_string(), "x bar x\nx");
+    assert_eq!(crate::replace_next_match(&mut editor, &matcher, "x"), None);
+
+    // Each replacement is its own undo step.
+    assert!(editor.undo());
+    assert_eq!(editor.rope().to_string(), "foo bar x\nx");
+}
+
+#[test]
+fn replace_next_skips_text_it_inserted() {
+    let matcher = LineMatcher::new("foo", true, false).unwrap();
+    let mut editor = rustide_editor::Editor::from_text("foo foo");
+
+    assert_eq!(
+        crate::replace_next_match(&mut editor, &matcher, "foofoo"),
+        Some(0)
+    );
+    assert_eq!(
+        crate::replace_next_match(&mut editor, &matcher, "foofoo"),
+        Some(7)
+    );
+    assert_eq!(editor.rope().to_string(), "foofoo foofoo");
+}
+
+#[test]
+fn replace_all_is_one_undo_step() {
+    let matcher = LineMatcher::new("foo", true, false).unwrap();
+    let mut editor = rustide_editor::Editor::from_text("foo bar\nfoo");
+
+    assert_eq!(
+        crate::replace_all_matches(&mut editor, &matcher, "foofoo"),
+        2
+    );
+    assert_eq!(editor.rope().to_string(), "foofoo bar\nfoofoo");
+    assert!(editor.undo());
+    assert_eq!(editor.rope().to_string(), "foo bar\nfoo");
+
+    let missing = LineMatcher::new("qux", true, false).unwrap();
+    assert_eq!(crate::replace_all_matches(&mut editor, &missing, "x"), 0);
+    assert_eq!(editor.rope().to_string(), "foo bar\nfoo");
+    // The undo above was the only edit, so nothing is left to undo.
+    assert!(!editor.undo());
+}
+
+#[test]
+fn replace_in_bytes_skips_binary_and_inexact_files() {
+    use crate::project::replace_in_bytes;
+    use rustide_editor::TextEncodingHint;
+
+    let matcher = LineMatcher::new("foo", true, false).unwrap();
+    let replace = |bytes: &[u8]| replace_in_bytes(bytes, &matcher, "bar", TextEncodingHint::Auto);
+
+    assert_eq!(
+        replace(b"foo\r\nfoo\n"),
+        Some((b"bar\r\nbar\n".to_vec(), 2))
+    );
+    assert_eq!(replace(b"nothing here\n"), None);
+    // A NUL means binary, even when the rest is valid UTF-8.
+    assert_eq!(replace(b"foo\0\x01\x02"), None);
+    // Invalid UTF-8 after a UTF-8 BOM decodes to U+FFFD, which can't be written back.
+    assert_eq!(replace(b"\xEF\xBB\xBFfoo \xFF\n"), None);
+}
+
+#[test]
+fn search_content_reports_match_end_columns() {
+    let matcher = LineMatcher::new(r"b\w+", true, true).unwrap();
+    let stop = AtomicBool::new(false);
+    let hits = search_content(Path::new("a.txt"), "foo\n  bär baz\n", &matcher, 0, &stop);
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].line_index, 1);
+    assert_eq!(hits[0].column_chars, 2);
+    assert_eq!(hits[0].match_end_column_chars, 5);
+}
+
+#[test]
+fn search_content_anchors_multiline_patterns_per_line() {
+    let matcher = LineMatcher::new(r"(?m)^fn \w+$", true, true).unwrap();
+    let stop = AtomicBool::new(false);
+    let content = "fn a\n  fn b\nfn c() {}\nfn d\r\n";
+    let lines: Vec<usize> = search_content(Path::new("a.rs"), content, &matcher, 0, &stop)
+        .iter()
+        .map(|m| m.line_index)
+        .collect();
+    assert_eq!(lines, vec![0, 3]);
+
+    // Lines are matched independently, so a pattern spanning a newline never matches.
+    let across = LineMatcher::new(r"a\nb", true, true).unwrap();
+    assert!(search_content(Path::new("a.rs"), "a\nb\n", &across, 0, &stop).is_empty());
+}
+
+#[test]
+fn search_content_case_insensitive_regex() {
+    let matcher = LineMatcher::new(r"todo:\s*(\w+)", false, true).unwrap();
+    let stop = AtomicBool::new(false);
+    let hits = search_content(
+        Path::new("a.rs"),
+        "// TODO: fix\n// todo: later\n",
+        &matcher,
+        0,
+        &stop,
+    );
+    assert_eq!(hits.len(), 2);
+    assert_eq!(hits[0].match_end_column_chars, 12);
+}
+
+#[test]
+fn search_content_handles_large_files() {
+    let mut content = String::new();
+    for i in 0..200_000 {
+        content.push_str(&format!("line {i} filler text\n"));
+    }
+    let matcher = LineMatcher::new(r"line \d*7 ", true, true).unwrap();
+    let stop = AtomicBool::new(false);
+    let hits = search_content(Path::new("big.txt"), &content, &matcher, 0, &stop);
+    assert_eq!(hits.len(), 20_000);
+    assert_eq!(hits[0].line_index, 7);
+    assert_eq!(hits.last().unwrap().line_index, 199_997);
+}
+
+#[test]
+fn search_content_captures_context_lines() {
+    let matcher = LineMatcher::new("hit", true, false).unwrap();
+    let stop = AtomicBool::new(false);
+    let content = "hit 0\na\nb\nhit 3\nc\nhit 5";
+    let hits = search_content(Path::new("a.txt"), content, &matcher, 2, &stop);
+    assert_eq!(hits.len(), 3);
+
+    // Start of file: nothing before.
+    assert!(hits[0].context_before.is_empty());
+    assert_eq!(hits[0].context_after, vec!["a", "b"]);
+
+    assert_eq!(hits[1].context_before, vec!["a", "b"]);
+    assert_eq!(hits[1].context_after, vec!["c", "hit 5"]);
+
+    // End of file: nothing after.
+    assert_eq!(hits[2].context_before, vec!["hit 3", "c"]);
+    assert!(hits[2].context_after.is_empty());
+}
+
+#[test]
+fn search_content_without_context_keeps_only_match_line() {
+    let matcher = LineMatcher::new("b", true, false).unwrap();
+    let stop = AtomicBool::new(false);
+    let hits = search_content(Path::new("a.txt"), "a\nb\nc", &matcher, 0, &stop);
+    assert!(hits[0].context_before.is_empty());
+    assert!(hits[0].context_after.is_empty());
+}
+
+#[test]
+fn search_content_stops_mid_file_when_canceled() {
+    use crate::project::content_matches;
+
+    let matcher = LineMatcher::new("x", true, false).unwrap();
+    let stop = AtomicBool::new(false);
+    let content = "x\n".repeat(50_000);
+    let mut matches = content_matches(Path::new("bundle.js"), &content, &matcher, 0, &stop);
+    assert_eq!(matches.next().map(|m| m.line_index), Some(0));
+    assert_eq!(matches.next().map(|m| m.line_index), Some(1));
+    // Canceled after the first results: the rest of the file isn't searched.
+    stop.store(true, std::sync::atomic::Ordering::Release);
+    assert_eq!(matches.count(), 0);
+}
+
+#[test]
+fn file_filter_matches_file_name_only_without_slash() {
+    let filter = FileFilter::new(Some("*.rs")).unwrap();
+    assert!(filter.matches(Path::new("main.rs")));
+    assert!(filter.matches(Path::new("crates/app/src/main.rs")));
+    assert!(!filter.matches(Path::new("Cargo.toml")));
+    assert!(!filter.matches(Path::new("src.rs/readme.md")));
+}
+
+#[test]
+fn file_filter_matches_relative_path_with_slash() {
+    let filter = FileFilter::new(Some("**/*.toml")).unwrap();
+    assert!(filter.matches(Path::new("Cargo.toml")));
+    assert!(filter.matches(Path::new("crates/app/Cargo.toml")));
+    assert!(!filter.matches(Path::new("crates/app/src/main.rs")));
+
+    let nested = FileFilter::new(Some("src/*.rs")).unwrap();
+    assert!(nested.matches(Path::new("src/main.rs")));
+    assert!(!nested.matches(Path::new("src/bin/tool.rs")));
+}
+
+#[test]
+fn file_filter_empty_or_wildcard_matches_everything() {
+    for glob in [None, Some(""), Some("  "), Some("*")] {
+        let filter = FileFilter::new(glob).unwrap();
+        assert!(filter.matches(Path::new("any/file.txt")), "{glob:?}");
+    }
+    assert!(FileFilter::new(Some("[")).is_err());
+}
+
+// Synthetic tree: `files` files with `hits_per_file` matching lines each.
+fn synthetic_matches(files: usize, hits_per_file: usize) -> Vec<Vec<SearchMatch>> {
+    let matcher = LineMatcher::new("needle", true, false).unwrap();
+    let stop = AtomicBool::new(false);
+    let content = "fn needle() {}\n".repeat(hits_per_file);
+    (0..files)
+        .map(|i| {
+            let path = format!("src/mod_{}/file_{i}.rs", i / 100);
+            search_content(Path::new(&path), &content, &matcher, 0, &stop)
+        })
+        .collect()
+}
+
+fn send_and_drain(per_file: &[Vec<SearchMatch>], batch_size: usize) -> (usize, usize) {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut batcher = MatchBatcher::new(&tx, 7, batch_size);
+    for matches in per_file {
+        batcher.extend(matches.iter().cloned());
+    }
+    batcher.flush();
+    drop(tx);
+
+    let (mut messages, mut total) = (0, 0);
+    for msg in rx {
+        let SearchMessage::Batch(7, batch) = msg else {
+            panic!("unexpected message");
+        };
+        assert!(!batch.is_empty() && batch.len() <= batch_size);
+        messages += 1;
+        total += batch.len();
+    }
+    (messages, total)
+}
+
+#[test]
+fn match_batcher_delivers_every_match_in_batches() {
+    let per_file = synthetic_matches(30, 7);
+    let (messages, total) = send_and_drain(&per_file, SEARCH_BATCH_SIZE);
+    assert_eq!(total, 210);
+    assert_eq!(messages, 210usize.div_ceil(SEARCH_BATCH_SIZE));
+}
+
+// Run with `cargo test -p rustide-app --release -- --ignored --nocapture`.
+#[test]
+#[ignore = "benchmark"]
+fn bench_search_batching_throughput() {
+    let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+    let per_file = synthetic_matches(10_000, 3);
+    for batch_size in [1, SEARCH_BATCH_SIZE] {
+        let started = std::time::Instant::now();
+        let (messages, total) = send_and_drain(&per_file, batch_size);
+        tracing::info!(
+            "batch size {batch_size:>3}: {total} matches in {messages} messages, {:?}",
+            started.elapsed()
+        );
+        assert_eq!(total, 30_000);
+    }
+}
+
+// Scratch directory under the system temp dir, removed when dropped.
+struct TempTree(std::path::PathBuf);
+
+impl TempTree {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("rustide-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    fn write(&self, relative: &str, content: &str) -> std::path::PathBuf {
+        let path = self.0.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+}
+
+impl Drop for TempTree {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn parallel_search_results_are_complete_and_ordered() {
+    let tree = TempTree::new("parallel-search");
+    let mut paths: Vec<_> = (0..400)
+        .map(|i| {
+            // Bigger files late in the list so they tend to finish last.
+            let filler = "x\n".repeat(i * 5);
+            tree.write(
+                &format!("dir_{}/file_{i:03}.txt", i % 7),
+                &format!("hit a\n{filler}hit b\n"),
+            )
+        })
+        .collect();
+    paths.sort();
+
+    let matcher = LineMatcher::new("hit", true, false).unwrap();
+    let stop = AtomicBool::new(false);
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(16)
+        .build()
+        .unwrap();
+    let hits = pool.install(|| {
+        search_files(
+            &paths,
+            &matcher,
+            0,
+            rustide_editor::TextEncodingHint::Auto,
+            &stop,
+        )
+    });
+
+    assert_eq!(hits.len(), 800);
+    let keys: Vec<_> = hits
+        .iter()
+        .map(|m| (m.path.clone(), m.line_index))
+        .collect();
+    let mut sorted = keys.clone();
+    sorted.sort();
+    assert_eq!(keys, sorted);
+}
+
+#[test]
+fn search_worker_streams_sorted_results() {
+    let tree = TempTree::new("search-worker");
+    for i in (0..60).rev() {
+        tree.write(&format!("f{i:02}.rs"), "fn needle() {}\nlet needle = 1;\n");
+    }
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let options = SearchOptions {
+        query: "needle".to_string(),
+        case_sensitive: true,
+        ..SearchOptions::default()
+    };
+    let _worker = SearchWorker::start(
+        tree.0.clone(),
+        options,
+        rustide_editor::TextEncodingHint::Auto,
+        1,
+        tx,
+    );
+
+    let mut hits = Vec::new();
+    loop {
+        match rx.recv_timeout(std::time::Duration::from_secs(10)).unwrap() {
+            SearchMessage::Batch(1, batch) => hits.extend(batch),
+            SearchMessage::Finished(1) => break,
+            SearchMessage::Error(_, e) => panic!("search failed: {e}"),
+            _ => {}
+        }
+    }
+    assert_eq!(hits.len(), 120);
+    assert!(hits
+        .windows(2)
+        .all(|w| (&w[0].path, w[0].line_index) < (&w[1].path, w[1].line_index)));
+}
+
+#[test]
+fn project_worker_loads_directories_on_request() {
+    let tree = TempTree::new("lazy-tree");
+    tree.write("src/main.rs", "");
+    tree.write("src/nested/mod.rs", "");
+    tree.write("README.md", "");
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut worker =
+        ProjectWorker::start(tree.0.clone(), rustide_project::TreeOptions::default(), tx);
+    let recv = || rx.recv_timeout(std::time::Duration::from_secs(10)).unwrap();
+
+    let ProjectMessage::TreeUpdated(root) = recv() else {
+        panic!("expected the initial tree");
+    };
+    assert_eq!(root.children.len(), 2);
+    let src = root.children.iter().find(|c| c.name == "src").unwrap();
+    assert!(!src.children_loaded);
+    assert!(src.children.is_empty());
+    // The totals are counted right after the first level is listed.
+    let ProjectMessage::StatsUpdated(stats) = recv() else {
+        panic!("expected the project totals");
+    };
+    assert_eq!(stats.files, 3);
+
+    let src_path = tree.0.join("src");
+    worker.load_children(src_path.clone());
+    let (dir, children) = loop {
+        match recv() {
+            ProjectMessage::ChildrenLoaded(dir, children) => break (dir, children),
+            ProjectMessage::ListingUpdated(_) => panic!("listing walked before the UI asked"),
+            ProjectMessage::Error(e) => panic!("project worker failed: {e}"),
+            _ => {}
+        }
+    };
+    assert_eq!(dir, src_path);
+    let names: Vec<_> = children.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["main.rs", "nested"]);
+    assert!(!children[1].children_loaded);
+
+    // The listing covers directories that were never expanded, so filtering finds them.
+    worker.want_listing(true);
+    let listing = loop {
+        match recv() {
+            ProjectMessage::ListingUpdated(listing) => break listing,
+            ProjectMessage::Error(e) => panic!("project worker failed: {e}"),
+            _ => {}
+        }
+    };
+    let filtered = rustide_project::filter_tree(&listing, "mod").unwrap();
+    let nested = &filtered.children[0].children[0];
+    assert_eq!(nested.name, "nested");
+    assert_eq!(nested.children[0].path, tree.0.join("src/nested/mod.rs"));
+}
+
+#[test]
+fn project_totals_follow_watcher_events() {
+    use crate::project::{ProjectStats, ProjectTotals};
+    use rustide_project::{ProjectEvent, TreeOptions};
+
+    let tree = TempTree::new("project-totals");
+    tree.write("src/main.rs", "fn main() {}\n");
+    tree.write("README.md", "# Demo\n");
+    let root = tree.0.as_path();
+    let options = TreeOptions::default();
+    let stats = |files, size_bytes| ProjectStats { files, size_bytes };
+
+    let mut totals = ProjectTotals::count(root, options);
+    assert_eq!(totals.stats(), stats(2, 13 + 7));
+
+    // A new file, and a directory moved in with a file already inside.
+    tree.write("src/lib.rs", "//\n");
+    tree.write("docs/guide/intro.md", "hello");
+    let created = [
+        ProjectEvent::FileCreated(root.join("src/lib.rs")),
+        ProjectEvent::FileCreated(root.join("docs")),
+    ];
+    assert!(totals.apply(root, options, &created));
+    assert_eq!(totals.stats(), stats(4, 13 + 7 + 3 + 5));
+
+    tree.write("README.md", "# A longer title\n");
+    let modified = [ProjectEvent::FileModified(root.join("README.md"))];
+    assert!(totals.apply(root, options, &modified));
+    assert_eq!(totals.stats(), stats(4, 13 + 17 + 3 + 5));
+
+    std::fs::remove_dir_all(root.join("docs")).unwrap();
+    let deleted = [ProjectEvent::FileDeleted(root.join("docs"))];
+    assert!(totals.apply(root, options, &deleted));
+    assert_eq!(totals.stats(), stats(3, 13 + 17 + 3));
+
+    // Writes to files that were never counted change nothing.
+    let outside = [ProjectEvent::FileModified(root.join("target/debug/app"))];
+    assert!(!totals.apply(root, options, &outside));
+}
+
+#[test]
+fn theme_accent_drives_derived_colors() {
+    use crate::theme::{build_theme, ThemeId, DEFAULT_ACCENT};
+
+    for id in [
+        ThemeId::Dark,
+        ThemeId::Light,
+        ThemeId::SolarizedDark,
+        ThemeId::Monokai,
+        ThemeId::Dracula,
+        ThemeId::Nord,
+        ThemeId::CatppuccinMocha,
+        ThemeId::HighContrast,
+    ] {
+        let theme = build_theme(id);
+        let accent = theme.accent_color;
+        assert_eq!(theme.minimap.caret_marker, accent, "{id}");
+        assert_eq!(theme.visuals.text_cursor.stroke.color, accent, "{id}");
+        assert_eq!(theme.visuals.hyperlink_color, accent, "{id}");
+        assert_eq!(theme.visuals.selection.stroke.color, accent, "{id}");
+    }
+    assert_eq!(build_theme(ThemeId::Dark).accent_color, DEFAULT_ACCENT);
+}
+
+#[test]
+fn syntax_colors_cover_every_highlight_tag() {
+    use rustide_syntax::HighlightTag;
+
+    use crate::theme::{build_theme, ThemeId};
+
+    for id in [
+        ThemeId::Dark,
+        ThemeId::Light,
+        ThemeId::SolarizedDark,
+        ThemeId::Monokai,
+        ThemeId::Dracula,
+        ThemeId::Nord,
+        ThemeId::CatppuccinMocha,
+        ThemeId::HighContrast,
+    ] {
+        let syntax = build_theme(id).syntax;
+        assert_eq!(syntax.for_tag(HighlightTag::Macro), syntax.r#macro, "{id}");
+        assert_eq!(
+            syntax.for_tag(HighlightTag::Attribute),
+            syntax.attribute,
+            "{id}"
+        );
+        assert_eq!(
+            syntax.for_tag(HighlightTag::Lifetime),
+            syntax.lifetime,
+            "{id}"
+        );
+        assert_eq!(syntax.for_tag(HighlightTag::Label), syntax.label, "{id}");
+        assert_eq!(
+            syntax.for_tag(HighlightTag::Namespace),
+            syntax.namespace,
+            "{id}"
+        );
+        assert_eq!(syntax.for_tag(HighlightTag::Regex), syntax.regex, "{id}");
+        assert_eq!(syntax.for_tag(HighlightTag::Error), syntax.error, "{id}");
+        assert_ne!(syntax.lifetime, syntax.keyword, "{id}");
+        assert_ne!(syntax.regex, syntax.string, "{id}");
+        assert_ne!(syntax.label, syntax.fallback, "{id}");
+    }
+}
+
+#[test]
+fn minimap_font_scales_with_width() {
+    use crate::editor_view::minimap_font_metrics;
+
+    let (default, default_chars) = minimap_font_metrics(80.0);
+    let (wide, wide_chars) = minimap_font_metrics(120.0);
+    assert_eq!(default, 6.0);
+    assert_eq!(wide, 9.0);
+    assert!(default_chars >= 8 && wide_chars >= 8);
+    assert_eq!(minimap_font_metrics(40.0).0, 3.0);
+    assert_eq!(minimap_font_metrics(1000.0).0, 10.0);
+}
+
+#[test]
+fn minimap_ticks_one_line_per_pixel_row() {
+    use crate::editor_view::minimap_match_lines;
+
+    let rope = ropey::Rope::from_str(&"ab ab\n".repeat(1000));
+    // Two matches on every line.
+    let matches: Vec<usize> = (0..1000).flat_map(|l| [l * 6, l * 6 + 3]).collect();
+    // Ten lines per pixel: one tick for each ten lines.
+    let lines = minimap_match_lines(&rope, &matches, 0.1);
+    assert_eq!(lines, (0..1000).step_by(10).collect::<Vec<_>>());
+    // Two pixels per line: every line.
+    let lines = minimap_match_lines(&rope, &matches, 2.0);
+    assert_eq!(lines, (0..1000).collect::<Vec<_>>());
+
+    assert_eq!(minimap_match_lines(&rope, &[600, 4000], 0.0), [100]);
+    assert_eq!(minimap_match_lines(&rope, &[], 1.0), Vec::<usize>::new());
+}
+
+#[test]
+fn folded_lines_are_skipped_in_row_mapping() {
+    use crate::editor_view::visible_lines;
+
+    assert_eq!(visible_lines(4, []), [0, 1, 2, 3]);
+    // Nested folds overlap; the outer one wins.
+    assert_eq!(visible_lines(8, [1..=5, 3..=4]), [0, 6, 7]);
+    assert_eq!(visible_lines(6, [1..=1, 3..=9]), [0, 2]);
+}
+
+#[test]
+fn folds_follow_edits_before_them() {
+    use crate::editor_view::shift_folds;
+    use std::collections::HashSet;
+
+    // Folds start at the two `fn`s.
+    let mut editor = rustide_editor::Editor::from_text("fn a() {\n}\nfn b() {\n}\n");
+    let mut folded = HashSet::from([0, 11]);
+    let drain = |editor: &mut rustide_editor::Editor, folded: &mut HashSet<usize>| {
+        for edit in editor.take_last_edits() {
+            shift_folds(folded, &edit);
+        }
+    };
+
+    // Typing before both folds moves them; typing between them only moves the second.
+    editor.set_cursor(0, false);
+    editor.insert_text("// x\n");
+    drain(&mut editor, &mut folded);
+    assert_eq!(folded, HashSet::from([5, 16]));
+    editor.set_cursor(15, false);
+    editor.insert_text("\n");
+    drain(&mut editor, &mut folded);
+    assert_eq!(folded, HashSet::from([5, 17]));
+
+    // Deleting the text a fold starts on drops that fold.
+    editor.set_cursor(5, false);
+    editor.set_cursor(7, true);
+    editor.insert_text("");
+    drain(&mut editor, &mut folded);
+    assert_eq!(folded, HashSet::from([15]));
+}
+
+#[test]
+fn editor_config_round_trips_per_language_sections() {
+    let tree = TempTree::new("editor-config");
+    let path = tree.write(
+        "config.ini",
+        "[editor]\ntab_width=2\ntrim_trailing_whitespace=true\n\n[editor.python]\ntab_width=4\n\n[editor.go]\nuse_tabs=true\n",
+    );
+    let cfg = crate::config::AppConfig::load_or_default(&path);
+    assert_eq!(cfg.editor.defaults.tab_width, 2);
+    // Language sections inherit what `[editor]` set.
+    let python = cfg.editor.per_language["python"];
+    assert_eq!(python.tab_width, 4);
+    assert!(python.trim_trailing_whitespace);
+    assert_eq!(
+        cfg.editor
+            .resolve(rustide_syntax::LanguageId::Go, None)
+            .indent_unit(),
+        "\t"
+    );
+    assert_eq!(
+        cfg.editor
+            .resolve(rustide_syntax::LanguageId::Rust, None)
+            .indent_unit(),
+        "  "
+    );
+
+    cfg.save(&path).unwrap();
+    let reloaded = crate::config::AppConfig::load_or_default(&path);
+    assert_eq!(reloaded.editor.defaults, cfg.editor.defaults);
+    assert_eq!(reloaded.editor.per_language, cfg.editor.per_language);
+}
+
+#[test]
+fn language_ids_serialize_as_their_config_keys() {
+    use crate::config::language_key;
+    use rustide_syntax::LanguageId;
+
+    for &language in LanguageId::ALL {
+        let json = serde_json::to_string(&language).unwrap();
+        assert_eq!(json, format!("\"{}\"", language_key(language)));
+        assert_eq!(serde_json::from_str::<LanguageId>(&json).unwrap(), language);
+    }
+    assert_eq!(
+        serde_json::from_str::<LanguageId>("\"plain-text\"").unwrap(),
+        LanguageId::PlainText
+    );
+}
+
+#[test]
+fn editorconfig_overrides_ide_settings() {
+    let mut settings = crate::config::EditorSettings::default();
+    settings.per_language.insert(
+        "python".to_string(),
+        crate::config::EditorConfig {
+            tab_width: 3,
+            ..Default::default()
+        },
+    );
+    let python = rustide_syntax::LanguageId::Python;
+    let overrides = rustide_editor::EditorConfig {
+        indent_style: Some(rustide_editor::IndentStyle::Tab),
+        insert_final_newline: Some(true),
+        ..Default::default()
+    };
+
+    let resolved = settings.resolve(python, Some(&overrides));
+    assert!(resolved.use_tabs);
+    assert!(resolved.insert_final_newline);
+    // Properties the `.editorconfig` leaves unset keep the IDE value.
+    assert_eq!(resolved.tab_width, 3);
+    assert!(!resolved.trim_trailing_whitespace);
+    assert_eq!(settings.resolve(python, None).indent_unit(), "   ");
+
+    // Tab indentation takes `tab_width`; space indentation takes `indent_size`.
+    let overrides = rustide_editor::EditorConfig {
+        indent_size: Some(rustide_editor::IndentSize::Columns(2)),
+        tab_width: Some(8),
+        ..overrides
+    };
+    assert_eq!(settings.resolve(python, Some(&overrides)).tab_width, 8);
+    let overrides = rustide_editor::EditorConfig {
+        indent_style: Some(rustide_editor::IndentStyle::Space),
+        ..overrides
+    };
+    assert_eq!(
+        settings.resolve(python, Some(&overrides)).indent_unit(),
+        "  "
+    );
+}
+
+#[test]
+fn editor_config_cleans_up_saved_text() {
+    let file = crate::config::FileConfig::default();
+    let editor = file.save_cleanup(crate::config::EditorConfig {
+        trim_trailing_whitespace: true,
+        insert_final_newline: true,
+        ..Default::default()
+    });
+    assert_eq!(
+        editor.prepare_for_save("a  \r\nb\t\r\nc ".to_string()),
+        "a\r\nb\r\nc\r\n"
+    );
+    assert_eq!(editor.prepare_for_save(String::new()), "");
+    // A final newline is only added; blank lines at the end stay.
+    assert_eq!(editor.prepare_for_save("a\n\n\n".to_string()), "a\n\n\n");
+    assert_eq!(
+        editor.prepare_for_save("a\r\n\r\n".to_string()),
+        "a\r\n\r\n"
+    );
+    assert_eq!(
+        file.save_cleanup(crate::config::EditorConfig::default())
+            .prepare_for_save("x \n".to_string()),
+        "x \n"
+    );
+}
+
+#[test]
+fn ruler_offset_scales_with_char_width() {
+    use crate::editor_view::ruler_offset;
+
+    // Monospace advance widths for 12, 14 and 20 px fonts.
+    for (char_width, at_80) in [(7.2, 576.0), (8.4, 672.0), (12.0, 960.0)] {
+        assert!((ruler_offset(80, char_width) - at_80).abs() < 1e-3);
+        assert!((ruler_offset(120, char_width) - 1.5 * at_80).abs() < 1e-3);
+    }
+    assert_eq!(ruler_offset(0, 8.4), 0.0);
+}
+
+#[test]
+fn ruler_columns_parse_from_ui_section() {
+    let tree = TempTree::new("ruler-config");
+    let path = tree.write("config.ini", "[ui]\nruler_columns=120, 80,x,80\n");
+    let cfg = crate::config::AppConfig::load_or_default(&path);
+    assert_eq!(cfg.ui.ruler_columns, [80, 120]);
+    assert!(cfg.to_string().contains("ruler_columns=80,120\n"));
+    assert!(crate::config::AppConfig::default()
+        .to_string()
+        .contains("ruler_columns=\n"));
+}
+
+#[test]
+fn current_line_highlight_follows_the_caret() {
+    use crate::editor_view::highlighted_line;
+
+    let mut editor = rustide_editor::Editor::from_text("one\ntwo\nthree");
+    assert_eq!(highlighted_line(&editor, true), Some(0));
+    editor.move_down(false);
+    assert_eq!(highlighted_line(&editor, true), Some(1));
+    editor.set_cursor(editor.rope().len_chars(), false);
+    assert_eq!(highlighted_line(&editor, true), Some(2));
+    // Unfocused (or disabled) editors draw no highlight.
+    assert_eq!(highlighted_line(&editor, false), None);
+}
+
+#[test]
+fn visible_whitespace_substitutes_glyphs_in_the_layout_job() {
+    use crate::config::ShowWhitespace;
+    use crate::editor_view::{append_styled_line, StyledLineArgs, WhitespaceGlyphs};
+    use eframe::egui::{text::LayoutJob, Color32, FontId};
+
+    let theme = crate::theme::build_theme(crate::theme::ThemeId::Dark);
+    let font_id = FontId::monospace(14.0);
+    let layout = |mode: ShowWhitespace, selection: Option<std::ops::Range<usize>>| {
+        let mut job = LayoutJob::default();
+        append_styled_line(
+            &mut job,
+            "a b\tc d",
+            StyledLineArgs {
+                selection,
+                font_id: &font_id,
+                selection_bg: Color32::BLUE,
+                selection_fg: theme.selection_fg,
+                highlight_spans: &[],
+                line_start_byte: 0,
+                syntax_colors: &theme.syntax,
+                whitespace: WhitespaceGlyphs {
+                    mode,
+                    color: Color32::RED,
+                    tab_padding: 12.0,
+                },
+            },
+        );
+        job
+    };
+
+    assert_eq!(layout(ShowWhitespace::None, None).text, "a b\tc d");
+    let all = layout(ShowWhitespace::All, None);
+    assert_eq!(all.text, "a\u{b7}b\u{2192}c\u{b7}d");
+    // Same char count as the buffer line, so char offsets map 1:1 onto the galley.
+    assert_eq!(all.text.chars().count(), "a b\tc d".chars().count());
+    let glyph_sections: Vec<_> = all
+        .sections
+        .iter()
+        .filter(|section| section.format.color == Color32::RED)
+        .map(|section| {
+            (
+                &all.text[section.byte_range.clone()],
+                section.format.extra_letter_spacing,
+            )
+        })
+        .collect();
+    assert_eq!(
+        glyph_sections,
+        [("\u{b7}", 0.0), ("\u{2192}", 12.0), ("\u{b7}", 0.0)]
+    );
+
+    // Only the selected chars (`b\tc`) are decorated in selection mode.
+    let selected = layout(ShowWhitespace::Selection, Some(2..5));
+    assert_eq!(selected.text, "a b\u{2192}c d");
+}
+
+#[test]
+fn occurrence_highlight_finds_whole_words_only() {
+    use crate::editor_view::{word_occurrences, word_under_cursor};
+
+    let rope = ropey::Rope::from_str("let foo = foo_bar + foo;");
+    // Inside the word, and right after its last char.
+    assert_eq!(word_under_cursor(&rope, 5), Some(4..7));
+    assert_eq!(word_under_cursor(&rope, 7), Some(4..7));
+    // Between a space and punctuation there is no identifier.
+    assert_eq!(word_under_cursor(&rope, 8), None);
+
+    let line = "let foo = foo_bar + foo; ffoo(foo)";
+    assert_eq!(word_occurrences(line, "foo", usize::MAX), [4, 20, 30]);
+    assert_eq!(word_occurrences(line, "foo", 2), [4, 20]);
+    assert_eq!(word_occurrences("ünï ünï", "ünï", usize::MAX), [0, 4]);
+}
+
+#[test]
+fn indent_guides_count_whole_levels() {
+    use crate::editor_view::{indent_columns, indent_depth};
+
+    assert_eq!(indent_columns("    "), 4);
+    assert_eq!(indent_columns("  "), 2);
+    assert_eq!(indent_columns("\t"), 4);
+
+    assert_eq!(indent_depth("        x".chars(), 4), 2);
+    // A partial level doesn't get a guide.
+    assert_eq!(indent_depth("      x".chars(), 4), 1);
+    assert_eq!(indent_depth("\t  x".chars(), 2), 3);
+    assert_eq!(indent_depth("x    ".chars(), 4), 0);
+}
+
+#[test]
+fn caret_blink_restarts_on_activity() {
+    use crate::editor_view::CaretBlink;
+    use std::time::{Duration, Instant};
+
+    let start = Instant::now();
+    let ms = Duration::from_millis;
+    let mut editor = rustide_editor::Editor::from_text("abc");
+    let mut blink = CaretBlink::new(start);
+
+    assert!(blink.is_visible(&editor, start + ms(100), Some(500)));
+    assert!(!blink.is_visible(&editor, start + ms(600), Some(500)));
+    assert!(blink.is_visible(&editor, start + ms(1100), Some(500)));
+    // Blinking off keeps the caret shown.
+    assert!(blink.is_visible(&editor, start + ms(600), None));
+
+    // A move restarts the shown half of the period.
+    editor.move_right(false);
+    assert!(blink.is_visible(&editor, start + ms(1600), Some(500)));
+    assert!(blink.is_visible(&editor, start + ms(2000), Some(500)));
+    assert!(!blink.is_visible(&editor, start + ms(2200), Some(500)));
+
+    // So does an edit.
+    editor.insert_text("x");
+    assert!(blink.is_visible(&editor, start + ms(2300), Some(500)));
+}
+
+#[test]
+fn caret_shapes_cover_the_expected_area() {
+    use crate::config::CursorStyle;
+    use crate::editor_view::caret_shape;
+    use eframe::egui::{pos2, Rect};
+
+    // The galley reports a zero-width caret at x = 10 spanning the row.
+    let caret = Rect::from_min_max(pos2(10.0, 4.0), pos2(10.0, 20.0));
+
+    let line = caret_shape(CursorStyle::Line, caret, 8.0);
+    assert_eq!(line, Rect::from_min_max(pos2(9.0, 4.0), pos2(11.0, 20.0)));
+
+    let block = caret_shape(CursorStyle::Block, caret, 8.0);
+    assert_eq!(block, Rect::from_min_max(pos2(10.0, 4.0), pos2(18.0, 20.0)));
+
+    let underline = caret_shape(CursorStyle::Underline, caret, 8.0);
+    assert_eq!(
+        underline,
+        Rect::from_min_max(pos2(10.0, 18.0), pos2(18.0, 20.0))
+    );
+
+    assert_eq!("block".parse::<CursorStyle>(), Ok(CursorStyle::Block));
+    assert_eq!(CursorStyle::Underline.to_string(), "underline");
+}
+
+#[test]
+fn font_zoom_accumulates_smooth_wheel_deltas() {
+    use crate::editor_view::zoom_steps;
+
+    let mut pending = 0.0;
+    assert_eq!(zoom_steps(&mut pending, 0.4), 0);
+    assert_eq!(zoom_steps(&mut pending, 0.4), 0);
+    assert_eq!(zoom_steps(&mut pending, 0.4), 1);
+    assert!((pending - 0.2).abs() < 1e-5);
+    assert_eq!(zoom_steps(&mut pending, -2.5), -2);
+    assert!((pending + 0.3).abs() < 1e-5);
+    assert_eq!(zoom_steps(&mut pending, 3.0), 2);
+}
+
+#[test]
+fn minimap_lines_use_syntax_colors() {
+    use crate::editor_view::minimap_line_job;
+    use rustide_syntax::{LanguageId, SyntaxState};
+
+    let theme = crate::theme::build_theme(crate::theme::ThemeId::Dark);
+    let rope = ropey::Rope::from_str("let x = 1;\n// note\n");
+    let mut syntax = SyntaxState::new(LanguageId::Rust).unwrap();
+    syntax.set_text(&rope).unwrap();
+
+    let line_start_byte = rope.line_to_byte(1);
+    let spans = syntax
+        .highlight_spans(&rope, line_start_byte..line_start_byte + "// note".len())
+        .unwrap();
+    let job = minimap_line_job("// note", &spans, line_start_byte, 5.0, &theme);
+    assert_eq!(job.text, "// note");
+    assert!(job
+        .sections
+        .iter()
+        .all(|section| section.format.color == theme.syntax.comment));
+
+    // Without spans the line keeps the minimap's text color.
+    let plain = minimap_line_job("// note", &[], line_start_byte, 5.0, &theme);
+    assert_eq!(plain.sections[0].format.color, theme.minimap.text);
+}
+
+#[test]
+fn theme_ids_round_trip_through_their_names() {
+    use crate::theme::ThemeId;
+
+    for id in [
+        ThemeId::Dark,
+        ThemeId::Light,
+        ThemeId::SolarizedDark,
+        ThemeId::Monokai,
+        ThemeId::Dracula,
+        ThemeId::Nord,
+        ThemeId::CatppuccinMocha,
+        ThemeId::HighContrast,
+        ThemeId::Custom,
+    ] {
+        assert_eq!(id.to_string().parse::<ThemeId>(), Ok(id));
+    }
+    assert_eq!(" Dracula ".parse::<ThemeId>(), Ok(ThemeId::Dracula));
+}
+
+#[test]
+fn nord_sets_every_syntax_color() {
+    use eframe::egui::Color32;
+
+    use crate::theme::{build_theme, ThemeId};
+
+    let s = build_theme(ThemeId::Nord).syntax;
+    let colors = [
+        s.comment,
+        s.r#macro,
+        s.attribute,
+        s.lifetime,
+        s.label,
+        s.namespace,
+        s.string,
+        s.regex,
+        s.number,
+        s.keyword,
+        s.r#type,
+        s.function,
+        s.constant,
+        s.variable,
+        s.property,
+        s.operator,
+        s.punctuation,
+        s.error,
+        s.fallback,
+    ];
+    for color in colors {
+        assert_ne!(color, Color32::TRANSPARENT);
+        assert_ne!(color, Color32::BLACK);
+    }
+    assert_eq!(s.error, Color32::from_rgb(0xBF, 0x61, 0x6A));
+}
+
+#[test]
+fn custom_theme_file_overrides_base_colors() {
+    use eframe::egui::Color32;
+
+    use crate::theme::{build_theme, parse_custom_theme, ThemeId};
+
+    let theme = parse_custom_theme(
+        "# My theme\n\
+         [syntax]\n\
+         comment=#6A9955\n\
+         keyword = #ff79c680\n\
+         \n\
+         [minimap]\n\
+         background=#101010\n\
+         \n\
+         [visuals]\n\
+         panel_fill=#202020\n\
+         accent_color=#00FF00\n",
+    )
+    .unwrap();
+    assert_eq!(theme.syntax.comment, Color32::from_rgb(0x6A, 0x99, 0x55));
+    assert_eq!(
+        theme.syntax.keyword,
+        Color32::from_rgba_unmultiplied(0xFF, 0x79, 0xC6, 0x80)
+    );
+    assert_eq!(theme.minimap.background, Color32::from_rgb(16, 16, 16));
+    assert_eq!(theme.visuals.panel_fill, Color32::from_rgb(32, 32, 32));
+    // The accent still drives the derived colors.
+    assert_eq!(theme.minimap.caret_marker, Color32::GREEN);
+    // Unset keys keep the Dark theme's colors.
+    assert_eq!(
+        theme.syntax.string,
+        build_theme(ThemeId::Dark).syntax.string
+    );
+
+    let light = parse_custom_theme("[visuals]\ndark_mode=false\n").unwrap();
+    assert!(!light.visuals.dark_mode);
+
+    assert!(parse_custom_theme("[syntax]\ncomment=green\n").is_err());
+    assert!(parse_custom_theme("[syntax]\nnot_a_field=#000000\n").is_err());
+}
+
+#[test]
+fn high_contrast_theme_meets_wcag_aa() {
+    use eframe::egui::Color32;
+
+    use crate::theme::{build_theme, SyntaxColors, ThemeId};
+
+    assert!((SyntaxColors::contrast_ratio(Color32::WHITE, Color32::BLACK) - 21.0).abs() < 1e-9);
+    assert!((SyntaxColors::contrast_ratio(Color32::RED, Color32::RED) - 1.0).abs() < 1e-9);
+
+    let theme = build_theme(ThemeId::HighContrast);
+    let s = theme.syntax;
+    let syntax = [
+        s.comment,
+        s.r#macro,
+        s.attribute,
+        s.lifetime,
+        s.label,
+        s.namespace,
+        s.string,
+        s.regex,
+        s.number,
+        s.keyword,
+        s.r#type,
+        s.function,
+        s.constant,
+        s.variable,
+        s.property,
+        s.operator,
+        s.punctuation,
+        s.error,
+        s.fallback,
+    ];
+    let backgrounds = [
+        theme.visuals.panel_fill,
+        theme.visuals.extreme_bg_color,
+        theme.current_line_bg,
+    ];
+    let assert_readable = |fg: Color32, bg: Color32| {
+        let ratio = SyntaxColors::contrast_ratio(fg, bg);
+        assert!(ratio >= 4.5, "{fg:?} on {bg:?}: {ratio:.2}");
+    };
+    for fg in syntax {
+        for bg in backgrounds {
+            assert_readable(fg, bg);
+        }
+        // Occurrences keep their syntax colors.
+        assert_readable(fg, theme.occurrence_bg);
+    }
+    // Selected text switches to `selection_fg` on the accent-derived selection fill.
+    assert_ne!(theme.selection_fg, Color32::PLACEHOLDER);
+    assert_readable(theme.selection_fg, theme.visuals.selection.bg_fill);
+    for fg in [theme.visuals.weak_text_color(), theme.minimap.text] {
+        for bg in backgrounds {
+            assert_readable(fg, bg);
+        }
+    }
+}
+
+#[test]
+fn file_icons_follow_the_extension() {
+    use crate::{dir_icon, icon_for_path};
+
+    assert_eq!(icon_for_path(Path::new("src/main.rs")), '🦀');
+    assert_eq!(icon_for_path(Path::new("tools/build.PY")), '🐍');
+    assert_eq!(icon_for_path(Path::new("Cargo.toml")), '📋');
+    assert_eq!(icon_for_path(Path::new("package.json")), '📋');
+    assert_eq!(icon_for_path(Path::new("assets/logo.png")), '🖼');
+    assert_eq!(icon_for_path(Path::new("docs/shot.JPEG")), '🖼');
+    assert_eq!(icon_for_path(Path::new("README.md")), '📄');
+    assert_eq!(icon_for_path(Path::new("Makefile")), '📄');
+    assert_eq!(dir_icon(true), '📂');
+    assert_eq!(dir_icon(false), '📁');
+}
+
+#[test]
+fn file_ops_create_rename_and_delete() {
+    let tree = TempTree::new("file-ops");
+    let dir = tree.0.join("notes");
+    let file = dir.join("todo.md");
+
+    FileOp::CreateDir(dir.clone()).apply().unwrap();
+    FileOp::CreateFile(file.clone()).apply().unwrap();
+    assert!(file.is_file());
+    // Creating never truncates an existing file.
+    std::fs::write(&file, "keep").unwrap();
+    assert!(FileOp::CreateFile(file.clone()).apply().is_err());
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "keep");
+
+    let other = tree.write("notes/done.md", "");
+    let rename_onto = FileOp::Rename {
+        from: file.clone(),
+        to: other.clone(),
+    };
+    assert!(rename_onto.apply().is_err());
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "keep");
+
+    let renamed = dir.join("later.md");
+    FileOp::Rename {
+        from: file.clone(),
+        to: renamed.clone(),
+    }
+    .apply()
+    .unwrap();
+    assert!(!file.exists() && renamed.is_file());
+
+    FileOp::Delete(renamed.clone()).apply().unwrap();
+    assert!(!renamed.exists());
+    FileOp::Delete(dir.clone()).apply().unwrap();
+    assert!(!dir.exists());
+    assert!(FileOp::Delete(dir).apply().is_err());
+}
+
+#[test]
+fn entry_names_are_single_path_components() {
+    use crate::validate_entry_name;
+
+    assert_eq!(validate_entry_name("  main.rs "), Ok("main.rs"));
+    assert_eq!(validate_entry_name(".gitignore"), Ok(".gitignore"));
+    for bad in ["", "   ", ".", "..", "src/main.rs", "a\\b"] {
+        assert!(
+            validate_entry_name(bad).is_err(),
+            "{bad:?} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn project_stats_round_sizes_up_to_kilobytes() {
+    use crate::format_project_stats;
+
+    assert_eq!(format_project_stats(0, 0), "0 files, 0 KB");
+    assert_eq!(format_project_stats(1, 1), "1 file, 1 KB");
+    assert_eq!(format_project_stats(3, 2048), "3 files, 2 KB");
+    assert_eq!(format_project_stats(12, 2049), "12 files, 3 KB");
+}
+
+#[test]
+fn watcher_events_reload_only_loaded_parents() {
+    use crate::project::refresh_targets;
+    use rustide_project::ProjectEvent;
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    let root = PathBuf::from("/p");
+    let expanded: HashSet<PathBuf> = [root.join("src"), root.join("src/ui")].into();
+    let targets = |events: &[ProjectEvent]| {
+        refresh_targets(&root, &expanded, events).map(|dirs| dirs.into_iter().collect::<Vec<_>>())
+    };
+
+    assert_eq!(
+        targets(&[ProjectEvent::FileCreated(root.join("src/ui/view.rs"))]),
+        Some(vec![root.join("src/ui")])
+    );
+    // Writing to a file changes nothing in the tree.
+    assert_eq!(
+        targets(&[ProjectEvent::FileModified(root.join("src/ui/view.rs"))]),
+        Some(vec![])
+    );
+    // A reload of `src` relists `src/ui` too.
+    assert_eq!(
+        targets(&[
+            ProjectEvent::FileCreated(root.join("src/ui/new.rs")),
+            ProjectEvent::FileDeleted(root.join("src/old.rs")),
+        ]),
+        Some(vec![root.join("src")])
+    );
+    assert_eq!(
+        targets(&[ProjectEvent::FileCreated(root.join("README.md"))]),
+        Some(vec![root.clone()])
+    );
+    // Nothing under `target` is listed yet.
+    assert_eq!(
+        targets(&[ProjectEvent::FileCreated(root.join("target/debug/app"))]),
+        Some(vec![])
+    );
+    assert_eq!(targets(&[ProjectEvent::Changed]), None);
+    assert_eq!(targets(&[ProjectEvent::FileDeleted(root.clone())]), None);
+
+    let many: HashSet<PathBuf> = (0..20).map(|i| root.join(format!("d{i}"))).collect();
+    let events: Vec<_> = many
+        .iter()
+        .map(|dir| ProjectEvent::FileCreated(dir.join("f")))
+        .collect();
+    assert_eq!(refresh_targets(&root, &many, &events), None);
+}
+
+#[test]
+fn disk_changes_reload_clean_documents_and_ask_for_dirty_ones() {
+    use crate::{DiskChange, OpenDocument};
+    use std::time::{Duration, SystemTime};
+
+    let saved_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+    let later = saved_at + Duration::from_secs(5);
+    let mut doc = OpenDocument::empty();
+    doc.disk_modified = Some(saved_at);
+
+    // Our own save: the file's time is the one recorded after writing it.
+    assert_eq!(doc.on_disk_change(Some(saved_at)), DiskChange::Ignore);
+    assert_eq!(doc.on_disk_change(Some(later)), DiskChange::Reload);
+
+    doc.doc.editor.insert_text("local edit");
+    assert_eq!(doc.on_disk_change(Some(later)), DiskChange::AskUser);
+    // The banner stays away while the file still matches what we last saw.
+    assert_eq!(doc.on_disk_change(Some(saved_at)), DiskChange::Ignore);
+
+    // A save in flight explains the change; its result records the new time.
+    doc.pending_save_version = Some(doc.doc.editor.version());
+    assert_eq!(doc.on_disk_change(Some(later)), DiskChange::Ignore);
+}
+
+#[test]
+fn language_override_resets_folds_and_preview() {
+    use crate::OpenDocument;
+    use rustide_syntax::LanguageId;
+
+    let mut doc = OpenDocument::empty();
+    doc.doc.editor.insert_text("fn main() {\n}\n");
+    doc.folded.insert(0);
+
+    doc.set_language_override(Some(LanguageId::Markdown));
+    assert_eq!(doc.language(), LanguageId::Markdown);
+    assert!(doc.markdown.is_some());
+    assert!(doc.folded.is_empty());
+
+    doc.folded.insert(0);
+    doc.set_language_override(Some(LanguageId::Rust));
+    assert_eq!(doc.language_override, Some(LanguageId::Rust));
+    assert!(doc.markdown.is_none());
+    assert!(doc.folded.is_empty());
+}
+
+#[test]
+fn autosave_waits_for_the_configured_interval() {
+    use crate::autosave_due;
+    use std::time::{Duration, Instant};
+
+    let tree = TempTree::new("autosave-config");
+    let path = tree.write(
+        "config.ini",
+        "[ui]\nautosave_enabled=false\nautosave_interval_secs=900\n",
+    );
+    let cfg = crate::config::AppConfig::load_or_default(&path);
+    assert!(!cfg.ui.autosave_enabled);
+    assert_eq!(cfg.ui.autosave_interval_secs, 300);
+    assert!(cfg.to_string().contains("autosave_interval_secs=300\n"));
+    let path = tree.write("config.ini", "[ui]\nautosave_interval_secs=0\n");
+    assert_eq!(
+        crate::config::AppConfig::load_or_default(&path)
+            .ui
+            .autosave_interval_secs,
+        1
+    );
+
+    let interval = crate::config::UiConfig {
+        autosave_interval_secs: 5,
+        ..crate::config::UiConfig::default()
+    }
+    .autosave_interval();
+    let saved = Instant::now();
+    assert!(autosave_due(None, saved, interval));
+    assert!(!autosave_due(
+        Some(saved),
+        saved + Duration::from_secs(4),
+        interval
+    ));
+    assert!(autosave_due(
+        Some(saved),
+        saved + Duration::from_secs(5),
+        interval
+    ));
+}
+
+#[test]
+fn backup_on_save_round_trips_through_the_file_section() {
+    let tree = TempTree::new("backup-config");
+    let path = tree.write("config.ini", "[file]\nbackup_on_save=true\n");
+    let cfg = crate::config::AppConfig::load_or_default(&path);
+    assert!(cfg.file.backup_on_save);
+    assert!(cfg
+        .to_string()
+        .contains("[file]\nencoding=auto\nbackup_on_save=true\n"));
+    assert!(!crate::config::AppConfig::default().file.backup_on_save);
+}
+
+#[test]
+fn file_section_cleanup_applies_to_every_file() {
+    let tree = TempTree::new("file-cleanup");
+    let path = tree.write(
+        "config.ini",
+        "[file]\ntrim_trailing_whitespace=true\ninsert_final_newline=true\n",
+    );
+    let file = crate::config::AppConfig::load_or_default(&path).file;
+    let cleanup = file.save_cleanup(crate::config::EditorConfig::default());
+    assert_eq!(
+        cleanup.prepare_for_save("fn main() { \t\n  x;  \n}".to_string()),
+        "fn main() {\n  x;\n}\n"
+    );
+    assert_eq!(cleanup.prepare_for_save("done\n".to_string()), "done\n");
+    // `[file]` collapses trailing newlines to exactly one.
+    assert_eq!(cleanup.prepare_for_save("a\n\n\n".to_string()), "a\n");
+    assert_eq!(cleanup.prepare_for_save("a\r\n\r\n".to_string()), "a\r\n");
+    assert_eq!(cleanup.prepare_for_save("\n\n".to_string()), "\n");
+
+    // Off by default, leaving per-language settings in charge.
+    let defaults = crate::config::FileConfig::default();
+    let plain = defaults.save_cleanup(crate::config::EditorConfig::default());
+    assert_eq!(plain.prepare_for_save("a \nb".to_string()), "a \nb");
+    let per_language = crate::config::EditorConfig {
+        insert_final_newline: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        defaults
+            .save_cleanup(per_language)
+            .prepare_for_save("a \nb".to_string()),
+        "a \nb\n"
+    );
+    assert_eq!(
+        defaults
+            .save_cleanup(per_language)
+            .prepare_for_save("a\n\n".to_string()),
+        "a\n\n"
+    );
+}
+
+#[test]
+fn default_line_ending_overrides_the_detected_one() {
+    use rustide_editor::{Document, LineEnding, TextEncodingHint};
+
+    let tree = TempTree::new("default-line-ending");
+    let path = tree.write("config.ini", "[file]\ndefault_line_ending=CRLF\n");
+    let cfg = crate::config::AppConfig::load_or_default(&path);
+    assert_eq!(cfg.file.default_line_ending, Some(LineEnding::CrLf));
+    assert!(cfg.to_string().contains("default_line_ending=crlf\n"));
+
+    let mut doc = Document::from_bytes(None, b"a\nb\r\n", TextEncodingHint::Utf8);
+    let auto = crate::config::FileConfig::default();
+    assert_eq!(auto.default_line_ending, None);
+    assert_eq!(auto.line_ending_for(&doc), None);
+    // Without a setting, mixed line endings are written back as they are.
+    assert_eq!(doc.to_bytes(), b"a\nb\r\n");
+
+    doc.set_line_ending(cfg.file.line_ending_for(&doc).unwrap());
+    assert_eq!(doc.to_bytes(), b"a\r\nb\r\n");
+    // The buffer itself is untouched.
+    assert_eq!(doc.editor.rope().to_string(), "a\nb\r\n");
+}
+
+#[test]
+fn hex_rows_show_offset_bytes_and_ascii() {
+    use crate::hex_view::{row_count, HexRow, BYTES_PER_ROW};
+
+    let bytes: Vec<u8> = b"Hello, hex view!\x00\x01\x7f \xff".to_vec();
+    assert_eq!(row_count(&bytes), 2);
+    assert_eq!(row_count(&[]), 0);
+
+    let mut first = HexRow::default();
+    assert!(first.fill(&bytes, 0));
+    assert_eq!(first.address, "00000000");
+    assert_eq!(
+        first.hex,
+        "48 65 6c 6c 6f 2c 20 68  65 78 20 76 69 65 77 21"
+    );
+    assert_eq!(first.ascii, "Hello, hex view!");
+
+    // A short last row is padded to the full width; control and high bytes show as dots.
+    let mut last = HexRow::default();
+    assert!(last.fill(&bytes, 1));
+    assert_eq!(last.address, format!("{BYTES_PER_ROW:08x}"));
+    assert_eq!(last.hex.len(), first.hex.len());
+    assert!(last.hex.starts_with("00 01 7f 20 ff   "));
+    assert_eq!(last.ascii, "... .");
+
+    // Refilling replaces the row's text rather than appending to it; past the end it's empty.
+    assert!(last.fill(&bytes, 0));
+    assert_eq!(last, first);
+    assert!(!last.fill(&bytes, 2));
+    assert_eq!(last, HexRow::default());
+}
+
+#[test]
+fn hex_read_stops_at_the_limit() {
+    use crate::hex_view::read_capped;
+
+    let (bytes, truncated) = read_capped(&b"0123456789"[..], 4).unwrap();
+    assert_eq!(bytes, b"0123");
+    assert!(truncated);
+    // A file of exactly the limit is whole.
+    let (bytes, truncated) = read_capped(&b"0123"[..], 4).unwrap();
+    assert_eq!(bytes, b"0123");
+    assert!(!truncated);
+}
+
+#[test]
+fn per_path_encoding_uses_the_longest_matching_prefix() {
+    use rustide_editor::TextEncodingHint;
+
+    let tree = TempTree::new("per-path-encoding");
+    let path = tree.write(
+        "config.ini",
+        "[file]\nencoding=utf-8\nencoding_path_0=/work/legacy:gbk\n\
+         encoding_path_1=/work/legacy/tw:big5\nencoding_path_2=C:\\data:shift-jis\n\
+         encoding_path_3=/work/broken\n",
+    );
+    let cfg = crate::config::AppConfig::load_or_default(&path);
+    assert_eq!(cfg.file.per_path_encoding.len(), 3);
+    assert_eq!(
+        cfg.file.per_path_encoding[2],
+        ("C:\\data".into(), TextEncodingHint::ShiftJis)
+    );
+
+    let file = &cfg.file;
+    assert_eq!(
+        file.encoding_for(Path::new("/work/legacy/tw/a.txt")),
+        TextEncodingHint::Big5
+    );
+    assert_eq!(
+        file.encoding_for(Path::new("/work/legacy/cn/a.txt")),
+        TextEncodingHint::Gbk
+    );
+    // Prefixes match whole components, not string prefixes.
+    assert_eq!(
+        file.encoding_for(Path::new("/work/legacy-new/a.txt")),
+        TextEncodingHint::Utf8
+    );
+    assert_eq!(
+        file.encoding_for(Path::new("/other/a.txt")),
+        TextEncodingHint::Utf8
+    );
+
+    let reloaded =
+        crate::config::AppConfig::load_or_default(&tree.write("again.ini", &cfg.to_string()));
+    assert_eq!(reloaded.file.per_path_encoding, cfg.file.per_path_encoding);
+}
+
+#[test]
+fn syntax_problems_map_error_spans_to_lines_and_char_columns() {
+    use rustide_syntax::{HighlightSpan, HighlightTag};
+
+    let rope = ropey::Rope::from_str("fn main() {\n    let 名 = ;\n}\n");
+    let error = |byte_range| HighlightSpan {
+        byte_range,
+        tag: HighlightTag::Error,
+    };
+    // `;` on the second line sits after a three-byte char, and a missing `}` at the very end.
+    let semicolon = "fn main() {\n    let 名 = ".len();
+    let end = rope.len_bytes();
+    let problems =
+        crate::syntax_problems(&rope, &[error(semicolon..semicolon + 1), error(end..end)]);
+    assert_eq!(
+        problems,
+        vec![(1, 12, "Syntax error"), (3, 0, "Missing token")]
+    );
+}
+
+#[test]
+fn strip_ansi_removes_colors_links_and_control_chars() {
+    use crate::build::strip_ansi;
+
+    assert_eq!(
+        strip_ansi("\u{1b}[1m\u{1b}[38;5;9merror[E0308]\u{1b}[0m: mismatched types"),
+        "error[E0308]: mismatched types"
+    );
+    assert_eq!(
+        strip_ansi("see \u{1b}]8;;https://example.com\u{1b}\\docs\u{1b}]8;;\u{7} here"),
+        "see docs here"
+    );
+    assert_eq!(strip_ansi("\u{1b}=plain\ttext\r"), "plain\ttext");
+    assert_eq!(strip_ansi("unterminated \u{1b}[31"), "unterminated ");
+}
+
+#[test]
+fn build_config_round_trips_through_the_build_section() {
+    let tree = TempTree::new("build-config");
+    let path = tree.write(
+        "config.ini",
+        "[build]\ncommand=make\nargs=  -j8   all \nworking_dir=native\n",
+    );
+    let cfg = crate::config::AppConfig::load_or_default(&path);
+    assert_eq!(cfg.build.command, "make");
+    assert_eq!(cfg.build.args, ["-j8", "all"]);
+    assert_eq!(cfg.build.working_dir.as_deref(), Some(Path::new("native")));
+
+    let reloaded =
+        crate::config::AppConfig::load_or_default(&tree.write("again.ini", &cfg.to_string()));
+    assert_eq!(reloaded.build, cfg.build);
+
+    let defaults = crate::config::BuildConfig::default();
+    assert_eq!(
+        (defaults.command.as_str(), defaults.args),
+        ("cargo", vec!["build".to_string()])
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn build_streams_stdout_and_stderr_then_the_exit_code() {
+    use crate::build::BuildMessage;
+
+    let tree = TempTree::new("build-run");
+    tree.write("sub/marker", "");
+    let config = crate::config::BuildConfig {
+        command: "sh".to_string(),
+        args: vec![
+            "-c".to_string(),
+            "printf '\\033[32mok\\033[0m\\n'; echo oops >&2; ls; exit 3".to_string(),
+        ],
+        working_dir: Some("sub".into()),
+    };
+    let (tx, rx) = std::sync::mpsc::channel();
+    crate::build::start(&config, Some(&tree.0), tx);
+    let messages: Vec<_> = rx.iter().collect();
+    assert_eq!(messages.last(), Some(&BuildMessage::Finished(Some(3))));
+    for line in ["ok", "oops", "marker"] {
+        assert!(messages.contains(&BuildMessage::Line(line.to_string())));
+    }
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let missing = crate::config::BuildConfig {
+        command: tree.0.join("no-such-tool").display().to_string(),
+        ..Default::default()
+    };
+    crate::build::start(&missing, None, tx);
+    assert!(matches!(rx.recv(), Ok(BuildMessage::Failed(_))));
+    assert!(rx.recv().is_err());
+}
+
+#[test]
+fn parse_rustc_error_reads_one_line_diagnostics() {
+    use crate::build::parse_rustc_error;
+    use std::path::PathBuf;
+
+    // rustc --error-format=short
+    assert_eq!(
+        parse_rustc_error(
+            "src/main.rs:4:18: error[E0308]: mismatched types: expected `u32`, found `&str`"
+        ),
+        Some((
+            PathBuf::from("src/main.rs"),
+            4,
+            18,
+            "error[E0308]: mismatched types: expected `u32`, found `&str`".to_string()
+        ))
+    );
+    assert_eq!(
+        parse_rustc_error("src/lib.rs:12:9: warning: unused variable: `x`")
+            .map(|p| (p.1, p.2, p.3)),
+        Some((12, 9, "warning: unused variable: `x`".to_string()))
+    );
+    // GCC and Clang.
+    assert_eq!(
+        parse_rustc_error("main.c:3:5: error: implicit declaration of function 'foo' [-Wimplicit-function-declaration]")
+            .map(|p| p.0),
+        Some(PathBuf::from("main.c"))
+    );
+    assert_eq!(
+        parse_rustc_error("src/a.cpp:10:1: fatal error: missing.h: No such file or directory")
+            .map(|p| (p.1, p.3)),
+        Some((
+            10,
+            "fatal error: missing.h: No such file or directory".to_string()
+        ))
+    );
+    assert_eq!(
+        parse_rustc_error(
+            r"C:\work\app\main.c:7:2: warning: unused variable 'y' [-Wunused-variable]"
+        )
+        .map(|p| (p.0, p.1, p.2)),
+        Some((PathBuf::from(r"C:\work\app\main.c"), 7, 2))
+    );
+
+    for line in [
+        "src/main.rs:4:18: note: expected due to this",
+        "main.c:3:5: note: previous declaration is here",
+        "error: could not compile `demo` (bin \"demo\") due to 1 previous error",
+        "   Compiling demo v0.1.0 (/work/demo)",
+        "  --> src/main.rs:4:18",
+        "main.c:0:5: error: bad line",
+        "main.c:x:5: error: bad line",
+    ] {
+        assert_eq!(parse_rustc_error(line), None, "{line}");
+    }
+}
+
+#[test]
+fn diagnostic_parser_pairs_rustc_messages_with_their_locations() {
+    use crate::build::{Diagnostic, DiagnosticParser};
+
+    let output = r#"   Compiling demo v0.1.0 (/work/demo)
+warning: unused variable: `count`
+ --> src/lib.rs:3:9
+  |
+3 |     let count = 1;
+  |         ^^^^^ help: if this is intentional, prefix it with an underscore: `_count`
+  |
+  = note: `#[warn(unused_variables)]` on by default
+
+error[E0308]: mismatched types
+   --> src/main.rs:4:18
+    |
+4   |     let x: u32 = "five";
+    |            ---   ^^^^^^ expected `u32`, found `&str`
+    |            |
+    |            expected due to this
+   ::: src/other.rs:1:1
+    |
+error[E0425]: cannot find value `y` in this scope
+  --> src/main.rs:10:5
+   |
+10 |     y
+   |     ^ not found in this scope
+
+Some errors have detailed explanations: E0308, E0425.
+error: could not compile `demo` (bin "demo") due to 2 previous errors
+src/short.rs:1:2: error: short form
+"#;
+    let mut parser = DiagnosticParser::default();
+    let diagnostics: Vec<Diagnostic> = output.lines().filter_map(|l| parser.push(l)).collect();
+    let summary: Vec<_> = diagnostics
+        .iter()
+        .map(|d| {
+            (
+                d.path.to_str().unwrap(),
+                d.line,
+                d.column,
+                d.message.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("src/lib.rs", 3, 9, "warning: unused variable: `count`"),
+            ("src/main.rs", 4, 18, "error[E0308]: mismatched types"),
+            (
+                "src/main.rs",
+                10,
+                5,
+                "error[E0425]: cannot find value `y` in this scope"
+            ),
+            ("src/short.rs", 1, 2, "error: short form"),
+        ]
+    );
+}
+
+#[test]
+fn key_bindings_parse_and_print() {
+    use crate::config::KeyBinding;
+    use eframe::egui::{Key, Modifiers};
+
+    let binding: KeyBinding = "ctrl + shift + z".parse().unwrap();
+    assert_eq!(binding.key, Key::Z);
+    assert_eq!(binding.modifiers, Modifiers::COMMAND | Modifiers::SHIFT);
+    assert_eq!(binding.to_string(), "Ctrl+Shift+Z");
+    assert_eq!(
+        "Ctrl+]".parse::<KeyBinding>().unwrap().to_string(),
+        "Ctrl+CloseBracket"
+    );
+    assert_eq!(
+        "Alt+F5".parse::<KeyBinding>().unwrap(),
+        KeyBinding {
+            key: Key::F5,
+            modifiers: Modifiers::ALT,
+        }
+    );
+    assert!("Hyper+Z".parse::<KeyBinding>().is_err());
+    assert!("Ctrl+".parse::<KeyBinding>().is_err());
+
+    // Extra modifiers don't count as a match.
+    assert!(binding.matches(Key::Z, Modifiers::COMMAND | Modifiers::SHIFT));
+    assert!(!binding.matches(Key::Z, Modifiers::COMMAND));
+    assert!(!binding.matches(
+        Key::Z,
+        Modifiers::COMMAND | Modifiers::SHIFT | Modifiers::ALT
+    ));
+}
+
+#[test]
+fn ctrl_f_focuses_the_find_bar() {
+    use crate::command_palette::{global_action_for_input, Action};
+    use crate::config::KeyboardConfig;
+    use eframe::egui::{Event, InputState, Key, Modifiers, RawInput};
+
+    let press = |key, modifiers| {
+        let raw = RawInput {
+            events: vec![Event::Key {
+                key,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers,
+            }],
+            modifiers,
+            ..Default::default()
+        };
+        InputState::default().begin_pass(raw, false, 1.0, Default::default())
+    };
+    // Ctrl+F opens the find bar, or focuses it again; "Toggle Find" is palette-only.
+    assert_eq!(
+        global_action_for_input(&press(Key::F, Modifiers::COMMAND)),
+        Some(Action::Find)
+    );
+    let keys = KeyboardConfig::defaults();
+    assert_eq!(Action::Find.shortcut(&keys), "Ctrl+F");
+    assert_eq!(Action::ToggleFind.shortcut(&keys), "");
+    assert_eq!(
+        global_action_for_input(&press(Key::F, Modifiers::COMMAND | Modifiers::SHIFT)),
+        Some(Action::ShowSearchPanel)
+    );
+}
+
+#[test]
+fn keybindings_cannot_take_fixed_or_shared_shortcuts() {
+    use crate::command_palette::Action;
+    use crate::config::{AppConfig, KeyBinding, KeyboardConfig};
+
+    let tree = TempTree::new("keybindings-collide");
+    let path = tree.write(
+        "config.ini",
+        "[keybindings]\nselect_all=Ctrl+F\nindent=Ctrl+Left\nduplicate_line=Ctrl+Y\nredo=none\ndedent=Ctrl+Shift+Z\n",
+    );
+    let keys = AppConfig::load_or_default(&path).keybindings;
+    let defaults = KeyboardConfig::defaults();
+    // Fixed shortcuts are refused, and the default stays.
+    assert_eq!(keys.bindings["select_all"], defaults.bindings["select_all"]);
+    assert_eq!(keys.bindings["indent"], defaults.bindings["indent"]);
+    // redo_alt comes first, so it keeps Ctrl+Y.
+    assert_eq!(keys.bindings["redo_alt"], defaults.bindings["redo_alt"]);
+    assert!(!keys.bindings.contains_key("duplicate_line"));
+    // Ctrl+Shift+Z is free once redo is unbound.
+    assert_eq!(keys.bindings["dedent"].to_string(), "Ctrl+Shift+Z");
+
+    for action in Action::ALL {
+        if let Some(shortcut) = action.fixed_shortcut() {
+            assert!(shortcut.parse::<KeyBinding>().is_ok(), "{shortcut}");
+        }
+    }
+}
+
+#[test]
+fn remapped_undo_triggers_on_the_new_shortcut() {
+    use crate::command_palette::Action;
+    use crate::config::{AppConfig, KeyboardConfig};
+    use crate::editor_view::handle_key;
+    use eframe::egui::{Key, Modifiers};
+
+    let tree = TempTree::new("keybindings");
+    let path = tree.write(
+        "config.ini",
+        "[keybindings]\nundo=Ctrl+U\nredo_alt=none\nbogus=Ctrl+Q\nselect_all=Ctrl+Nope\n",
+    );
+    let cfg = AppConfig::load_or_default(&path);
+    let keys = &cfg.keybindings;
+    assert!(!keys.bindings.contains_key("redo_alt"));
+    assert!(!keys.bindings.contains_key("bogus"));
+    assert_eq!(
+        keys.bindings["select_all"],
+        KeyboardConfig::defaults().bindings["select_all"]
+    );
+
+    let mut editor = rustide_editor::Editor::from_text("");
+    editor.insert_text("hello");
+    assert!(!handle_key(
+        &mut editor,
+        Key::Z,
+        Modifiers::COMMAND,
+        "    ",
+        keys
+    ));
+    assert_eq!(editor.rope().to_string(), "hello");
+    assert!(handle_key(
+        &mut editor,
+        Key::U,
+        Modifiers::COMMAND,
+        "    ",
+        keys
+    ));
+    assert_eq!(editor.rope().to_string(), "");
+    // Ctrl+Y was unbound; Ctrl+Shift+Z still redoes.
+    assert!(!handle_key(
+        &mut editor,
+        Key::Y,
+        Modifiers::COMMAND,
+        "    ",
+        keys
+    ));
+    let ctrl_shift = Modifiers::COMMAND | Modifiers::SHIFT;
+    assert!(handle_key(&mut editor, Key::Z, ctrl_shift, "    ", keys));
+    assert_eq!(editor.rope().to_string(), "hello");
+
+    // The command palette shows the shortcuts in use.
+    assert_eq!(Action::Undo.shortcut(keys), "Ctrl+U");
+    assert_eq!(Action::Redo.shortcut(keys), "Ctrl+Shift+Z");
+    assert_eq!(Action::OpenFile.shortcut(keys), "Ctrl+O");
+    let defaults = KeyboardConfig::defaults();
+    assert_eq!(Action::Undo.shortcut(&defaults), "Ctrl+Z");
+    let mut unbound = defaults.clone();
+    unbound.bindings.remove("undo");
+    assert_eq!(Action::Undo.shortcut(&unbound), "");
+
+    let text = cfg.to_string();
+    assert!(text.contains("undo=Ctrl+U\n") && text.contains("redo_alt=none\n"));
+    let again = AppConfig::load_or_default(&tree.write("again.ini", &text));
+    assert_eq!(again.keybindings, cfg.keybindings);
+}
+
+#[test]
+fn scroll_margin_scrolls_when_the_caret_nears_an_edge() {
+    use crate::editor_view::scroll_for_margin;
+
+    // Ten 10px rows visible, three rows of margin.
+    let scroll = |row, offset| scroll_for_margin(row, offset, 100.0, 10.0, 3);
+    // Rows 3..=6 are clear of both margins.
+    assert_eq!(scroll(3, 0.0), None);
+    assert_eq!(scroll(6, 0.0), None);
+    // The last visible row scrolls so three rows show below it.
+    assert_eq!(scroll(9, 0.0), Some(30.0));
+    assert_eq!(scroll(7, 0.0), Some(10.0));
+    // Near the top edge the view scrolls up, but never past the start.
+    assert_eq!(scroll(6, 50.0), Some(30.0));
+    assert_eq!(scroll(1, 50.0), Some(0.0));
+    assert_eq!(scroll(1, 0.0), None);
+
+    // 0 turns it off, even for a caret below the view.
+    assert_eq!(scroll_for_margin(20, 0.0, 100.0, 10.0, 0), None);
+    // A view of five rows only fits a margin of two.
+    assert_eq!(scroll_for_margin(4, 0.0, 50.0, 10.0, 10), Some(20.0));
+    assert_eq!(scroll_for_margin(2, 0.0, 50.0, 10.0, 10), None);
+
+    let tree = TempTree::new("scroll-margin");
+    let cfg = crate::config::AppConfig::load_or_default(
+        &tree.write("config.ini", "[ui]\nscroll_margin=50\n"),
+    );
+    assert_eq!(cfg.ui.scroll_margin, crate::config::MAX_SCROLL_MARGIN);
+    assert_eq!(crate::config::UiConfig::default().scroll_margin, 3);
+}
+
+#[test]
+fn drag_select_below_the_view_scrolls_to_the_end() {
+    use eframe::egui::Rangef;
+
+    use crate::editor_view::drag_scroll_offset;
+
+    // A 100px view of 10px rows over 300px of content, 60 frames a second.
+    let view = Rangef::new(0.0, 100.0);
+    let step =
+        |pointer_y, offset| drag_scroll_offset(pointer_y, view, offset, 200.0, 10.0, 1.0 / 60.0);
+    assert_eq!(step(50.0, 40.0), None);
+    assert_eq!(step(100.0, 40.0), None);
+
+    // Held below the view, the drag scrolls down every frame until the end.
+    let mut offset = 0.0;
+    let mut frames = 0;
+    while let Some(next) = step(130.0, offset) {
+        assert!(next > offset);
+        offset = next;
+        frames += 1;
+        assert!(frames < 1000, "drag scroll never reached the end");
+    }
+    assert_eq!(offset, 200.0);
+
+    // Farther from the edge is faster, up to a cap; above the view scrolls up.
+    let near = step(110.0, 0.0).unwrap();
+    let far = step(200.0, 0.0).unwrap();
+    assert!(far > near);
+    assert_eq!(step(10_000.0, 0.0), step(20_000.0, 0.0));
+    assert!(step(-20.0, 100.0).unwrap() < 100.0);
+    assert_eq!(step(-20.0, 0.0), None);
+}
+
+#[test]
+fn smooth_scroll_reaches_its_target_in_about_150ms() {
+    use crate::editor_view::smooth_scroll_step;
+
+    // A 900px jump at 60 frames a second, at the speed a jump starts with.
+    let velocity = 900.0 / 0.15;
+    let mut offset = 0.0;
+    let mut frames = 0;
+    while offset != 900.0 {
+        let next = smooth_scroll_step(offset, 900.0, velocity, 1.0 / 60.0);
+        assert!(next > offset && next <= 900.0);
+        offset = next;
+        frames += 1;
+        assert!(frames <= 10, "smooth scroll took {frames} frames");
+    }
+    assert!(frames >= 9);
+
+    // Upward jumps work the same way, and a long frame lands on the target.
+    assert_eq!(smooth_scroll_step(900.0, 0.0, velocity, 1.0 / 60.0), 800.0);
+    assert_eq!(smooth_scroll_step(900.0, 0.0, velocity, 1.0), 0.0);
+    assert_eq!(smooth_scroll_step(40.0, 40.0, 0.0, 1.0 / 60.0), 40.0);
+
+    assert!(crate::config::UiConfig::default().smooth_scroll);
+    let tree = TempTree::new("smooth-scroll");
+    let cfg = crate::config::AppConfig::load_or_default(
+        &tree.write("config.ini", "[ui]\nsmooth_scroll=false\n"),
+    );
+    assert!(!cfg.ui.smooth_scroll);
+}
+
+#[test]
+fn middle_click_inside_the_editor_is_found() {
+    use eframe::egui::{pos2, Event, Modifiers, PointerButton, Rect};
+
+    use crate::editor_view::middle_click_pos;
+
+    let rect = Rect::from_min_max(pos2(0.0, 0.0), pos2(200.0, 100.0));
+    let press = |button, pos, pressed| Event::PointerButton {
+        pos,
+        button,
+        pressed,
+        modifiers: Modifiers::NONE,
+    };
+    let inside = pos2(50.0, 40.0);
+    assert_eq!(
+        middle_click_pos(
+            &[
+                Event::PointerMoved(inside),
+                press(PointerButton::Middle, inside, true),
+            ],
+            rect,
+        ),
+        Some(inside)
+    );
+    // Releases, other buttons and presses outside the view don't paste.
+    assert_eq!(
+        middle_click_pos(&[press(PointerButton::Middle, inside, false)], rect),
+        None
+    );
+    assert_eq!(
+        middle_click_pos(&[press(PointerButton::Primary, inside, true)], rect),
+        None
+    );
+    assert_eq!(
+        middle_click_pos(
+            &[press(PointerButton::Middle, pos2(250.0, 40.0), true)],
+            rect
+        ),
+        None
+    );
+    assert_eq!(middle_click_pos(&[], rect), None);
+}
+
+#[test]
+fn panes_keep_their_document_when_tabs_close() {
+    use crate::index_after_removal;
+
+    // Tabs 1 and 3 of five close.
+    let kept = [true, false, true, false, true];
+    assert_eq!(index_after_removal(&kept, 0), 0);
+    assert_eq!(index_after_removal(&kept, 2), 1);
+    assert_eq!(index_after_removal(&kept, 4), 2);
+    // A pane on a closed tab moves to the next one.
+    assert_eq!(index_after_removal(&kept, 1), 1);
+    assert_eq!(index_after_removal(&kept, 3), 2);
+    // Past the last tab it's one past the end; callers clamp.
+    assert_eq!(index_after_removal(&[true, false], 1), 1);
+
+    let layout = crate::default_dock_state();
+    assert!(layout.find_tab(&crate::DockTab::Editor(0)).is_some());
+    assert!(layout.find_tab(&crate::DockTab::Editor(1)).is_none());
+}
+
+#[test]
+fn editor_panes_focus_pane_swaps_the_shown_document() {
+    use crate::panes::EditorPanes;
+
+    let mut panes = EditorPanes::default();
+    let mut active_doc = 0;
+    panes.sync(active_doc);
+    active_doc = 1;
+    panes.sync(active_doc);
+    assert_eq!(panes.tabs(0), [0, 1]);
+
+    // A pane seen for the first time starts on the focused document, then has its own tabs.
+    panes.focus_pane(1, &mut active_doc);
+    assert_eq!(active_doc, 1);
+    panes.sync(active_doc);
+    active_doc = 2;
+    panes.sync(active_doc);
+    assert_eq!(panes.tabs(1), [1, 2]);
+    assert_eq!(panes.tabs(0), [0, 1]);
+
+    panes.focus_pane(0, &mut active_doc);
+    assert_eq!((panes.focused(), active_doc), (0, 1));
+    panes.focus_pane(1, &mut active_doc);
+    assert_eq!((panes.focused(), active_doc), (1, 2));
+}
+
+#[test]
+fn editor_panes_shown_keeps_the_focus() {
+    use crate::panes::EditorPanes;
+
+    let mut panes = EditorPanes::default();
+    let mut active_doc = 0;
+    panes.sync(active_doc);
+    panes.open_to_side(1, 1, &mut active_doc);
+    panes.focus_pane(0, &mut active_doc);
+    assert_eq!(active_doc, 0);
+
+    // Drawing another pane reads its document without focusing it.
+    assert_eq!(panes.shown(1, active_doc), 1);
+    assert_eq!(panes.shown(0, active_doc), 0);
+    assert_eq!(panes.focused(), 0);
+
+    // A pane restored with the layout starts on the focused pane's document.
+    assert_eq!(panes.shown(2, active_doc), 0);
+    assert_eq!(panes.tabs(2), [0]);
+    assert_eq!(panes.pane_count(), 3);
+    assert_eq!(panes.focused(), 0);
+}
+
+#[test]
+fn editor_panes_open_to_side_lists_the_document_in_that_pane_only() {
+    use crate::panes::EditorPanes;
+
+    let mut panes = EditorPanes::default();
+    let mut active_doc = 1;
+    panes.sync(active_doc);
+    active_doc = 0;
+    panes.sync(active_doc);
+    assert_eq!(panes.side_pane(), None);
+
+    panes.open_to_side(1, 1, &mut active_doc);
+    assert_eq!((panes.focused(), active_doc), (1, 1));
+    assert_eq!(panes.tabs(1), [1]);
+    assert_eq!(panes.tabs(0), [1, 0]);
+    assert_eq!(panes.side_pane(), Some(0));
+
+    panes.focus_pane(0, &mut active_doc);
+    assert_eq!(active_doc, 0);
+}
+
+#[test]
+fn editor_panes_close_tabs_and_retain_documents() {
+    use crate::panes::EditorPanes;
+
+    // Pane 0 lists documents 0, 1 and 2 and shows 1; pane 1 lists 1 and 3 and shows 3.
+    let mut panes = EditorPanes::default();
+    let mut active_doc = 0;
+    for doc in [0, 2, 1] {
+        panes.sync(doc);
+    }
+    panes.open_to_side(1, 1, &mut active_doc);
+    panes.sync(3);
+    active_doc = 3;
+    panes.focus_pane(0, &mut active_doc);
+    assert_eq!(panes.tabs(0), [0, 2, 1]);
+    assert_eq!(active_doc, 1);
+
+    // Closing the shown tab shows the one that took its place, or else the last one.
+    assert!(!panes.close_tabs(&mut active_doc, |doc| doc == 2));
+    assert_eq!((panes.tabs(0), active_doc), (&[0, 1][..], 1));
+    assert!(!panes.close_tabs(&mut active_doc, |doc| doc == 1));
+    assert_eq!((panes.tabs(0), active_doc), (&[0][..], 0));
+    // Pane 1 still lists document 1; nothing lists 2 any more.
+    assert!(panes.lists(1));
+    assert!(!panes.lists(2));
+
+    let kept = [true, true, false, true];
+    panes.retain_documents(&kept);
+    assert_eq!(panes.tabs(0), [0]);
+    assert_eq!(panes.tabs(1), [1, 2]);
+
+    // A pane losing its last tab goes, and the focus moves to the other one.
+    assert!(panes.close_tabs(&mut active_doc, |_| true));
+    assert_eq!(panes.pane_count(), 1);
+    assert_eq!((panes.focused(), active_doc), (1, 2));
+    // The last pane stays, even without tabs.
+    assert!(!panes.close_tabs(&mut active_doc, |_| true));
+    assert_eq!(panes.pane_count(), 1);
+    assert!(panes.tabs(1).is_empty());
+
+    let mut active_doc = 0;
+    assert!(panes.retain_panes(|pane| pane != 1, &mut active_doc));
+    assert_eq!(panes.pane_count(), 0);
+}
+
+#[test]
+fn project_search_and_replace_skip_binary_files() {
+    use crate::project::{ReplaceMessage, ReplaceWorker};
+
+    let tree = TempTree::new("replace-binary");
+    let text = tree.write("notes.txt", "needle\n");
+    let binary = tree.write("image.png", "\u{89}PNG\0\0needle\0\x01\x02");
+    let binary_before = std::fs::read(&binary).unwrap();
+
+    let matcher = LineMatcher::new("needle", true, false).unwrap();
+    let hits = search_files(
+        &[text.clone(), binary.clone()],
+        &matcher,
+        0,
+        rustide_editor::TextEncodingHint::Auto,
+        &AtomicBool::new(false),
+    );
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].path, text);
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let options = SearchOptions {
+        query: "needle".to_string(),
+        case_sensitive: true,
+        ..SearchOptions::default()
+    };
+    let _worker = ReplaceWorker::start(
+        tree.0.clone(),
+        options,
+        "pin".to_string(),
+        rustide_editor::TextEncodingHint::Auto,
+        tx,
+    );
+    let mut replaced = Vec::new();
+    for message in rx.iter() {
+        match message {
+            ReplaceMessage::Replaced { path, .. } => replaced.push(path),
+            ReplaceMessage::Failed { path, error } => panic!("{}: {error}", path.display()),
+            ReplaceMessage::Finished { .. } => break,
+        }
+    }
+    assert_eq!(replaced, std::slice::from_ref(&text));
+    assert_eq!(std::fs::read_to_string(&text).unwrap(), "pin\n");
+    assert_eq!(std::fs::read(&binary).unwrap(), binary_before);
+}
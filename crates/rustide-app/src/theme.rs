@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use eframe::egui::{self, Color32};
 use rustide_syntax::HighlightTag;
 
@@ -8,6 +10,12 @@ pub enum ThemeId {
     Light,
     SolarizedDark,
     Monokai,
+    Dracula,
+    Nord,
+    CatppuccinMocha,
+    HighContrast,
+    // Read from `UiConfig::custom_theme_path`; see `load_custom_theme`.
+    Custom,
 }
 
 impl std::str::FromStr for ThemeId {
@@ -19,6 +27,13 @@ impl std::str::FromStr for ThemeId {
             "light" => Ok(Self::Light),
             "solarized-dark" | "solarized_dark" | "solarizeddark" => Ok(Self::SolarizedDark),
             "monokai" => Ok(Self::Monokai),
+            "dracula" => Ok(Self::Dracula),
+            "nord" => Ok(Self::Nord),
+            "catppuccin-mocha" | "catppuccin_mocha" | "catppuccinmocha" => {
+                Ok(Self::CatppuccinMocha)
+            }
+            "high-contrast" | "high_contrast" | "highcontrast" => Ok(Self::HighContrast),
+            "custom" => Ok(Self::Custom),
             _ => Err(()),
         }
     }
@@ -31,6 +46,11 @@ impl std::fmt::Display for ThemeId {
             Self::Light => f.write_str("light"),
             Self::SolarizedDark => f.write_str("solarized-dark"),
             Self::Monokai => f.write_str("monokai"),
+            Self::Dracula => f.write_str("dracula"),
+            Self::Nord => f.write_str("nord"),
+            Self::CatppuccinMocha => f.write_str("catppuccin-mocha"),
+            Self::HighContrast => f.write_str("high-contrast"),
+            Self::Custom => f.write_str("custom"),
         }
     }
 }
@@ -38,7 +58,13 @@ impl std::fmt::Display for ThemeId {
 #[derive(Debug, Clone, Copy)]
 pub struct SyntaxColors {
     pub comment: Color32,
+    pub r#macro: Color32,
+    pub attribute: Color32,
+    pub lifetime: Color32,
+    pub label: Color32,
+    pub namespace: Color32,
     pub string: Color32,
+    pub regex: Color32,
     pub number: Color32,
     pub keyword: Color32,
     pub r#type: Color32,
@@ -48,6 +74,7 @@ pub struct SyntaxColors {
     pub property: Color32,
     pub operator: Color32,
     pub punctuation: Color32,
+    pub error: Color32,
     pub fallback: Color32,
 }
 
@@ -55,7 +82,13 @@ impl SyntaxColors {
     pub fn for_tag(&self, tag: HighlightTag) -> Color32 {
         match tag {
             HighlightTag::Comment => self.comment,
+            HighlightTag::Macro => self.r#macro,
+            HighlightTag::Attribute => self.attribute,
+            HighlightTag::Lifetime => self.lifetime,
+            HighlightTag::Label => self.label,
+            HighlightTag::Namespace => self.namespace,
             HighlightTag::String => self.string,
+            HighlightTag::Regex => self.regex,
             HighlightTag::Number => self.number,
             HighlightTag::Keyword => self.keyword,
             HighlightTag::Type => self.r#type,
@@ -65,8 +98,27 @@ impl SyntaxColors {
             HighlightTag::Property => self.property,
             HighlightTag::Operator => self.operator,
             HighlightTag::Punctuation => self.punctuation,
+            HighlightTag::Error => self.error,
         }
     }
+
+    /// WCAG 2 contrast ratio between two opaque colors, from 1 (same luminance) to 21 (black
+    /// on white). Alpha is ignored.
+    pub fn contrast_ratio(fg: Color32, bg: Color32) -> f64 {
+        fn luminance(c: Color32) -> f64 {
+            let channel = |v: u8| {
+                let v = f64::from(v) / 255.0;
+                if v <= 0.03928 {
+                    v / 12.92
+                } else {
+                    ((v + 0.055) / 1.055).powf(2.4)
+                }
+            };
+            0.2126 * channel(c.r()) + 0.7152 * channel(c.g()) + 0.0722 * channel(c.b())
+        }
+        let (a, b) = (luminance(fg), luminance(bg));
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -77,6 +129,9 @@ pub struct MinimapColors {
     pub viewport_fill: Color32,
     pub viewport_stroke: Color32,
     pub caret_marker: Color32,
+    pub selection_fill: Color32,
+    // Ticks at the lines of the find bar's matches.
+    pub search_match: Color32,
 }
 
 #[derive(Debug, Clone)]
@@ -84,15 +139,104 @@ pub struct Theme {
     pub visuals: egui::Visuals,
     pub syntax: SyntaxColors,
     pub minimap: MinimapColors,
+    // Vertical guides at `UiConfig::ruler_columns`.
+    pub ruler_color: Color32,
+    // Fill behind the caret's line; a faint overlay so selections and text stay readable.
+    pub current_line_bg: Color32,
+    // Glyphs drawn for spaces and tabs when `UiConfig::show_whitespace` is on.
+    pub whitespace_color: Color32,
+    // Behind other occurrences of the word under the caret.
+    pub occurrence_bg: Color32,
+    // Vertical lines at each indentation level; the caret's level uses the active color.
+    pub indent_guide: Color32,
+    pub active_indent_guide: Color32,
+    // Block caret: the cell fill (derived from `accent_color`) and the char drawn over it.
+    pub cursor_bg: Color32,
+    pub cursor_fg: Color32,
+    // Selected text, or `Color32::PLACEHOLDER` to keep the syntax colors on the selection.
+    pub selection_fg: Color32,
+    // Single accent for the caret, selection, links, hover outlines, and progress bars; see
+    // `apply_accent`.
+    pub accent_color: Color32,
 }
 
+// VS Code blue.
+pub const DEFAULT_ACCENT: Color32 = Color32::from_rgb(0, 122, 204);
+
 pub fn build_theme(id: ThemeId) -> Theme {
-    match id {
-        ThemeId::Dark => Theme {
+    let mut theme = match id {
+        // White text on black. Syntax hues come from the Okabe-Ito palette, which stays
+        // distinguishable with deuteranopia and protanopia; every color clears WCAG AA (4.5:1)
+        // against the background and the occurrence fill. Selected text is drawn in white.
+        ThemeId::HighContrast => Theme {
+            visuals: {
+                let mut v = egui::Visuals::dark();
+                v.panel_fill = Color32::BLACK;
+                v.window_fill = Color32::BLACK;
+                v.extreme_bg_color = Color32::BLACK;
+                v.faint_bg_color = Color32::from_gray(24);
+                v.override_text_color = Some(Color32::WHITE);
+                v.weak_text_color = Some(Color32::from_gray(200));
+                v.widgets.noninteractive.bg_stroke.color = Color32::WHITE;
+                v.window_stroke.color = Color32::WHITE;
+                v
+            },
+            syntax: SyntaxColors {
+                comment: Color32::from_gray(190),
+                r#macro: Color32::from_rgb(213, 94, 0),
+                attribute: Color32::from_rgb(213, 94, 0),
+                lifetime: Color32::from_rgb(86, 180, 233),
+                label: Color32::from_rgb(230, 159, 0),
+                namespace: Color32::from_rgb(240, 228, 66),
+                string: Color32::from_rgb(230, 159, 0),
+                regex: Color32::from_rgb(213, 94, 0),
+                number: Color32::from_rgb(204, 121, 167),
+                keyword: Color32::from_rgb(86, 180, 233),
+                r#type: Color32::from_rgb(240, 228, 66),
+                function: Color32::from_rgb(0, 158, 115),
+                constant: Color32::from_rgb(204, 121, 167),
+                variable: Color32::WHITE,
+                property: Color32::WHITE,
+                operator: Color32::WHITE,
+                punctuation: Color32::WHITE,
+                error: Color32::from_rgb(255, 100, 100),
+                fallback: Color32::WHITE,
+            },
+            minimap: MinimapColors {
+                background: Color32::BLACK,
+                border: Color32::WHITE,
+                text: Color32::from_gray(200),
+                viewport_fill: Color32::from_rgba_unmultiplied(255, 255, 255, 40),
+                viewport_stroke: Color32::WHITE,
+                caret_marker: Color32::PLACEHOLDER, // derived from `accent_color`
+                selection_fill: Color32::from_rgb(47, 99, 128),
+                search_match: Color32::from_rgb(240, 228, 66),
+            },
+            ruler_color: Color32::from_gray(128),
+            current_line_bg: Color32::from_gray(20),
+            whitespace_color: Color32::from_gray(128),
+            // Dark enough that every syntax color keeps 4.5:1 on it; blue adds the least
+            // luminance for the most visible tint.
+            occurrence_bg: Color32::from_rgb(0, 0, 96),
+            indent_guide: Color32::from_gray(90),
+            active_indent_guide: Color32::from_gray(200),
+            cursor_bg: Color32::PLACEHOLDER, // derived from `accent_color`
+            cursor_fg: Color32::BLACK,
+            selection_fg: Color32::WHITE,
+            accent_color: Color32::from_rgb(86, 180, 233),
+        },
+        // A custom theme starts from Dark when there is no file to read it from.
+        ThemeId::Dark | ThemeId::Custom => Theme {
             visuals: egui::Visuals::dark(),
             syntax: SyntaxColors {
                 comment: Color32::from_rgb(106, 153, 85),
+                r#macro: Color32::from_rgb(86, 156, 214),
+                attribute: Color32::from_rgb(215, 186, 125),
+                lifetime: Color32::from_rgb(86, 156, 214),
+                label: Color32::from_rgb(215, 186, 125),
+                namespace: Color32::from_rgb(114, 170, 180),
                 string: Color32::from_rgb(206, 145, 120),
+                regex: Color32::from_rgb(232, 150, 70),
                 number: Color32::from_rgb(181, 206, 168),
                 keyword: Color32::from_rgb(197, 134, 192),
                 r#type: Color32::from_rgb(78, 201, 176),
@@ -102,6 +246,7 @@ pub fn build_theme(id: ThemeId) -> Theme {
                 property: Color32::from_rgb(156, 220, 254),
                 operator: Color32::from_rgb(212, 212, 212),
                 punctuation: Color32::from_rgb(212, 212, 212),
+                error: Color32::from_rgb(244, 71, 71),
                 fallback: Color32::from_rgb(212, 212, 212),
             },
             minimap: MinimapColors {
@@ -110,14 +255,32 @@ pub fn build_theme(id: ThemeId) -> Theme {
                 text: Color32::from_rgba_unmultiplied(220, 220, 220, 90),
                 viewport_fill: Color32::from_rgba_unmultiplied(255, 255, 255, 24),
                 viewport_stroke: Color32::from_rgba_unmultiplied(255, 255, 255, 80),
-                caret_marker: Color32::from_rgb(0, 122, 204),
+                caret_marker: Color32::PLACEHOLDER, // derived from `accent_color`
+                selection_fill: Color32::from_rgba_unmultiplied(38, 79, 120, 170),
+                search_match: Color32::from_rgb(230, 180, 80),
             },
+            ruler_color: Color32::from_rgba_unmultiplied(255, 255, 255, 28),
+            current_line_bg: Color32::from_rgba_unmultiplied(255, 255, 255, 10),
+            whitespace_color: Color32::from_rgba_unmultiplied(200, 200, 200, 70),
+            occurrence_bg: Color32::from_rgba_unmultiplied(255, 255, 255, 24),
+            indent_guide: Color32::from_rgba_unmultiplied(255, 255, 255, 22),
+            active_indent_guide: Color32::from_rgba_unmultiplied(255, 255, 255, 70),
+            cursor_bg: Color32::PLACEHOLDER, // derived from `accent_color`
+            cursor_fg: Color32::from_gray(20),
+            selection_fg: Color32::PLACEHOLDER, // syntax colors
+            accent_color: DEFAULT_ACCENT,
         },
         ThemeId::Light => Theme {
             visuals: egui::Visuals::light(),
             syntax: SyntaxColors {
                 comment: Color32::from_rgb(0, 128, 0),
+                r#macro: Color32::from_rgb(175, 0, 219),
+                attribute: Color32::from_rgb(128, 128, 0),
+                lifetime: Color32::from_rgb(38, 127, 153),
+                label: Color32::from_rgb(128, 128, 0),
+                namespace: Color32::from_rgb(38, 120, 130),
                 string: Color32::from_rgb(163, 21, 21),
+                regex: Color32::from_rgb(196, 100, 20),
                 number: Color32::from_rgb(9, 134, 88),
                 keyword: Color32::from_rgb(0, 0, 255),
                 r#type: Color32::from_rgb(43, 145, 175),
@@ -127,6 +290,7 @@ pub fn build_theme(id: ThemeId) -> Theme {
                 property: Color32::from_rgb(0, 0, 0),
                 operator: Color32::from_rgb(0, 0, 0),
                 punctuation: Color32::from_rgb(0, 0, 0),
+                error: Color32::from_rgb(228, 0, 0),
                 fallback: Color32::from_rgb(0, 0, 0),
             },
             minimap: MinimapColors {
@@ -135,8 +299,20 @@ pub fn build_theme(id: ThemeId) -> Theme {
                 text: Color32::from_rgba_unmultiplied(0, 0, 0, 90),
                 viewport_fill: Color32::from_rgba_unmultiplied(0, 0, 0, 18),
                 viewport_stroke: Color32::from_rgba_unmultiplied(0, 0, 0, 60),
-                caret_marker: Color32::from_rgb(0, 122, 204),
+                caret_marker: Color32::PLACEHOLDER, // derived from `accent_color`
+                selection_fill: Color32::from_rgba_unmultiplied(173, 214, 255, 200),
+                search_match: Color32::from_rgb(200, 130, 0),
             },
+            ruler_color: Color32::from_rgba_unmultiplied(0, 0, 0, 28),
+            current_line_bg: Color32::from_rgba_unmultiplied(0, 0, 0, 10),
+            whitespace_color: Color32::from_rgba_unmultiplied(0, 0, 0, 60),
+            occurrence_bg: Color32::from_rgba_unmultiplied(0, 0, 0, 24),
+            indent_guide: Color32::from_rgba_unmultiplied(0, 0, 0, 22),
+            active_indent_guide: Color32::from_rgba_unmultiplied(0, 0, 0, 70),
+            cursor_bg: Color32::PLACEHOLDER, // derived from `accent_color`
+            cursor_fg: Color32::WHITE,
+            selection_fg: Color32::PLACEHOLDER, // syntax colors
+            accent_color: DEFAULT_ACCENT,
         },
         ThemeId::SolarizedDark => Theme {
             visuals: {
@@ -149,7 +325,13 @@ pub fn build_theme(id: ThemeId) -> Theme {
             },
             syntax: SyntaxColors {
                 comment: Color32::from_rgb(88, 110, 117),
+                r#macro: Color32::from_rgb(220, 50, 47),
+                attribute: Color32::from_rgb(133, 153, 0),
+                lifetime: Color32::from_rgb(108, 113, 196),
+                label: Color32::from_rgb(181, 137, 0),
+                namespace: Color32::from_rgb(88, 146, 150),
                 string: Color32::from_rgb(42, 161, 152),
+                regex: Color32::from_rgb(203, 75, 22),
                 number: Color32::from_rgb(211, 54, 130),
                 keyword: Color32::from_rgb(203, 75, 22),
                 r#type: Color32::from_rgb(38, 139, 210),
@@ -159,6 +341,7 @@ pub fn build_theme(id: ThemeId) -> Theme {
                 property: Color32::from_rgb(131, 148, 150),
                 operator: Color32::from_rgb(131, 148, 150),
                 punctuation: Color32::from_rgb(131, 148, 150),
+                error: Color32::from_rgb(220, 50, 47),
                 fallback: Color32::from_rgb(131, 148, 150),
             },
             minimap: MinimapColors {
@@ -167,8 +350,20 @@ pub fn build_theme(id: ThemeId) -> Theme {
                 text: Color32::from_rgba_unmultiplied(238, 232, 213, 90),
                 viewport_fill: Color32::from_rgba_unmultiplied(238, 232, 213, 22),
                 viewport_stroke: Color32::from_rgba_unmultiplied(238, 232, 213, 70),
-                caret_marker: Color32::from_rgb(38, 139, 210),
+                caret_marker: Color32::PLACEHOLDER, // derived from `accent_color`
+                selection_fill: Color32::from_rgba_unmultiplied(7, 54, 66, 220),
+                search_match: Color32::from_rgb(181, 137, 0),
             },
+            ruler_color: Color32::from_rgba_unmultiplied(238, 232, 213, 30),
+            current_line_bg: Color32::from_rgba_unmultiplied(238, 232, 213, 12),
+            whitespace_color: Color32::from_rgba_unmultiplied(147, 161, 161, 90),
+            occurrence_bg: Color32::from_rgba_unmultiplied(238, 232, 213, 26),
+            indent_guide: Color32::from_rgba_unmultiplied(147, 161, 161, 40),
+            active_indent_guide: Color32::from_rgba_unmultiplied(147, 161, 161, 110),
+            cursor_bg: Color32::PLACEHOLDER, // derived from `accent_color`
+            cursor_fg: Color32::from_rgb(0, 43, 54),
+            selection_fg: Color32::PLACEHOLDER, // syntax colors
+            accent_color: Color32::from_rgb(38, 139, 210),
         },
         ThemeId::Monokai => Theme {
             visuals: {
@@ -181,7 +376,13 @@ pub fn build_theme(id: ThemeId) -> Theme {
             },
             syntax: SyntaxColors {
                 comment: Color32::from_rgb(117, 113, 94),
+                r#macro: Color32::from_rgb(253, 151, 31),
+                attribute: Color32::from_rgb(190, 132, 255),
+                lifetime: Color32::from_rgb(253, 151, 31),
+                label: Color32::from_rgb(230, 219, 116),
+                namespace: Color32::from_rgb(120, 180, 190),
                 string: Color32::from_rgb(230, 219, 116),
+                regex: Color32::from_rgb(253, 151, 31),
                 number: Color32::from_rgb(174, 129, 255),
                 keyword: Color32::from_rgb(249, 38, 114),
                 r#type: Color32::from_rgb(102, 217, 239),
@@ -191,6 +392,7 @@ pub fn build_theme(id: ThemeId) -> Theme {
                 property: Color32::from_rgb(248, 248, 242),
                 operator: Color32::from_rgb(248, 248, 242),
                 punctuation: Color32::from_rgb(248, 248, 242),
+                error: Color32::from_rgb(249, 38, 114),
                 fallback: Color32::from_rgb(248, 248, 242),
             },
             minimap: MinimapColors {
@@ -199,10 +401,338 @@ pub fn build_theme(id: ThemeId) -> Theme {
                 text: Color32::from_rgba_unmultiplied(248, 248, 242, 90),
                 viewport_fill: Color32::from_rgba_unmultiplied(255, 255, 255, 18),
                 viewport_stroke: Color32::from_rgba_unmultiplied(255, 255, 255, 70),
-                caret_marker: Color32::from_rgb(249, 38, 114),
+                caret_marker: Color32::PLACEHOLDER, // derived from `accent_color`
+                selection_fill: Color32::from_rgba_unmultiplied(73, 72, 62, 220),
+                search_match: Color32::from_rgb(230, 219, 116),
+            },
+            ruler_color: Color32::from_rgba_unmultiplied(248, 248, 242, 30),
+            current_line_bg: Color32::from_rgba_unmultiplied(248, 248, 242, 12),
+            whitespace_color: Color32::from_rgba_unmultiplied(117, 113, 94, 160),
+            occurrence_bg: Color32::from_rgba_unmultiplied(248, 248, 242, 26),
+            indent_guide: Color32::from_rgba_unmultiplied(117, 113, 94, 90),
+            active_indent_guide: Color32::from_rgba_unmultiplied(117, 113, 94, 200),
+            cursor_bg: Color32::PLACEHOLDER, // derived from `accent_color`
+            cursor_fg: Color32::from_rgb(39, 40, 34),
+            selection_fg: Color32::PLACEHOLDER, // syntax colors
+            accent_color: Color32::from_rgb(249, 38, 114),
+        },
+        // https://draculatheme.com/spec
+        ThemeId::Dracula => Theme {
+            visuals: {
+                let mut v = egui::Visuals::dark();
+                v.panel_fill = Color32::from_rgb(40, 42, 54);
+                v.window_fill = Color32::from_rgb(40, 42, 54);
+                v.extreme_bg_color = Color32::from_rgb(33, 34, 44);
+                v.faint_bg_color = Color32::from_rgb(68, 71, 90);
+                v
+            },
+            syntax: SyntaxColors {
+                comment: Color32::from_rgb(98, 114, 164),
+                r#macro: Color32::from_rgb(80, 250, 123),
+                attribute: Color32::from_rgb(80, 250, 123),
+                lifetime: Color32::from_rgb(255, 184, 108),
+                label: Color32::from_rgb(255, 184, 108),
+                namespace: Color32::from_rgb(139, 233, 253),
+                string: Color32::from_rgb(241, 250, 140),
+                regex: Color32::from_rgb(255, 85, 85),
+                number: Color32::from_rgb(189, 147, 249),
+                keyword: Color32::from_rgb(255, 121, 198),
+                r#type: Color32::from_rgb(139, 233, 253),
+                function: Color32::from_rgb(80, 250, 123),
+                constant: Color32::from_rgb(189, 147, 249),
+                variable: Color32::from_rgb(248, 248, 242),
+                property: Color32::from_rgb(248, 248, 242),
+                operator: Color32::from_rgb(255, 121, 198),
+                punctuation: Color32::from_rgb(248, 248, 242),
+                error: Color32::from_rgb(255, 85, 85),
+                fallback: Color32::from_rgb(248, 248, 242),
+            },
+            minimap: MinimapColors {
+                background: Color32::from_rgba_unmultiplied(40, 42, 54, 160),
+                border: Color32::from_rgba_unmultiplied(33, 34, 44, 140),
+                text: Color32::from_rgba_unmultiplied(248, 248, 242, 90),
+                viewport_fill: Color32::from_rgba_unmultiplied(255, 255, 255, 18),
+                viewport_stroke: Color32::from_rgba_unmultiplied(255, 255, 255, 70),
+                caret_marker: Color32::PLACEHOLDER, // derived from `accent_color`
+                selection_fill: Color32::from_rgba_unmultiplied(68, 71, 90, 220),
+                search_match: Color32::from_rgb(241, 250, 140),
             },
+            ruler_color: Color32::from_rgba_unmultiplied(248, 248, 242, 30),
+            current_line_bg: Color32::from_rgba_unmultiplied(68, 71, 90, 110),
+            whitespace_color: Color32::from_rgba_unmultiplied(98, 114, 164, 160),
+            occurrence_bg: Color32::from_rgba_unmultiplied(248, 248, 242, 26),
+            indent_guide: Color32::from_rgba_unmultiplied(98, 114, 164, 90),
+            active_indent_guide: Color32::from_rgba_unmultiplied(98, 114, 164, 200),
+            cursor_bg: Color32::PLACEHOLDER, // derived from `accent_color`
+            cursor_fg: Color32::from_rgb(40, 42, 54),
+            selection_fg: Color32::PLACEHOLDER, // syntax colors
+            accent_color: Color32::from_rgb(189, 147, 249),
         },
+        // https://www.nordtheme.com/docs/colors-and-palettes
+        ThemeId::Nord => Theme {
+            visuals: {
+                let mut v = egui::Visuals::dark();
+                v.panel_fill = Color32::from_rgb(46, 52, 64);
+                v.window_fill = Color32::from_rgb(46, 52, 64);
+                v.extreme_bg_color = Color32::from_rgb(59, 66, 82);
+                v.faint_bg_color = Color32::from_rgb(59, 66, 82);
+                v
+            },
+            syntax: SyntaxColors {
+                comment: Color32::from_rgb(97, 110, 136),
+                r#macro: Color32::from_rgb(136, 192, 208),
+                attribute: Color32::from_rgb(208, 135, 112),
+                lifetime: Color32::from_rgb(143, 188, 187),
+                label: Color32::from_rgb(235, 203, 139),
+                namespace: Color32::from_rgb(143, 188, 187),
+                string: Color32::from_rgb(235, 203, 139),
+                regex: Color32::from_rgb(235, 203, 139),
+                number: Color32::from_rgb(208, 135, 112),
+                keyword: Color32::from_rgb(180, 142, 173),
+                r#type: Color32::from_rgb(143, 188, 187),
+                function: Color32::from_rgb(163, 190, 140),
+                constant: Color32::from_rgb(129, 161, 193),
+                variable: Color32::from_rgb(216, 222, 233),
+                property: Color32::from_rgb(216, 222, 233),
+                operator: Color32::from_rgb(129, 161, 193),
+                punctuation: Color32::from_rgb(236, 239, 244),
+                error: Color32::from_rgb(191, 97, 106),
+                fallback: Color32::from_rgb(236, 239, 244),
+            },
+            minimap: MinimapColors {
+                background: Color32::from_rgba_unmultiplied(46, 52, 64, 160),
+                border: Color32::from_rgba_unmultiplied(59, 66, 82, 140),
+                text: Color32::from_rgba_unmultiplied(236, 239, 244, 90),
+                viewport_fill: Color32::from_rgba_unmultiplied(216, 222, 233, 20),
+                viewport_stroke: Color32::from_rgba_unmultiplied(216, 222, 233, 70),
+                caret_marker: Color32::PLACEHOLDER, // derived from `accent_color`
+                selection_fill: Color32::from_rgba_unmultiplied(67, 76, 94, 220),
+                search_match: Color32::from_rgb(235, 203, 139),
+            },
+            ruler_color: Color32::from_rgba_unmultiplied(216, 222, 233, 30),
+            current_line_bg: Color32::from_rgba_unmultiplied(59, 66, 82, 140),
+            whitespace_color: Color32::from_rgba_unmultiplied(76, 86, 106, 200),
+            occurrence_bg: Color32::from_rgba_unmultiplied(216, 222, 233, 26),
+            indent_guide: Color32::from_rgba_unmultiplied(76, 86, 106, 120),
+            active_indent_guide: Color32::from_rgba_unmultiplied(129, 161, 193, 180),
+            cursor_bg: Color32::PLACEHOLDER, // derived from `accent_color`
+            cursor_fg: Color32::from_rgb(46, 52, 64),
+            selection_fg: Color32::PLACEHOLDER, // syntax colors
+            accent_color: Color32::from_rgb(136, 192, 208),
+        },
+        // https://catppuccin.com/palette (Mocha); comments use overlay2 as the style guide
+        // suggests.
+        ThemeId::CatppuccinMocha => Theme {
+            visuals: {
+                let mut v = egui::Visuals::dark();
+                v.panel_fill = Color32::from_rgb(30, 30, 46);
+                v.window_fill = Color32::from_rgb(30, 30, 46);
+                v.extreme_bg_color = Color32::from_rgb(24, 24, 37);
+                v.faint_bg_color = Color32::from_rgb(49, 50, 68);
+                v
+            },
+            syntax: SyntaxColors {
+                comment: Color32::from_rgb(147, 153, 178),
+                r#macro: Color32::from_rgb(148, 226, 213),
+                attribute: Color32::from_rgb(249, 226, 175),
+                lifetime: Color32::from_rgb(242, 205, 205),
+                label: Color32::from_rgb(116, 199, 236),
+                namespace: Color32::from_rgb(180, 190, 254),
+                string: Color32::from_rgb(166, 227, 161),
+                regex: Color32::from_rgb(245, 194, 231),
+                number: Color32::from_rgb(250, 179, 135),
+                keyword: Color32::from_rgb(203, 166, 247),
+                r#type: Color32::from_rgb(249, 226, 175),
+                function: Color32::from_rgb(137, 180, 250),
+                constant: Color32::from_rgb(250, 179, 135),
+                variable: Color32::from_rgb(205, 214, 244),
+                property: Color32::from_rgb(180, 190, 254),
+                operator: Color32::from_rgb(137, 220, 235),
+                punctuation: Color32::from_rgb(147, 153, 178),
+                error: Color32::from_rgb(243, 139, 168),
+                fallback: Color32::from_rgb(205, 214, 244),
+            },
+            minimap: MinimapColors {
+                background: Color32::from_rgba_unmultiplied(30, 30, 46, 160),
+                border: Color32::from_rgba_unmultiplied(49, 50, 68, 140),
+                text: Color32::from_rgba_unmultiplied(205, 214, 244, 90),
+                viewport_fill: Color32::from_rgba_unmultiplied(205, 214, 244, 20),
+                viewport_stroke: Color32::from_rgba_unmultiplied(205, 214, 244, 70),
+                caret_marker: Color32::PLACEHOLDER, // derived from `accent_color`
+                selection_fill: Color32::from_rgba_unmultiplied(69, 71, 90, 220),
+                search_match: Color32::from_rgb(249, 226, 175),
+            },
+            ruler_color: Color32::from_rgba_unmultiplied(205, 214, 244, 30),
+            current_line_bg: Color32::from_rgba_unmultiplied(49, 50, 68, 120),
+            whitespace_color: Color32::from_rgba_unmultiplied(108, 112, 134, 180),
+            occurrence_bg: Color32::from_rgba_unmultiplied(205, 214, 244, 26),
+            indent_guide: Color32::from_rgba_unmultiplied(69, 71, 90, 200),
+            active_indent_guide: Color32::from_rgba_unmultiplied(127, 132, 156, 220),
+            cursor_bg: Color32::PLACEHOLDER, // derived from `accent_color`
+            cursor_fg: Color32::from_rgb(30, 30, 46),
+            selection_fg: Color32::PLACEHOLDER, // syntax colors
+            accent_color: Color32::from_rgb(203, 166, 247),
+        },
+    };
+    apply_accent(&mut theme);
+    theme
+}
+
+// Derives every accent-colored field from `theme.accent_color` so they never drift apart.
+fn apply_accent(theme: &mut Theme) {
+    let accent = theme.accent_color;
+    let v = &mut theme.visuals;
+    // egui also uses the selection fill for progress bars and selected widgets.
+    v.selection.bg_fill = accent.gamma_multiply(if v.dark_mode { 0.55 } else { 0.35 });
+    v.selection.stroke.color = accent;
+    v.hyperlink_color = accent;
+    v.text_cursor.stroke.color = accent;
+    v.widgets.hovered.bg_stroke.color = accent;
+    theme.minimap.caret_marker = accent;
+    theme.cursor_bg = accent;
+}
+
+/// `build_theme`, except that `ThemeId::Custom` is read from `custom_path`. An unreadable or
+/// invalid file falls back to Dark with a warning.
+pub fn resolve_theme(id: ThemeId, custom_path: Option<&Path>) -> Theme {
+    if id != ThemeId::Custom {
+        return build_theme(id);
+    }
+    let Some(path) = custom_path else {
+        tracing::warn!("Custom theme selected but `custom_theme_path` is not set");
+        return build_theme(ThemeId::Dark);
+    };
+    load_custom_theme(path).unwrap_or_else(|err| {
+        tracing::warn!("Failed to load theme {}: {err}", path.display());
+        build_theme(ThemeId::Dark)
+    })
+}
+
+pub fn load_custom_theme(path: &Path) -> Result<Theme, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    parse_custom_theme(&text)
+}
+
+/// Parses a theme file: `[syntax]`, `[minimap]` and `[visuals]` sections of `key=#RRGGBB` (or
+/// `#RRGGBBAA`) lines named after the `SyntaxColors`, `MinimapColors` and `Theme` fields.
+/// `dark_mode=false` in `[visuals]` starts from Light instead of Dark; anything not set keeps
+/// that base theme's color.
+pub fn parse_custom_theme(text: &str) -> Result<Theme, String> {
+    let mut entries = Vec::new();
+    let mut section = String::new();
+    for (index, raw_line) in text.lines().enumerate() {
+        // `#` starts colors, so only whole-line comments are supported.
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with("//") {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.trim().to_ascii_lowercase();
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("line {}: expected `key=value`", index + 1));
+        };
+        let key = key.trim().to_ascii_lowercase();
+        entries.push((index + 1, section.clone(), key, value.trim()));
+    }
+
+    let dark_mode = entries
+        .iter()
+        .find(|(_, section, key, _)| section == "visuals" && key == "dark_mode")
+        .map_or(Ok(true), |(line, _, _, value)| match *value {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(format!("line {line}: dark_mode must be true or false")),
+        })?;
+    let mut theme = build_theme(if dark_mode {
+        ThemeId::Dark
+    } else {
+        ThemeId::Light
+    });
+
+    for (line, section, key, value) in entries {
+        if section == "visuals" && key == "dark_mode" {
+            continue;
+        }
+        let color = parse_hex_color(value)
+            .ok_or_else(|| format!("line {line}: `{value}` is not a #RRGGBB color"))?;
+        let slot = theme_color_slot(&mut theme, &section, &key)
+            .ok_or_else(|| format!("line {line}: unknown key `{key}` in [{section}]"))?;
+        *slot = color;
+    }
+    apply_accent(&mut theme);
+    Ok(theme)
+}
+
+fn theme_color_slot<'a>(theme: &'a mut Theme, section: &str, key: &str) -> Option<&'a mut Color32> {
+    let s = &mut theme.syntax;
+    let m = &mut theme.minimap;
+    let v = &mut theme.visuals;
+    let slot = match (section, key) {
+        ("syntax", "comment") => &mut s.comment,
+        ("syntax", "macro") => &mut s.r#macro,
+        ("syntax", "attribute") => &mut s.attribute,
+        ("syntax", "lifetime") => &mut s.lifetime,
+        ("syntax", "label") => &mut s.label,
+        ("syntax", "namespace") => &mut s.namespace,
+        ("syntax", "string") => &mut s.string,
+        ("syntax", "regex") => &mut s.regex,
+        ("syntax", "number") => &mut s.number,
+        ("syntax", "keyword") => &mut s.keyword,
+        ("syntax", "type") => &mut s.r#type,
+        ("syntax", "function") => &mut s.function,
+        ("syntax", "constant") => &mut s.constant,
+        ("syntax", "variable") => &mut s.variable,
+        ("syntax", "property") => &mut s.property,
+        ("syntax", "operator") => &mut s.operator,
+        ("syntax", "punctuation") => &mut s.punctuation,
+        ("syntax", "error") => &mut s.error,
+        ("syntax", "fallback") => &mut s.fallback,
+
+        ("minimap", "background") => &mut m.background,
+        ("minimap", "border") => &mut m.border,
+        ("minimap", "text") => &mut m.text,
+        ("minimap", "viewport_fill") => &mut m.viewport_fill,
+        ("minimap", "viewport_stroke") => &mut m.viewport_stroke,
+        ("minimap", "selection_fill") => &mut m.selection_fill,
+        ("minimap", "search_match") => &mut m.search_match,
+
+        ("visuals", "panel_fill") => &mut v.panel_fill,
+        ("visuals", "window_fill") => &mut v.window_fill,
+        ("visuals", "extreme_bg_color") => &mut v.extreme_bg_color,
+        ("visuals", "faint_bg_color") => &mut v.faint_bg_color,
+        ("visuals", "accent_color") => &mut theme.accent_color,
+        ("visuals", "ruler_color") => &mut theme.ruler_color,
+        ("visuals", "current_line_bg") => &mut theme.current_line_bg,
+        ("visuals", "whitespace_color") => &mut theme.whitespace_color,
+        ("visuals", "occurrence_bg") => &mut theme.occurrence_bg,
+        ("visuals", "indent_guide") => &mut theme.indent_guide,
+        ("visuals", "active_indent_guide") => &mut theme.active_indent_guide,
+        ("visuals", "cursor_fg") => &mut theme.cursor_fg,
+        ("visuals", "selection_fg") => &mut theme.selection_fg,
+        _ => return None,
+    };
+    Some(slot)
+}
+
+// `#RRGGBB` or `#RRGGBBAA` (alpha unmultiplied).
+fn parse_hex_color(value: &str) -> Option<Color32> {
+    let hex = value.trim().strip_prefix('#')?;
+    if !hex.is_ascii() || !matches!(hex.len(), 6 | 8) {
+        return None;
     }
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    let alpha = if hex.len() == 8 { byte(6)? } else { 255 };
+    Some(Color32::from_rgba_unmultiplied(
+        byte(0)?,
+        byte(2)?,
+        byte(4)?,
+        alpha,
+    ))
 }
 
 pub fn apply_theme(ctx: &egui::Context, theme: &Theme) {
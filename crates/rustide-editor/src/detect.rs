@@ -0,0 +1,265 @@
+//! Guessing the legacy encoding of bytes that aren't valid UTF-8.
+//!
+//! Most legacy CJK encodings decode each other's bytes without errors, so validity alone can't
+//! tell them apart. Each candidate's decoded text is scored instead: how many chars fall in the
+//! scripts of that encoding's language, how many are among its most frequent chars, and how
+//! often those chars sit next to each other.
+
+use std::sync::OnceLock;
+
+use encoding_rs::{Encoding, BIG5, EUC_KR, GBK, SHIFT_JIS, WINDOWS_1252};
+
+use crate::TextEncoding;
+
+// Roughly the 200 most frequent chars of each language.
+const SIMPLIFIED_CHINESE: &str = "的一是不了在人有我他这个们中来上大为和国地到以说时要就出会可也你\
+    对生能而子那得于着下自之年过发后作里用道行所然家种事成方多经么去法学如都同现当没动面起看定天\
+    分还进好小部其些主样理心她本前开但因只从想实日军者意无力它与长把机十民第公此已工使情明性知全\
+    三又关点正业外将两高间由问很最重并物手应战向头文体政美相见被利什二等产或新己制身果加西斯月话\
+    合回特代内信表化老给世位次度门任常先海通教儿原东声提立及比员解水名真论处走义各入几口认条平系\
+    气题活";
+const TRADITIONAL_CHINESE: &str =
+    "的一是不了在人有我他這個們中來上大為和國地到以說時要就出會可也你\
+    對生能而子那得於著下自之年過發後作裡用道行所然家種事成方多經麼去法學如都同現當沒動面起看定天\
+    分還進好小部其些主樣理心她本前開但因只從想實日軍者意無力它與長把機十民第公此已工使情明性知全\
+    三又關點正業外將兩高間由問很最重並物手應戰向頭文體政美相見被利什二等產或新己制身果加西斯月話\
+    合回特代內信表化老給世位次度門任常先海通教兒原東聲提立及比員解水名真論處走義各入幾口認條平系\
+    氣題活";
+const KOREAN: &str =
+    "이다의는에을를가한하고서지기도로자사그수나있해것들일대시어아게정리전상보인여면요우주부\
+    중할제성만내원니라까없했되과와습터러거학국년동생문장연간및경용화다음모든위때계통신그리고같";
+
+// Score of one char, before the bigram bonus.
+const REPLACEMENT: f64 = -10.0;
+const FOREIGN: f64 = -2.0;
+const IN_SCRIPT: f64 = 1.0;
+const FREQUENT: f64 = 3.0;
+// Bonus for a char following another in-script char, and extra when both are frequent.
+const BIGRAM: f64 = 1.0;
+const FREQUENT_BIGRAM: f64 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Ascii { letter: bool },
+    Neutral,
+    Foreign,
+    InScript,
+    Frequent,
+}
+
+// How much of a file `decode_legacy` scores; plenty to tell the candidates apart, and it
+// keeps detection cheap on large files.
+const SAMPLE_BYTES: usize = 1 << 20;
+
+/// How plausible `bytes` are as text in `encoding`, per input byte; higher is better.
+///
+/// Replacement chars and chars from scripts the encoding's language doesn't use are
+/// penalized; chars in that language's scripts, its most frequent chars, and runs of them are
+/// rewarded. Only scores for the same bytes are comparable. Runs in time linear in
+/// `bytes.len()`, with one decode and one pass over the text.
+pub fn score_encoding(bytes: &[u8], encoding: &'static Encoding) -> f64 {
+    score_sample(bytes, encoding, true) / bytes.len().max(1) as f64
+}
+
+/// Decodes non-UTF-8 `bytes` with whichever legacy encoding scores best; ties keep the earlier
+/// candidate, so GBK wins when nothing else stands out.
+///
+/// Only up to `SAMPLE_BYTES` are scored, starting at the first non-ASCII byte. That byte
+/// always starts a char: in every candidate, a trail byte follows a non-ASCII lead byte.
+pub(crate) fn decode_legacy(bytes: &[u8]) -> (String, TextEncoding) {
+    let start = bytes.iter().position(|b| !b.is_ascii()).unwrap_or(0);
+    let end = bytes.len().min(start + SAMPLE_BYTES);
+    let sample = &bytes[start..end];
+    let candidates = [
+        (GBK, TextEncoding::Gbk),
+        (BIG5, TextEncoding::Big5),
+        (SHIFT_JIS, TextEncoding::ShiftJis),
+        (EUC_KR, TextEncoding::EucKr),
+        // Also covers ISO-8859-1, whose printable chars Windows-1252 maps the same way.
+        (WINDOWS_1252, TextEncoding::Windows1252),
+    ];
+    let mut best: Option<(f64, &'static Encoding, TextEncoding)> = None;
+    for (encoding, text_encoding) in candidates {
+        let score = score_sample(sample, encoding, end == bytes.len());
+        if best.is_none_or(|(best_score, _, _)| score > best_score) {
+            best = Some((score, encoding, text_encoding));
+        }
+    }
+    let (_, encoding, text_encoding) = best.expect("candidate list is not empty");
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    (text.into_owned(), text_encoding)
+}
+
+// Raw score of `bytes`. Unless `last`, a char cut off at the end is ignored rather than
+// counted as an error.
+fn score_sample(bytes: &[u8], encoding: &'static Encoding, last: bool) -> f64 {
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let capacity = decoder
+        .max_utf8_buffer_length(bytes.len())
+        .unwrap_or(bytes.len() * 3);
+    let mut text = String::with_capacity(capacity);
+    let _ = decoder.decode_to_string(bytes, &mut text, last);
+    score_text(&text, encoding)
+}
+
+fn score_text(text: &str, encoding: &'static Encoding) -> f64 {
+    let classify: fn(char) -> Class = if encoding == GBK {
+        |c| classify_chinese(c, simplified_chinese())
+    } else if encoding == BIG5 {
+        |c| classify_chinese(c, traditional_chinese())
+    } else if encoding == SHIFT_JIS {
+        classify_japanese
+    } else if encoding == EUC_KR {
+        classify_korean
+    } else if encoding == WINDOWS_1252 {
+        classify_latin
+    } else {
+        |c| match c {
+            '\u{FFFD}' => Class::Foreign,
+            c if c.is_ascii() => Class::Ascii { letter: false },
+            _ => Class::InScript,
+        }
+    };
+    let latin = encoding == WINDOWS_1252;
+
+    let mut score = 0.0;
+    let mut prev = Class::Ascii { letter: false };
+    for c in text.chars() {
+        if c.is_ascii() {
+            prev = Class::Ascii {
+                letter: c.is_ascii_alphabetic(),
+            };
+            continue;
+        }
+        let class = if c == '\u{FFFD}' {
+            score += REPLACEMENT;
+            Class::Foreign
+        } else {
+            classify(c)
+        };
+        score += match class {
+            Class::Foreign => FOREIGN,
+            Class::InScript => IN_SCRIPT,
+            Class::Frequent => FREQUENT,
+            Class::Ascii { .. } | Class::Neutral => 0.0,
+        };
+        score += if latin {
+            // Accented letters belong inside words of plain ASCII letters; two in a row are
+            // far more often mojibake.
+            match (prev, class) {
+                (Class::Ascii { letter: true }, Class::InScript) => BIGRAM * 2.0,
+                (Class::InScript, Class::InScript) => -BIGRAM * 2.0,
+                _ => 0.0,
+            }
+        } else {
+            match (prev, class) {
+                (Class::Frequent, Class::Frequent) => BIGRAM + FREQUENT_BIGRAM,
+                (Class::InScript | Class::Frequent, Class::InScript | Class::Frequent) => BIGRAM,
+                _ => 0.0,
+            }
+        };
+        prev = class;
+    }
+    score
+}
+
+fn is_cjk_ideograph(c: char) -> bool {
+    matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}')
+}
+
+// CJK and full-width punctuation: common in all three CJK languages, so it neither helps nor
+// hurts.
+fn is_cjk_punctuation(c: char) -> bool {
+    matches!(c, '\u{3000}'..='\u{303F}' | '\u{FF01}'..='\u{FF5E}')
+}
+
+fn classify_chinese(c: char, frequent: &CharSet) -> Class {
+    if frequent.contains(c) {
+        Class::Frequent
+    } else if is_cjk_ideograph(c) {
+        Class::InScript
+    } else if is_cjk_punctuation(c) {
+        Class::Neutral
+    } else {
+        Class::Foreign
+    }
+}
+
+fn classify_japanese(c: char) -> Class {
+    match c {
+        // Hiragana and katakana.
+        '\u{3041}'..='\u{30FF}' => Class::Frequent,
+        c if is_cjk_ideograph(c) => Class::InScript,
+        // Half-width katakana: rare in text, but what single high bytes decode to.
+        '\u{FF61}'..='\u{FF9F}' => Class::Neutral,
+        c if is_cjk_punctuation(c) => Class::Neutral,
+        _ => Class::Foreign,
+    }
+}
+
+fn classify_korean(c: char) -> Class {
+    match c {
+        c if korean().contains(c) => Class::Frequent,
+        // Hangul syllables and compatibility jamo.
+        '\u{AC00}'..='\u{D7A3}' | '\u{3131}'..='\u{318E}' => Class::InScript,
+        // Hanja still turns up in older Korean text.
+        c if is_cjk_ideograph(c) || is_cjk_punctuation(c) => Class::Neutral,
+        _ => Class::Foreign,
+    }
+}
+
+fn classify_latin(c: char) -> Class {
+    match c {
+        '×' | '÷' => Class::Foreign,
+        c if c.is_alphabetic() => Class::InScript,
+        // Typographic punctuation and common symbols.
+        '‘' | '’' | '‚' | '“' | '”' | '„' | '–' | '—' | '…' | '•' | '€' | '«' | '»' | '°' | '£'
+        | '©' | '®' | '±' | '·' | '§' | '\u{A0}' | '¿' | '¡' => Class::Neutral,
+        _ => Class::Foreign,
+    }
+}
+
+fn simplified_chinese() -> &'static CharSet {
+    static SET: OnceLock<CharSet> = OnceLock::new();
+    SET.get_or_init(|| CharSet::new(SIMPLIFIED_CHINESE))
+}
+
+fn traditional_chinese() -> &'static CharSet {
+    static SET: OnceLock<CharSet> = OnceLock::new();
+    SET.get_or_init(|| CharSet::new(TRADITIONAL_CHINESE))
+}
+
+fn korean() -> &'static CharSet {
+    static SET: OnceLock<CharSet> = OnceLock::new();
+    SET.get_or_init(|| CharSet::new(KOREAN))
+}
+
+// Bitset over the code points between a list's smallest and largest char, so lookups stay
+// constant-time on large files.
+struct CharSet {
+    base: u32,
+    bits: Vec<u64>,
+}
+
+impl CharSet {
+    fn new(chars: &str) -> Self {
+        let chars = || chars.chars().filter(|c| !c.is_whitespace()).map(u32::from);
+        let base = chars().min().unwrap_or(0);
+        let len = chars().max().map_or(0, |max| max - base + 1);
+        let mut bits = vec![0u64; len.div_ceil(64) as usize];
+        for c in chars() {
+            let offset = c - base;
+            bits[(offset / 64) as usize] |= 1 << (offset % 64);
+        }
+        Self { base, bits }
+    }
+
+    fn contains(&self, c: char) -> bool {
+        let Some(offset) = u32::from(c).checked_sub(self.base) else {
+            return false;
+        };
+        self.bits
+            .get((offset / 64) as usize)
+            .is_some_and(|word| word & (1 << (offset % 64)) != 0)
+    }
+}
@@ -0,0 +1,129 @@
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Unchanged,
+}
+
+/// A run of lines with the same [`DiffKind`].
+///
+/// `Removed` and `Unchanged` ranges index the old lines, `Added` ranges index the new lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffHunk {
+    pub kind: DiffKind,
+    pub lines: Range<usize>,
+}
+
+/// Line diff using Myers' O(ND) algorithm (shortest edit script), in its linear-space
+/// divide-and-conquer form, so memory stays O(N+M) however different the inputs are.
+pub fn diff_lines<T: PartialEq>(old: &[T], new: &[T]) -> Vec<DiffHunk> {
+    let mut steps: Vec<(DiffKind, usize)> = Vec::new();
+    diff_range(old, new, 0, 0, &mut steps);
+    // Within each changed block, list the removed lines before the added ones.
+    for block in steps.split_mut(|(kind, _)| *kind == DiffKind::Unchanged) {
+        block.sort_by_key(|(kind, _)| *kind != DiffKind::Removed);
+    }
+
+    let mut hunks: Vec<DiffHunk> = Vec::new();
+    for (kind, line) in steps {
+        match hunks.last_mut() {
+            Some(hunk) if hunk.kind == kind && hunk.lines.end == line => hunk.lines.end += 1,
+            _ => hunks.push(DiffHunk {
+                kind,
+                lines: line..line + 1,
+            }),
+        }
+    }
+    hunks
+}
+
+// Appends the single-line steps turning `old` into `new`, in order. The slices start at old
+// line `x0` and new line `y0`. Splits at a point on a shortest path found by `middle_snake`
+// and recurses on both sides.
+fn diff_range<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    x0: usize,
+    y0: usize,
+    steps: &mut Vec<(DiffKind, usize)>,
+) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    steps.extend((x0..x0 + prefix).map(|x| (DiffKind::Unchanged, x)));
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+    let (x0, y0) = (x0 + prefix, y0 + prefix);
+    let suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old, new) = (&old[..old.len() - suffix], &new[..new.len() - suffix]);
+
+    if old.is_empty() {
+        steps.extend((y0..y0 + new.len()).map(|y| (DiffKind::Added, y)));
+    } else if new.is_empty() {
+        steps.extend((x0..x0 + old.len()).map(|x| (DiffKind::Removed, x)));
+    } else {
+        let (x, y) = middle_snake(old, new);
+        diff_range(&old[..x], &new[..y], x0, y0, steps);
+        diff_range(&old[x..], &new[y..], x0 + x, y0 + y, steps);
+    }
+
+    let end = x0 + old.len();
+    steps.extend((end..end + suffix).map(|x| (DiffKind::Unchanged, x)));
+}
+
+// Runs the Myers search from both ends at once until the paths overlap, and returns where the
+// overlapping snake starts: a point on a shortest edit path, at least one edit from either
+// end. Expects `old` and `new` to differ at both their first and last lines. Only the
+// furthest-reaching x per diagonal is kept, for the current edit distance.
+fn middle_snake<T: PartialEq>(old: &[T], new: &[T]) -> (usize, usize) {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2;
+    let offset = max + 1;
+    // `backward` holds x measured from the ends, on diagonals of the reversed inputs.
+    let mut forward = vec![0isize; 2 * offset as usize + 1];
+    let mut backward = vec![0isize; 2 * offset as usize + 1];
+    let at = |k: isize| (k + offset) as usize;
+
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let start = (x, y);
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[at(k)] = x;
+            if odd && (k - delta).abs() < d && x + backward[at(delta - k)] >= n {
+                return (start.0 as usize, start.1 as usize);
+            }
+        }
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[(n - x - 1) as usize] == new[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[at(k)] = x;
+            if !odd && (k - delta).abs() <= d && x + forward[at(delta - k)] >= n {
+                return ((n - x) as usize, (m - y) as usize);
+            }
+        }
+    }
+    unreachable!("the searches meet by half the total length")
+}
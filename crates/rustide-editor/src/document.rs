@@ -1,8 +1,14 @@
+use std::borrow::Cow;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use crate::Editor;
+use serde::{Deserialize, Serialize};
 
+use crate::editorconfig::line_endings;
+use crate::{diff_lines, normalize_line_endings, DiffHunk, Editor, EditorConfig, LineEnding};
+
+/// Serialized with the names [`Display`](std::fmt::Display) writes, e.g. `utf-16le`, so
+/// session state and the INI config agree.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TextEncodingHint {
     Auto,
@@ -11,6 +17,10 @@ pub enum TextEncodingHint {
     Utf16Be,
     Gbk,
     Big5,
+    Latin1,
+    Windows1252,
+    ShiftJis,
+    EucKr,
 }
 
 impl FromStr for TextEncodingHint {
@@ -24,6 +34,10 @@ impl FromStr for TextEncodingHint {
             "utf16be" | "utf-16be" | "utf16-be" => Ok(Self::Utf16Be),
             "gbk" | "gb2312" | "cp936" => Ok(Self::Gbk),
             "big5" | "big-5" | "cp950" => Ok(Self::Big5),
+            "latin1" | "latin-1" | "iso-8859-1" | "iso8859-1" => Ok(Self::Latin1),
+            "windows-1252" | "windows1252" | "cp1252" => Ok(Self::Windows1252),
+            "shift-jis" | "shift_jis" | "sjis" | "cp932" => Ok(Self::ShiftJis),
+            "euc-kr" | "euckr" | "cp949" => Ok(Self::EucKr),
             _ => Err(()),
         }
     }
@@ -38,18 +52,99 @@ impl std::fmt::Display for TextEncodingHint {
             Self::Utf16Be => f.write_str("utf-16be"),
             Self::Gbk => f.write_str("gbk"),
             Self::Big5 => f.write_str("big5"),
+            Self::Latin1 => f.write_str("iso-8859-1"),
+            Self::Windows1252 => f.write_str("windows-1252"),
+            Self::ShiftJis => f.write_str("shift-jis"),
+            Self::EucKr => f.write_str("euc-kr"),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+impl Serialize for TextEncodingHint {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+// Also takes every alias `FromStr` does.
+impl<'de> Deserialize<'de> for TextEncodingHint {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse()
+            .map_err(|()| serde::de::Error::custom(format!("unknown encoding `{name}`")))
+    }
+}
+
+/// Serialized with the [`TextEncodingHint`] names (`utf-8-bom` for the BOM variant, as in
+/// `.editorconfig`). The kebab-case names used before still deserialize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum TextEncoding {
+    #[serde(rename = "utf-8", alias = "utf8")]
     Utf8,
+    #[serde(rename = "utf-8-bom", alias = "utf8-bom")]
     Utf8Bom,
+    #[serde(rename = "utf-16le", alias = "utf16-le")]
     Utf16Le,
+    #[serde(rename = "utf-16be", alias = "utf16-be")]
     Utf16Be,
     Gbk,
     Big5,
+    #[serde(rename = "iso-8859-1", alias = "latin1")]
+    Latin1,
+    #[serde(rename = "windows-1252", alias = "windows1252")]
+    Windows1252,
+    ShiftJis,
+    EucKr,
+}
+
+/// The hint that decodes text saved in an encoding; BOMs are detected either way.
+impl From<TextEncoding> for TextEncodingHint {
+    fn from(encoding: TextEncoding) -> Self {
+        match encoding {
+            TextEncoding::Utf8 | TextEncoding::Utf8Bom => Self::Utf8,
+            TextEncoding::Utf16Le => Self::Utf16Le,
+            TextEncoding::Utf16Be => Self::Utf16Be,
+            TextEncoding::Gbk => Self::Gbk,
+            TextEncoding::Big5 => Self::Big5,
+            TextEncoding::Latin1 => Self::Latin1,
+            TextEncoding::Windows1252 => Self::Windows1252,
+            TextEncoding::ShiftJis => Self::ShiftJis,
+            TextEncoding::EucKr => Self::EucKr,
+        }
+    }
+}
+
+impl TextEncoding {
+    pub const ALL: &'static [TextEncoding] = &[
+        Self::Utf8,
+        Self::Utf8Bom,
+        Self::Utf16Le,
+        Self::Utf16Be,
+        Self::Gbk,
+        Self::Big5,
+        Self::Latin1,
+        Self::Windows1252,
+        Self::ShiftJis,
+        Self::EucKr,
+    ];
+}
+
+impl std::fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Utf8 => f.write_str("UTF-8"),
+            Self::Utf8Bom => f.write_str("UTF-8 with BOM"),
+            Self::Utf16Le => f.write_str("UTF-16 LE"),
+            Self::Utf16Be => f.write_str("UTF-16 BE"),
+            Self::Gbk => f.write_str("GBK"),
+            Self::Big5 => f.write_str("Big5"),
+            Self::Latin1 => f.write_str("ISO-8859-1"),
+            Self::Windows1252 => f.write_str("Windows-1252"),
+            Self::ShiftJis => f.write_str("Shift-JIS"),
+            Self::EucKr => f.write_str("EUC-KR"),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -58,6 +153,16 @@ pub struct Document {
     pub encoding: TextEncoding,
     pub max_line_chars: usize,
     pub editor: Editor,
+    /// The file's most common line ending (`Lf` if it has none), or the one set through
+    /// [`Document::set_line_ending`].
+    pub line_ending: LineEnding,
+    /// Whether [`Document::save_text`] rewrites every line ending to `line_ending`. Off until
+    /// the user or a setting picks an ending, so mixed files are saved as they are.
+    pub normalize_line_endings: bool,
+    /// Settings from the `.editorconfig` files above `path`, found by [`Document::load`].
+    pub editor_config: Option<EditorConfig>,
+    // Leading bytes of a UTF-8 char split across `io::Write::write` calls.
+    pending_utf8: Vec<u8>,
 }
 
 impl Document {
@@ -67,53 +172,186 @@ impl Document {
             encoding: TextEncoding::Utf8,
             max_line_chars: 0,
             editor: Editor::empty(),
+            line_ending: LineEnding::Lf,
+            normalize_line_endings: false,
+            editor_config: None,
+            pending_utf8: Vec::new(),
         }
     }
 
-    pub fn load(path: impl AsRef<Path>) -> Result<Self, EditorError> {
-        Self::load_with_encoding(path, TextEncodingHint::Auto)
-    }
-
-    pub fn load_with_encoding(
-        path: impl AsRef<Path>,
+    /// Decodes file contents without touching the filesystem; see [`decode_bytes`].
+    pub fn from_bytes(
+        path: Option<PathBuf>,
+        bytes: &[u8],
         encoding_hint: TextEncodingHint,
-    ) -> Result<Self, EditorError> {
-        let path = path.as_ref().to_path_buf();
-        let bytes = std::fs::read(&path).map_err(|source| EditorError::Io {
-            path: path.clone(),
-            source,
-        })?;
-
-        let (text, encoding) = decode_bytes(&bytes, encoding_hint);
+    ) -> Self {
+        let (text, encoding) = decode_bytes(bytes, encoding_hint);
         let max_line_chars = text
             .lines()
             .map(|line| line.chars().count())
             .max()
             .unwrap_or(0);
-        Ok(Self {
-            path: Some(path),
+        Self {
+            path,
             encoding,
             max_line_chars,
+            line_ending: LineEnding::detect(&text).unwrap_or(LineEnding::Lf),
+            normalize_line_endings: false,
             editor: Editor::from_text(&text),
-        })
+            editor_config: None,
+            pending_utf8: Vec::new(),
+        }
     }
 
-    pub fn save(&self) -> Result<(), EditorError> {
-        let path = self.path.as_ref().ok_or(EditorError::MissingPath)?;
-        let bytes = encode_text(&self.editor.rope().to_string(), self.encoding);
-        std::fs::write(path, bytes).map_err(|source| EditorError::Io {
-            path: path.clone(),
-            source,
-        })
+    /// Saves in `new_encoding` from now on. The buffer already holds Unicode text, so nothing
+    /// is converted until [`Document::to_bytes`]; to fix text decoded with the wrong encoding,
+    /// load the file again with a different hint instead.
+    pub fn convert_encoding(&mut self, new_encoding: TextEncoding) {
+        self.encoding = new_encoding;
+    }
+
+    /// The buffer encoded back into the document's original encoding.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        encode_text(&self.save_text(), self.encoding)
+    }
+
+    /// The buffer as it should be written: as-is, or with every line ending normalized to
+    /// [`Document::line_ending`] once one has been set.
+    pub fn save_text(&self) -> String {
+        let text = self.editor.rope().to_string();
+        if self.normalize_line_endings {
+            normalize_line_endings(&text, self.line_ending)
+        } else {
+            text
+        }
+    }
+
+    /// Saves with `ending` throughout from now on, whatever the buffer holds.
+    pub fn set_line_ending(&mut self, ending: LineEnding) {
+        self.line_ending = ending;
+        self.normalize_line_endings = true;
+    }
+
+    /// Rewrites every line ending in the buffer that isn't `target` as one undoable edit, and
+    /// saves with `target` from now on. Returns the number of line endings changed.
+    pub fn convert_line_endings(&mut self, target: LineEnding) -> usize {
+        self.set_line_ending(target);
+        let ranges: Vec<_> = line_endings(self.editor.rope().chars())
+            .filter(|&(_, ending)| ending != target)
+            .map(|(range, _)| range)
+            .collect();
+        self.editor.replace_ranges(&ranges, target.as_str())
+    }
+
+    /// Line diff from `self` to `other`; line endings are ignored.
+    pub fn diff(&self, other: &Document) -> Vec<DiffHunk> {
+        fn lines(doc: &Document) -> Vec<String> {
+            doc.editor
+                .rope()
+                .lines()
+                .map(|line| line.to_string().trim_end_matches(['\n', '\r']).to_string())
+                .collect()
+        }
+        diff_lines(&lines(self), &lines(other))
     }
 }
 
+/// Appends streamed UTF-8 output (LSP logs, terminal output, ...) to the end of the document.
+///
+/// A char split across writes is held back until its remaining bytes arrive; invalid sequences
+/// are replaced with U+FFFD rather than failing the write.
+impl std::io::Write for Document {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.pending_utf8.extend_from_slice(buf);
+        let consumed = match std::str::from_utf8(&self.pending_utf8) {
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            _ => self.pending_utf8.len(),
+        };
+        let bytes: Vec<u8> = self.pending_utf8.drain(..consumed).collect();
+        let text = String::from_utf8_lossy(&bytes);
+
+        let rope = self.editor.rope();
+        let first_line = rope.char_to_line(rope.len_chars());
+        self.editor.append_text(&text);
+        let rope = self.editor.rope();
+        for line in first_line..rope.len_lines() {
+            let len = rope
+                .line(line)
+                .chars()
+                .filter(|&c| c != '\n' && c != '\r')
+                .count();
+            self.max_line_chars = self.max_line_chars.max(len);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Display helper for a document's optional path: the full path, or [`Self::UNTITLED`].
+#[derive(Debug, Clone, Copy)]
+pub struct DocumentPath<'a>(pub &'a Option<PathBuf>);
+
+impl<'a> DocumentPath<'a> {
+    /// What a document without a path is called, both as a path and as a file name.
+    pub const UNTITLED: &'static str = "<untitled>";
+
+    pub fn is_untitled(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// The last path component (lossy for non-UTF-8 names), or [`Self::UNTITLED`].
+    pub fn file_name(&self) -> Cow<'a, str> {
+        self.0
+            .as_deref()
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy())
+            .unwrap_or(Cow::Borrowed(Self::UNTITLED))
+    }
+}
+
+impl std::fmt::Display for DocumentPath<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(path) => write!(f, "{}", path.display()),
+            None => f.write_str(Self::UNTITLED),
+        }
+    }
+}
+
+// How much of a file `is_likely_binary` looks at.
+const BINARY_SAMPLE_BYTES: usize = 8 * 1024;
+
+/// Whether `bytes` look like a binary file rather than text: the first 8 KiB contain a NUL, or
+/// more than a tenth of them are control bytes other than whitespace and escape.
+///
+/// A UTF-16 BOM (whose text is full of NULs) means text. Bytes that aren't valid UTF-8 don't
+/// count against a file: every legacy encoding produces them.
+pub fn is_likely_binary(bytes: &[u8]) -> bool {
+    if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+        return false;
+    }
+    let sample = &bytes[..bytes.len().min(BINARY_SAMPLE_BYTES)];
+    if sample.contains(&0) {
+        return true;
+    }
+    let controls = sample
+        .iter()
+        .filter(|&&b| {
+            (b.is_ascii_control() && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B)) || b == 0x7F
+        })
+        .count();
+    controls * 10 > sample.len()
+}
+
 pub fn decode_bytes(bytes: &[u8], hint: TextEncodingHint) -> (String, TextEncoding) {
     // Decode order:
     // 1) BOM (UTF-8/UTF-16LE/UTF-16BE)
     // 2) Explicit user hint (if any)
     // 3) UTF-8 strict
-    // 4) Heuristic fallback for common CJK encodings (GBK/Big5)
+    // 4) The best-scoring legacy encoding; see `detect::score_encoding`
     if let Some(without_bom) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
         return (
             String::from_utf8_lossy(without_bom).into_owned(),
@@ -152,6 +390,26 @@ pub fn decode_bytes(bytes: &[u8], hint: TextEncodingHint) -> (String, TextEncodi
             let (text, _, _had_errors) = encoding_rs::BIG5.decode(bytes);
             return (text.into_owned(), TextEncoding::Big5);
         }
+        // Every byte is the code point of the same value; `encoding_rs` only has the WHATWG
+        // "latin1", which is really Windows-1252.
+        TextEncodingHint::Latin1 => {
+            return (
+                bytes.iter().map(|&b| char::from(b)).collect(),
+                TextEncoding::Latin1,
+            )
+        }
+        TextEncodingHint::Windows1252 => {
+            let (text, _, _had_errors) = encoding_rs::WINDOWS_1252.decode(bytes);
+            return (text.into_owned(), TextEncoding::Windows1252);
+        }
+        TextEncodingHint::ShiftJis => {
+            let (text, _, _had_errors) = encoding_rs::SHIFT_JIS.decode(bytes);
+            return (text.into_owned(), TextEncoding::ShiftJis);
+        }
+        TextEncodingHint::EucKr => {
+            let (text, _, _had_errors) = encoding_rs::EUC_KR.decode(bytes);
+            return (text.into_owned(), TextEncoding::EucKr);
+        }
         TextEncodingHint::Auto => {}
     }
 
@@ -159,14 +417,7 @@ pub fn decode_bytes(bytes: &[u8], hint: TextEncodingHint) -> (String, TextEncodi
         return (text.to_string(), TextEncoding::Utf8);
     }
 
-    let (gbk_text, _, gbk_errors) = encoding_rs::GBK.decode(bytes);
-    let (big5_text, _, big5_errors) = encoding_rs::BIG5.decode(bytes);
-
-    match (gbk_errors, big5_errors) {
-        (false, true) => (gbk_text.into_owned(), TextEncoding::Gbk),
-        (true, false) => (big5_text.into_owned(), TextEncoding::Big5),
-        _ => (gbk_text.into_owned(), TextEncoding::Gbk),
-    }
+    crate::detect::decode_legacy(bytes)
 }
 
 pub fn encode_text(text: &str, encoding: TextEncoding) -> Vec<u8> {
@@ -178,22 +429,25 @@ pub fn encode_text(text: &str, encoding: TextEncoding) -> Vec<u8> {
             output.extend_from_slice(text.as_bytes());
             output
         }
-        TextEncoding::Utf16Le => {
-            let (encoded, _, _) = encoding_rs::UTF_16LE.encode(text);
-            let mut output = Vec::with_capacity(encoded.len() + 2);
-            output.extend_from_slice(&[0xFF, 0xFE]);
-            output.extend_from_slice(&encoded);
-            output
-        }
-        TextEncoding::Utf16Be => {
-            let (encoded, _, _) = encoding_rs::UTF_16BE.encode(text);
-            let mut output = Vec::with_capacity(encoded.len() + 2);
-            output.extend_from_slice(&[0xFE, 0xFF]);
-            output.extend_from_slice(&encoded);
-            output
-        }
+        // `encoding_rs` has no UTF-16 encoders (its UTF-16 `encode` writes UTF-8).
+        TextEncoding::Utf16Le => [0xFF, 0xFE]
+            .into_iter()
+            .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+            .collect(),
+        TextEncoding::Utf16Be => [0xFE, 0xFF]
+            .into_iter()
+            .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+            .collect(),
         TextEncoding::Gbk => encoding_rs::GBK.encode(text).0.into_owned(),
         TextEncoding::Big5 => encoding_rs::BIG5.encode(text).0.into_owned(),
+        // Chars outside Latin-1 become `?`.
+        TextEncoding::Latin1 => text
+            .chars()
+            .map(|c| u8::try_from(c).unwrap_or(b'?'))
+            .collect(),
+        TextEncoding::Windows1252 => encoding_rs::WINDOWS_1252.encode(text).0.into_owned(),
+        TextEncoding::ShiftJis => encoding_rs::SHIFT_JIS.encode(text).0.into_owned(),
+        TextEncoding::EucKr => encoding_rs::EUC_KR.encode(text).0.into_owned(),
     }
 }
 
@@ -206,4 +460,6 @@ pub enum EditorError {
     },
     #[error("document has no path")]
     MissingPath,
+    #[error("'{path}' looks like a binary file")]
+    BinaryFile { path: PathBuf },
 }
@@ -1,18 +1,20 @@
 use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use ropey::Rope;
 
-use crate::Selection;
+use crate::{wrap_line, wrap_row, MultiSelection, Selection};
 
 // Character-indexed editor state backed by a rope, with a simple per-edit undo/redo log.
 #[derive(Debug, Clone)]
 pub struct Editor {
     rope: Rope,
-    selection: Selection,
+    selection: MultiSelection,
     preferred_column: Option<usize>,
+    wrap_columns: Option<usize>,
     history: History,
-    version: u64,
-    last_edit: Option<EditorEdit>,
+    version: Generation,
+    last_edits: Vec<EditorEdit>,
 }
 
 impl Editor {
@@ -22,14 +24,15 @@ impl Editor {
 
     pub fn from_text(text: &str) -> Self {
         let rope = Rope::from_str(text);
-        let selection = Selection::collapsed(0);
+        let selection = MultiSelection::from_single(Selection::collapsed(0));
         Self {
             rope,
             selection,
             preferred_column: None,
+            wrap_columns: None,
             history: History::default(),
-            version: 0,
-            last_edit: None,
+            version: Generation::next(),
+            last_edits: Vec::new(),
         }
     }
 
@@ -37,59 +40,117 @@ impl Editor {
         &self.rope
     }
 
-    pub fn version(&self) -> u64 {
+    pub fn version(&self) -> Generation {
         self.version
     }
 
+    /// The primary selection.
     pub fn selection(&self) -> Selection {
-        self.selection
+        self.selection.primary()
     }
 
-    pub fn take_last_edit(&mut self) -> Option<EditorEdit> {
-        self.last_edit.take()
+    pub fn selections(&self) -> &MultiSelection {
+        &self.selection
     }
 
+    /// Replaces all selections, clamping them to the text.
+    pub fn set_selections(&mut self, selections: MultiSelection) {
+        let len = self.rope.len_chars();
+        let ranges = selections
+            .ranges()
+            .iter()
+            .map(|sel| Selection {
+                anchor: sel.anchor.min(len),
+                cursor: sel.cursor.min(len),
+            })
+            .collect();
+        self.selection = MultiSelection::from_ranges(ranges);
+        self.preferred_column = None;
+    }
+
+    /// Adds a caret at `pos`, keeping the current selections; see
+    /// [`MultiSelection::add_cursor`].
+    pub fn add_cursor(&mut self, pos: usize) {
+        self.selection.add_cursor(pos.min(self.rope.len_chars()));
+        self.preferred_column = None;
+    }
+
+    /// What the last mutation changed, one entry per replaced range in the order they were
+    /// applied, so each is relative to the text the ones before it left.
+    pub fn take_last_edits(&mut self) -> Vec<EditorEdit> {
+        std::mem::take(&mut self.last_edits)
+    }
+
+    /// Moves the primary selection's cursor and drops the other selections.
     pub fn set_cursor(&mut self, cursor: usize, extend: bool) {
         let cursor = cursor.min(self.rope.len_chars());
-        self.selection.set_cursor(cursor, extend);
+        let mut selection = self.selection.primary();
+        selection.set_cursor(cursor, extend);
+        self.selection = MultiSelection::from_single(selection);
         if !extend {
             self.preferred_column = None;
         }
     }
 
+    /// Soft-wrap width in chars, or `None` when lines are not wrapped. While set, Up/Down move
+    /// between wrapped rows (see [`wrap_line`]) rather than logical lines.
+    pub fn set_wrap_columns(&mut self, columns: Option<usize>) {
+        if self.wrap_columns != columns {
+            self.wrap_columns = columns;
+            self.preferred_column = None;
+        }
+    }
+
     pub fn select_all(&mut self) {
-        self.selection.anchor = 0;
-        self.selection.cursor = self.rope.len_chars();
-        self.preferred_column = None;
+        self.select_range(0..self.rope.len_chars());
     }
 
     pub fn select_range(&mut self, range: Range<usize>) {
         let start = range.start.min(self.rope.len_chars());
         let end = range.end.min(self.rope.len_chars());
-        self.selection.anchor = start;
-        self.selection.cursor = end;
+        self.selection = MultiSelection::from_single(Selection {
+            anchor: start,
+            cursor: end,
+        });
         self.preferred_column = None;
     }
 
+    /// The text of every non-empty selection in document order, one per line.
     pub fn selected_text(&self) -> String {
-        let range = self.selection.range();
-        if range.is_empty() {
-            return String::new();
-        }
-        self.rope.slice(range).to_string()
+        let mut ranges: Vec<Range<usize>> = self
+            .selection
+            .ranges()
+            .iter()
+            .map(Selection::range)
+            .filter(|range| !range.is_empty())
+            .collect();
+        ranges.sort_by_key(|range| range.start);
+        let texts: Vec<String> = ranges
+            .into_iter()
+            .map(|range| self.rope.slice(range).to_string())
+            .collect();
+        texts.join("\n")
     }
 
     pub fn insert_text(&mut self, text: &str) {
-        let range = self.selection.range();
-        if text.is_empty() && range.is_empty() {
-            return;
-        }
-        self.replace_range(range, text);
+        let caret = text.chars().count();
+        self.edit_selections(|_, sel| Some((sel.range(), text.to_string(), caret)));
+    }
+
+    /// Inserts a newline that keeps the current line's indentation, one `indent_unit` deeper
+    /// after `{` and one shallower before `}`.
+    pub fn insert_newline_auto_indent(&mut self, indent_unit: &str) {
+        self.edit_selections(|editor, sel| {
+            let (text, caret) = editor.auto_indent_newline(sel.cursor, indent_unit);
+            Some((sel.range(), text, caret))
+        });
     }
 
-    pub fn insert_newline_auto_indent(&mut self) {
+    // The newline `insert_newline_auto_indent` types at `cursor`, and how far into it the caret
+    // goes.
+    fn auto_indent_newline(&self, cursor: usize, indent_unit: &str) -> (String, usize) {
         let rope = &self.rope;
-        let cursor = self.selection.cursor.min(rope.len_chars());
+        let cursor = cursor.min(rope.len_chars());
         let line_index = rope.char_to_line(cursor);
         let line_start = rope.line_to_char(line_index);
         let line_end = line_start + self.line_visible_len(line_index);
@@ -101,7 +162,6 @@ impl Editor {
         let before_trim = before.trim_end_matches(|c: char| c.is_whitespace());
         let after_trim = after.trim_start_matches(|c: char| c.is_whitespace());
 
-        let indent_unit = "    ";
         let mut next_indent = base_indent.clone();
         if before_trim.ends_with('{') {
             next_indent.push_str(indent_unit);
@@ -118,54 +178,175 @@ impl Editor {
             //     |
             // }
             let inner = format!("\n{next_indent}\n{base_indent}");
-            self.insert_text(&inner);
-            let new_cursor = cursor + 1 + next_indent.chars().count();
-            self.set_cursor(new_cursor, false);
-            return;
+            return (inner, 1 + next_indent.chars().count());
         }
 
-        self.insert_text(&format!("\n{next_indent}"));
+        let text = format!("\n{next_indent}");
+        let caret = text.chars().count();
+        (text, caret)
     }
 
-    pub fn backspace(&mut self) {
-        let range = self.selection.range();
-        if !range.is_empty() {
-            self.replace_range(range, "");
-            return;
+    pub fn duplicate_line(&mut self) {
+        // Duplicate every line touched by a selection and move each cursor onto the copy.
+        // Selections sharing a line share one copy.
+        let mut changes = Vec::new();
+        // Each block's span and where its copy starts once every copy is in.
+        let mut copies: Vec<(Range<usize>, usize)> = Vec::new();
+        let mut inserted = 0;
+        for lines in self.selected_line_blocks(false) {
+            let block_start = self.rope.line_to_char(lines.start);
+            let last_line = lines.end - 1;
+            let block_end = self.rope.line_to_char(last_line) + self.line_visible_len(last_line);
+            let block = self.rope.slice(block_start..block_end).to_string();
+            copies.push((block_start..block_end, block_end + inserted + 1));
+            inserted += 1 + block_end - block_start;
+            changes.push((block_end..block_end, format!("\n{block}")));
         }
 
-        let cursor = self.selection.cursor;
-        if cursor == 0 {
-            return;
-        }
-        self.replace_range(cursor - 1..cursor, "");
+        let ranges = self
+            .selection
+            .ranges()
+            .iter()
+            .map(|sel| {
+                let cursor = copies
+                    .iter()
+                    .find(|(block, _)| block.start <= sel.cursor && sel.cursor <= block.end)
+                    .map_or(sel.cursor, |(block, copy_start)| {
+                        copy_start + sel.cursor - block.start
+                    });
+                Selection::collapsed(cursor)
+            })
+            .collect();
+        self.record_changes(changes, MultiSelection::from_ranges(ranges));
     }
 
-    pub fn delete_forward(&mut self) {
-        let range = self.selection.range();
-        if !range.is_empty() {
-            self.replace_range(range, "");
+    /// Prefixes every non-blank line touched by the selection with `unit`.
+    pub fn indent_selection(&mut self, unit: &str) {
+        self.shift_selected_lines(|line| {
+            if line.trim().is_empty() {
+                (0, "")
+            } else {
+                (0, unit)
+            }
+        });
+    }
+
+    /// Removes one level of indentation (`unit`, a tab, or up to `unit`'s width of spaces)
+    /// from every line touched by the selection.
+    pub fn dedent_selection(&mut self, unit: &str) {
+        let unit_width = unit.chars().count();
+        self.shift_selected_lines(|line| {
+            if line.starts_with(unit) {
+                (unit_width, "")
+            } else if line.starts_with('\t') {
+                (1, "")
+            } else {
+                let spaces = line.chars().take(unit_width).take_while(|&c| c == ' ');
+                (spaces.count(), "")
+            }
+        });
+    }
+
+    /// Appends `text` at the end of the buffer without moving the selection. Streamed output is
+    /// not a user edit, so no undo step is recorded; earlier undo entries stay valid because
+    /// they all lie before the appended text.
+    pub fn append_text(&mut self, text: &str) {
+        if text.is_empty() {
             return;
         }
+        let end = self.rope.len_chars();
+        let info = self.apply_raw_edit(end, 0, text);
+        self.version = Generation::next();
+        self.last_edits = vec![info];
+    }
 
-        let cursor = self.selection.cursor;
-        if cursor >= self.rope.len_chars() {
-            return;
+    /// Replaces the whole buffer with `new_text` as a single undoable edit.
+    pub fn replace_all(&mut self, new_text: &str) {
+        self.replace_range(0..self.rope.len_chars(), new_text);
+    }
+
+    /// Replaces every range in `ranges` (sorted, non-overlapping, char indices) with
+    /// `replacement` as a single undoable edit. Returns the number of ranges replaced.
+    pub fn replace_ranges(&mut self, ranges: &[Range<usize>], replacement: &str) -> usize {
+        if ranges.is_empty() {
+            return 0;
         }
-        self.replace_range(cursor..cursor + 1, "");
+        let len = self.rope.len_chars();
+        let replacement_len = replacement.chars().count();
+        let mut changes = Vec::with_capacity(ranges.len());
+        let mut pos = 0;
+        // The caret ends up after the last replacement, which moves by what the ones before it
+        // removed and inserted.
+        let (mut caret, mut removed, mut inserted) = (0, 0, 0);
+        for range in ranges {
+            let start = range.start.clamp(pos, len);
+            let end = range.end.clamp(start, len);
+            caret = start - removed + inserted + replacement_len;
+            removed += end - start;
+            inserted += replacement_len;
+            changes.push((start..end, replacement.to_string()));
+            pos = end;
+        }
+
+        let after = MultiSelection::from_single(Selection::collapsed(caret));
+        self.record_changes(changes, after);
+        ranges.len()
+    }
+
+    pub fn backspace(&mut self) {
+        self.delete_selections(|_, cursor| cursor.saturating_sub(1)..cursor);
+    }
+
+    pub fn delete_forward(&mut self) {
+        let len = self.rope.len_chars();
+        self.delete_selections(|_, cursor| cursor..(cursor + 1).min(len));
+    }
+
+    /// Ctrl+Backspace: deletes back to where `move_word_left` would land.
+    pub fn delete_word_backward(&mut self) {
+        self.delete_selections(|editor, cursor| editor.prev_word_start(cursor)..cursor);
+    }
+
+    /// Ctrl+Delete: deletes forward to where `move_word_right` would land.
+    pub fn delete_word_forward(&mut self) {
+        self.delete_selections(|editor, cursor| cursor..editor.next_word_start(cursor));
+    }
+
+    // Deletes every non-empty selection, and `range(cursor)` at every caret.
+    fn delete_selections(&mut self, range: impl Fn(&Self, usize) -> Range<usize>) {
+        self.edit_selections(|editor, sel| {
+            let deleted = if sel.is_empty() {
+                range(editor, sel.cursor)
+            } else {
+                sel.range()
+            };
+            Some((deleted, String::new(), 0))
+        });
     }
 
     pub fn undo(&mut self) -> bool {
         let Some(edit) = self.history.undo.pop() else {
             return false;
         };
-        let inserted_len = edit.inserted.chars().count();
-        let info = self.apply_raw_edit(edit.start, inserted_len, &edit.deleted);
-        self.selection = edit.before;
+        // Each change now starts later by what the ones before it inserted, and earlier by what
+        // they removed.
+        let (mut removed, mut inserted) = (0, 0);
+        let mut changes = Vec::with_capacity(edit.changes.len());
+        for change in &edit.changes {
+            let inserted_len = change.inserted.chars().count();
+            changes.push((
+                change.start - removed + inserted,
+                inserted_len,
+                change.deleted.as_str(),
+            ));
+            removed += change.deleted.chars().count();
+            inserted += inserted_len;
+        }
+        self.apply_changes(&changes);
+        self.selection = edit.before.clone();
         self.preferred_column = None;
         self.history.redo.push(edit);
-        self.version = self.version.wrapping_add(1);
-        self.last_edit = Some(info);
+        self.version = Generation::next();
         true
     }
 
@@ -173,48 +354,61 @@ impl Editor {
         let Some(edit) = self.history.redo.pop() else {
             return false;
         };
-        let deleted_len = edit.deleted.chars().count();
-        let info = self.apply_raw_edit(edit.start, deleted_len, &edit.inserted);
-        self.selection = edit.after;
+        let changes: Vec<_> = edit
+            .changes
+            .iter()
+            .map(|change| {
+                let deleted_len = change.deleted.chars().count();
+                (change.start, deleted_len, change.inserted.as_str())
+            })
+            .collect();
+        self.apply_changes(&changes);
+        self.selection = edit.after.clone();
         self.preferred_column = None;
         self.history.undo.push(edit);
-        self.version = self.version.wrapping_add(1);
-        self.last_edit = Some(info);
+        self.version = Generation::next();
         true
     }
 
     pub fn move_left(&mut self, extend: bool) {
-        if !extend && !self.selection.is_empty() {
-            let start = self.selection.range().start;
-            self.selection.collapse_to(start);
-            self.preferred_column = None;
-            return;
-        }
-
-        let cursor = self.selection.cursor;
-        let next = cursor.saturating_sub(1);
-        self.selection.set_cursor(next, extend);
+        self.move_selections(extend, |_, sel| {
+            if !extend && !sel.is_empty() {
+                sel.range().start
+            } else {
+                sel.cursor.saturating_sub(1)
+            }
+        });
         if !extend {
             self.preferred_column = None;
         }
     }
 
     pub fn move_right(&mut self, extend: bool) {
-        if !extend && !self.selection.is_empty() {
-            let end = self.selection.range().end;
-            self.selection.collapse_to(end);
-            self.preferred_column = None;
-            return;
-        }
-
-        let cursor = self.selection.cursor;
-        let next = (cursor + 1).min(self.rope.len_chars());
-        self.selection.set_cursor(next, extend);
+        let len = self.rope.len_chars();
+        self.move_selections(extend, |_, sel| {
+            if !extend && !sel.is_empty() {
+                sel.range().end
+            } else {
+                (sel.cursor + 1).min(len)
+            }
+        });
         if !extend {
             self.preferred_column = None;
         }
     }
 
+    /// Ctrl+Left: skips non-word chars before the cursor, then the word before them.
+    pub fn move_word_left(&mut self, extend: bool) {
+        self.move_selections(extend, |editor, sel| editor.prev_word_start(sel.cursor));
+        self.preferred_column = None;
+    }
+
+    /// Ctrl+Right: skips the word under the cursor, then the non-word chars after it.
+    pub fn move_word_right(&mut self, extend: bool) {
+        self.move_selections(extend, |editor, sel| editor.next_word_start(sel.cursor));
+        self.preferred_column = None;
+    }
+
     pub fn move_up(&mut self, extend: bool) {
         self.move_vertical(-1, extend);
     }
@@ -224,23 +418,124 @@ impl Editor {
     }
 
     pub fn move_line_start(&mut self, extend: bool) {
-        let (line, _col) = self.cursor_line_col();
-        let start = self.rope.line_to_char(line);
-        self.selection.set_cursor(start, extend);
+        self.move_selections(extend, |editor, sel| {
+            let (line, _col) = editor.line_col(sel.cursor);
+            editor.rope.line_to_char(line)
+        });
         if !extend {
             self.preferred_column = None;
         }
     }
 
     pub fn move_line_end(&mut self, extend: bool) {
-        let (line, _col) = self.cursor_line_col();
-        let end = self.rope.line_to_char(line) + self.line_visible_len(line);
-        self.selection.set_cursor(end, extend);
+        self.move_selections(extend, |editor, sel| {
+            let (line, _col) = editor.line_col(sel.cursor);
+            editor.rope.line_to_char(line) + editor.line_visible_len(line)
+        });
         if !extend {
             self.preferred_column = None;
         }
     }
 
+    // Moves the cursor of every selection to `target`, extending the selection or collapsing
+    // it there. Selections that end up overlapping are merged.
+    fn move_selections(&mut self, extend: bool, target: impl Fn(&Self, Selection) -> usize) {
+        let ranges = self
+            .selection
+            .ranges()
+            .iter()
+            .map(|&sel| {
+                let mut moved = sel;
+                moved.set_cursor(target(self, sel), extend);
+                moved
+            })
+            .collect();
+        self.selection = MultiSelection::from_ranges(ranges);
+    }
+
+    // Rewrites the lines touched by any selection as one undoable edit. `shift` returns how
+    // many leading chars to strip from a line and what to prefix it with.
+    fn shift_selected_lines<'a>(&mut self, shift: impl Fn(&str) -> (usize, &'a str)) {
+        let mut changes = Vec::new();
+        // Each changed line with the chars stripped from it and added to it.
+        let mut shifts: Vec<(usize, usize, usize)> = Vec::new();
+        for lines in self.selected_line_blocks(true) {
+            for line in lines {
+                let line_start = self.rope.line_to_char(line);
+                let line_end = line_start + self.line_visible_len(line);
+                let content = self.rope.slice(line_start..line_end).to_string();
+                let (strip, prefix) = shift(&content);
+                let strip = strip.min(line_end - line_start);
+                if strip > 0 || !prefix.is_empty() {
+                    changes.push((line_start..line_start + strip, prefix.to_string()));
+                    shifts.push((line, strip, prefix.chars().count()));
+                }
+            }
+        }
+        if changes.is_empty() {
+            return;
+        }
+
+        // A line starts earlier by what was stripped from the lines above it and later by
+        // what was added to them.
+        let remap = |pos: usize| {
+            let (line, col) = self.line_col(pos);
+            let (mut removed, mut added) = (0, 0);
+            let mut col = col;
+            for &(shifted, strip, prefix) in &shifts {
+                if shifted < line {
+                    removed += strip;
+                    added += prefix;
+                } else if shifted == line {
+                    col = col.saturating_sub(strip) + prefix;
+                }
+            }
+            self.rope.line_to_char(line) - removed + added + col
+        };
+        let ranges = self
+            .selection
+            .ranges()
+            .iter()
+            .map(|sel| Selection {
+                anchor: remap(sel.anchor),
+                cursor: remap(sel.cursor),
+            })
+            .collect();
+        self.record_changes(changes, MultiSelection::from_ranges(ranges));
+    }
+
+    // The lines touched by each selection as `first..last + 1`, in document order and merged
+    // where selections share a line. With `skip_column_zero`, a selection that ends at column
+    // 0 of a later line does not include that line.
+    fn selected_line_blocks(&self, skip_column_zero: bool) -> Vec<Range<usize>> {
+        let mut blocks: Vec<Range<usize>> = self
+            .selection
+            .ranges()
+            .iter()
+            .map(|sel| {
+                let range = sel.range();
+                let first_line = self.rope.char_to_line(range.start);
+                let mut last_line = self.rope.char_to_line(range.end);
+                if skip_column_zero
+                    && last_line > first_line
+                    && range.end == self.rope.line_to_char(last_line)
+                {
+                    last_line -= 1;
+                }
+                first_line..last_line + 1
+            })
+            .collect();
+        blocks.sort_by_key(|lines| lines.start);
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(blocks.len());
+        for lines in blocks {
+            match merged.last_mut() {
+                Some(last) if lines.start < last.end => last.end = last.end.max(lines.end),
+                _ => merged.push(lines),
+            }
+        }
+        merged
+    }
+
     fn replace_range(&mut self, range: Range<usize>, inserted: &str) {
         let start = range.start.min(self.rope.len_chars());
         let end = range.end.min(self.rope.len_chars());
@@ -248,33 +543,155 @@ impl Editor {
             return;
         }
 
-        let before = self.selection;
-        let range = start..end;
-        let deleted = if start == end {
-            String::new()
-        } else {
-            self.rope.slice(range.clone()).to_string()
+        let cursor = start + inserted.chars().count();
+        let after = MultiSelection::from_single(Selection::collapsed(cursor));
+        self.record_changes(vec![(start..end, inserted.to_string())], after);
+    }
+
+    // Applies one edit per selection as a single undoable edit. `edit` returns the range a
+    // selection replaces, the replacement, and how many chars into the replacement its caret
+    // ends up; `None`, or an edit that changes nothing, leaves the selection as it is.
+    //
+    // Overlapping ranges are merged into one edit that keeps the earlier caret. Each caret
+    // only shifts by the edits before it.
+    fn edit_selections(
+        &mut self,
+        mut edit: impl FnMut(&Self, Selection) -> Option<(Range<usize>, String, usize)>,
+    ) {
+        let len = self.rope.len_chars();
+        let mut edits: Vec<SelectionEdit> = Vec::new();
+        let mut untouched: Vec<(bool, Selection)> = Vec::new();
+        for (idx, &sel) in self.selection.ranges().iter().enumerate() {
+            let primary = idx == 0;
+            match edit(self, sel) {
+                Some((range, text, caret)) => {
+                    let start = range.start.min(len);
+                    let end = range.end.clamp(start, len);
+                    if start == end && text.is_empty() {
+                        untouched.push((primary, sel));
+                    } else {
+                        let caret = caret.min(text.chars().count());
+                        edits.push(SelectionEdit {
+                            range: start..end,
+                            text,
+                            caret,
+                            primary,
+                        });
+                    }
+                }
+                None => untouched.push((primary, sel)),
+            }
+        }
+        if edits.is_empty() {
+            return;
+        }
+
+        edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+        let mut merged: Vec<SelectionEdit> = Vec::with_capacity(edits.len());
+        for edit in edits {
+            match merged.last_mut() {
+                Some(last) if edit.range.start < last.range.end => {
+                    last.range.end = last.range.end.max(edit.range.end);
+                    last.text.push_str(&edit.text);
+                    last.primary |= edit.primary;
+                }
+                _ => merged.push(edit),
+            }
+        }
+
+        let mut after: Vec<(bool, Selection)> = Vec::with_capacity(merged.len());
+        // Where each edit's replacement ends in the new text.
+        let mut new_ends = Vec::with_capacity(merged.len());
+        let (mut removed, mut inserted) = (0, 0);
+        for edit in &merged {
+            let new_start = edit.range.start - removed + inserted;
+            after.push((edit.primary, Selection::collapsed(new_start + edit.caret)));
+            removed += edit.range.len();
+            inserted += edit.text.chars().count();
+            new_ends.push(edit.range.end - removed + inserted);
+        }
+
+        // Selections without an edit move with the text around them; one inside a replaced
+        // range goes to the end of the replacement.
+        let shift = |pos: usize| {
+            let mut shifted = pos;
+            for (edit, &new_end) in merged.iter().zip(&new_ends) {
+                if pos >= edit.range.end {
+                    shifted = pos - edit.range.end + new_end;
+                } else if pos > edit.range.start {
+                    return new_end;
+                } else {
+                    break;
+                }
+            }
+            shifted
         };
+        for (primary, sel) in untouched {
+            let moved = Selection {
+                anchor: shift(sel.anchor),
+                cursor: shift(sel.cursor),
+            };
+            after.push((primary, moved));
+        }
+        let primary_at = after.iter().position(|(primary, _)| *primary).unwrap_or(0);
+        let primary = after.remove(primary_at).1;
+        let ranges = std::iter::once(primary)
+            .chain(after.into_iter().map(|(_, sel)| sel))
+            .collect();
+
+        let changes = merged
+            .into_iter()
+            .map(|edit| (edit.range, edit.text))
+            .collect();
+        self.record_changes(changes, MultiSelection::from_ranges(ranges));
+    }
 
-        let deleted_len = end - start;
-        let info = self.apply_raw_edit(start, deleted_len, inserted);
+    // Replaces each range (clamped, sorted and non-overlapping) with its text as one undo step
+    // and leaves `after` selected.
+    fn record_changes(&mut self, edits: Vec<(Range<usize>, String)>, after: MultiSelection) {
+        let changes: Vec<Change> = edits
+            .into_iter()
+            .filter(|(range, inserted)| !range.is_empty() || !inserted.is_empty())
+            .map(|(range, inserted)| Change {
+                start: range.start,
+                deleted: self.rope.slice(range).to_string(),
+                inserted,
+            })
+            .collect();
+        if changes.is_empty() {
+            return;
+        }
 
-        let cursor = start + inserted.chars().count();
-        let after = Selection::collapsed(cursor);
-        self.selection = after;
+        let raw: Vec<_> = changes
+            .iter()
+            .map(|change| {
+                let deleted_len = change.deleted.chars().count();
+                (change.start, deleted_len, change.inserted.as_str())
+            })
+            .collect();
+        self.apply_changes(&raw);
+        let before = std::mem::replace(&mut self.selection, after.clone());
         self.preferred_column = None;
 
         self.history.redo.clear();
         self.history.undo.push(EditRecord {
-            start,
-            inserted: inserted.to_string(),
-            deleted,
+            changes,
             before,
             after,
         });
+        self.version = Generation::next();
+    }
 
-        self.version = self.version.wrapping_add(1);
-        self.last_edit = Some(info);
+    // Applies `(start, removed chars, inserted)` replacements, sorted and non-overlapping in
+    // the current text, last to first so every start is still valid when its turn comes, and
+    // reports them as the last edits.
+    fn apply_changes(&mut self, changes: &[(usize, usize, &str)]) {
+        let edits = changes
+            .iter()
+            .rev()
+            .map(|&(start, removed, inserted)| self.apply_raw_edit(start, removed, inserted))
+            .collect();
+        self.last_edits = edits;
     }
 
     fn apply_raw_edit(
@@ -314,10 +731,33 @@ impl Editor {
         }
     }
 
-    fn cursor_line_col(&self) -> (usize, usize) {
-        let cursor = self.selection.cursor.min(self.rope.len_chars());
-        let line = self.rope.char_to_line(cursor);
-        let col = cursor.saturating_sub(self.rope.line_to_char(line));
+    fn prev_word_start(&self, pos: usize) -> usize {
+        let mut pos = pos.min(self.rope.len_chars());
+        while pos > 0 && !is_word_char(self.rope.char(pos - 1)) {
+            pos -= 1;
+        }
+        while pos > 0 && is_word_char(self.rope.char(pos - 1)) {
+            pos -= 1;
+        }
+        pos
+    }
+
+    fn next_word_start(&self, pos: usize) -> usize {
+        let len = self.rope.len_chars();
+        let mut pos = pos.min(len);
+        while pos < len && is_word_char(self.rope.char(pos)) {
+            pos += 1;
+        }
+        while pos < len && !is_word_char(self.rope.char(pos)) {
+            pos += 1;
+        }
+        pos
+    }
+
+    fn line_col(&self, pos: usize) -> (usize, usize) {
+        let pos = pos.min(self.rope.len_chars());
+        let line = self.rope.char_to_line(pos);
+        let col = pos.saturating_sub(self.rope.line_to_char(line));
         (line, col)
     }
 
@@ -338,28 +778,117 @@ impl Editor {
         len
     }
 
+    // Moves every cursor `delta_lines` lines or wrapped rows. Only the primary cursor keeps a
+    // preferred column; the others start from their own column each time.
     fn move_vertical(&mut self, delta_lines: isize, extend: bool) {
-        let cursor = self.selection.cursor.min(self.rope.len_chars());
-        let (line, col) = self.cursor_line_col();
-
-        let desired = self.preferred_column.unwrap_or(col);
-        let target_line = if delta_lines.is_negative() {
-            line.saturating_sub(delta_lines.unsigned_abs())
-        } else {
-            (line + delta_lines as usize).min(self.rope.len_lines().saturating_sub(1))
-        };
-
-        let line_start = self.rope.line_to_char(target_line);
-        let line_col = desired.min(self.line_visible_len(target_line));
-        let next = (line_start + line_col).min(self.rope.len_chars());
-
-        self.selection.set_cursor(next, extend);
+        let cursor = self.selection.primary().cursor.min(self.rope.len_chars());
+        let mut primary_target = (cursor, 0);
+        let ranges = self
+            .selection
+            .ranges()
+            .iter()
+            .enumerate()
+            .map(|(idx, &sel)| {
+                let preferred = if idx == 0 {
+                    self.preferred_column
+                } else {
+                    None
+                };
+                let target = self.vertical_target(sel.cursor, preferred, delta_lines);
+                if idx == 0 {
+                    primary_target = target;
+                }
+                let mut moved = sel;
+                moved.set_cursor(target.0, extend);
+                moved
+            })
+            .collect();
+        self.selection = MultiSelection::from_ranges(ranges);
+
+        let (next, desired) = primary_target;
         self.preferred_column = Some(desired);
 
         if !extend && cursor == next {
             self.preferred_column = None;
         }
     }
+
+    // Where a cursor at `cursor` lands `delta_lines` away, and the column it aims for.
+    fn vertical_target(
+        &self,
+        cursor: usize,
+        preferred: Option<usize>,
+        delta_lines: isize,
+    ) -> (usize, usize) {
+        let (line, col) = self.line_col(cursor);
+        match self.wrap_columns {
+            Some(columns) => {
+                self.wrapped_vertical_target(line, col, delta_lines, columns, preferred)
+            }
+            None => {
+                let desired = preferred.unwrap_or(col);
+                let target_line = if delta_lines.is_negative() {
+                    line.saturating_sub(delta_lines.unsigned_abs())
+                } else {
+                    (line + delta_lines as usize).min(self.rope.len_lines().saturating_sub(1))
+                };
+
+                let line_start = self.rope.line_to_char(target_line);
+                let line_col = desired.min(self.line_visible_len(target_line));
+                ((line_start + line_col).min(self.rope.len_chars()), desired)
+            }
+        }
+    }
+
+    // Moves `delta_rows` wrapped rows from (`line`, `col`). The preferred column is measured
+    // from the start of the row, and a row's last position is the next row's first char, so
+    // the caret never lands on the boundary of the row it was not moved to.
+    fn wrapped_vertical_target(
+        &self,
+        mut line: usize,
+        col: usize,
+        delta_rows: isize,
+        columns: usize,
+        preferred: Option<usize>,
+    ) -> (usize, usize) {
+        let mut starts = self.wrap_starts(line, columns);
+        let mut row = wrap_row(&starts, col);
+        let desired = preferred.unwrap_or(col - starts[row]);
+
+        for _ in 0..delta_rows.unsigned_abs() {
+            if delta_rows.is_negative() {
+                if row > 0 {
+                    row -= 1;
+                } else if line > 0 {
+                    line -= 1;
+                    starts = self.wrap_starts(line, columns);
+                    row = starts.len() - 1;
+                }
+            } else if row + 1 < starts.len() {
+                row += 1;
+            } else if line + 1 < self.rope.len_lines() {
+                line += 1;
+                starts = self.wrap_starts(line, columns);
+                row = 0;
+            }
+        }
+
+        let row_end = starts
+            .get(row + 1)
+            .map_or(self.line_visible_len(line), |&next| next - 1);
+        let col = (starts[row] + desired).min(row_end);
+        (self.rope.line_to_char(line) + col, desired)
+    }
+
+    fn wrap_starts(&self, line: usize, columns: usize) -> Vec<usize> {
+        let start = self.rope.line_to_char(line);
+        let text = self.rope.slice(start..start + self.line_visible_len(line));
+        wrap_line(&text.to_string(), columns)
+    }
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
 }
 
 fn leading_indent(rope: &Rope, start: usize, end: usize) -> String {
@@ -388,6 +917,28 @@ fn decrease_indent(indent: &str, indent_unit: &str) -> String {
     }
 }
 
+/// Edit counter bumped by every mutation (edits, undo, redo).
+///
+/// Only equality is meaningful: callers compare a stored generation against the current one
+/// to detect changes (e.g. "modified since last save"). Every new editor and every mutation
+/// takes the next value of one process-wide counter, so two editors never share a
+/// generation, and per-view caches keyed on it can't mix up documents. The counter wraps on
+/// overflow, which would take 2^64 edits, so there is deliberately no ordering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Generation(u64);
+
+impl Generation {
+    /// A generation no editor has had yet.
+    pub fn next() -> Self {
+        static LAST: AtomicU64 = AtomicU64::new(0);
+        Self(LAST.fetch_add(1, Ordering::Relaxed).wrapping_add(1))
+    }
+
+    pub fn get(self) -> u64 {
+        self.0
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct EditorPoint {
     pub row: usize,
@@ -413,13 +964,28 @@ fn point_for_char(rope: &Rope, char_idx: usize) -> EditorPoint {
     EditorPoint { row, column: col }
 }
 
+// One undo step: `changes` are sorted, and each `start` is where the change begins in the
+// text before the step.
 #[derive(Debug, Clone)]
 struct EditRecord {
+    changes: Vec<Change>,
+    before: MultiSelection,
+    after: MultiSelection,
+}
+
+#[derive(Debug, Clone)]
+struct Change {
     start: usize,
-    inserted: String,
     deleted: String,
-    before: Selection,
-    after: Selection,
+    inserted: String,
+}
+
+// One selection's part of `Editor::edit_selections`.
+struct SelectionEdit {
+    range: Range<usize>,
+    text: String,
+    caret: usize,
+    primary: bool,
 }
 
 #[derive(Debug, Clone, Default)]
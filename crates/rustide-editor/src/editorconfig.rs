@@ -0,0 +1,461 @@
+//! `.editorconfig` parsing and glob matching (<https://editorconfig.org>). Finding the files
+//! next to a document lives in `io`, so this module also works on `wasm32`.
+
+use crate::TextEncoding;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentStyle {
+    Tab,
+    Space,
+}
+
+/// `indent_size`: a number of columns, or `tab` to indent by `tab_width`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentSize {
+    Columns(u8),
+    Tab,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+    Cr,
+}
+
+impl LineEnding {
+    pub const ALL: &'static [LineEnding] = &[Self::Lf, Self::CrLf, Self::Cr];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+            Self::Cr => "\r",
+        }
+    }
+
+    /// Short label for the status bar: `LF`, `CRLF` or `CR`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Lf => "LF",
+            Self::CrLf => "CRLF",
+            Self::Cr => "CR",
+        }
+    }
+
+    /// The most common line ending in `text`, or `None` if it has no line breaks. Ties go to
+    /// `Lf`, then `CrLf`.
+    pub fn detect(text: &str) -> Option<Self> {
+        let mut counts = [0usize; 3];
+        for (_, ending) in line_endings(text.chars()) {
+            counts[ending as usize] += 1;
+        }
+        let (index, &count) = counts
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|&(_, count)| count)?;
+        (count > 0).then(|| Self::ALL[index])
+    }
+}
+
+/// Char ranges of every line ending in `chars`, with their kind.
+pub(crate) fn line_endings(
+    chars: impl Iterator<Item = char>,
+) -> impl Iterator<Item = (std::ops::Range<usize>, LineEnding)> {
+    let mut chars = chars.enumerate().peekable();
+    std::iter::from_fn(move || loop {
+        let (index, c) = chars.next()?;
+        match c {
+            '\r' if chars.next_if(|&(_, c)| c == '\n').is_some() => {
+                return Some((index..index + 2, LineEnding::CrLf))
+            }
+            '\r' => return Some((index..index + 1, LineEnding::Cr)),
+            '\n' => return Some((index..index + 1, LineEnding::Lf)),
+            _ => {}
+        }
+    })
+}
+
+/// Properties set by the `.editorconfig` files that apply to one document.
+///
+/// `None` means no file set the property (or set it to `unset`), so the caller's own default
+/// applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EditorConfig {
+    pub indent_style: Option<IndentStyle>,
+    pub indent_size: Option<IndentSize>,
+    pub tab_width: Option<u8>,
+    pub end_of_line: Option<LineEnding>,
+    pub charset: Option<TextEncoding>,
+    pub trim_trailing_whitespace: Option<bool>,
+    pub insert_final_newline: Option<bool>,
+}
+
+impl EditorConfig {
+    /// Applies the sections of one `.editorconfig` file whose glob matches `relative`, the
+    /// document's `/`-separated path relative to that file's directory.
+    ///
+    /// Apply files from the outermost directory inwards so closer files win.
+    pub fn apply(&mut self, text: &str, relative: &str) {
+        for (section, key, value) in properties(text) {
+            if !section.is_some_and(|glob| section_matches(glob, relative)) {
+                continue;
+            }
+            let value = value.to_ascii_lowercase();
+            let unset = value == "unset";
+            match key.as_str() {
+                "indent_style" => {
+                    self.indent_style = match value.as_str() {
+                        "tab" => Some(IndentStyle::Tab),
+                        "space" => Some(IndentStyle::Space),
+                        _ if unset => None,
+                        _ => self.indent_style,
+                    }
+                }
+                "indent_size" => {
+                    if let Ok(size) = value.parse::<u8>() {
+                        self.indent_size = Some(IndentSize::Columns(size.clamp(1, 16)));
+                    } else if value == "tab" {
+                        self.indent_size = Some(IndentSize::Tab);
+                    } else if unset {
+                        self.indent_size = None;
+                    }
+                }
+                "tab_width" => {
+                    if let Ok(width) = value.parse::<u8>() {
+                        self.tab_width = Some(width.clamp(1, 16));
+                    } else if unset {
+                        self.tab_width = None;
+                    }
+                }
+                "end_of_line" => {
+                    self.end_of_line = match value.as_str() {
+                        "lf" => Some(LineEnding::Lf),
+                        "crlf" => Some(LineEnding::CrLf),
+                        "cr" => Some(LineEnding::Cr),
+                        _ if unset => None,
+                        _ => self.end_of_line,
+                    }
+                }
+                "charset" => {
+                    self.charset = match value.as_str() {
+                        "latin1" => Some(TextEncoding::Latin1),
+                        "utf-8" => Some(TextEncoding::Utf8),
+                        "utf-8-bom" => Some(TextEncoding::Utf8Bom),
+                        "utf-16le" => Some(TextEncoding::Utf16Le),
+                        "utf-16be" => Some(TextEncoding::Utf16Be),
+                        _ if unset => None,
+                        _ => self.charset,
+                    }
+                }
+                "trim_trailing_whitespace" => {
+                    self.trim_trailing_whitespace = match value.as_str() {
+                        "true" => Some(true),
+                        "false" => Some(false),
+                        _ if unset => None,
+                        _ => self.trim_trailing_whitespace,
+                    }
+                }
+                "insert_final_newline" => {
+                    self.insert_final_newline = match value.as_str() {
+                        "true" => Some(true),
+                        "false" => Some(false),
+                        _ if unset => None,
+                        _ => self.insert_final_newline,
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Columns per indentation level: `indent_size`, or `tab_width` for `indent_size = tab`.
+    pub fn indent_columns(&self) -> Option<u8> {
+        match self.indent_size? {
+            IndentSize::Columns(columns) => Some(columns),
+            IndentSize::Tab => self.tab_width,
+        }
+    }
+
+    /// Columns per tab character: `tab_width`, which defaults to a numeric `indent_size`.
+    pub fn tab_columns(&self) -> Option<u8> {
+        self.tab_width.or(match self.indent_size? {
+            IndentSize::Columns(columns) => Some(columns),
+            IndentSize::Tab => None,
+        })
+    }
+
+    /// One indentation level, or `None` when neither `indent_style` nor `indent_size` is set.
+    /// A size alone implies spaces; a style alone uses four columns.
+    pub fn indent_unit(&self) -> Option<String> {
+        match (self.indent_style, self.indent_size) {
+            (None, None) => None,
+            (Some(IndentStyle::Tab), _) => Some("\t".to_string()),
+            _ => Some(" ".repeat(usize::from(self.indent_columns().unwrap_or(4)))),
+        }
+    }
+}
+
+/// Whether the file's preamble (the part before the first section) contains `root = true`.
+pub fn is_root_editorconfig(text: &str) -> bool {
+    for (section, key, value) in properties(text) {
+        if section.is_some() {
+            break;
+        }
+        if key == "root" {
+            return value.eq_ignore_ascii_case("true");
+        }
+    }
+    false
+}
+
+/// Replaces every line ending in `text` (`\r\n`, `\r` or `\n`) with `ending`.
+pub fn normalize_line_endings(text: &str, ending: LineEnding) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' => {
+                chars.next_if_eq(&'\n');
+                out.push_str(ending.as_str());
+            }
+            '\n' => out.push_str(ending.as_str()),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// `(section glob, lowercase key, value)` for every property, with `None` for the preamble.
+fn properties(text: &str) -> impl Iterator<Item = (Option<&str>, String, &str)> {
+    let mut section = None;
+    text.lines().filter_map(move |raw_line| {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            return None;
+        }
+        if let Some(glob) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = Some(glob);
+            return None;
+        }
+        let (key, value) = line.split_once('=')?;
+        Some((section, key.trim().to_ascii_lowercase(), value.trim()))
+    })
+}
+
+/// Matches an `.editorconfig` section glob against a `/`-separated relative path.
+///
+/// Globs without a `/` match the file name in any directory; globs with one are anchored to
+/// the `.editorconfig`'s directory. Supports `*`, `**`, `?`, `[abc]`, `[!a-z]`, `{a,b}` and
+/// `{1..10}`.
+pub fn section_matches(glob: &str, relative: &str) -> bool {
+    let relative: Vec<char> = relative.chars().collect();
+    let file_name_start = relative
+        .iter()
+        .rposition(|&c| c == '/')
+        .map_or(0, |slash| slash + 1);
+    expand_braces(glob).iter().any(|alternative| {
+        let (alternative, target) = match alternative.strip_prefix('/') {
+            Some(anchored) => (anchored, &relative[..]),
+            None if alternative.contains('/') => (alternative.as_str(), &relative[..]),
+            None => (alternative.as_str(), &relative[file_name_start..]),
+        };
+        match_tokens(&tokenize(alternative), target)
+    })
+}
+
+enum Token {
+    Char(char),
+    // `*`: anything except `/`.
+    Star,
+    // `**`: anything, including `/`.
+    DoubleStar,
+    Question,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    Number(i64, i64),
+}
+
+// Expands the first `{a,b}` group (and, recursively, any later ones). Braces without a comma
+// are left for `tokenize`, which reads them as numeric ranges or literals.
+fn expand_braces(glob: &str) -> Vec<String> {
+    let mut depth = 0usize;
+    let mut open = None;
+    let mut commas = Vec::new();
+    for (i, c) in glob.char_indices() {
+        match c {
+            '{' => {
+                if depth == 0 {
+                    open = Some(i);
+                    commas.clear();
+                }
+                depth += 1;
+            }
+            ',' if depth == 1 => commas.push(i),
+            '}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 && !commas.is_empty() {
+                    let open = open.unwrap_or(0);
+                    let (head, tail) = (&glob[..open], &glob[i + 1..]);
+                    let mut starts = vec![open + 1];
+                    starts.extend(commas.iter().map(|comma| comma + 1));
+                    let mut ends = commas.clone();
+                    ends.push(i);
+                    return starts
+                        .into_iter()
+                        .zip(ends)
+                        .flat_map(|(start, end)| {
+                            expand_braces(&format!("{head}{}{tail}", &glob[start..end]))
+                        })
+                        .collect();
+                }
+            }
+            _ => {}
+        }
+    }
+    vec![glob.to_string()]
+}
+
+fn tokenize(glob: &str) -> Vec<Token> {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                tokens.push(Token::DoubleStar);
+                i += 2;
+                continue;
+            }
+            '*' => tokens.push(Token::Star),
+            '?' => tokens.push(Token::Question),
+            '\\' if i + 1 < chars.len() => {
+                tokens.push(Token::Char(chars[i + 1]));
+                i += 2;
+                continue;
+            }
+            '[' => {
+                if let Some((token, len)) = parse_class(&chars[i..]) {
+                    tokens.push(token);
+                    i += len;
+                    continue;
+                }
+                tokens.push(Token::Char('['));
+            }
+            '{' => {
+                if let Some((token, len)) = parse_number_range(&chars[i..]) {
+                    tokens.push(token);
+                    i += len;
+                    continue;
+                }
+                tokens.push(Token::Char('{'));
+            }
+            c => tokens.push(Token::Char(c)),
+        }
+        i += 1;
+    }
+    tokens
+}
+
+// `[...]` starting at `chars[0]`; returns the token and the number of chars consumed.
+fn parse_class(chars: &[char]) -> Option<(Token, usize)> {
+    let close = chars.iter().skip(2).position(|&c| c == ']')? + 2;
+    let mut body = &chars[1..close];
+    let negated = matches!(body.first(), Some('!' | '^'));
+    if negated {
+        body = &body[1..];
+    }
+    let mut ranges = Vec::new();
+    let mut j = 0;
+    while j < body.len() {
+        if j + 2 < body.len() && body[j + 1] == '-' {
+            ranges.push((body[j], body[j + 2]));
+            j += 3;
+        } else {
+            ranges.push((body[j], body[j]));
+            j += 1;
+        }
+    }
+    Some((Token::Class { negated, ranges }, close + 1))
+}
+
+// `{n1..n2}` starting at `chars[0]`.
+fn parse_number_range(chars: &[char]) -> Option<(Token, usize)> {
+    let close = chars.iter().position(|&c| c == '}')?;
+    let body: String = chars[1..close].iter().collect();
+    let (lo, hi) = body.split_once("..")?;
+    let (lo, hi) = (lo.parse::<i64>().ok()?, hi.parse::<i64>().ok()?);
+    Some((Token::Number(lo.min(hi), lo.max(hi)), close + 1))
+}
+
+// Marks which `(token, text position)` pairs are reachable, in text order. Every step either
+// consumes text or moves to a later token, so one pass over the table decides the match in
+// `O(tokens × text)` however many stars the glob has.
+fn match_tokens(tokens: &[Token], text: &[char]) -> bool {
+    let width = tokens.len() + 1;
+    let mut reached = vec![false; width * (text.len() + 1)];
+    reached[0] = true;
+    for pos in 0..=text.len() {
+        for (index, token) in tokens.iter().enumerate() {
+            if !reached[pos * width + index] {
+                continue;
+            }
+            let mut reach = |pos: usize, index: usize| reached[pos * width + index] = true;
+            let next = text.get(pos).copied();
+            match token {
+                Token::Char(c) => {
+                    if next == Some(*c) {
+                        reach(pos + 1, index + 1);
+                    }
+                }
+                Token::Question => {
+                    if next.is_some_and(|c| c != '/') {
+                        reach(pos + 1, index + 1);
+                    }
+                }
+                Token::Class { negated, ranges } => {
+                    if next.is_some_and(|c| {
+                        let hit = ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(&c));
+                        c != '/' && hit != *negated
+                    }) {
+                        reach(pos + 1, index + 1);
+                    }
+                }
+                // Match nothing more, or one more char and stay on the star.
+                Token::Star => {
+                    reach(pos, index + 1);
+                    if next.is_some_and(|c| c != '/') {
+                        reach(pos + 1, index);
+                    }
+                }
+                Token::DoubleStar => {
+                    reach(pos, index + 1);
+                    if next.is_some() {
+                        reach(pos + 1, index);
+                    }
+                }
+                Token::Number(lo, hi) => {
+                    let rest = &text[pos..];
+                    let sign = usize::from(rest.first() == Some(&'-'));
+                    let digits = rest[sign..]
+                        .iter()
+                        .take_while(|c| c.is_ascii_digit())
+                        .count();
+                    for len in 1..=digits {
+                        let number: String = rest[..sign + len].iter().collect();
+                        if number
+                            .parse::<i64>()
+                            .is_ok_and(|n| (*lo..=*hi).contains(&n))
+                        {
+                            reach(pos + sign + len, index + 1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    reached[text.len() * width + tokens.len()]
+}
@@ -0,0 +1,146 @@
+//! Filesystem load/save for [`Document`]. Compiled out on `wasm32`, where callers obtain the
+//! bytes themselves and use [`Document::from_bytes`] / [`Document::to_bytes`].
+
+use std::io::Write;
+use std::path::Path;
+
+use crate::{
+    is_likely_binary, is_root_editorconfig, Document, EditorConfig, EditorError, TextEncodingHint,
+};
+
+impl Document {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, EditorError> {
+        Self::load_with_encoding(path, TextEncodingHint::Auto)
+    }
+
+    pub fn load_with_encoding(
+        path: impl AsRef<Path>,
+        encoding_hint: TextEncodingHint,
+    ) -> Result<Self, EditorError> {
+        let path = path.as_ref().to_path_buf();
+        let bytes = std::fs::read(&path).map_err(|source| EditorError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        let editor_config = EditorConfig::for_path(&path);
+        // An explicit hint wins over the `.editorconfig` charset, which wins over detection.
+        let encoding_hint = match editor_config.and_then(|config| config.charset) {
+            Some(charset) if encoding_hint == TextEncodingHint::Auto => charset.into(),
+            _ => encoding_hint,
+        };
+        let utf16 = matches!(
+            encoding_hint,
+            TextEncodingHint::Utf16Le | TextEncodingHint::Utf16Be
+        );
+        if !utf16 && is_likely_binary(&bytes) {
+            return Err(EditorError::BinaryFile { path });
+        }
+        let mut doc = Self::from_bytes(Some(path), &bytes, encoding_hint);
+        if let Some(ending) = editor_config.and_then(|config| config.end_of_line) {
+            doc.set_line_ending(ending);
+        }
+        doc.editor_config = editor_config;
+        Ok(doc)
+    }
+
+    pub fn save(&self) -> Result<(), EditorError> {
+        let path = self.path.as_ref().ok_or(EditorError::MissingPath)?;
+        write_atomically(path, &self.to_bytes()).map_err(|source| EditorError::Io {
+            path: path.clone(),
+            source,
+        })
+    }
+
+    /// Like [`Document::save`], but first copies the file currently on disk (if any) to its
+    /// [`write_backup`] location, replacing any older backup.
+    pub fn save_with_backup(&self) -> Result<(), EditorError> {
+        let path = self.path.as_ref().ok_or(EditorError::MissingPath)?;
+        write_backup(path).map_err(|source| EditorError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        self.save()
+    }
+}
+
+/// Copies `path` to `path.with_extension("bak")`, overwriting an existing backup so only one
+/// is kept. A missing `path` (nothing saved yet) is not an error, and nothing is copied when
+/// the backup is `path` itself (a `.bak` file, or a symlink to `path`), which the copy would
+/// truncate.
+pub fn write_backup(path: &Path) -> std::io::Result<()> {
+    let backup = path.with_extension("bak");
+    if let (Ok(source), Ok(target)) = (std::fs::canonicalize(path), std::fs::canonicalize(&backup))
+    {
+        if source == target {
+            return Ok(());
+        }
+    }
+    match std::fs::copy(path, backup) {
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound && !path.exists() => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Writes `bytes` to a hidden sibling temp file, flushes it to disk and renames it over
+/// `path`, so readers (and a crash mid-save) see either the old or the new contents, never a
+/// partial or missing file.
+///
+/// `std::fs::rename` replaces the destination atomically on every platform; on Windows it is
+/// `MoveFileExW` with `MOVEFILE_REPLACE_EXISTING`. The old file's permissions are kept, and a
+/// symlink is written through to its target rather than replaced by a regular file.
+pub fn write_atomically(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let is_symlink = std::fs::symlink_metadata(path).is_ok_and(|meta| meta.is_symlink());
+    let path = if is_symlink {
+        std::fs::canonicalize(path)?
+    } else {
+        path.to_path_buf()
+    };
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tmp = path.with_file_name(format!(".{file_name}.rustide-tmp"));
+    let result = (|| {
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        drop(file);
+        if let Ok(meta) = std::fs::metadata(&path) {
+            let _ = std::fs::set_permissions(&tmp, meta.permissions());
+        }
+        std::fs::rename(&tmp, &path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+impl EditorConfig {
+    /// Reads the `.editorconfig` files in `path`'s parent directories, stopping after one with
+    /// `root = true`. `None` when there are none.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let mut files = Vec::new();
+        for dir in path.ancestors().skip(1) {
+            if let Ok(text) = std::fs::read_to_string(dir.join(".editorconfig")) {
+                let root = is_root_editorconfig(&text);
+                files.push((dir, text));
+                if root {
+                    break;
+                }
+            }
+        }
+        if files.is_empty() {
+            return None;
+        }
+
+        let mut config = Self::default();
+        for (dir, text) in files.iter().rev() {
+            if let Ok(relative) = path.strip_prefix(dir) {
+                config.apply(text, &relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+        Some(config)
+    }
+}
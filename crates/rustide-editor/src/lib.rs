@@ -1,12 +1,32 @@
+mod detect;
+mod diff;
 mod document;
 mod editor;
+mod editorconfig;
+#[cfg(not(target_arch = "wasm32"))]
+mod io;
+mod multi_selection;
+mod search;
 mod selection;
+mod wrap;
 
+pub use detect::score_encoding;
+pub use diff::{diff_lines, DiffHunk, DiffKind};
 pub use document::decode_bytes;
 pub use document::encode_text;
-pub use document::{Document, EditorError, TextEncoding, TextEncodingHint};
-pub use editor::{Editor, EditorEdit, EditorPoint};
+pub use document::is_likely_binary;
+pub use document::{Document, DocumentPath, EditorError, TextEncoding, TextEncodingHint};
+pub use editor::{Editor, EditorEdit, EditorPoint, Generation};
+pub use editorconfig::{
+    is_root_editorconfig, normalize_line_endings, section_matches, EditorConfig, IndentSize,
+    IndentStyle, LineEnding,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use io::{write_atomically, write_backup};
+pub use multi_selection::MultiSelection;
+pub use search::{char_to_byte_index, find_first_from, find_last_before, find_normalized_line};
 pub use selection::Selection;
+pub use wrap::{char_columns, wrap_line, wrap_row};
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,108 @@
+use crate::Selection;
+
+/// One or more selections edited together, for multiple cursors.
+///
+/// `ranges[0]` is the primary selection: the one the view follows and the one commands that
+/// work on a single selection use. The others are kept in document order, and no two of them
+/// overlap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiSelection {
+    ranges: Vec<Selection>,
+}
+
+impl MultiSelection {
+    pub fn from_single(selection: Selection) -> Self {
+        Self {
+            ranges: vec![selection],
+        }
+    }
+
+    /// `ranges` with the first as the primary selection, normalized as [`Self::add_cursor`]
+    /// describes. Empty input gives a caret at 0.
+    pub fn from_ranges(ranges: Vec<Selection>) -> Self {
+        let mut selection = Self { ranges };
+        if selection.ranges.is_empty() {
+            selection.ranges.push(Selection::collapsed(0));
+        }
+        selection.normalize();
+        selection
+    }
+
+    pub fn primary(&self) -> Selection {
+        self.ranges[0]
+    }
+
+    pub fn ranges(&self) -> &[Selection] {
+        &self.ranges
+    }
+
+    /// Whether there is more than the primary selection.
+    pub fn is_multiple(&self) -> bool {
+        self.ranges.len() > 1
+    }
+
+    /// Adds a caret at `pos`. A caret or range that overlaps another, or a caret touching one,
+    /// is merged into it, so adding a caret where one already is does nothing.
+    pub fn add_cursor(&mut self, pos: usize) {
+        self.ranges.push(Selection::collapsed(pos));
+        self.normalize();
+    }
+
+    /// Turns every range into a caret at its cursor end.
+    pub fn collapse_all(&mut self) {
+        for range in &mut self.ranges {
+            range.collapse_to(range.cursor);
+        }
+        self.normalize();
+    }
+
+    // Sorts the secondary ranges and merges any that overlap. A merged range keeps the
+    // direction of its first non-empty part, and is the primary if any part was.
+    fn normalize(&mut self) {
+        let mut order: Vec<(usize, Selection)> = self.ranges.iter().copied().enumerate().collect();
+        order.sort_by_key(|(_, sel)| (sel.range().start, sel.range().end));
+
+        let mut merged: Vec<(bool, Selection)> = Vec::with_capacity(order.len());
+        for (idx, sel) in order {
+            let primary = idx == 0;
+            match merged.last_mut() {
+                Some((last_primary, last)) if overlaps(*last, sel) => {
+                    let start = last.range().start;
+                    let end = last.range().end.max(sel.range().end);
+                    let forward = if last.is_empty() {
+                        sel.anchor <= sel.cursor
+                    } else {
+                        last.anchor <= last.cursor
+                    };
+                    *last = if forward {
+                        Selection {
+                            anchor: start,
+                            cursor: end,
+                        }
+                    } else {
+                        Selection {
+                            anchor: end,
+                            cursor: start,
+                        }
+                    };
+                    *last_primary |= primary;
+                }
+                _ => merged.push((primary, sel)),
+            }
+        }
+
+        let primary_at = merged.iter().position(|(primary, _)| *primary).unwrap_or(0);
+        let primary = merged.remove(primary_at).1;
+        self.ranges = std::iter::once(primary)
+            .chain(merged.into_iter().map(|(_, sel)| sel))
+            .collect();
+    }
+}
+
+// Whether `next`, which starts no earlier than `prev`, shares a position with it. Adjacent
+// non-empty ranges stay apart; a caret at either end of a range does not.
+fn overlaps(prev: Selection, next: Selection) -> bool {
+    let prev = prev.range();
+    let next = next.range();
+    next.start < prev.end || (next.start == prev.end && (prev.is_empty() || next.is_empty()))
+}
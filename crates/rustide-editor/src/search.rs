@@ -0,0 +1,62 @@
+use ropey::RopeSlice;
+
+/// Returns a rope line without its line ending, lowercased when `case_sensitive` is false,
+/// together with its length in chars.
+///
+/// Case folding is ASCII-only so every char keeps its width and char offsets found in the
+/// returned string map straight back onto the original line.
+pub fn find_normalized_line(line: RopeSlice<'_>, case_sensitive: bool) -> (String, usize) {
+    let mut text = line.to_string();
+    if text.ends_with('\n') {
+        text.pop();
+        if text.ends_with('\r') {
+            text.pop();
+        }
+    }
+    let len_chars = text.chars().count();
+    let hay = if case_sensitive {
+        text
+    } else {
+        text.to_ascii_lowercase()
+    };
+    (hay, len_chars)
+}
+
+/// Char offset of the first `needle` in `hay` starting at or after `from_char`.
+///
+/// An empty needle never matches.
+pub fn find_first_from(hay: &str, needle: &str, from_char: usize) -> Option<usize> {
+    if needle.is_empty() {
+        return None;
+    }
+    let from_byte = char_to_byte_index(hay, from_char);
+    let idx = hay.get(from_byte..)?.find(needle)?;
+    let byte = from_byte + idx;
+    Some(hay[..byte].chars().count())
+}
+
+/// Char offset of the last `needle` in `hay` that ends at or before `before_char`.
+///
+/// Overlapping matches are considered, so `"aa"` in `"aaa"` is found at 1. An empty needle
+/// never matches.
+pub fn find_last_before(hay: &str, needle: &str, before_char: usize) -> Option<usize> {
+    if needle.is_empty() {
+        return None;
+    }
+    let before_byte = char_to_byte_index(hay, before_char);
+    let prefix = hay.get(..before_byte).unwrap_or(hay);
+    // The last match to start is also the last to end, overlapping or not.
+    let best_byte = prefix.rfind(needle)?;
+    Some(prefix[..best_byte].chars().count())
+}
+
+/// Byte offset of the `char_index`-th char of `text`, clamped to `text.len()`.
+pub fn char_to_byte_index(text: &str, char_index: usize) -> usize {
+    if char_index == 0 {
+        return 0;
+    }
+    match text.char_indices().nth(char_index) {
+        Some((byte, _)) => byte,
+        None => text.len(),
+    }
+}
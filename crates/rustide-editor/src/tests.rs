@@ -49,7 +49,7 @@ fn undo_redo_roundtrip() {
 fn auto_indent_newline_basic() {
     let mut ed = Editor::from_text("    let x = 1;");
     ed.set_cursor(ed.rope().len_chars(), false);
-    ed.insert_newline_auto_indent();
+    ed.insert_newline_auto_indent("    ");
     assert_eq!(ed.rope().to_string(), "    let x = 1;\n    ");
 }
 
@@ -57,11 +57,20 @@ fn auto_indent_newline_basic() {
 fn auto_indent_newline_block_braces() {
     let mut ed = Editor::from_text("{\n}");
     ed.set_cursor(1, false); // between '{' and '\n'
-    ed.insert_newline_auto_indent();
+    ed.insert_newline_auto_indent("    ");
     assert_eq!(ed.rope().to_string(), "{\n    \n}");
     assert_eq!(ed.selection().cursor, 1 + 1 + 4);
 }
 
+#[test]
+fn auto_indent_newline_uses_indent_unit() {
+    let mut ed = Editor::from_text("\tif (x) {\n\t}");
+    ed.set_cursor(9, false); // after '{'
+    ed.insert_newline_auto_indent("\t");
+    assert_eq!(ed.rope().to_string(), "\tif (x) {\n\t\t\n\t}");
+    assert_eq!(ed.selection().cursor, 9 + 1 + 2);
+}
+
 #[test]
 fn move_up_down_preserves_column_when_possible() {
     let mut editor = Editor::empty();
@@ -76,3 +85,1146 @@ fn move_up_down_preserves_column_when_possible() {
     editor.move_down(false);
     assert_eq!(editor.selection().cursor, line1_start + 3); // preferred column restored
 }
+
+#[test]
+fn duplicate_line_copies_below_and_moves_cursor() {
+    let mut editor = Editor::from_text("one\ntwo\nthree");
+    let line1_start = editor.rope().line_to_char(1);
+    editor.set_cursor(line1_start + 1, false);
+
+    editor.duplicate_line();
+    assert_eq!(rope_text(&editor), "one\ntwo\ntwo\nthree");
+    assert_eq!(editor.selection().cursor, editor.rope().line_to_char(2) + 1);
+
+    assert!(editor.undo());
+    assert_eq!(rope_text(&editor), "one\ntwo\nthree");
+}
+
+#[test]
+fn replace_ranges_is_a_single_undo_step() {
+    let mut editor = Editor::from_text("foo bar foo\nfoo");
+    let count = editor.replace_ranges(&[0..3, 8..11, 12..15], "baz");
+    assert_eq!(count, 3);
+    assert_eq!(rope_text(&editor), "baz bar baz\nbaz");
+
+    assert!(editor.undo());
+    assert_eq!(rope_text(&editor), "foo bar foo\nfoo");
+    assert!(!editor.undo());
+}
+
+#[test]
+fn version_increases_on_every_mutation() {
+    let mut editor = Editor::from_text("abc");
+    let mut last = editor.version().get();
+    let mut assert_bumped = |editor: &Editor| {
+        let now = editor.version().get();
+        assert!(now > last, "version did not increase: {now} <= {last}");
+        last = now;
+    };
+
+    editor.set_cursor(3, false);
+    editor.insert_text("d");
+    assert_bumped(&editor);
+    editor.backspace();
+    assert_bumped(&editor);
+    editor.set_cursor(0, false);
+    editor.delete_forward();
+    assert_bumped(&editor);
+    editor.insert_newline_auto_indent("    ");
+    assert_bumped(&editor);
+    editor.duplicate_line();
+    assert_bumped(&editor);
+    editor.replace_ranges(&[0..1, 2..3], "x");
+    assert_bumped(&editor);
+    assert!(editor.undo());
+    assert_bumped(&editor);
+    assert!(editor.redo());
+    assert_bumped(&editor);
+
+    // Cursor movement is not a mutation.
+    let before = editor.version();
+    editor.move_right(false);
+    editor.select_all();
+    assert_eq!(editor.version(), before);
+
+    // Separate editors never share a generation, even with the same text.
+    assert_ne!(
+        Editor::from_text("abc").version(),
+        Editor::from_text("abc").version()
+    );
+}
+
+#[test]
+fn document_path_display_and_file_name() {
+    use std::path::PathBuf;
+
+    use crate::DocumentPath;
+
+    let untitled = None;
+    assert!(DocumentPath(&untitled).is_untitled());
+    assert_eq!(DocumentPath(&untitled).to_string(), DocumentPath::UNTITLED);
+    assert_eq!(DocumentPath(&untitled).file_name(), DocumentPath::UNTITLED);
+    assert_eq!(DocumentPath::UNTITLED, "<untitled>");
+
+    let path = Some(PathBuf::from("src").join("main.rs"));
+    assert!(!DocumentPath(&path).is_untitled());
+    assert_eq!(
+        DocumentPath(&path).to_string(),
+        path.as_ref().unwrap().display().to_string()
+    );
+    assert_eq!(DocumentPath(&path).file_name(), "main.rs");
+}
+
+#[test]
+fn indent_and_dedent_selected_lines() {
+    let mut editor = Editor::from_text("fn a() {\nlet x = 1;\n\n    let y = 2;\n}");
+    let line1 = editor.rope().line_to_char(1);
+    let line4 = editor.rope().line_to_char(4);
+    editor.set_cursor(line1 + 4, false);
+    editor.set_cursor(line4, true); // ends at column 0, so line 4 is untouched
+
+    editor.indent_selection("    ");
+    assert_eq!(
+        rope_text(&editor),
+        "fn a() {\n    let x = 1;\n\n        let y = 2;\n}"
+    );
+    assert_eq!(editor.selection().anchor, line1 + 8);
+    assert_eq!(editor.selection().cursor, editor.rope().line_to_char(4));
+
+    editor.dedent_selection("    ");
+    editor.dedent_selection("    ");
+    assert_eq!(rope_text(&editor), "fn a() {\nlet x = 1;\n\nlet y = 2;\n}");
+    assert_eq!(editor.selection().anchor, line1 + 4);
+
+    assert!(editor.undo());
+    assert_eq!(
+        rope_text(&editor),
+        "fn a() {\nlet x = 1;\n\n    let y = 2;\n}"
+    );
+}
+
+#[test]
+fn move_word_left_stops_at_word_starts() {
+    let mut editor = Editor::from_text("let foo_bar = baz.qux;");
+    editor.set_cursor(editor.rope().len_chars(), false);
+
+    editor.move_word_left(false); // punctuation -> word
+    assert_eq!(editor.selection().cursor, 18); // "qux"
+    editor.move_word_left(false); // '.' between words
+    assert_eq!(editor.selection().cursor, 14); // "baz"
+    editor.move_word_left(false); // space and '=' -> word, '_' is a word char
+    assert_eq!(editor.selection().cursor, 4); // "foo_bar"
+    editor.set_cursor(6, false); // inside a word
+    editor.move_word_left(true);
+    assert_eq!(editor.selection().range(), 4..6);
+    editor.set_cursor(0, false);
+    editor.move_word_left(false);
+    assert_eq!(editor.selection().cursor, 0);
+}
+
+#[test]
+fn move_word_right_stops_at_next_word_starts() {
+    let mut editor = Editor::from_text("let foo_bar = baz.qux;\nnext");
+
+    editor.move_word_right(false); // word -> space
+    assert_eq!(editor.selection().cursor, 4); // "foo_bar"
+    editor.move_word_right(false); // word, then " = "
+    assert_eq!(editor.selection().cursor, 14); // "baz"
+    editor.move_word_right(true); // word, then '.'
+    assert_eq!(editor.selection().range(), 14..18);
+    editor.set_cursor(21, false); // on ';' (non-word): skip to the next line's word
+    editor.move_word_right(false);
+    assert_eq!(editor.selection().cursor, 23); // "next"
+    editor.move_word_right(false);
+    assert_eq!(editor.selection().cursor, editor.rope().len_chars());
+}
+
+#[test]
+fn delete_word_backward_and_forward() {
+    let mut editor = Editor::from_text("let foo_bar = baz;");
+    editor.set_cursor(13, false); // after '='
+
+    editor.delete_word_backward(); // " =" then "foo_bar"
+    assert_eq!(rope_text(&editor), "let  baz;");
+    assert_eq!(editor.selection().cursor, 4);
+
+    editor.delete_word_forward(); // only the space before "baz"
+    assert_eq!(rope_text(&editor), "let baz;");
+    editor.delete_word_forward(); // "baz" then ";"
+    assert_eq!(rope_text(&editor), "let ");
+    editor.delete_word_forward(); // at end: nothing to delete
+    assert_eq!(rope_text(&editor), "let ");
+
+    assert!(editor.undo());
+    assert_eq!(rope_text(&editor), "let baz;");
+
+    editor.set_cursor(0, false);
+    editor.set_cursor(2, true);
+    editor.delete_word_backward(); // a selection is deleted as-is
+    assert_eq!(rope_text(&editor), "t baz;");
+}
+
+#[test]
+fn replace_all_is_a_single_undo_step() {
+    let mut editor = Editor::from_text("\tone\n\ttwo");
+    let before = editor.version();
+
+    editor.replace_all("    one\n    two");
+    assert_eq!(rope_text(&editor), "    one\n    two");
+    assert_ne!(editor.version(), before);
+    let edits = editor.take_last_edits();
+    assert_eq!(edits.len(), 1, "replace_all reports one edit");
+    let edit = edits[0];
+    assert_eq!(edit.start_byte, 0);
+    assert_eq!(edit.old_end_byte, "\tone\n\ttwo".len());
+    assert_eq!(edit.new_end_byte, "    one\n    two".len());
+
+    assert!(editor.undo());
+    assert_eq!(rope_text(&editor), "\tone\n\ttwo");
+    assert!(!editor.undo());
+}
+
+#[test]
+fn document_write_appends_streamed_utf8() {
+    use std::io::Write;
+
+    use crate::Document;
+
+    let mut doc = Document::empty();
+    doc.editor.insert_text("log:");
+    doc.editor.set_cursor(0, false);
+    let before = doc.editor.version();
+
+    doc.write_all(b" started\n").unwrap();
+    // "né" with the two-byte 'é' split across writes.
+    doc.write_all(&[b'n', 0xC3]).unwrap();
+    assert_eq!(rope_text(&doc.editor), "log: started\nn");
+    doc.write_all(&[0xA9, b'\n']).unwrap();
+
+    assert_eq!(rope_text(&doc.editor), "log: started\nné\n");
+    assert_ne!(doc.editor.version(), before);
+    assert_eq!(doc.editor.selection().cursor, 0);
+    assert_eq!(doc.max_line_chars, "log: started".len());
+
+    // Streamed output is not undoable; the typed text still is.
+    assert!(doc.editor.undo());
+    assert_eq!(rope_text(&doc.editor), " started\nné\n");
+}
+
+#[test]
+fn diff_lines_finds_shortest_edit_script() {
+    use crate::{diff_lines, DiffHunk, DiffKind};
+
+    let hunk = |kind, lines| DiffHunk { kind, lines };
+    let old = ["a", "b", "c", "a", "b", "b", "a"];
+    let new = ["c", "b", "a", "b", "a", "c"];
+    let hunks = diff_lines(&old, &new);
+
+    // Myers' classic example: edit distance 5.
+    let edits: usize = hunks
+        .iter()
+        .filter(|h| h.kind != DiffKind::Unchanged)
+        .map(|h| h.lines.len())
+        .sum();
+    assert_eq!(edits, 5);
+    let unchanged: Vec<&str> = hunks
+        .iter()
+        .filter(|h| h.kind == DiffKind::Unchanged)
+        .flat_map(|h| old[h.lines.clone()].iter().copied())
+        .collect();
+    assert_eq!(unchanged, ["b", "a", "b", "a"]);
+
+    assert_eq!(diff_lines::<&str>(&[], &[]), vec![]);
+    assert_eq!(
+        diff_lines(&["x", "y"], &[]),
+        vec![hunk(DiffKind::Removed, 0..2)]
+    );
+    assert_eq!(diff_lines(&[], &["x"]), vec![hunk(DiffKind::Added, 0..1)]);
+    assert_eq!(
+        diff_lines(&["x", "y"], &["x", "y"]),
+        vec![hunk(DiffKind::Unchanged, 0..2)]
+    );
+
+    let old: Vec<usize> = (0..1000).collect();
+    let new: Vec<usize> = (1000..2000).collect();
+    assert_eq!(
+        diff_lines(&old, &new),
+        vec![
+            hunk(DiffKind::Removed, 0..1000),
+            hunk(DiffKind::Added, 0..1000)
+        ]
+    );
+}
+
+#[test]
+fn document_diff_reports_line_hunks() {
+    use crate::{DiffHunk, DiffKind, Document};
+
+    let mut saved = Document::empty();
+    saved
+        .editor
+        .insert_text("fn main() {\r\n    old();\r\n}\r\n");
+    let mut current = Document::empty();
+    current
+        .editor
+        .insert_text("fn main() {\n    new();\n    more();\n}\n");
+
+    let hunk = |kind, lines| DiffHunk { kind, lines };
+    assert_eq!(
+        saved.diff(&current),
+        vec![
+            hunk(DiffKind::Unchanged, 0..1),
+            hunk(DiffKind::Removed, 1..2),
+            hunk(DiffKind::Added, 1..3),
+            hunk(DiffKind::Unchanged, 2..4),
+        ]
+    );
+}
+
+#[test]
+fn search_offsets_are_chars_not_bytes() {
+    use crate::{char_to_byte_index, find_first_from, find_last_before};
+
+    let hay = "héllo wörld héllo";
+    assert_eq!(char_to_byte_index(hay, 2), 3);
+    assert_eq!(char_to_byte_index(hay, 100), hay.len());
+    assert_eq!(find_first_from(hay, "héllo", 0), Some(0));
+    assert_eq!(find_first_from(hay, "héllo", 1), Some(12));
+    assert_eq!(find_first_from(hay, "ö", 0), Some(7));
+    assert_eq!(find_last_before(hay, "héllo", 17), Some(12));
+    assert_eq!(find_last_before(hay, "héllo", 16), Some(0));
+    assert_eq!(find_last_before("aaa", "aa", 3), Some(1));
+    // Matches after one starting with a multi-byte char are still found.
+    assert_eq!(find_last_before("ö x ö", "ö", 5), Some(4));
+    assert_eq!(find_last_before("öö", "ö", 2), Some(1));
+    assert_eq!(find_first_from("🦀🦀x", "x", 1), Some(2));
+    assert_eq!(find_first_from(hay, "", 0), None);
+    assert_eq!(find_last_before(hay, "", 17), None);
+}
+
+#[test]
+fn normalized_line_strips_endings_and_folds_ascii_only() {
+    use crate::{find_first_from, find_normalized_line};
+
+    let rope = ropey::Rope::from_str("Grüße AUS Köln\r\nnext\n");
+    let (exact, len) = find_normalized_line(rope.line(0), true);
+    assert_eq!(exact, "Grüße AUS Köln");
+    assert_eq!(len, 14);
+
+    let (folded, len) = find_normalized_line(rope.line(0), false);
+    assert_eq!(folded, "grüße aus köln");
+    assert_eq!(len, 14);
+    assert_eq!(find_first_from(&folded, "aus", 0), Some(6));
+
+    // Non-ASCII letters are left alone so char offsets stay aligned with the original line.
+    let (folded, _) = find_normalized_line(ropey::Rope::from_str("ÄÖ").line(0), false);
+    assert_eq!(folded, "ÄÖ");
+}
+
+#[test]
+fn document_bytes_round_trip_without_filesystem() {
+    use crate::{Document, TextEncoding, TextEncodingHint};
+
+    let bytes = b"\xEF\xBB\xBFfn main() {}\r\nlonger line\r\n";
+    let doc = Document::from_bytes(None, bytes, TextEncodingHint::Auto);
+    assert_eq!(doc.encoding, TextEncoding::Utf8Bom);
+    assert_eq!(doc.max_line_chars, 12);
+    assert!(doc.path.is_none());
+    assert_eq!(doc.to_bytes(), bytes);
+}
+
+#[test]
+fn editorconfig_globs_match_like_the_spec() {
+    use crate::section_matches;
+
+    assert!(section_matches("*", "src/main.rs"));
+    assert!(section_matches("*.rs", "src/main.rs"));
+    assert!(!section_matches("*.rs", "src/main.rsx"));
+    assert!(section_matches("*.{js,py}", "a/b.py"));
+    assert!(section_matches("{Makefile,*.mk}", "build/rules.mk"));
+    assert!(section_matches("file?.txt", "file1.txt"));
+    assert!(section_matches("[Mm]akefile", "makefile"));
+    assert!(!section_matches("[!Mm]akefile", "Makefile"));
+    assert!(section_matches("test{1..12}.py", "test10.py"));
+    assert!(!section_matches("test{1..12}.py", "test13.py"));
+
+    // A `/` anchors the glob to the `.editorconfig` directory; only `**` crosses directories.
+    assert!(section_matches("src/*.rs", "src/lib.rs"));
+    assert!(!section_matches("src/*.rs", "src/nested/lib.rs"));
+    assert!(!section_matches("src/*.rs", "other/src/lib.rs"));
+    assert!(section_matches("/src/**.rs", "src/nested/lib.rs"));
+    assert!(section_matches("lib/**/*.js", "lib/a/b/c.js"));
+
+    // Many stars against a near miss: too slow to finish if every split were retried.
+    let name = format!("{}.txt", "a".repeat(200));
+    assert!(!section_matches(&format!("{}b", "*a".repeat(30)), &name));
+    assert!(section_matches(&format!("{}.txt", "*a".repeat(30)), &name));
+    assert!(!section_matches(
+        &format!("**{}/x", "a**".repeat(30)),
+        &name
+    ));
+}
+
+#[test]
+fn editorconfig_later_sections_override_earlier_ones() {
+    use crate::{EditorConfig, IndentSize, IndentStyle, LineEnding, TextEncoding};
+
+    let text = "root = true\n\n[*]\nindent_style = space\nindent_size = 4\nend_of_line = lf\n\n\
+                [*.go]\nindent_style = tab\ncharset = utf-8-bom\n\n[vendor/**]\nend_of_line = unset\n";
+    let mut config = EditorConfig::default();
+    config.apply(text, "cmd/main.go");
+    assert_eq!(config.indent_style, Some(IndentStyle::Tab));
+    assert_eq!(config.indent_size, Some(IndentSize::Columns(4)));
+    assert_eq!(config.end_of_line, Some(LineEnding::Lf));
+    assert_eq!(config.charset, Some(TextEncoding::Utf8Bom));
+    assert_eq!(config.indent_unit().as_deref(), Some("\t"));
+
+    let mut vendored = EditorConfig::default();
+    vendored.apply(text, "vendor/x/y.go");
+    assert_eq!(vendored.end_of_line, None);
+    assert_eq!(EditorConfig::default().indent_unit(), None);
+}
+
+#[test]
+fn editorconfig_indent_size_tab_uses_tab_width() {
+    use crate::{EditorConfig, IndentSize};
+
+    let mut config = EditorConfig::default();
+    config.apply(
+        "[*]\nindent_style = space\nindent_size = tab\ntab_width = 8\n",
+        "a.c",
+    );
+    assert_eq!(config.indent_size, Some(IndentSize::Tab));
+    assert_eq!(config.indent_columns(), Some(8));
+    assert_eq!(config.tab_columns(), Some(8));
+    assert_eq!(config.indent_unit().as_deref(), Some("        "));
+
+    // `tab_width` defaults to a numeric `indent_size`, and both can be unset again.
+    let mut config = EditorConfig::default();
+    config.apply("[*]\nindent_size = 2\n", "a.c");
+    assert_eq!(config.tab_columns(), Some(2));
+    config.apply("[*]\ntab_width = 3\n", "a.c");
+    assert_eq!(
+        (config.indent_columns(), config.tab_columns()),
+        (Some(2), Some(3))
+    );
+    config.apply("[*]\nindent_size = unset\ntab_width = unset\n", "a.c");
+    assert_eq!(config, EditorConfig::default());
+}
+
+#[test]
+fn document_load_applies_editorconfig() {
+    use crate::{Document, EditorConfig, IndentSize, LineEnding};
+
+    let dir = std::env::temp_dir().join(format!("rustide-editorconfig-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(
+        dir.join(".editorconfig"),
+        "root = true\n[*]\nend_of_line = crlf\nindent_size = 2\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("src/.editorconfig"), "[*.rs]\nindent_size = 4\n").unwrap();
+    let path = dir.join("src/main.rs");
+    std::fs::write(&path, "fn main() {\n}\n").unwrap();
+
+    let doc = Document::load(&path).unwrap();
+    let config = doc.editor_config.unwrap();
+    assert_eq!(config.end_of_line, Some(LineEnding::CrLf));
+    assert_eq!(config.indent_size, Some(IndentSize::Columns(4)));
+    assert_eq!(doc.to_bytes(), b"fn main() {\r\n}\r\n");
+    assert_eq!(
+        EditorConfig::for_path(&dir.join("notes.txt"))
+            .unwrap()
+            .indent_size,
+        Some(IndentSize::Columns(2))
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn wrap_line_breaks_at_word_boundaries() {
+    use crate::wrap_line;
+
+    assert_eq!(wrap_line("", 10), [0]);
+    assert_eq!(wrap_line("short", 10), [0]);
+    // Trailing whitespace hangs past the edge instead of starting the next row.
+    assert_eq!(wrap_line("aaaa bbbb cc", 5), [0, 5, 10]);
+    assert_eq!(wrap_line("aaaa  bb", 4), [0, 6]);
+    // Words longer than a row are split.
+    assert_eq!(wrap_line("abcdefghij", 4), [0, 4, 8]);
+    assert_eq!(wrap_line("a bcdefgh", 4), [0, 2, 6]);
+
+    // Rows are measured in display columns: a tab takes four, a CJK char two.
+    assert_eq!(wrap_line("\tab cd", 6), [0, 4]);
+    assert_eq!(wrap_line("漢字漢字", 4), [0, 2]);
+    assert_eq!(wrap_line("ab 漢字", 4), [0, 3]);
+    // A char wider than the row still gets a row of its own.
+    assert_eq!(wrap_line("漢字", 1), [0, 1]);
+}
+
+#[test]
+fn move_vertical_steps_through_wrapped_rows() {
+    let mut ed = Editor::from_text("aaaa bbbb cc\nxy");
+    ed.set_wrap_columns(Some(5));
+    ed.set_cursor(1, false);
+
+    // Rows of line 0 start at 0, 5 and 10.
+    ed.move_down(false);
+    assert_eq!(ed.selection().cursor, 6);
+    ed.move_down(false);
+    assert_eq!(ed.selection().cursor, 11);
+    ed.move_down(false);
+    assert_eq!(ed.selection().cursor, 14);
+    ed.move_up(false);
+    assert_eq!(ed.selection().cursor, 11);
+
+    // The end of a row is clamped before the next row's first char.
+    ed.set_cursor(9, false);
+    ed.move_up(false);
+    assert_eq!(ed.selection().cursor, 4);
+    ed.move_down(false);
+    assert_eq!(ed.selection().cursor, 9);
+
+    ed.set_wrap_columns(None);
+    ed.set_cursor(6, false);
+    ed.move_down(false);
+    assert_eq!(ed.selection().cursor, 15);
+}
+
+#[test]
+fn atomic_writes_never_expose_a_partial_file() {
+    use crate::write_atomically;
+
+    let dir = std::env::temp_dir().join(format!("rustide-atomic-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.ini");
+    let old = vec![b'a'; 64 * 1024];
+    let new = vec![b'b'; 64 * 1024];
+    write_atomically(&path, &old).unwrap();
+
+    // A reader racing the writer must always find one complete version.
+    let done = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let reader = {
+        let (path, done) = (path.clone(), done.clone());
+        std::thread::spawn(move || {
+            let mut reads = 0;
+            while !done.load(std::sync::atomic::Ordering::Acquire) || reads == 0 {
+                let bytes = std::fs::read(&path).expect("destination went missing");
+                assert_eq!(bytes.len(), 64 * 1024);
+                assert!(bytes.iter().all(|&b| b == bytes[0]), "partial write seen");
+                reads += 1;
+            }
+        })
+    };
+    for i in 0..200 {
+        write_atomically(&path, if i % 2 == 0 { &new } else { &old }).unwrap();
+    }
+    done.store(true, std::sync::atomic::Ordering::Release);
+    reader.join().unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), old);
+
+    // Nothing is left behind, including after a failed write.
+    assert!(write_atomically(&dir.join("missing/file.txt"), b"x").is_err());
+    let leftovers: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(leftovers, ["config.ini"]);
+
+    #[cfg(unix)]
+    {
+        let link = dir.join("link.ini");
+        std::os::unix::fs::symlink(&path, &link).unwrap();
+        write_atomically(&link, b"through").unwrap();
+        assert!(std::fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(std::fs::read(&path).unwrap(), b"through");
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn save_with_backup_keeps_previous_contents() {
+    use crate::{Document, TextEncodingHint};
+
+    let dir = std::env::temp_dir().join(format!("rustide-backup-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("notes.txt");
+    let backup = dir.join("notes.bak");
+
+    // First save: nothing on disk yet, so no backup.
+    let mut doc = Document::from_bytes(Some(path.clone()), b"one", TextEncodingHint::Utf8);
+    doc.save_with_backup().unwrap();
+    assert!(!backup.exists());
+
+    doc = Document::from_bytes(Some(path.clone()), b"two", TextEncodingHint::Utf8);
+    doc.save_with_backup().unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), b"two");
+    assert_eq!(std::fs::read(&backup).unwrap(), b"one");
+
+    doc = Document::from_bytes(Some(path.clone()), b"three", TextEncodingHint::Utf8);
+    doc.save_with_backup().unwrap();
+    assert_eq!(std::fs::read(&backup).unwrap(), b"two");
+
+    // A backup that is the file itself is left alone rather than truncated.
+    crate::write_backup(&backup).unwrap();
+    assert_eq!(std::fs::read(&backup).unwrap(), b"two");
+    #[cfg(unix)]
+    {
+        let linked = dir.join("linked.txt");
+        std::fs::write(&linked, "kept").unwrap();
+        std::os::unix::fs::symlink(&linked, dir.join("linked.bak")).unwrap();
+        crate::write_backup(&linked).unwrap();
+        assert_eq!(std::fs::read(&linked).unwrap(), b"kept");
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn line_endings_are_detected_converted_and_saved() {
+    use crate::{Document, LineEnding, TextEncodingHint};
+
+    let crlf = b"one\r\ntwo\r\nthree\r\n";
+    let doc = Document::from_bytes(None, crlf, TextEncodingHint::Utf8);
+    assert_eq!(doc.line_ending, LineEnding::CrLf);
+    assert_eq!(doc.to_bytes(), crlf);
+
+    // Mixed files report the majority ending but are saved as they are until one is set.
+    let mixed = b"a\r\nb\r\nc\nd\re";
+    let mut doc = Document::from_bytes(None, mixed, TextEncodingHint::Utf8);
+    assert_eq!(doc.line_ending, LineEnding::CrLf);
+    assert_eq!(doc.to_bytes(), mixed);
+    doc.set_line_ending(LineEnding::CrLf);
+    assert_eq!(doc.to_bytes(), b"a\r\nb\r\nc\r\nd\r\ne");
+    assert_eq!(doc.editor.rope().to_string(), "a\r\nb\r\nc\nd\re");
+
+    assert_eq!(doc.convert_line_endings(LineEnding::Lf), 3);
+    assert_eq!(doc.editor.rope().to_string(), "a\nb\nc\nd\ne");
+    assert_eq!(doc.to_bytes(), b"a\nb\nc\nd\ne");
+    assert_eq!(doc.convert_line_endings(LineEnding::Lf), 0);
+    assert_eq!(doc.convert_line_endings(LineEnding::CrLf), 4);
+    assert_eq!(doc.to_bytes(), b"a\r\nb\r\nc\r\nd\r\ne");
+    // The conversion is a single undoable edit.
+    doc.editor.undo();
+    assert_eq!(doc.editor.rope().to_string(), "a\nb\nc\nd\ne");
+
+    assert_eq!(LineEnding::detect("no breaks"), None);
+    assert_eq!(LineEnding::detect("a\rb\nc\r"), Some(LineEnding::Cr));
+    assert_eq!(LineEnding::detect("a\nb\r\n"), Some(LineEnding::Lf));
+    assert_eq!(
+        Document::from_bytes(None, b"x", TextEncodingHint::Utf8).line_ending,
+        LineEnding::Lf
+    );
+}
+
+#[test]
+fn latin1_and_windows1252_round_trip_high_bytes() {
+    use crate::{decode_bytes, encode_text, TextEncoding, TextEncodingHint};
+
+    let bytes: Vec<u8> = (0x80..=0xFF).collect();
+    for (hint, encoding) in [
+        (TextEncodingHint::Latin1, TextEncoding::Latin1),
+        (TextEncodingHint::Windows1252, TextEncoding::Windows1252),
+    ] {
+        let (text, detected) = decode_bytes(&bytes, hint);
+        assert_eq!(detected, encoding);
+        assert_eq!(text.chars().count(), bytes.len());
+        assert_eq!(encode_text(&text, encoding), bytes, "{encoding}");
+        assert_eq!(hint.to_string().parse::<TextEncodingHint>(), Ok(hint));
+    }
+
+    let (latin1, _) = decode_bytes(&[0x80, 0xE9], TextEncodingHint::Latin1);
+    assert_eq!(latin1, "\u{80}é");
+    let (cp1252, _) = decode_bytes(&[0x80, 0xE9], TextEncodingHint::Windows1252);
+    assert_eq!(cp1252, "€é");
+    assert_eq!(encode_text("€", TextEncoding::Latin1), b"?");
+    assert_eq!("cp1252".parse(), Ok(TextEncodingHint::Windows1252));
+    assert_eq!("Latin-1".parse(), Ok(TextEncodingHint::Latin1));
+}
+
+#[test]
+fn shift_jis_and_euc_kr_decode() {
+    use crate::{decode_bytes, encode_text, Document, TextEncoding, TextEncodingHint};
+
+    let japanese = "日本語のテキスト\nｱｲｳ\n";
+    let (sjis, _, _) = encoding_rs::SHIFT_JIS.encode(japanese);
+    let doc = Document::from_bytes(None, &sjis, TextEncodingHint::ShiftJis);
+    assert_eq!(doc.encoding, TextEncoding::ShiftJis);
+    assert_eq!(doc.editor.rope().to_string(), japanese);
+    assert_eq!(doc.to_bytes(), sjis.as_ref());
+
+    let korean = "한국어 텍스트\n";
+    let (euc_kr, _, _) = encoding_rs::EUC_KR.encode(korean);
+    let (text, encoding) = decode_bytes(&euc_kr, TextEncodingHint::EucKr);
+    assert_eq!((text.as_str(), encoding), (korean, TextEncoding::EucKr));
+    assert_eq!(encode_text(&text, encoding), euc_kr.as_ref());
+
+    // Half-width katakana is a run of single bytes that leaves GBK and Big5 with a dangling
+    // lead byte, so auto-detection falls through to Shift-JIS.
+    let (text, encoding) = decode_bytes(&[0xB1, 0xB2, 0xB3], TextEncodingHint::Auto);
+    assert_eq!((text.as_str(), encoding), ("ｱｲｳ", TextEncoding::ShiftJis));
+
+    for name in ["shift-jis", "SJIS", "euc-kr", "euckr"] {
+        let hint: TextEncodingHint = name.parse().unwrap();
+        assert_eq!(hint.to_string().parse(), Ok(hint));
+    }
+}
+
+#[test]
+fn score_encoding_prefers_the_encoding_that_wrote_the_text() {
+    use crate::score_encoding;
+
+    let gbk = encoding_rs::GBK.encode("我们是中国人，这个问题很重要。").0;
+    assert!(score_encoding(&gbk, encoding_rs::GBK) > score_encoding(&gbk, encoding_rs::BIG5));
+    let big5 = encoding_rs::BIG5.encode("我們是中國人，這個問題很重要。").0;
+    assert!(score_encoding(&big5, encoding_rs::BIG5) > score_encoding(&big5, encoding_rs::GBK));
+    assert_eq!(score_encoding(b"", encoding_rs::GBK), 0.0);
+}
+
+const DETECTION_SAMPLES: [(crate::TextEncoding, &[&str]); 5] = {
+    use crate::TextEncoding;
+    [
+        (
+            TextEncoding::Gbk,
+            &[
+                "我们", "中国", "的", "是", "一个", "不是", "时候", "他们", "这个", "可以", "没有",
+                "因为", "所以", "学生", "工作", "今天", "问题", "发展", "经济", "社会", "国家",
+                "人民", "现在", "知道", "已经", "自己", "什么", "说话",
+            ],
+        ),
+        (
+            TextEncoding::Big5,
+            &[
+                "我們", "中國", "的", "是", "一個", "不是", "時候", "他們", "這個", "可以", "沒有",
+                "因為", "所以", "學生", "工作", "今天", "問題", "發展", "經濟", "社會", "國家",
+                "人民", "現在", "知道", "已經", "自己", "什麼", "說話",
+            ],
+        ),
+        (
+            TextEncoding::ShiftJis,
+            &[
+                "これは",
+                "日本語",
+                "です",
+                "ありがとう",
+                "テスト",
+                "ファイル",
+                "私は",
+                "学生",
+                "東京",
+                "行きます",
+                "ません",
+                "ください",
+                "コンピュータ",
+                "として",
+                "ている",
+            ],
+        ),
+        (
+            TextEncoding::EucKr,
+            &[
+                "안녕하세요",
+                "한국어",
+                "입니다",
+                "이것은",
+                "사람",
+                "우리는",
+                "학교",
+                "그리고",
+                "있습니다",
+                "하지만",
+                "대한민국",
+                "컴퓨터",
+                "파일",
+                "시간",
+                "사랑",
+            ],
+        ),
+        (
+            TextEncoding::Windows1252,
+            &[
+                "café",
+                "élève",
+                "naïve",
+                "über",
+                "straße",
+                "français",
+                "niño",
+                "señor",
+                "Köln",
+                "crème",
+                "façade",
+                "mañana",
+                "Größe",
+                "résumé",
+                "déjà",
+                "garçon",
+            ],
+        ),
+    ]
+};
+
+proptest::proptest! {
+    #[test]
+    fn legacy_encodings_are_reidentified(
+        language in 0..DETECTION_SAMPLES.len(),
+        words in proptest::collection::vec((0..64usize, 0..4usize), 8..40),
+    ) {
+        use crate::{decode_bytes, TextEncodingHint};
+
+        let (encoding, vocabulary) = DETECTION_SAMPLES[language];
+        let mut text = String::new();
+        for (word, separator) in words {
+            text.push_str(vocabulary[word % vocabulary.len()]);
+            text.push_str([" ", "\n", ", ", " x = 1; "][separator]);
+        }
+        let encoder = match encoding {
+            crate::TextEncoding::Gbk => encoding_rs::GBK,
+            crate::TextEncoding::Big5 => encoding_rs::BIG5,
+            crate::TextEncoding::ShiftJis => encoding_rs::SHIFT_JIS,
+            crate::TextEncoding::EucKr => encoding_rs::EUC_KR,
+            _ => encoding_rs::WINDOWS_1252,
+        };
+        let bytes = encoder.encode(&text).0;
+        let (decoded, detected) = decode_bytes(&bytes, TextEncodingHint::Auto);
+        proptest::prop_assert_eq!(detected, encoding);
+        proptest::prop_assert_eq!(decoded, text);
+    }
+}
+
+#[test]
+fn convert_encoding_changes_only_the_saved_bytes() {
+    use crate::{encode_text, Document, TextEncoding, TextEncodingHint};
+
+    let mut doc = Document::from_bytes(None, "naïve café\n".as_bytes(), TextEncodingHint::Auto);
+    assert_eq!(doc.encoding, TextEncoding::Utf8);
+    doc.convert_encoding(TextEncoding::Latin1);
+    assert_eq!(doc.editor.rope().to_string(), "naïve café\n");
+    assert_eq!(doc.to_bytes(), b"na\xEFve caf\xE9\n");
+    assert_eq!(
+        doc.to_bytes(),
+        encode_text(&doc.save_text(), TextEncoding::Latin1)
+    );
+
+    doc.convert_encoding(TextEncoding::Utf16Le);
+    assert_eq!(&doc.to_bytes()[..4], [0xFF, 0xFE, b'n', 0]);
+    assert_eq!(
+        TextEncodingHint::from(TextEncoding::Utf8Bom),
+        TextEncodingHint::Utf8
+    );
+}
+
+#[test]
+fn utf16_round_trips() {
+    use crate::{decode_bytes, encode_text, TextEncoding, TextEncodingHint};
+
+    for encoding in [TextEncoding::Utf16Le, TextEncoding::Utf16Be] {
+        let bytes = encode_text("a€😀\n", encoding);
+        assert_eq!(bytes.len(), 2 + 2 * 5);
+        assert_eq!(
+            decode_bytes(&bytes, TextEncodingHint::Auto),
+            ("a€😀\n".to_string(), encoding)
+        );
+    }
+}
+
+#[test]
+fn encoding_names_round_trip_through_serde_and_from_str() {
+    use crate::{TextEncoding, TextEncodingHint};
+
+    for &encoding in TextEncoding::ALL {
+        let hint = TextEncodingHint::from(encoding);
+        let json = serde_json::to_string(&hint).unwrap();
+        assert_eq!(json, format!("\"{hint}\""));
+        assert_eq!(
+            serde_json::from_str::<TextEncodingHint>(&json).unwrap(),
+            hint
+        );
+        assert_eq!(hint.to_string().parse::<TextEncodingHint>(), Ok(hint));
+
+        // Apart from the BOM, an encoding goes by its hint's name.
+        let json = serde_json::to_string(&encoding).unwrap();
+        if encoding == TextEncoding::Utf8Bom {
+            assert_eq!(json, "\"utf-8-bom\"");
+        } else {
+            assert_eq!(json, format!("\"{hint}\""));
+        }
+        assert_eq!(
+            serde_json::from_str::<TextEncoding>(&json).unwrap(),
+            encoding
+        );
+    }
+    let auto = serde_json::to_string(&TextEncodingHint::Auto).unwrap();
+    assert_eq!(
+        serde_json::from_str::<TextEncodingHint>(&auto).unwrap(),
+        TextEncodingHint::Auto
+    );
+
+    // Names written before the two agreed.
+    assert_eq!(
+        serde_json::from_str::<TextEncodingHint>("\"utf16-le\"").unwrap(),
+        TextEncodingHint::Utf16Le
+    );
+    assert_eq!(
+        serde_json::from_str::<TextEncoding>("\"utf16-le\"").unwrap(),
+        TextEncoding::Utf16Le
+    );
+    assert!(serde_json::from_str::<TextEncodingHint>("\"utf-7\"").is_err());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn binary_files_are_not_loaded_as_text() {
+    use crate::{is_likely_binary, Document, EditorError, TextEncodingHint};
+
+    let dir = std::env::temp_dir().join(format!("rustide-binary-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let png = dir.join("image.png");
+    std::fs::write(&png, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x10").unwrap();
+    assert!(matches!(
+        Document::load(&png),
+        Err(EditorError::BinaryFile { path }) if path == png
+    ));
+    #[cfg(unix)]
+    if let Ok(ls) = std::fs::read("/bin/ls") {
+        assert!(is_likely_binary(&ls));
+    }
+
+    // No NULs, but mostly control bytes.
+    assert!(is_likely_binary(&[0x01, 0x02, 0x03, b'a', 0x04, 0x05]));
+    // Text, including legacy encodings and UTF-16.
+    assert!(!is_likely_binary(
+        b"fn main() {\r\n\tprintln!(\"\x1b[1mhi\");\r\n}\x0c\n"
+    ));
+    assert!(!is_likely_binary(&encoding_rs::GBK.encode("中文文本").0));
+    assert!(!is_likely_binary(&crate::encode_text(
+        "text",
+        crate::TextEncoding::Utf16Le
+    )));
+    assert!(!is_likely_binary(b""));
+    let utf16 = dir.join("utf16.txt");
+    std::fs::write(&utf16, b"h\0i\0").unwrap();
+    let doc = Document::load_with_encoding(&utf16, TextEncodingHint::Utf16Le).unwrap();
+    assert_eq!(doc.editor.rope().to_string(), "hi");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn multi_selection_merges_overlapping_ranges() {
+    use crate::{MultiSelection, Selection};
+
+    let mut sel = MultiSelection::from_single(Selection::collapsed(5));
+    sel.add_cursor(9);
+    sel.add_cursor(1);
+    sel.add_cursor(9);
+    let carets: Vec<usize> = sel.ranges().iter().map(|s| s.cursor).collect();
+    // The primary stays first; the rest are in document order and a duplicate is dropped.
+    assert_eq!(carets, [5, 1, 9]);
+
+    // A caret touching a range is merged into it, and the merge keeps the range's direction.
+    let mut sel = MultiSelection::from_single(Selection {
+        anchor: 8,
+        cursor: 3,
+    });
+    sel.add_cursor(8);
+    sel.add_cursor(3);
+    assert_eq!(
+        sel.ranges(),
+        [Selection {
+            anchor: 8,
+            cursor: 3
+        }]
+    );
+    assert!(!sel.is_multiple());
+
+    // Adjacent ranges stay apart until they are collapsed onto each other.
+    let mut sel = MultiSelection::from_ranges(vec![
+        Selection {
+            anchor: 0,
+            cursor: 4,
+        },
+        Selection {
+            anchor: 8,
+            cursor: 4,
+        },
+    ]);
+    assert_eq!(sel.ranges().len(), 2);
+    sel.collapse_all();
+    assert_eq!(sel.ranges(), [Selection::collapsed(4)]);
+}
+
+#[test]
+fn edits_apply_at_every_cursor() {
+    let mut ed = Editor::from_text("one\ntwo\nthree");
+    ed.set_cursor(3, false);
+    ed.add_cursor(7);
+    ed.add_cursor(13);
+    ed.insert_text("!");
+    assert_eq!(rope_text(&ed), "one!\ntwo!\nthree!");
+    let carets: Vec<usize> = ed.selections().ranges().iter().map(|s| s.cursor).collect();
+    assert_eq!(carets, [4, 9, 16]);
+    assert_eq!(ed.selection().cursor, 4);
+
+    ed.backspace();
+    ed.backspace();
+    assert_eq!(rope_text(&ed), "on\ntw\nthre");
+
+    // One undo step per command, restoring every cursor.
+    assert!(ed.undo());
+    assert_eq!(rope_text(&ed), "one\ntwo\nthree");
+    let carets: Vec<usize> = ed.selections().ranges().iter().map(|s| s.cursor).collect();
+    assert_eq!(carets, [3, 7, 13]);
+    assert!(ed.undo());
+    assert_eq!(rope_text(&ed), "one!\ntwo!\nthree!");
+    assert!(ed.redo());
+    assert!(ed.redo());
+    assert_eq!(rope_text(&ed), "on\ntw\nthre");
+
+    // A plain click goes back to one cursor.
+    ed.set_cursor(0, false);
+    assert!(!ed.selections().is_multiple());
+}
+
+#[test]
+fn multi_cursor_edits_are_reported_per_range() {
+    let mut ed = Editor::from_text("aé\nb\nc");
+    ed.set_cursor(2, false);
+    ed.add_cursor(4);
+    ed.add_cursor(6);
+    let _ = ed.take_last_edits();
+    ed.insert_text("ß");
+    assert_eq!(rope_text(&ed), "aéß\nbß\ncß");
+
+    // Last range first, so each edit is relative to the text the ones before it left.
+    let edits = ed.take_last_edits();
+    let spans: Vec<_> = edits
+        .iter()
+        .map(|e| (e.start_byte, e.old_end_byte, e.new_end_byte))
+        .collect();
+    assert_eq!(spans, [(7, 7, 9), (5, 5, 7), (3, 3, 5)]);
+    assert_eq!(
+        edits[0].start_point,
+        crate::EditorPoint { row: 2, column: 1 }
+    );
+
+    assert!(ed.undo());
+    assert_eq!(rope_text(&ed), "aé\nb\nc");
+    let spans: Vec<_> = ed
+        .take_last_edits()
+        .iter()
+        .map(|e| (e.start_byte, e.old_end_byte, e.new_end_byte))
+        .collect();
+    assert_eq!(spans, [(11, 13, 11), (7, 9, 7), (3, 5, 3)]);
+    assert!(ed.redo());
+    assert_eq!(rope_text(&ed), "aéß\nbß\ncß");
+    assert_eq!(ed.take_last_edits().len(), 3);
+
+    // Replacements shorter than what they replace still land where they should.
+    let mut ed = Editor::from_text("foo x foo y foo");
+    assert_eq!(ed.replace_ranges(&[0..3, 6..9, 12..15], "z"), 3);
+    assert_eq!(rope_text(&ed), "z x z y z");
+    assert_eq!(ed.selection().cursor, 9);
+    assert_eq!(ed.take_last_edits().len(), 3);
+    assert!(ed.undo());
+    assert_eq!(rope_text(&ed), "foo x foo y foo");
+}
+
+#[test]
+fn multi_cursor_edits_merge_when_they_overlap() {
+    // Both carets are inside "hello"; deleting back to the word start removes it once.
+    let mut ed = Editor::from_text("hello world");
+    ed.set_cursor(4, false);
+    ed.add_cursor(2);
+    ed.delete_word_backward();
+    assert_eq!(rope_text(&ed), "o world");
+    assert_eq!(ed.selections().ranges(), [crate::Selection::collapsed(0)]);
+
+    // A caret that has nothing to delete still moves with the text before it.
+    let mut ed = Editor::from_text("ab");
+    ed.set_cursor(2, false);
+    ed.add_cursor(0);
+    ed.backspace();
+    assert_eq!(rope_text(&ed), "a");
+    let carets: Vec<usize> = ed.selections().ranges().iter().map(|s| s.cursor).collect();
+    assert_eq!(carets, [1, 0]);
+
+    // Selections are replaced, and their text is copied one per line.
+    let mut ed = Editor::from_text("foo bar baz");
+    ed.set_selections(crate::MultiSelection::from_ranges(vec![
+        crate::Selection {
+            anchor: 0,
+            cursor: 3,
+        },
+        crate::Selection {
+            anchor: 8,
+            cursor: 20,
+        },
+    ]));
+    assert_eq!(ed.selected_text(), "foo\nbaz");
+    ed.insert_text("X");
+    assert_eq!(rope_text(&ed), "X bar X");
+}
+
+#[test]
+fn line_commands_apply_at_every_cursor() {
+    let mut ed = Editor::from_text("ab\nc\nd");
+    ed.set_cursor(0, false);
+    ed.add_cursor(2);
+    ed.add_cursor(5);
+    ed.duplicate_line();
+    // Two cursors on one line still give one copy of it.
+    assert_eq!(rope_text(&ed), "ab\nab\nc\nd\nd");
+    let carets: Vec<usize> = ed.selections().ranges().iter().map(|s| s.cursor).collect();
+    assert_eq!(carets, [3, 5, 10]);
+    assert!(ed.undo());
+    assert_eq!(rope_text(&ed), "ab\nc\nd");
+
+    let mut ed = Editor::from_text("x\n  y\nz\nw");
+    ed.set_cursor(1, false);
+    ed.add_cursor(5);
+    ed.add_cursor(8);
+    ed.indent_selection("  ");
+    assert_eq!(rope_text(&ed), "  x\n    y\nz\n  w");
+    let carets: Vec<usize> = ed.selections().ranges().iter().map(|s| s.cursor).collect();
+    assert_eq!(carets, [3, 9, 14]);
+    assert_eq!(ed.take_last_edits().len(), 3);
+
+    ed.dedent_selection("  ");
+    ed.dedent_selection("  ");
+    assert_eq!(rope_text(&ed), "x\ny\nz\nw");
+    let carets: Vec<usize> = ed.selections().ranges().iter().map(|s| s.cursor).collect();
+    assert_eq!(carets, [1, 3, 6]);
+}
+
+#[test]
+fn cursors_move_together_and_merge() {
+    let mut ed = Editor::from_text("abc\nabc\nabc");
+    ed.set_cursor(1, false);
+    ed.add_cursor(5);
+    ed.add_cursor(9);
+    ed.move_down(false);
+    let carets: Vec<usize> = ed.selections().ranges().iter().map(|s| s.cursor).collect();
+    // The last cursor can't go further down, so it meets the one that moved onto its line.
+    assert_eq!(carets, [5, 9]);
+
+    ed.move_line_start(true);
+    let ranges: Vec<_> = ed.selections().ranges().iter().map(|s| s.range()).collect();
+    assert_eq!(ranges, [4..5, 8..9]);
+    ed.move_left(false);
+    let carets: Vec<usize> = ed.selections().ranges().iter().map(|s| s.cursor).collect();
+    assert_eq!(carets, [4, 8]);
+
+    let mut ed = Editor::from_text("ab");
+    ed.set_cursor(1, false);
+    ed.add_cursor(2);
+    ed.move_right(false);
+    assert!(!ed.selections().is_multiple());
+}
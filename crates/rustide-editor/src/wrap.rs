@@ -0,0 +1,62 @@
+use unicode_width::UnicodeWidthChar;
+
+// egui lays a tab out as four space advances whatever the document's tab width.
+const TAB_COLUMNS: usize = 4;
+
+/// Display columns `c` takes up: 2 for wide (e.g. CJK) chars, 0 for combining marks.
+pub fn char_columns(c: char) -> usize {
+    match c {
+        '\t' => TAB_COLUMNS,
+        c => c.width().unwrap_or(0),
+    }
+}
+
+/// Char offsets where each soft-wrapped row of `line` starts; the first is always 0.
+///
+/// Rows break after the last whitespace that fits in `columns` display columns (see
+/// [`char_columns`]), or mid-word when a word is longer than a row. Whitespace at a break
+/// stays at the end of its row, even past `columns`, so rows never start with the space that
+/// separated them.
+pub fn wrap_line(line: &str, columns: usize) -> Vec<usize> {
+    let columns = columns.max(1);
+    let mut starts = vec![0];
+    let mut row_start = 0;
+    let mut row_columns = 0;
+    // Just past the last whitespace in the current row, and the columns after it.
+    let mut break_at = None;
+    let mut columns_after_break = 0;
+    for (i, c) in line.chars().enumerate() {
+        let width = char_columns(c);
+        if c.is_whitespace() {
+            break_at = Some(i + 1);
+            columns_after_break = 0;
+            row_columns += width;
+            continue;
+        }
+        // A row always takes its first char, even one wider than the row.
+        if row_columns + width > columns && i > row_start {
+            match break_at.filter(|&at| at > row_start) {
+                Some(at) => {
+                    row_start = at;
+                    row_columns = columns_after_break;
+                }
+                None => {
+                    row_start = i;
+                    row_columns = 0;
+                }
+            }
+            starts.push(row_start);
+            break_at = None;
+        }
+        row_columns += width;
+        columns_after_break += width;
+    }
+    starts
+}
+
+/// Index of the wrapped row containing `col`, given the row starts from [`wrap_line`].
+pub fn wrap_row(starts: &[usize], col: usize) -> usize {
+    starts
+        .partition_point(|&start| start <= col)
+        .saturating_sub(1)
+}
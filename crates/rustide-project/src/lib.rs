@@ -1,5 +1,11 @@
 mod tree;
 mod watcher;
 
-pub use tree::{build_tree, TreeNode};
-pub use watcher::{debounce_events, ProjectEvent, ProjectWatcher};
+pub use tree::{
+    build_subtree, build_tree, build_tree_lazy, build_tree_with_options, filter_tree,
+    load_children, FileIndex, TreeNode, TreeOptions,
+};
+pub use watcher::{debounce_events, project_events, ProjectEvent, ProjectWatcher};
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,345 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    build_subtree, build_tree_lazy, build_tree_with_options, filter_tree, load_children, FileIndex,
+    TreeNode, TreeOptions,
+};
+
+fn node(path: &str, children: Vec<TreeNode>) -> TreeNode {
+    let path = PathBuf::from(path);
+    TreeNode {
+        name: path.file_name().unwrap().to_string_lossy().to_string(),
+        is_dir: !children.is_empty(),
+        path,
+        children,
+        children_loaded: true,
+        size_bytes: None,
+    }
+}
+
+#[test]
+fn file_index_finds_nested_nodes() {
+    let tree = node(
+        "root",
+        vec![
+            node("root/Cargo.toml", vec![]),
+            node(
+                "root/src",
+                vec![
+                    node("root/src/main.rs", vec![]),
+                    node("root/src/lib.rs", vec![]),
+                ],
+            ),
+        ],
+    );
+    let index = FileIndex::from_tree(&tree);
+
+    assert_eq!(index.len(), 5);
+    assert_eq!(index.get(Path::new("root")).unwrap().children.len(), 2);
+    let main = index.get(Path::new("root/src/main.rs")).unwrap();
+    assert_eq!(main.name, "main.rs");
+    assert!(!main.is_dir);
+    assert!(index.get(Path::new("root/src")).unwrap().is_dir);
+    assert!(index.get(Path::new("root/missing.rs")).is_none());
+}
+
+#[test]
+fn tree_options_limit_depth_and_entries() {
+    let root = std::env::temp_dir().join(format!("rustide-tree-limits-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    for file in ["a/b/c/deep.txt", "a/x.txt", "f0.txt", "f1.txt", "f2.txt"] {
+        let path = root.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "").unwrap();
+    }
+    let child = |node: &TreeNode, name: &str| -> TreeNode {
+        node.children
+            .iter()
+            .find(|c| c.name == name)
+            .cloned()
+            .unwrap_or_else(|| panic!("missing {name}"))
+    };
+
+    let shallow = build_tree_with_options(
+        &root,
+        TreeOptions {
+            max_depth: 2,
+            ..TreeOptions::default()
+        },
+    );
+    assert_eq!(shallow.children.len(), 4);
+    let a = child(&shallow, "a");
+    assert_eq!(a.children.len(), 2);
+    // `a/b` is listed but nothing below it.
+    let b = child(&a, "b");
+    assert!(b.is_dir);
+    assert!(b.children.is_empty());
+    assert_eq!(FileIndex::from_tree(&shallow).len(), 7);
+
+    let capped = build_tree_with_options(
+        &root,
+        TreeOptions {
+            max_entries_per_dir: 2,
+            ..TreeOptions::default()
+        },
+    );
+    // The cap keeps the first entries by name.
+    let names = |node: &TreeNode| {
+        let mut names: Vec<_> = node.children.iter().map(|c| c.name.clone()).collect();
+        names.sort();
+        names
+    };
+    assert_eq!(names(&capped), ["a", "f0.txt"]);
+    // Nothing under a dropped entry is listed either: with one entry per directory, the
+    // tree is a single chain.
+    let single = build_tree_with_options(
+        &root,
+        TreeOptions {
+            max_entries_per_dir: 1,
+            ..TreeOptions::default()
+        },
+    );
+    let mut chain = vec![&single];
+    while let [child] = chain.last().unwrap().children.as_slice() {
+        chain.push(child);
+    }
+    assert!(chain.last().unwrap().children.is_empty());
+    assert_eq!(FileIndex::from_tree(&single).len(), chain.len());
+    let chain_names: Vec<_> = chain[1..].iter().map(|node| node.name.as_str()).collect();
+    assert_eq!(chain_names, ["a", "b", "c", "deep.txt"]);
+
+    let full = build_tree_with_options(&root, TreeOptions::default());
+    assert_eq!(FileIndex::from_tree(&full).len(), 9);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn lazy_tree_loads_one_level_per_expansion() {
+    let root = std::env::temp_dir().join(format!("rustide-tree-lazy-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    for file in ["a/b/deep.txt", "a/x.txt", "top.txt"] {
+        let path = root.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "").unwrap();
+    }
+    let options = TreeOptions::default();
+
+    let mut tree = build_tree_lazy(&root, &HashSet::new(), options);
+    assert!(tree.children_loaded);
+    assert_eq!(tree.children.len(), 2);
+    let a = tree.children.iter().find(|c| c.name == "a").unwrap();
+    assert!(a.is_dir && !a.children_loaded && a.children.is_empty());
+    let top = tree.children.iter().find(|c| c.name == "top.txt").unwrap();
+    assert!(top.children_loaded);
+
+    let children = build_subtree(&root.join("a"), 1, usize::MAX);
+    assert_eq!(children.len(), 2);
+    assert!(children.iter().any(|c| c.name == "b" && !c.children_loaded));
+    assert!(tree.set_children(&root.join("a"), children));
+    let b = tree.find_mut(&root.join("a/b")).unwrap();
+    assert!(!b.children_loaded);
+    assert!(!tree.set_children(&root.join("missing"), Vec::new()));
+    assert!(!tree.set_children(&root.join("top.txt"), Vec::new()));
+
+    // A rebuild keeps previously expanded directories loaded.
+    let expanded: HashSet<PathBuf> = [root.join("a"), root.join("a/b")].into();
+    let mut rebuilt = build_tree_lazy(&root, &expanded, options);
+    let b = rebuilt.find_mut(&root.join("a/b")).unwrap();
+    assert!(b.children_loaded);
+    assert_eq!(b.children.len(), 1);
+
+    // Expansion stops at `max_depth`.
+    let capped = TreeOptions {
+        max_depth: 1,
+        ..options
+    };
+    assert!(load_children(&root, &root.join("a"), &HashSet::new(), capped).is_empty());
+    assert_eq!(
+        load_children(&root, &root.join("a"), &HashSet::new(), options).len(),
+        2
+    );
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn filter_tree_keeps_matches_and_their_parents() {
+    let tree = node(
+        "root",
+        vec![
+            node("root/Cargo.toml", vec![]),
+            node(
+                "root/src",
+                vec![
+                    node("root/src/lib.rs", vec![]),
+                    node("root/src/main.rs", vec![]),
+                ],
+            ),
+            node("root/tests", vec![node("root/tests/test_main.rs", vec![])]),
+        ],
+    );
+
+    let filtered = filter_tree(&tree, "MAIN").unwrap();
+    let index = FileIndex::from_tree(&filtered);
+    assert_eq!(index.len(), 5);
+    assert_eq!(index.get(Path::new("root/src")).unwrap().children.len(), 1);
+    assert!(index.get(Path::new("root/src/main.rs")).is_some());
+    assert!(index.get(Path::new("root/tests/test_main.rs")).is_some());
+    assert!(index.get(Path::new("root/Cargo.toml")).is_none());
+
+    // A matching directory keeps everything under it.
+    let src = filter_tree(&tree, "src").unwrap();
+    assert_eq!(src.children[0].children.len(), 2);
+
+    assert!(filter_tree(&tree, "root").is_none());
+    assert!(filter_tree(&tree, "missing").is_none());
+}
+
+#[test]
+fn tree_counts_files_and_sizes() {
+    let root = std::env::temp_dir().join(format!("rustide-tree-stats-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    for (file, content) in [
+        ("src/main.rs", "fn main() {}\n"),
+        ("src/util/mod.rs", "// util\n"),
+        ("README.md", "# Demo\n"),
+    ] {
+        let path = root.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    std::fs::create_dir_all(root.join("empty")).unwrap();
+
+    let tree = build_tree_with_options(&root, TreeOptions::default());
+    assert_eq!(tree.total_file_count(), 3);
+    assert_eq!(tree.total_size_bytes(), 13 + 8 + 7);
+    assert_eq!(tree.size_bytes, None);
+    let src = tree.children.iter().find(|c| c.name == "src").unwrap();
+    assert_eq!(src.total_file_count(), 2);
+    assert_eq!(src.total_size_bytes(), 21);
+    let readme = tree
+        .children
+        .iter()
+        .find(|c| c.name == "README.md")
+        .unwrap();
+    assert_eq!(readme.size_bytes, Some(7));
+    assert_eq!(readme.total_file_count(), 1);
+
+    // Files past `max_depth` are not listed, so they are not counted.
+    let shallow = build_tree_with_options(
+        &root,
+        TreeOptions {
+            max_depth: 2,
+            ..TreeOptions::default()
+        },
+    );
+    assert_eq!(shallow.total_file_count(), 2);
+    assert_eq!(shallow.total_size_bytes(), 13 + 7);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn debounce_merges_events_per_path() {
+    use crate::{debounce_events, ProjectEvent};
+
+    let (raw_tx, raw_rx) = std::sync::mpsc::channel();
+    let (tx, rx) = std::sync::mpsc::channel();
+    let a = PathBuf::from("root/a.rs");
+    let b = PathBuf::from("root/b.rs");
+    for event in [
+        ProjectEvent::FileCreated(a.clone()),
+        ProjectEvent::FileModified(a.clone()),
+        ProjectEvent::FileModified(b.clone()),
+        ProjectEvent::FileModified(b.clone()),
+        ProjectEvent::FileDeleted(b.clone()),
+    ] {
+        raw_tx.send(event).unwrap();
+    }
+    drop(raw_tx);
+    debounce_events(raw_rx, tx, std::time::Duration::from_secs(60));
+    let events: Vec<_> = rx.try_iter().collect();
+    assert_eq!(
+        events,
+        [ProjectEvent::FileCreated(a), ProjectEvent::FileDeleted(b)]
+    );
+
+    let (raw_tx, raw_rx) = std::sync::mpsc::channel();
+    let (tx, rx) = std::sync::mpsc::channel();
+    raw_tx
+        .send(ProjectEvent::FileModified(PathBuf::from("root/c.rs")))
+        .unwrap();
+    raw_tx.send(ProjectEvent::Changed).unwrap();
+    raw_tx
+        .send(ProjectEvent::Error("lost".to_string()))
+        .unwrap();
+    drop(raw_tx);
+    debounce_events(raw_rx, tx, std::time::Duration::from_secs(60));
+    let events: Vec<_> = rx.try_iter().collect();
+    assert_eq!(
+        events,
+        [
+            ProjectEvent::Error("lost".to_string()),
+            ProjectEvent::FileModified(PathBuf::from("root/c.rs")),
+            ProjectEvent::Changed
+        ]
+    );
+}
+
+#[test]
+fn notify_events_map_to_project_events() {
+    use crate::{project_events, ProjectEvent};
+    use notify::event::{
+        AccessKind, CreateKind, DataChange, EventKind, ModifyKind, RemoveKind, RenameMode,
+    };
+
+    let event = |kind: EventKind, paths: &[&str]| {
+        paths.iter().fold(notify::Event::new(kind), |event, path| {
+            event.add_path(PathBuf::from(path))
+        })
+    };
+    let p = PathBuf::from;
+
+    assert_eq!(
+        project_events(event(EventKind::Create(CreateKind::File), &["x"])),
+        [ProjectEvent::FileCreated(p("x"))]
+    );
+    assert_eq!(
+        project_events(event(EventKind::Remove(RemoveKind::Any), &["x", "y"])),
+        [
+            ProjectEvent::FileDeleted(p("x")),
+            ProjectEvent::FileDeleted(p("y"))
+        ]
+    );
+    assert_eq!(
+        project_events(event(
+            EventKind::Modify(ModifyKind::Data(DataChange::Content)),
+            &["x"]
+        )),
+        [ProjectEvent::FileModified(p("x"))]
+    );
+    assert_eq!(
+        project_events(event(
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+            &["old", "new"]
+        )),
+        [
+            ProjectEvent::FileDeleted(p("old")),
+            ProjectEvent::FileCreated(p("new"))
+        ]
+    );
+    assert_eq!(
+        project_events(event(
+            EventKind::Modify(ModifyKind::Name(RenameMode::Any)),
+            &["x"]
+        )),
+        [ProjectEvent::Changed]
+    );
+    assert_eq!(
+        project_events(event(EventKind::Create(CreateKind::Any), &[])),
+        [ProjectEvent::Changed]
+    );
+    assert!(project_events(event(EventKind::Access(AccessKind::Any), &["x"])).is_empty());
+}
@@ -1,5 +1,6 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
 
 #[derive(Debug, Clone)]
 pub struct TreeNode {
@@ -7,9 +8,90 @@ pub struct TreeNode {
     pub path: PathBuf,
     pub is_dir: bool,
     pub children: Vec<TreeNode>,
+    /// False for a directory the walk stopped above, so `children` is empty until it is
+    /// loaded with [`load_children`]. Always true for files.
+    pub children_loaded: bool,
+    /// File size from the walk; `None` for directories or when it could not be read.
+    pub size_bytes: Option<u64>,
+}
+
+impl TreeNode {
+    /// Files in this subtree (this node counts if it is a file). Unloaded directories add
+    /// nothing, so count a fully walked tree, as the project worker does.
+    pub fn total_file_count(&self) -> usize {
+        if !self.is_dir {
+            return 1;
+        }
+        self.children.iter().map(TreeNode::total_file_count).sum()
+    }
+
+    /// Sum of `size_bytes` over the files in this subtree.
+    pub fn total_size_bytes(&self) -> u64 {
+        if !self.is_dir {
+            return self.size_bytes.unwrap_or(0);
+        }
+        self.children.iter().map(TreeNode::total_size_bytes).sum()
+    }
+
+    pub fn find_mut(&mut self, path: &Path) -> Option<&mut TreeNode> {
+        if self.path == path {
+            return Some(self);
+        }
+        self.children
+            .iter_mut()
+            .find(|child| path.starts_with(&child.path))
+            .and_then(|child| child.find_mut(path))
+    }
+
+    fn clone_without_children(&self) -> TreeNode {
+        TreeNode {
+            name: self.name.clone(),
+            path: self.path.clone(),
+            is_dir: self.is_dir,
+            children: Vec::new(),
+            children_loaded: self.children_loaded,
+            size_bytes: self.size_bytes,
+        }
+    }
+
+    /// Fills in the directory at `dir`; returns false when the tree has no such directory
+    /// (it was removed or the tree was rebuilt for another root).
+    pub fn set_children(&mut self, dir: &Path, children: Vec<TreeNode>) -> bool {
+        match self.find_mut(dir) {
+            Some(node) if node.is_dir => {
+                node.children = children;
+                node.children_loaded = true;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Limits that keep `build_tree_with_options` fast on huge repositories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeOptions {
+    /// Deepest level listed; the root's children are depth 1.
+    pub max_depth: usize,
+    /// Entries kept per directory, in walk order; the rest (and anything under them) are
+    /// dropped.
+    pub max_entries_per_dir: usize,
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: usize::MAX,
+            max_entries_per_dir: usize::MAX,
+        }
+    }
 }
 
 pub fn build_tree(root: &Path) -> TreeNode {
+    build_tree_with_options(root, TreeOptions::default())
+}
+
+pub fn build_tree_with_options(root: &Path, options: TreeOptions) -> TreeNode {
     let mut builder = NodeMap {
         name: root
             .file_name()
@@ -18,18 +100,37 @@ pub fn build_tree(root: &Path) -> TreeNode {
             .unwrap_or_else(|| root.to_string_lossy().to_string()),
         path: root.to_path_buf(),
         is_dir: true,
+        size_bytes: None,
         children: BTreeMap::new(),
     };
 
-    for entry in ignore::WalkBuilder::new(root)
-        .hidden(false)
+    let mut walk = ignore::WalkBuilder::new(root);
+    walk.hidden(false)
         .git_ignore(true)
         .git_exclude(true)
         .git_global(true)
         .follow_links(false)
-        .build()
-        .flatten()
-    {
+        // Stops the walk from descending at all, which is where the time goes.
+        .max_depth(Some(options.max_depth));
+    if options.max_entries_per_dir < usize::MAX {
+        // Likewise, entries past a directory's cap are skipped before the walk reads them.
+        // Sorting first makes the cap keep the same entries every time, not whichever the
+        // file system happens to return first.
+        walk.sort_by_file_name(|a, b| a.cmp(b));
+        let max_entries = options.max_entries_per_dir;
+        let seen = Mutex::new(HashMap::<PathBuf, usize>::new());
+        walk.filter_entry(move |entry| {
+            let Some(parent) = entry.path().parent().filter(|_| entry.depth() > 0) else {
+                return true;
+            };
+            let mut seen = seen.lock().unwrap_or_else(PoisonError::into_inner);
+            let count = seen.entry(parent.to_path_buf()).or_default();
+            *count += 1;
+            *count <= max_entries
+        });
+    }
+
+    for entry in walk.build().flatten() {
         let path = entry.path();
         if path == root {
             continue;
@@ -42,7 +143,7 @@ pub fn build_tree(root: &Path) -> TreeNode {
             .components()
             .map(|c| c.as_os_str().to_string_lossy().to_string())
             .collect();
-        if comps.is_empty() {
+        if comps.is_empty() || comps.len() > options.max_depth {
             continue;
         }
 
@@ -50,10 +151,100 @@ pub fn build_tree(root: &Path) -> TreeNode {
             .file_type()
             .map(|t| t.is_dir())
             .unwrap_or_else(|| path.is_dir());
-        builder.insert(&comps, path.to_path_buf(), is_dir);
+        let size_bytes = if is_dir {
+            None
+        } else {
+            entry.metadata().ok().map(|meta| meta.len())
+        };
+        builder.insert(&comps, path.to_path_buf(), is_dir, size_bytes);
+    }
+
+    builder.into_tree(0, options.max_depth)
+}
+
+/// Lists `dir` `depth` levels deep; directories at the last level are left unloaded.
+pub fn build_subtree(dir: &Path, depth: usize, max_entries_per_dir: usize) -> Vec<TreeNode> {
+    build_tree_with_options(
+        dir,
+        TreeOptions {
+            max_depth: depth,
+            max_entries_per_dir,
+        },
+    )
+    .children
+}
+
+/// One level below `dir`, or nothing once `dir` sits at `options.max_depth` under `root`.
+/// Subdirectories in `expanded` are listed too, so reloading a directory keeps what was
+/// already open below it.
+pub fn load_children(
+    root: &Path,
+    dir: &Path,
+    expanded: &HashSet<PathBuf>,
+    options: TreeOptions,
+) -> Vec<TreeNode> {
+    let level = dir
+        .strip_prefix(root)
+        .map_or(0, |rel| rel.components().count());
+    if level >= options.max_depth {
+        return Vec::new();
+    }
+    let mut children = build_subtree(dir, 1, options.max_entries_per_dir);
+    for child in &mut children {
+        if child.is_dir && expanded.contains(&child.path) {
+            child.children = load_children(root, &child.path, expanded, options);
+            child.children_loaded = true;
+        }
+    }
+    children
+}
+
+/// The root's children plus the contents of every directory in `expanded` whose parent is
+/// loaded too; everything else is left for [`load_children`].
+pub fn build_tree_lazy(root: &Path, expanded: &HashSet<PathBuf>, options: TreeOptions) -> TreeNode {
+    let mut tree = build_tree_with_options(
+        root,
+        TreeOptions {
+            max_depth: 0,
+            ..options
+        },
+    );
+    tree.children = load_children(root, root, expanded, options);
+    tree.children_loaded = true;
+    tree
+}
+
+/// A copy of `node` pruned to entries whose name contains `query` (case-insensitive) and the
+/// directories leading to them. A directory whose own name matches keeps all its children.
+///
+/// `node` itself is only a container and is never matched; returns `None` when nothing below
+/// it matches. Unloaded directories are not searched.
+pub fn filter_tree(node: &TreeNode, query: &str) -> Option<TreeNode> {
+    fn prune(node: &TreeNode, query: &str) -> Option<TreeNode> {
+        if node.name.to_lowercase().contains(query) {
+            return Some(node.clone());
+        }
+        let children: Vec<TreeNode> = node
+            .children
+            .iter()
+            .filter_map(|child| prune(child, query))
+            .collect();
+        (!children.is_empty()).then(|| TreeNode {
+            children,
+            ..node.clone_without_children()
+        })
     }
 
-    builder.into_tree()
+    let query = query.to_lowercase();
+    let children: Vec<TreeNode> = node
+        .children
+        .iter()
+        .filter_map(|child| prune(child, &query))
+        .collect();
+    (!children.is_empty()).then(|| TreeNode {
+        children,
+        ..node.clone_without_children()
+    })
 }
 
 #[derive(Debug, Clone)]
@@ -61,11 +252,18 @@ struct NodeMap {
     name: String,
     path: PathBuf,
     is_dir: bool,
+    size_bytes: Option<u64>,
     children: BTreeMap<String, NodeMap>,
 }
 
 impl NodeMap {
-    fn insert(&mut self, comps: &[String], full_path: PathBuf, is_dir: bool) {
+    fn insert(
+        &mut self,
+        comps: &[String],
+        full_path: PathBuf,
+        is_dir: bool,
+        size_bytes: Option<u64>,
+    ) {
         let mut cur = self;
         for (idx, name) in comps.iter().enumerate() {
             let at_end = idx + 1 == comps.len();
@@ -73,16 +271,18 @@ impl NodeMap {
                 name: name.clone(),
                 path: cur.path.join(name),
                 is_dir: true,
+                size_bytes: None,
                 children: BTreeMap::new(),
             });
             if at_end {
                 cur.path = full_path.clone();
                 cur.is_dir = is_dir;
+                cur.size_bytes = size_bytes;
             }
         }
     }
 
-    fn into_tree(self) -> TreeNode {
+    fn into_tree(self, depth: usize, max_depth: usize) -> TreeNode {
         TreeNode {
             name: self.name,
             path: self.path,
@@ -90,8 +290,42 @@ impl NodeMap {
             children: self
                 .children
                 .into_values()
-                .map(NodeMap::into_tree)
+                .map(|child| child.into_tree(depth + 1, max_depth))
                 .collect(),
+            children_loaded: !self.is_dir || depth < max_depth,
+            size_bytes: self.size_bytes,
+        }
+    }
+}
+
+/// Path -> node lookup over a [`TreeNode`] without a recursive walk per query.
+///
+/// Borrows the tree, so rebuild it whenever the tree is replaced.
+#[derive(Debug, Clone, Default)]
+pub struct FileIndex<'a> {
+    nodes: HashMap<&'a Path, &'a TreeNode>,
+}
+
+impl<'a> FileIndex<'a> {
+    pub fn from_tree(tree: &'a TreeNode) -> Self {
+        let mut nodes = HashMap::new();
+        let mut stack = vec![tree];
+        while let Some(node) = stack.pop() {
+            nodes.insert(node.path.as_path(), node);
+            stack.extend(&node.children);
         }
+        Self { nodes }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&'a TreeNode> {
+        self.nodes.get(path).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
     }
 }
@@ -1,15 +1,49 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{Receiver, Sender};
 use std::time::{Duration, Instant};
 
+use notify::event::{EventKind, ModifyKind, RenameMode};
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ProjectEvent {
+    FileCreated(PathBuf),
+    FileDeleted(PathBuf),
+    FileModified(PathBuf),
+    /// Something changed but the watcher could not say what; refresh everything.
     Changed,
     Error(String),
 }
 
+/// Maps one `notify` event to project events. Renames become a delete of the old path and a
+/// create of the new one; access events change nothing and are dropped.
+pub fn project_events(event: notify::Event) -> Vec<ProjectEvent> {
+    let paths = event.paths;
+    let each = |make: fn(PathBuf) -> ProjectEvent| -> Vec<ProjectEvent> {
+        if paths.is_empty() {
+            vec![ProjectEvent::Changed]
+        } else {
+            paths.iter().cloned().map(make).collect()
+        }
+    };
+    match event.kind {
+        EventKind::Access(_) => Vec::new(),
+        EventKind::Create(_) => each(ProjectEvent::FileCreated),
+        EventKind::Remove(_) => each(ProjectEvent::FileDeleted),
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => each(ProjectEvent::FileDeleted),
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => each(ProjectEvent::FileCreated),
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if paths.len() == 2 => vec![
+            ProjectEvent::FileDeleted(paths[0].clone()),
+            ProjectEvent::FileCreated(paths[1].clone()),
+        ],
+        // Some backends can't tell which side of a rename a path is.
+        EventKind::Modify(ModifyKind::Name(_)) => vec![ProjectEvent::Changed],
+        EventKind::Modify(_) => each(ProjectEvent::FileModified),
+        EventKind::Any | EventKind::Other => vec![ProjectEvent::Changed],
+    }
+}
+
 pub struct ProjectWatcher {
     // Keep the watcher alive for the lifetime of this struct.
     #[allow(dead_code)]
@@ -22,8 +56,10 @@ impl ProjectWatcher {
         let root = root.to_path_buf();
         let mut watcher = notify::recommended_watcher(
             move |res: Result<notify::Event, notify::Error>| match res {
-                Ok(_event) => {
-                    let _ = tx.send(ProjectEvent::Changed);
+                Ok(event) => {
+                    for event in project_events(event) {
+                        let _ = tx.send(event);
+                    }
                 }
                 Err(e) => {
                     let _ = tx.send(ProjectEvent::Error(e.to_string()));
@@ -44,24 +80,69 @@ impl ProjectWatcher {
     }
 }
 
+/// Coalesces watcher bursts: once no event has arrived for `delay`, sends one event per
+/// distinct path (in path order), then a single `Changed` if any event was unspecific.
+/// Errors are forwarded immediately, and pending events are flushed when `rx` disconnects.
 pub fn debounce_events(rx: Receiver<ProjectEvent>, tx: Sender<ProjectEvent>, delay: Duration) {
-    // Coalesce watcher bursts into a single Changed event.
-    let mut last_changed: Option<Instant> = None;
+    let mut pending = PendingEvents::default();
+    let mut last_event: Option<Instant> = None;
     loop {
         match rx.recv_timeout(Duration::from_millis(50)) {
-            Ok(ProjectEvent::Changed) => last_changed = Some(Instant::now()),
             Ok(ProjectEvent::Error(e)) => {
                 let _ = tx.send(ProjectEvent::Error(e));
             }
+            Ok(event) => {
+                pending.push(event);
+                last_event = Some(Instant::now());
+            }
             Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
-            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                pending.flush(&tx);
+                break;
+            }
+        }
+
+        if last_event.is_some_and(|t| t.elapsed() >= delay) {
+            pending.flush(&tx);
+            last_event = None;
         }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PendingEvents {
+    changed: bool,
+    paths: BTreeMap<PathBuf, ProjectEvent>,
+}
 
-        if let Some(t) = last_changed {
-            if t.elapsed() >= delay {
-                let _ = tx.send(ProjectEvent::Changed);
-                last_changed = None;
+impl PendingEvents {
+    // The latest event for a path wins, except that a file created in this window stays
+    // created when it is then written to.
+    fn push(&mut self, event: ProjectEvent) {
+        let path = match &event {
+            ProjectEvent::FileCreated(path)
+            | ProjectEvent::FileDeleted(path)
+            | ProjectEvent::FileModified(path) => path.clone(),
+            ProjectEvent::Changed | ProjectEvent::Error(_) => {
+                self.changed = true;
+                return;
             }
+        };
+        let keep_created = matches!(event, ProjectEvent::FileModified(_))
+            && matches!(self.paths.get(&path), Some(ProjectEvent::FileCreated(_)));
+        if !keep_created {
+            self.paths.insert(path, event);
+        }
+    }
+
+    // `Changed` doesn't stand in for the per-path events: they also say which open files to
+    // reload.
+    fn flush(&mut self, tx: &Sender<ProjectEvent>) {
+        for event in std::mem::take(&mut self.paths).into_values() {
+            let _ = tx.send(event);
+        }
+        if std::mem::take(&mut self.changed) {
+            let _ = tx.send(ProjectEvent::Changed);
         }
     }
 }
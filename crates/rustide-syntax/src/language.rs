@@ -1,13 +1,73 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+
+/// Serialized in lowercase (`javascript`, `plaintext`), the names the config's
+/// `[editor.<language>]` sections use. The kebab-case names used before still deserialize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum LanguageId {
+    Rust,
+    #[serde(alias = "java-script")]
+    JavaScript,
+    #[serde(alias = "type-script")]
+    TypeScript,
     Cpp,
     Python,
     Hlsl,
+    Json,
+    Toml,
+    Yaml,
+    Go,
+    Lua,
+    Xml,
+    Html,
+    Shell,
     Markdown,
+    #[serde(alias = "plain-text")]
     PlainText,
 }
 
 impl LanguageId {
+    pub const ALL: &'static [LanguageId] = &[
+        Self::Rust,
+        Self::JavaScript,
+        Self::TypeScript,
+        Self::Cpp,
+        Self::Python,
+        Self::Hlsl,
+        Self::Json,
+        Self::Toml,
+        Self::Yaml,
+        Self::Go,
+        Self::Lua,
+        Self::Xml,
+        Self::Html,
+        Self::Shell,
+        Self::Markdown,
+        Self::PlainText,
+    ];
+
+    /// Name shown in menus, e.g. `C++` or `Plain Text`.
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Self::Rust => "Rust",
+            Self::JavaScript => "JavaScript",
+            Self::TypeScript => "TypeScript",
+            Self::Cpp => "C++",
+            Self::Python => "Python",
+            Self::Hlsl => "HLSL",
+            Self::Json => "JSON",
+            Self::Toml => "TOML",
+            Self::Yaml => "YAML",
+            Self::Go => "Go",
+            Self::Lua => "Lua",
+            Self::Xml => "XML",
+            Self::Html => "HTML",
+            Self::Shell => "Shell",
+            Self::Markdown => "Markdown",
+            Self::PlainText => "Plain Text",
+        }
+    }
+
     pub fn from_path(path: Option<&std::path::Path>) -> Self {
         let Some(path) = path else {
             return Self::PlainText;
@@ -16,11 +76,68 @@ impl LanguageId {
             return Self::PlainText;
         };
         match ext.to_ascii_lowercase().as_str() {
+            "rs" => Self::Rust,
+            "js" | "mjs" | "cjs" | "jsx" => Self::JavaScript,
+            "ts" | "tsx" => Self::TypeScript,
             "cc" | "cpp" | "cxx" | "h" | "hpp" | "hh" => Self::Cpp,
             "py" => Self::Python,
             "hlsl" | "hlsli" | "fx" => Self::Hlsl,
+            "json" | "jsonc" => Self::Json,
+            "toml" => Self::Toml,
+            "yaml" | "yml" => Self::Yaml,
+            "go" => Self::Go,
+            "lua" => Self::Lua,
+            "xml" | "svg" | "xaml" => Self::Xml,
+            "html" | "htm" => Self::Html,
+            "sh" | "bash" | "zsh" => Self::Shell,
             "md" | "markdown" => Self::Markdown,
             _ => Self::PlainText,
         }
     }
+
+    /// Like [`Self::from_path`], but when the path gives no language (no extension, unknown
+    /// extension, or no path at all) the file's first line is checked with
+    /// [`Self::from_content`].
+    pub fn detect(path: Option<&std::path::Path>, first_line: &str) -> Self {
+        match Self::from_path(path) {
+            Self::PlainText => Self::from_content(first_line).unwrap_or(Self::PlainText),
+            language => language,
+        }
+    }
+
+    /// Recognizes a `#!` line, an `<?xml` declaration or an HTML doctype at the start of `text`.
+    pub fn from_content(text: &str) -> Option<Self> {
+        let text = text.trim_start_matches('\u{feff}');
+        if text.starts_with("#!") {
+            return Self::from_shebang(text.lines().next().unwrap_or_default());
+        }
+        let head = text.trim_start();
+        let lower = head.get(..14).unwrap_or(head).to_ascii_lowercase();
+        if lower.starts_with("<?xml") {
+            Some(Self::Xml)
+        } else if lower.starts_with("<!doctype html") || lower.starts_with("<html") {
+            Some(Self::Html)
+        } else {
+            None
+        }
+    }
+
+    pub fn from_shebang(first_line: &str) -> Option<Self> {
+        let command = first_line.strip_prefix("#!")?;
+        let mut words = command.split_whitespace();
+        let mut program = words.next()?.rsplit('/').next()?;
+        if program == "env" {
+            // `env -S prog args` / `env prog`: skip env's own flags.
+            program = words.find(|word| !word.starts_with('-'))?;
+        }
+        // `python3.12` -> `python`
+        let name = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+        match name {
+            "sh" | "bash" | "zsh" | "dash" | "ksh" => Some(Self::Shell),
+            "python" => Some(Self::Python),
+            "node" | "deno" => Some(Self::JavaScript),
+            "lua" | "luajit" => Some(Self::Lua),
+            _ => None,
+        }
+    }
 }
@@ -1,5 +1,12 @@
 mod language;
+mod queries;
+mod symbols;
 mod syntax;
 
 pub use language::LanguageId;
+pub use queries::default_query;
+pub use symbols::{Symbol, SymbolKind};
 pub use syntax::{HighlightSpan, HighlightTag, SyntaxError, SyntaxState};
+
+#[cfg(test)]
+mod tests;
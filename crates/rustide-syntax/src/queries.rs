@@ -0,0 +1,518 @@
+use std::sync::OnceLock;
+
+use crate::LanguageId;
+
+/// The highlight query [`crate::SyntaxState`] compiles for `language`; empty for languages
+/// without a grammar.
+pub fn default_query(language: LanguageId) -> &'static str {
+    match language {
+        LanguageId::Rust => RUST_HIGHLIGHTS,
+        LanguageId::JavaScript => JAVASCRIPT_HIGHLIGHTS,
+        LanguageId::TypeScript => {
+            static QUERY: OnceLock<String> = OnceLock::new();
+            QUERY.get_or_init(|| format!("{TYPESCRIPT_HIGHLIGHTS}\n{JAVASCRIPT_HIGHLIGHTS}"))
+        }
+        LanguageId::Cpp => {
+            static QUERY: OnceLock<String> = OnceLock::new();
+            QUERY.get_or_init(|| {
+                format!(
+                    "{}\n{CPP_EXTRA_HIGHLIGHTS}",
+                    tree_sitter_cpp::HIGHLIGHT_QUERY
+                )
+            })
+        }
+        LanguageId::Python => tree_sitter_python::HIGHLIGHTS_QUERY,
+        LanguageId::Hlsl => HLSL_HIGHLIGHTS,
+        LanguageId::Json => JSON_HIGHLIGHTS,
+        LanguageId::Toml => TOML_HIGHLIGHTS,
+        LanguageId::Yaml => YAML_HIGHLIGHTS,
+        LanguageId::Go => GO_HIGHLIGHTS,
+        LanguageId::Lua => LUA_HIGHLIGHTS,
+        LanguageId::Xml => XML_HIGHLIGHTS,
+        LanguageId::Html => HTML_HIGHLIGHTS,
+        LanguageId::Shell => SHELL_HIGHLIGHTS,
+        LanguageId::Markdown | LanguageId::PlainText => "",
+    }
+}
+
+// Custom rather than the grammar's bundled query so macros, lifetimes, labels, and
+// attributes get their own captures.
+const RUST_HIGHLIGHTS: &str = r#"
+(line_comment) @comment
+(block_comment) @comment
+
+(attribute_item) @attribute
+(inner_attribute_item) @attribute
+
+(macro_invocation
+  macro: (identifier) @macro
+  "!" @macro)
+(macro_invocation
+  macro: (scoped_identifier
+    name: (identifier) @macro)
+  "!" @macro)
+(macro_definition
+  name: (identifier) @macro)
+
+(lifetime) @lifetime
+(label) @label
+
+(char_literal) @string
+(string_literal) @string
+(raw_string_literal) @string
+
+(integer_literal) @number
+(float_literal) @number
+(boolean_literal) @constant
+
+((identifier) @constant
+ (#match? @constant "^[A-Z][A-Z\\d_]+$"))
+
+(type_identifier) @type
+(primitive_type) @type
+((scoped_identifier
+  path: (identifier) @type)
+ (#match? @type "^[A-Z]"))
+((scoped_type_identifier
+  path: (identifier) @type)
+ (#match? @type "^[A-Z]"))
+(scoped_identifier
+  path: (identifier) @namespace)
+(scoped_type_identifier
+  path: (identifier) @namespace)
+(scoped_identifier
+  path: (scoped_identifier
+    name: (identifier) @namespace))
+(scoped_type_identifier
+  path: (scoped_identifier
+    name: (identifier) @namespace))
+(scoped_use_list
+  path: (identifier) @namespace)
+
+(function_item
+  name: (identifier) @function)
+(function_signature_item
+  name: (identifier) @function)
+(call_expression
+  function: (identifier) @function)
+(call_expression
+  function: (field_expression
+    field: (field_identifier) @function))
+(call_expression
+  function: (scoped_identifier
+    name: (identifier) @function))
+(generic_function
+  function: (identifier) @function)
+(generic_function
+  function: (scoped_identifier
+    name: (identifier) @function))
+(generic_function
+  function: (field_expression
+    field: (field_identifier) @function))
+
+(field_identifier) @property
+(shorthand_field_identifier) @property
+
+(self) @keyword
+(crate) @keyword
+(super) @keyword
+(mutable_specifier) @keyword
+[
+  "as" "async" "await" "break" "const" "continue" "default" "dyn" "else" "enum"
+  "extern" "fn" "for" "gen" "if" "impl" "in" "let" "loop" "macro_rules!" "match"
+  "mod" "move" "pub" "raw" "ref" "return" "static" "struct" "trait" "type" "union"
+  "unsafe" "use" "where" "while" "yield"
+] @keyword
+
+[
+  "+" "-" "*" "/" "%" "^" "!" "&" "|" "&&" "||" "<<" ">>" "=" "+=" "-=" "*=" "/="
+  "%=" "^=" "&=" "|=" "<<=" ">>=" "==" "!=" "<" ">" "<=" ">=" "=>" "->" ".." "..="
+  "?"
+] @operator
+
+["(" ")" "[" "]" "{" "}" "::" ":" "." "," ";"] @punctuation
+"#;
+
+// Earlier patterns win when several capture the same node, so specific patterns come first
+// and there is no catch-all `(identifier) @variable`.
+const JAVASCRIPT_HIGHLIGHTS: &str = r#"
+(comment) @comment
+
+(regex) @regex
+[
+  (string)
+  (template_string)
+] @string
+(number) @number
+[
+  (true)
+  (false)
+  (null)
+  (undefined)
+] @constant
+
+(template_substitution
+  "${" @punctuation
+  "}" @punctuation)
+
+(function_declaration
+  name: (identifier) @function)
+(function_expression
+  name: (identifier) @function)
+(generator_function_declaration
+  name: (identifier) @function)
+(method_definition
+  name: (property_identifier) @function)
+(variable_declarator
+  name: (identifier) @function
+  value: [(function_expression) (arrow_function)])
+(pair
+  key: (property_identifier) @function
+  value: [(function_expression) (arrow_function)])
+(call_expression
+  function: (identifier) @function)
+(call_expression
+  function: (member_expression
+    property: (property_identifier) @function))
+
+(class_declaration
+  name: (_) @type)
+(class
+  name: (_) @type)
+(new_expression
+  constructor: (identifier) @type)
+
+([
+  (identifier)
+  (shorthand_property_identifier)
+  (shorthand_property_identifier_pattern)
+] @constant
+ (#match? @constant "^[A-Z_][A-Z\\d_]+$"))
+((identifier) @type
+ (#match? @type "^[A-Z]"))
+
+(property_identifier) @property
+(shorthand_property_identifier) @property
+
+(this) @keyword
+(super) @keyword
+[
+  "as" "async" "await" "break" "case" "catch" "class" "const" "continue" "debugger"
+  "default" "delete" "do" "else" "export" "extends" "finally" "for" "from" "function"
+  "get" "if" "import" "in" "instanceof" "let" "new" "of" "return" "set" "static"
+  "switch" "target" "throw" "try" "typeof" "var" "void" "while" "with" "yield"
+] @keyword
+
+[
+  "-" "--" "-=" "+" "++" "+=" "*" "*=" "**" "**=" "/" "/=" "%" "%=" "<" "<=" "<<"
+  "<<=" "=" "==" "===" "!" "!=" "!==" "=>" ">" ">=" ">>" ">>=" ">>>" ">>>=" "~" "^"
+  "&" "|" "^=" "&=" "|=" "&&" "||" "??" "&&=" "||=" "??=" "?"
+] @operator
+
+["(" ")" "[" "]" "{" "}" ";" "." "," ":"] @punctuation
+"#;
+
+// Prepended to the JavaScript query for TypeScript.
+const TYPESCRIPT_HIGHLIGHTS: &str = r#"
+(type_identifier) @type
+(predefined_type) @type
+(type_annotation
+  (_) @type)
+
+[
+  "abstract" "declare" "enum" "implements" "interface" "keyof" "namespace" "private"
+  "protected" "public" "readonly" "override" "satisfies" "type"
+] @keyword
+"#;
+
+// Appended to the grammar's bundled query for literals and preprocessor lines it leaves out.
+const CPP_EXTRA_HIGHLIGHTS: &str = r#"
+(comment) @comment
+(number_literal) @number
+(char_literal) @string
+(string_literal) @string
+(raw_string_literal) @string
+(concatenated_string) @string
+(system_lib_string) @string
+(preproc_directive) @keyword
+(namespace_identifier) @namespace
+"#;
+
+const HLSL_HIGHLIGHTS: &str = r#"
+(comment) @comment
+(number_literal) @number
+(string_literal) @string
+(raw_string_literal) @string
+(concatenated_string) @string
+(system_lib_string) @string
+(preproc_directive) @keyword
+(primitive_type) @type
+(type_identifier) @type
+(namespace_identifier) @namespace
+
+(call_expression
+  function: (identifier) @function)
+
+(call_expression
+  function: (field_expression
+              field: (field_identifier) @function))
+
+(function_definition
+  declarator: (function_declarator
+                declarator: (identifier) @function))
+
+(field_identifier) @property
+"#;
+
+// Keys come first so they win over the generic string capture on the same node.
+const JSON_HIGHLIGHTS: &str = r#"
+(pair
+  key: (string) @property)
+
+(string) @string
+(escape_sequence) @escape
+(number) @number
+[
+  (true)
+  (false)
+  (null)
+] @constant
+(comment) @comment
+
+["{" "}" "[" "]" "," ":"] @punctuation
+"#;
+
+// Header keys are matched before pair keys so `[package]` reads as a type.
+const TOML_HIGHLIGHTS: &str = r#"
+(table
+  [(bare_key) (quoted_key) (dotted_key)] @type)
+(table_array_element
+  [(bare_key) (quoted_key) (dotted_key)] @type)
+
+(pair
+  [(bare_key) (quoted_key) (dotted_key)] @property)
+
+(string) @string
+[
+  (integer)
+  (float)
+] @number
+(boolean) @constant
+[
+  (offset_date_time)
+  (local_date_time)
+  (local_date)
+  (local_time)
+] @string
+(comment) @comment
+
+"=" @operator
+["[" "]" "[[" "]]" "{" "}" "," "."] @punctuation
+"#;
+
+// Keys come first so they win over the generic scalar captures on the same node.
+const YAML_HIGHLIGHTS: &str = r#"
+(block_mapping_pair
+  key: (flow_node
+    [
+      (double_quote_scalar)
+      (single_quote_scalar)
+      (plain_scalar)
+    ] @property))
+(flow_pair
+  key: (flow_node
+    [
+      (double_quote_scalar)
+      (single_quote_scalar)
+      (plain_scalar)
+    ] @property))
+
+[
+  (double_quote_scalar)
+  (single_quote_scalar)
+  (block_scalar)
+  (string_scalar)
+] @string
+(escape_sequence) @escape
+[
+  (integer_scalar)
+  (float_scalar)
+] @number
+[
+  (boolean_scalar)
+  (null_scalar)
+] @constant
+[
+  (anchor)
+  (alias)
+] @variable
+(tag) @type
+(comment) @comment
+
+["-" ":" "," "?" "|" ">" "[" "]" "{" "}"] @punctuation
+"#;
+
+// Builtins (`make`, `len`, `append`, ...) are plain identifiers in Go, so the
+// call rule below already tags them as functions.
+const GO_HIGHLIGHTS: &str = r#"
+(comment) @comment
+
+(interpreted_string_literal) @string
+(raw_string_literal) @string
+(rune_literal) @string
+(escape_sequence) @escape
+
+(int_literal) @number
+(float_literal) @number
+(imaginary_literal) @number
+[
+  (true)
+  (false)
+  (nil)
+  (iota)
+] @constant
+
+(package_identifier) @type
+(type_identifier) @type
+
+(function_declaration
+  name: (identifier) @function)
+(method_declaration
+  name: (field_identifier) @function)
+(method_elem
+  name: (field_identifier) @function)
+(call_expression
+  function: (identifier) @function)
+(call_expression
+  function: (selector_expression
+    field: (field_identifier) @function))
+
+(field_identifier) @property
+
+[
+  "break" "case" "chan" "const" "continue" "default" "defer" "else" "fallthrough" "for"
+  "func" "go" "goto" "if" "import" "interface" "map" "package" "range" "return" "select"
+  "struct" "switch" "type" "var"
+] @keyword
+
+[
+  "+" "-" "*" "/" "%" "&" "|" "^" "<<" ">>" "&^" "+=" "-=" "*=" "/=" "%=" "&=" "|="
+  "^=" "<<=" ">>=" "&^=" "&&" "||" "<-" "++" "--" "==" "<" ">" "=" "!" "~" "!=" "<="
+  ">=" ":=" "..."
+] @operator
+
+["(" ")" "[" "]" "{" "}" "." "," ";" ":"] @punctuation
+"#;
+
+// `comment` and `string` cover the long `--[[ ]]` / `[[ ]]` forms as well.
+const LUA_HIGHLIGHTS: &str = r##"
+(comment) @comment
+(string) @string
+(escape_sequence) @escape
+(number) @number
+[
+  (nil)
+  (true)
+  (false)
+] @constant
+(vararg_expression) @constant
+
+(function_declaration
+  name: (identifier) @function)
+(function_declaration
+  name: (dot_index_expression
+    field: (identifier) @function))
+(function_declaration
+  name: (method_index_expression
+    method: (identifier) @function))
+(function_call
+  name: (identifier) @function)
+(function_call
+  name: (dot_index_expression
+    field: (identifier) @function))
+(function_call
+  name: (method_index_expression
+    method: (identifier) @function))
+
+(field
+  name: (identifier) @property)
+(dot_index_expression
+  field: (identifier) @property)
+
+(break_statement) @keyword
+[
+  "and" "do" "else" "elseif" "end" "for" "function" "goto" "if" "in" "local" "not" "or"
+  "repeat" "return" "then" "until" "while"
+] @keyword
+
+[
+  "+" "-" "*" "/" "//" "%" "^" "#" "&" "~" "|" "<<" ">>" "==" "~=" "<" ">" "<=" ">="
+  "=" ".."
+] @operator
+
+["(" ")" "[" "]" "{" "}" "." ":" "," ";"] @punctuation
+"##;
+
+// Text content is left uncaptured so it draws in the fallback color.
+const XML_HIGHLIGHTS: &str = r#"
+(Comment) @comment
+
+(STag
+  (Name) @type)
+(ETag
+  (Name) @type)
+(EmptyElemTag
+  (Name) @type)
+
+(Attribute
+  (Name) @property)
+(AttValue) @string
+
+["<" ">" "</" "/>" "="] @punctuation
+"#;
+
+// Embedded <script>/<style> bodies are not injected yet; they show as strings.
+const HTML_HIGHLIGHTS: &str = r#"
+(comment) @comment
+(doctype) @keyword
+
+(tag_name) @type
+(erroneous_end_tag_name) @type
+
+(attribute_name) @property
+(quoted_attribute_value) @string
+(attribute_value) @string
+
+(script_element
+  (raw_text) @embedded)
+(style_element
+  (raw_text) @embedded)
+
+["<" ">" "</" "/>" "="] @punctuation
+"#;
+
+const SHELL_HIGHLIGHTS: &str = r#"
+(comment) @comment
+
+(string) @string
+(raw_string) @string
+(ansi_c_string) @string
+(heredoc_body) @string
+(heredoc_start) @string
+(number) @number
+
+(simple_expansion) @variable
+(expansion) @variable
+(variable_name) @variable
+
+(command_name) @function
+(function_definition
+  name: (word) @function)
+
+[
+  "case" "do" "done" "elif" "else" "esac" "export" "fi" "for" "function" "if" "in"
+  "local" "readonly" "select" "then" "unset" "until" "while"
+] @keyword
+
+["&&" "||" "|" ">" ">>" "<" "=" "!"] @operator
+"#;
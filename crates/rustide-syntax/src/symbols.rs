@@ -0,0 +1,96 @@
+use tree_sitter::Node;
+
+use crate::LanguageId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Type,
+    Constant,
+    Variable,
+}
+
+/// A named declaration for the document outline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Zero-based line of the declaration's first byte.
+    pub line: usize,
+}
+
+// Declaration node kinds per grammar. Variables are only reported outside function bodies so
+// locals don't flood the outline.
+pub(crate) fn symbol_kind(language: LanguageId, node: Node<'_>) -> Option<SymbolKind> {
+    let kind = match (language, node.kind()) {
+        (LanguageId::Rust, "function_item" | "function_signature_item") => SymbolKind::Function,
+        (
+            LanguageId::Rust,
+            "struct_item" | "enum_item" | "union_item" | "trait_item" | "type_item",
+        ) => SymbolKind::Type,
+        (LanguageId::Rust, "const_item" | "static_item") => SymbolKind::Constant,
+
+        (
+            LanguageId::JavaScript | LanguageId::TypeScript,
+            "function_declaration" | "generator_function_declaration" | "method_definition",
+        ) => SymbolKind::Function,
+        (
+            LanguageId::JavaScript | LanguageId::TypeScript,
+            "class_declaration"
+            | "abstract_class_declaration"
+            | "interface_declaration"
+            | "type_alias_declaration"
+            | "enum_declaration",
+        ) => SymbolKind::Type,
+        (LanguageId::JavaScript | LanguageId::TypeScript, "variable_declarator") => {
+            SymbolKind::Variable
+        }
+
+        (LanguageId::Cpp | LanguageId::Hlsl, "function_definition") => SymbolKind::Function,
+        // `struct Foo x;` also uses a specifier node; only definitions have a body.
+        (
+            LanguageId::Cpp | LanguageId::Hlsl,
+            "class_specifier" | "struct_specifier" | "union_specifier" | "enum_specifier",
+        ) if node.child_by_field_name("body").is_some() => SymbolKind::Type,
+        (LanguageId::Cpp | LanguageId::Hlsl, "type_definition" | "alias_declaration") => {
+            SymbolKind::Type
+        }
+
+        (LanguageId::Python, "function_definition") => SymbolKind::Function,
+        (LanguageId::Python, "class_definition") => SymbolKind::Type,
+        (LanguageId::Python, "assignment")
+            if node
+                .parent()
+                .and_then(|p| p.parent())
+                .is_some_and(|p| matches!(p.kind(), "module" | "block")) =>
+        {
+            SymbolKind::Variable
+        }
+
+        (LanguageId::Go, "function_declaration" | "method_declaration") => SymbolKind::Function,
+        (LanguageId::Go, "type_spec") => SymbolKind::Type,
+        (LanguageId::Go, "const_spec") => SymbolKind::Constant,
+        (LanguageId::Go, "var_spec") => SymbolKind::Variable,
+
+        (LanguageId::Lua, "function_declaration") => SymbolKind::Function,
+        (LanguageId::Shell, "function_definition") => SymbolKind::Function,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+// The node holding a declaration's name: the `name` field, the innermost `declarator` (C++
+// functions and typedefs), or the `left` side of a Python assignment.
+pub(crate) fn symbol_name_node(node: Node<'_>) -> Option<Node<'_>> {
+    if let Some(name) = node.child_by_field_name("name") {
+        return Some(name);
+    }
+    if let Some(mut declarator) = node.child_by_field_name("declarator") {
+        while let Some(inner) = declarator.child_by_field_name("declarator") {
+            declarator = inner;
+        }
+        return Some(declarator);
+    }
+    node.child_by_field_name("left")
+        .filter(|left| left.kind() == "identifier")
+}
@@ -1,11 +1,16 @@
+use std::cell::OnceCell;
 use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use ropey::Rope;
 use streaming_iterator::StreamingIterator;
 use tree_sitter::{InputEdit, Parser, Query, QueryCursor, TextProvider, Tree};
 
-use crate::LanguageId;
+use crate::symbols::{symbol_kind, symbol_name_node};
+use crate::{default_query, LanguageId, Symbol, SymbolKind};
 
 #[derive(thiserror::Error, Debug)]
 pub enum SyntaxError {
@@ -20,7 +25,13 @@ pub enum SyntaxError {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HighlightTag {
     Comment,
+    Macro,
+    Attribute,
+    Lifetime,
+    Label,
+    Namespace,
     String,
+    Regex,
     Number,
     Keyword,
     Type,
@@ -30,6 +41,7 @@ pub enum HighlightTag {
     Property,
     Operator,
     Punctuation,
+    Error,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -40,94 +52,58 @@ pub struct HighlightSpan {
 
 pub struct SyntaxState {
     language: LanguageId,
+    grammar: Option<tree_sitter::Language>,
     parser: Parser,
+    background: Option<BackgroundParser>,
     tree: Option<Tree>,
     query: Option<Query>,
     cursor: QueryCursor,
     debounce: Duration,
     pending_since: Option<Instant>,
+    // See `Self::generation`.
+    generation: u64,
+    // Derived from `tree` on first use and dropped whenever it changes.
+    errors: OnceCell<Vec<HighlightSpan>>,
+    folds: OnceCell<Vec<Range<usize>>>,
+    symbols: OnceCell<Vec<Symbol>>,
+}
+
+// Generations are unique across all states, so a reset state never reuses an old one.
+fn next_generation() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    NEXT.fetch_add(1, Ordering::Relaxed)
 }
 
 impl SyntaxState {
     pub fn new(language: LanguageId) -> Result<Self, SyntaxError> {
         let mut parser = Parser::new();
-        let (query, debounce) = match language {
-            LanguageId::Cpp => {
+        let grammar = grammar(language);
+        let query = match &grammar {
+            Some(grammar) => {
                 parser
-                    .set_language(&tree_sitter_cpp::LANGUAGE.into())
+                    .set_language(grammar)
                     .map_err(|_| SyntaxError::ParserInit)?;
-                let lang = tree_sitter_cpp::LANGUAGE.into();
-                let query_src = format!(
-                    "{}\n{}",
-                    tree_sitter_cpp::HIGHLIGHT_QUERY,
-                    r#"
-(comment) @comment
-(number_literal) @number
-(char_literal) @string
-(string_literal) @string
-(raw_string_literal) @string
-(concatenated_string) @string
-(system_lib_string) @string
-(preproc_directive) @keyword
-"#
-                );
-                let query =
-                    Query::new(&lang, &query_src).map_err(|e| SyntaxError::Query(e.message))?;
-                (Some(query), Duration::from_millis(40))
-            }
-            LanguageId::Python => {
-                parser
-                    .set_language(&tree_sitter_python::LANGUAGE.into())
-                    .map_err(|_| SyntaxError::ParserInit)?;
-                let lang = tree_sitter_python::LANGUAGE.into();
-                let query = Query::new(&lang, tree_sitter_python::HIGHLIGHTS_QUERY)
+                let query = Query::new(grammar, default_query(language))
                     .map_err(|e| SyntaxError::Query(e.message))?;
-                (Some(query), Duration::from_millis(40))
-            }
-            LanguageId::Hlsl => {
-                parser
-                    .set_language(&tree_sitter_hlsl::LANGUAGE_HLSL.into())
-                    .map_err(|_| SyntaxError::ParserInit)?;
-                let lang = tree_sitter_hlsl::LANGUAGE_HLSL.into();
-                let query_src = r#"
-(comment) @comment
-(number_literal) @number
-(string_literal) @string
-(raw_string_literal) @string
-(concatenated_string) @string
-(system_lib_string) @string
-(preproc_directive) @keyword
-(primitive_type) @type
-(type_identifier) @type
-
-(call_expression
-  function: (identifier) @function)
-
-(call_expression
-  function: (field_expression
-              field: (field_identifier) @function))
-
-(function_definition
-  declarator: (function_declarator
-                declarator: (identifier) @function))
-
-(field_identifier) @property
-"#;
-                let query =
-                    Query::new(&lang, query_src).map_err(|e| SyntaxError::Query(e.message))?;
-                (Some(query), Duration::from_millis(40))
+                Some(query)
             }
-            LanguageId::Markdown | LanguageId::PlainText => (None, Duration::from_millis(0)),
+            None => None,
         };
 
         Ok(Self {
             language,
+            grammar,
             parser,
+            background: None,
             tree: None,
             query,
             cursor: QueryCursor::new(),
-            debounce,
+            debounce: reparse_debounce(language),
             pending_since: None,
+            generation: next_generation(),
+            errors: OnceCell::new(),
+            folds: OnceCell::new(),
+            symbols: OnceCell::new(),
         })
     }
 
@@ -135,9 +111,32 @@ impl SyntaxState {
         self.language
     }
 
+    /// Changes whenever the tree does (a parse is adopted, an edit is queued or the state is
+    /// reset), so callers can cache what they derive from it.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn tree_changed(&mut self) {
+        self.generation = next_generation();
+        self.errors = OnceCell::new();
+        self.folds = OnceCell::new();
+        self.symbols = OnceCell::new();
+    }
+
+    /// Switches to `new_language` and parses `rope` from scratch, e.g. when the user overrides
+    /// the detected language. On error `self` is left unchanged.
+    pub fn reset(&mut self, new_language: LanguageId, rope: &Rope) -> Result<(), SyntaxError> {
+        let mut fresh = Self::new(new_language)?;
+        fresh.set_text(rope)?;
+        *self = fresh;
+        Ok(())
+    }
+
     pub fn set_text(&mut self, rope: &Rope) -> Result<(), SyntaxError> {
         if self.language == LanguageId::PlainText || self.language == LanguageId::Markdown {
             self.tree = None;
+            self.tree_changed();
             return Ok(());
         }
 
@@ -149,7 +148,10 @@ impl SyntaxState {
             )
             .ok_or(SyntaxError::ParseFailed)?;
         self.tree = Some(tree);
+        self.tree_changed();
         self.pending_since = None;
+        // A parse still running on the old text must not replace this tree.
+        self.background = None;
         Ok(())
     }
 
@@ -160,30 +162,100 @@ impl SyntaxState {
 
         if let Some(tree) = &mut self.tree {
             tree.edit(&edit);
+            self.tree_changed();
+        }
+        if let Some(edits) = self.background.as_mut().and_then(|b| b.in_flight.as_mut()) {
+            edits.push(edit);
         }
         self.pending_since = Some(Instant::now());
     }
 
+    /// Sends `rope` to this document's parse thread (started on first use), reusing the current
+    /// tree for an incremental parse. Does nothing while an earlier parse is still running;
+    /// edits queued meanwhile keep the state pending so the next call picks them up.
+    pub fn queue_parse(&mut self, rope: Arc<Rope>) {
+        let Some(grammar) = &self.grammar else {
+            return;
+        };
+        if self.is_parsing() {
+            return;
+        }
+        if self.background.is_none() {
+            self.background = BackgroundParser::spawn(grammar);
+        }
+        let job = ParseJob {
+            old_tree: self.tree.clone(),
+            rope,
+        };
+        let sent = match &mut self.background {
+            Some(background) => background.jobs.send(job).map_err(|err| err.0),
+            None => Err(job),
+        };
+        match sent {
+            Ok(()) => {
+                if let Some(background) = &mut self.background {
+                    background.in_flight = Some(Vec::new());
+                }
+                self.pending_since = None;
+            }
+            // No parse thread available: parse here rather than never updating.
+            Err(job) => {
+                let _ = self.set_text(&job.rope);
+            }
+        }
+    }
+
+    /// Adopts the tree from a finished background parse, if there is one, after replaying the
+    /// edits made while it ran. Never blocks.
+    pub fn poll_result(&mut self) -> Option<Tree> {
+        let background = self.background.as_mut()?;
+        background.in_flight.as_ref()?;
+        let tree = match background.results.try_recv() {
+            Ok(tree) => tree,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => {
+                // The thread is gone; the next `queue_parse` starts a new one.
+                self.background = None;
+                self.pending_since.get_or_insert_with(Instant::now);
+                return None;
+            }
+        };
+        let edits = background.in_flight.take().unwrap_or_default();
+        let Some(mut tree) = tree else {
+            self.pending_since.get_or_insert_with(Instant::now);
+            return None;
+        };
+        for edit in &edits {
+            tree.edit(edit);
+        }
+        self.tree = Some(tree.clone());
+        self.tree_changed();
+        Some(tree)
+    }
+
+    /// Whether a background parse has been queued and not yet collected by
+    /// [`Self::poll_result`].
+    pub fn is_parsing(&self) -> bool {
+        self.background
+            .as_ref()
+            .is_some_and(|b| b.in_flight.is_some())
+    }
+
+    /// Collects a finished background parse and, once edits have settled for the language's
+    /// debounce, queues the next one. Never blocks: until the new tree arrives, queries keep
+    /// using the previous tree with the pending edits applied.
     pub fn ensure_parsed(&mut self, rope: &Rope) -> Result<(), SyntaxError> {
         if self.language == LanguageId::PlainText || self.language == LanguageId::Markdown {
             return Ok(());
         }
+        self.poll_result();
         let Some(pending_since) = self.pending_since else {
             return Ok(());
         };
         if pending_since.elapsed() < self.debounce {
             return Ok(());
         }
-
-        let tree = self
-            .parser
-            .parse_with(
-                &mut |byte_offset, _| rope_chunk_from_byte(rope, byte_offset),
-                self.tree.as_ref(),
-            )
-            .ok_or(SyntaxError::ParseFailed)?;
-        self.tree = Some(tree);
-        self.pending_since = None;
+        self.queue_parse(Arc::new(rope.clone()));
         Ok(())
     }
 
@@ -228,14 +300,279 @@ impl SyntaxState {
         spans.sort_by_key(|s| (s.byte_range.start, s.byte_range.end));
         Ok(spans)
     }
+
+    /// `ERROR` and `MISSING` nodes of the current tree, in document order, tagged
+    /// [`HighlightTag::Error`].
+    ///
+    /// A missing token has an empty byte range at the position where the parser expected it.
+    /// Uses the last parsed tree as-is; call [`Self::ensure_parsed`] first for fresh results.
+    /// The tree is only walked once per [`Self::generation`].
+    pub fn error_spans(&self) -> &[HighlightSpan] {
+        self.errors.get_or_init(|| self.collect_error_spans())
+    }
+
+    /// The [`Self::error_spans`] that overlap `byte_range` or touch either end of it, found by
+    /// binary search. Spans never nest, so they are ordered by both start and end.
+    pub fn error_spans_in(&self, byte_range: Range<usize>) -> &[HighlightSpan] {
+        let spans = self.error_spans();
+        let first = spans.partition_point(|span| span.byte_range.end < byte_range.start);
+        let end = spans.partition_point(|span| span.byte_range.start <= byte_range.end);
+        &spans[first..end.max(first)]
+    }
+
+    fn collect_error_spans(&self) -> Vec<HighlightSpan> {
+        let mut spans = Vec::new();
+        let Some(tree) = &self.tree else {
+            return spans;
+        };
+
+        let mut cursor = tree.walk();
+        loop {
+            let node = cursor.node();
+            if node.is_error() || node.is_missing() {
+                spans.push(HighlightSpan {
+                    byte_range: node.byte_range(),
+                    tag: HighlightTag::Error,
+                });
+            } else if node.has_error() && cursor.goto_first_child() {
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    return spans;
+                }
+            }
+        }
+    }
+
+    /// Declarations for an outline panel, in document order.
+    ///
+    /// Function bodies and variable initializers are not searched, so locals and closures are
+    /// left out; methods inside classes, impls and structs are included.
+    ///
+    /// `rope` must be the text the tree was last parsed or edited to; the list is built once
+    /// per [`Self::generation`].
+    pub fn symbol_list(&self, rope: &Rope) -> &[Symbol] {
+        self.symbols.get_or_init(|| self.collect_symbols(rope))
+    }
+
+    fn collect_symbols(&self, rope: &Rope) -> Vec<Symbol> {
+        let mut symbols = Vec::new();
+        let Some(tree) = &self.tree else {
+            return symbols;
+        };
+
+        let mut cursor = tree.walk();
+        loop {
+            let node = cursor.node();
+            let kind = symbol_kind(self.language, node);
+            let name = kind
+                .and_then(|_| symbol_name_node(node))
+                .filter(|name| !name.kind().ends_with("pattern"))
+                .and_then(|name| rope.get_byte_slice(name.byte_range()));
+            if let (Some(kind), Some(name)) = (kind, name) {
+                symbols.push(Symbol {
+                    name: name.to_string(),
+                    kind,
+                    line: node.start_position().row,
+                });
+            }
+
+            let has_body = matches!(kind, Some(SymbolKind::Function | SymbolKind::Variable))
+                || matches!(
+                    node.kind(),
+                    "arrow_function" | "function_expression" | "closure_expression" | "lambda"
+                );
+            if !has_body && cursor.goto_first_child() {
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    return symbols;
+                }
+            }
+        }
+    }
+
+    /// Byte ranges of collapsible multi-line regions (bodies, blocks, long comments), in
+    /// document order.
+    ///
+    /// At most one range starts on any given line: the outermost one. Like
+    /// [`Self::error_spans`], the tree is only walked once per [`Self::generation`].
+    pub fn fold_ranges(&self) -> &[Range<usize>] {
+        self.folds.get_or_init(|| self.collect_fold_ranges())
+    }
+
+    fn collect_fold_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let Some(tree) = &self.tree else {
+            return ranges;
+        };
+        let kinds = fold_kinds(self.language);
+
+        let mut last_start_row = None;
+        let mut cursor = tree.walk();
+        loop {
+            let node = cursor.node();
+            let start_row = node.start_position().row;
+            let end = node.end_position();
+            // A node that ends at column 0 (trailing newline) really ends on the previous line.
+            let end_row = if end.column == 0 {
+                end.row.saturating_sub(1)
+            } else {
+                end.row
+            };
+            let multiline = end_row > start_row;
+            if multiline && last_start_row != Some(start_row) && kinds.contains(&node.kind()) {
+                ranges.push(node.byte_range());
+                last_start_row = Some(start_row);
+            }
+            // Single-line nodes cannot contain a multi-line region, so skip their children.
+            if multiline && cursor.goto_first_child() {
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    return ranges;
+                }
+            }
+        }
+    }
+}
+
+fn grammar(language: LanguageId) -> Option<tree_sitter::Language> {
+    let grammar = match language {
+        LanguageId::Rust => tree_sitter_rust::LANGUAGE,
+        LanguageId::JavaScript => tree_sitter_javascript::LANGUAGE,
+        // The TSX grammar parses plain `.ts` too (except legacy `<T>expr` casts), so one
+        // grammar covers both extensions.
+        LanguageId::TypeScript => tree_sitter_typescript::LANGUAGE_TSX,
+        LanguageId::Cpp => tree_sitter_cpp::LANGUAGE,
+        LanguageId::Python => tree_sitter_python::LANGUAGE,
+        LanguageId::Hlsl => tree_sitter_hlsl::LANGUAGE_HLSL,
+        LanguageId::Json => tree_sitter_json::LANGUAGE,
+        LanguageId::Toml => tree_sitter_toml_ng::LANGUAGE,
+        LanguageId::Yaml => tree_sitter_yaml::LANGUAGE,
+        LanguageId::Go => tree_sitter_go::LANGUAGE,
+        LanguageId::Lua => tree_sitter_lua::LANGUAGE,
+        LanguageId::Xml => tree_sitter_xml::LANGUAGE_XML,
+        LanguageId::Html => tree_sitter_html::LANGUAGE,
+        LanguageId::Shell => tree_sitter_bash::LANGUAGE,
+        LanguageId::Markdown | LanguageId::PlainText => return None,
+    };
+    Some(grammar.into())
+}
+
+// Node kinds that can be folded. Indentation-based grammars (Python, YAML, ...) list the
+// whole statement so the fold starts on its header line rather than the first body line.
+fn fold_kinds(language: LanguageId) -> &'static [&'static str] {
+    match language {
+        LanguageId::Rust => &[
+            "block",
+            "declaration_list",
+            "field_declaration_list",
+            "enum_variant_list",
+            "match_block",
+            "use_list",
+            "block_comment",
+        ],
+        LanguageId::JavaScript | LanguageId::TypeScript => &[
+            "statement_block",
+            "class_body",
+            "switch_body",
+            "object",
+            "array",
+            "object_type",
+            "interface_body",
+            "enum_body",
+            "comment",
+        ],
+        LanguageId::Cpp | LanguageId::Hlsl => &[
+            "compound_statement",
+            "field_declaration_list",
+            "declaration_list",
+            "enumerator_list",
+            "initializer_list",
+            "comment",
+        ],
+        LanguageId::Python => &[
+            "function_definition",
+            "class_definition",
+            "if_statement",
+            "for_statement",
+            "while_statement",
+            "with_statement",
+            "try_statement",
+            "dictionary",
+            "list",
+            "string",
+        ],
+        LanguageId::Json => &["object", "array"],
+        LanguageId::Toml => &["table", "table_array_element", "array", "inline_table"],
+        LanguageId::Yaml => &["block_mapping_pair", "block_sequence_item"],
+        LanguageId::Go => &[
+            "block",
+            "field_declaration_list",
+            "interface_type",
+            "import_spec_list",
+            "literal_value",
+            "comment",
+        ],
+        LanguageId::Lua => &[
+            "function_declaration",
+            "function_definition",
+            "if_statement",
+            "for_statement",
+            "while_statement",
+            "repeat_statement",
+            "do_statement",
+            "table_constructor",
+            "comment",
+        ],
+        LanguageId::Xml | LanguageId::Html => {
+            &["element", "script_element", "style_element", "comment"]
+        }
+        LanguageId::Shell => &[
+            "function_definition",
+            "if_statement",
+            "case_statement",
+            "for_statement",
+            "c_style_for_statement",
+            "while_statement",
+            "heredoc_body",
+        ],
+        LanguageId::Markdown | LanguageId::PlainText => &[],
+    }
+}
+
+fn reparse_debounce(language: LanguageId) -> Duration {
+    let millis = match language {
+        LanguageId::Json | LanguageId::Toml => 20,
+        // YAML is often edited in quick bursts (CI configs), so reparse a little later.
+        LanguageId::Yaml => 60,
+        LanguageId::Markdown | LanguageId::PlainText => 0,
+        _ => 40,
+    };
+    Duration::from_millis(millis)
 }
 
 fn tag_from_capture_name(name: &str) -> Option<HighlightTag> {
     let name = name.trim_start_matches('@');
+    match name {
+        "function.macro" => return Some(HighlightTag::Macro),
+        "string.regex" => return Some(HighlightTag::Regex),
+        _ => {}
+    }
     let head = name.split('.').next().unwrap_or(name);
     match head {
         "comment" => Some(HighlightTag::Comment),
+        "macro" => Some(HighlightTag::Macro),
+        "attribute" => Some(HighlightTag::Attribute),
+        "lifetime" => Some(HighlightTag::Lifetime),
+        "label" => Some(HighlightTag::Label),
+        "namespace" | "module" => Some(HighlightTag::Namespace),
         "string" => Some(HighlightTag::String),
+        "regex" => Some(HighlightTag::Regex),
         "number" => Some(HighlightTag::Number),
         "keyword" => Some(HighlightTag::Keyword),
         "type" => Some(HighlightTag::Type),
@@ -252,6 +589,48 @@ fn tag_from_capture_name(name: &str) -> Option<HighlightTag> {
     }
 }
 
+struct ParseJob {
+    rope: Arc<Rope>,
+    old_tree: Option<Tree>,
+}
+
+// One parse thread per document. Dropping this closes the job channel, which ends the thread
+// once its current parse (if any) finishes.
+struct BackgroundParser {
+    jobs: Sender<ParseJob>,
+    results: Receiver<Option<Tree>>,
+    // Edits made after the in-flight job was sent, replayed onto its tree when it returns.
+    in_flight: Option<Vec<InputEdit>>,
+}
+
+impl BackgroundParser {
+    fn spawn(grammar: &tree_sitter::Language) -> Option<Self> {
+        let mut parser = Parser::new();
+        parser.set_language(grammar).ok()?;
+        let (jobs, job_rx) = mpsc::channel::<ParseJob>();
+        let (result_tx, results) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("rustide-parse".to_string())
+            .spawn(move || {
+                while let Ok(job) = job_rx.recv() {
+                    let tree = parser.parse_with(
+                        &mut |byte_offset, _| rope_chunk_from_byte(&job.rope, byte_offset),
+                        job.old_tree.as_ref(),
+                    );
+                    if result_tx.send(tree).is_err() {
+                        break;
+                    }
+                }
+            })
+            .ok()?;
+        Some(Self {
+            jobs,
+            results,
+            in_flight: None,
+        })
+    }
+}
+
 fn rope_chunk_from_byte(rope: &Rope, byte_offset: usize) -> &[u8] {
     if byte_offset >= rope.len_bytes() {
         return &[];
@@ -0,0 +1,822 @@
+use ropey::Rope;
+
+use crate::{HighlightTag, LanguageId, SyntaxState};
+
+// Parses `source` and returns `(text, tag)` for every highlight span.
+fn highlights(language: LanguageId, source: &str) -> Vec<(String, HighlightTag)> {
+    let rope = Rope::from_str(source);
+    let mut syntax = SyntaxState::new(language).expect("query should compile");
+    syntax.set_text(&rope).unwrap();
+    syntax
+        .highlight_spans(&rope, 0..rope.len_bytes())
+        .unwrap()
+        .into_iter()
+        .map(|span| (source[span.byte_range].to_string(), span.tag))
+        .collect()
+}
+
+fn has(spans: &[(String, HighlightTag)], text: &str, tag: HighlightTag) -> bool {
+    spans.iter().any(|(t, g)| t == text && *g == tag)
+}
+
+#[test]
+fn rust_extension_maps_to_rust() {
+    let path = std::path::Path::new("src/main.rs");
+    assert_eq!(LanguageId::from_path(Some(path)), LanguageId::Rust);
+}
+
+#[test]
+fn rust_highlights_cover_core_captures() {
+    let source = r#"// greet
+#[derive(Debug)]
+struct Wrapper<'a> {
+    name: &'a str,
+}
+
+fn greet(count: u32) -> String {
+    let total = count + 1;
+    'outer: loop {
+        break 'outer;
+    }
+    println!("hi {}", total);
+    format!("{}", MAX_COUNT)
+}
+"#;
+    let spans = highlights(LanguageId::Rust, source);
+    assert!(has(&spans, "// greet", HighlightTag::Comment));
+    assert!(has(&spans, "#[derive(Debug)]", HighlightTag::Attribute));
+    assert!(has(&spans, "println", HighlightTag::Macro));
+    assert!(has(&spans, "'a", HighlightTag::Lifetime));
+    assert!(has(&spans, "'outer", HighlightTag::Label));
+    assert!(has(&spans, "fn", HighlightTag::Keyword));
+    assert!(has(&spans, "greet", HighlightTag::Function));
+    assert!(has(&spans, "u32", HighlightTag::Type));
+    assert!(has(&spans, "Wrapper", HighlightTag::Type));
+    assert!(has(&spans, "1", HighlightTag::Number));
+    assert!(has(&spans, "\"hi {}\"", HighlightTag::String));
+    assert!(has(&spans, "+", HighlightTag::Operator));
+    assert!(has(&spans, "MAX_COUNT", HighlightTag::Constant));
+}
+
+#[test]
+fn javascript_and_typescript_extensions() {
+    use std::path::Path;
+
+    for ext in ["js", "mjs", "cjs", "jsx"] {
+        let path = format!("app.{ext}");
+        assert_eq!(
+            LanguageId::from_path(Some(Path::new(&path))),
+            LanguageId::JavaScript
+        );
+    }
+    for ext in ["ts", "tsx"] {
+        let path = format!("app.{ext}");
+        assert_eq!(
+            LanguageId::from_path(Some(Path::new(&path))),
+            LanguageId::TypeScript
+        );
+    }
+}
+
+#[test]
+fn javascript_highlights_core_constructs() {
+    let source = r#"import { readFile } from "fs";
+// load config
+class Loader extends Base {
+  load(path) { return readFile(path); }
+}
+const parse = (text) => `cfg: ${text}`;
+"#;
+    let spans = highlights(LanguageId::JavaScript, source);
+    assert!(has(&spans, "import", HighlightTag::Keyword));
+    assert!(has(&spans, "\"fs\"", HighlightTag::String));
+    assert!(has(&spans, "// load config", HighlightTag::Comment));
+    assert!(has(&spans, "Loader", HighlightTag::Type));
+    assert!(has(&spans, "load", HighlightTag::Function));
+    assert!(has(&spans, "parse", HighlightTag::Function));
+    assert!(spans
+        .iter()
+        .any(|(t, g)| t.starts_with("`cfg") && *g == HighlightTag::String));
+}
+
+#[test]
+fn typescript_highlights_type_annotations() {
+    let source = r#"interface User {
+  id: number;
+  name: string;
+}
+
+export function greet(user: User, times: number = 1): string {
+  const label: string = `hi ${user.name}`;
+  return label.repeat(times);
+}
+
+const View = (props: { user: User }) => <div>{props.user.name}</div>;
+"#;
+    let spans = highlights(LanguageId::TypeScript, source);
+    assert!(!spans.is_empty());
+    assert!(has(&spans, "interface", HighlightTag::Keyword));
+    assert!(has(&spans, "User", HighlightTag::Type));
+    assert!(has(&spans, "number", HighlightTag::Type));
+    assert!(has(&spans, "string", HighlightTag::Type));
+    assert!(has(&spans, "greet", HighlightTag::Function));
+    assert!(has(&spans, "export", HighlightTag::Keyword));
+}
+
+#[test]
+fn json_highlights_package_manifest() {
+    use std::path::Path;
+
+    assert_eq!(
+        LanguageId::from_path(Some(Path::new("package.json"))),
+        LanguageId::Json
+    );
+    assert_eq!(
+        LanguageId::from_path(Some(Path::new("tsconfig.jsonc"))),
+        LanguageId::Json
+    );
+
+    let source = r#"{
+  "name": "demo",
+  "version": "1.0.0",
+  "private": true,
+  "workspaces": null,
+  "engines": { "node": ">=18", "retries": 3 },
+  "files": ["dist", "README.md"]
+}
+"#;
+    let spans = highlights(LanguageId::Json, source);
+    assert!(has(&spans, "\"name\"", HighlightTag::Property));
+    assert!(has(&spans, "\"node\"", HighlightTag::Property));
+    assert!(has(&spans, "\"demo\"", HighlightTag::String));
+    assert!(has(&spans, "\"dist\"", HighlightTag::String));
+    assert!(has(&spans, "3", HighlightTag::Number));
+    assert!(has(&spans, "true", HighlightTag::Constant));
+    assert!(has(&spans, "null", HighlightTag::Constant));
+    assert!(has(&spans, "{", HighlightTag::Punctuation));
+}
+
+#[test]
+fn toml_highlights_cargo_manifest() {
+    use std::path::Path;
+
+    assert_eq!(
+        LanguageId::from_path(Some(Path::new("Cargo.toml"))),
+        LanguageId::Toml
+    );
+
+    let source = r#"# workspace member
+[package]
+name = "demo"
+version = "0.1.0"
+publish = false
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+
+[profile.release]
+opt-level = 3
+lto-ratio = 0.5
+"#;
+    let spans = highlights(LanguageId::Toml, source);
+    assert!(has(&spans, "# workspace member", HighlightTag::Comment));
+    assert!(has(&spans, "package", HighlightTag::Type));
+    assert!(has(&spans, "profile.release", HighlightTag::Type));
+    assert!(has(&spans, "name", HighlightTag::Property));
+    assert!(has(&spans, "features", HighlightTag::Property));
+    assert!(has(&spans, "\"demo\"", HighlightTag::String));
+    assert!(has(&spans, "\"derive\"", HighlightTag::String));
+    assert!(has(&spans, "3", HighlightTag::Number));
+    assert!(has(&spans, "0.5", HighlightTag::Number));
+    assert!(has(&spans, "false", HighlightTag::Constant));
+}
+
+#[test]
+fn yaml_highlights_github_workflow() {
+    use std::path::Path;
+
+    assert_eq!(
+        LanguageId::from_path(Some(Path::new(".github/workflows/ci.yml"))),
+        LanguageId::Yaml
+    );
+    assert_eq!(
+        LanguageId::from_path(Some(Path::new("compose.yaml"))),
+        LanguageId::Yaml
+    );
+
+    let source = r#"# CI
+name: CI
+on:
+  push:
+    branches: [main]
+env: &defaults
+  RUST_BACKTRACE: 1
+  CARGO_INCREMENTAL: null
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    continue-on-error: false
+    env: *defaults
+    steps:
+      - uses: actions/checkout@v4
+      - run: "cargo test --workspace"
+"#;
+    let spans = highlights(LanguageId::Yaml, source);
+    assert!(has(&spans, "# CI", HighlightTag::Comment));
+    assert!(has(&spans, "name", HighlightTag::Property));
+    assert!(has(&spans, "runs-on", HighlightTag::Property));
+    assert!(has(&spans, "branches", HighlightTag::Property));
+    assert!(has(&spans, "ubuntu-latest", HighlightTag::String));
+    assert!(has(&spans, "main", HighlightTag::String));
+    assert!(has(
+        &spans,
+        "\"cargo test --workspace\"",
+        HighlightTag::String
+    ));
+    assert!(has(&spans, "1", HighlightTag::Number));
+    assert!(has(&spans, "false", HighlightTag::Constant));
+    assert!(has(&spans, "null", HighlightTag::Constant));
+    assert!(has(&spans, "&defaults", HighlightTag::Variable));
+    assert!(has(&spans, "*defaults", HighlightTag::Variable));
+}
+
+#[test]
+fn go_highlights_declarations_and_builtins() {
+    let path = std::path::Path::new("cmd/server/main.go");
+    assert_eq!(LanguageId::from_path(Some(path)), LanguageId::Go);
+
+    let source = r#"package main
+
+import "fmt"
+
+// Shape has an area.
+type Shape interface {
+	Area() float64
+}
+
+type Rect struct {
+	Width, Height float64
+}
+
+func (r Rect) Area() float64 { return r.Width * r.Height }
+
+func main() {
+	items := make([]rune, 0, 4)
+	items = append(items, 'x')
+	fmt.Println(len(items), `raw`, 0x1F, nil)
+}
+"#;
+    let spans = highlights(LanguageId::Go, source);
+    assert!(has(&spans, "// Shape has an area.", HighlightTag::Comment));
+    assert!(has(&spans, "package", HighlightTag::Keyword));
+    assert!(has(&spans, "import", HighlightTag::Keyword));
+    assert!(has(&spans, "\"fmt\"", HighlightTag::String));
+    assert!(has(&spans, "Shape", HighlightTag::Type));
+    assert!(has(&spans, "float64", HighlightTag::Type));
+    assert!(has(&spans, "Area", HighlightTag::Function));
+    assert!(has(&spans, "main", HighlightTag::Function));
+    assert!(has(&spans, "Println", HighlightTag::Function));
+    assert!(has(&spans, "Width", HighlightTag::Property));
+    assert!(has(&spans, "make", HighlightTag::Function));
+    assert!(has(&spans, "append", HighlightTag::Function));
+    assert!(has(&spans, "len", HighlightTag::Function));
+    assert!(has(&spans, "'x'", HighlightTag::String));
+    assert!(has(&spans, "`raw`", HighlightTag::String));
+    assert!(has(&spans, "0x1F", HighlightTag::Number));
+    assert!(has(&spans, "nil", HighlightTag::Constant));
+}
+
+#[test]
+fn lua_highlights_functions_tables_and_long_strings() {
+    let path = std::path::Path::new("scripts/player.lua");
+    assert_eq!(LanguageId::from_path(Some(path)), LanguageId::Lua);
+
+    let source = r#"-- player module
+--[[ block
+comment ]]
+local Player = { speed = 4.5 }
+
+function Player.new(name)
+  local self = setmetatable({}, Player)
+  self.name = name or [[unnamed]]
+  return self
+end
+
+function Player:move(dx)
+  self:log("move", dx * self.speed, nil)
+end
+"#;
+    let spans = highlights(LanguageId::Lua, source);
+    assert!(has(&spans, "-- player module", HighlightTag::Comment));
+    assert!(has(&spans, "--[[ block\ncomment ]]", HighlightTag::Comment));
+    assert!(has(&spans, "[[unnamed]]", HighlightTag::String));
+    assert!(has(&spans, "\"move\"", HighlightTag::String));
+    assert!(has(&spans, "4.5", HighlightTag::Number));
+    assert!(has(&spans, "nil", HighlightTag::Constant));
+    assert!(has(&spans, "local", HighlightTag::Keyword));
+    assert!(has(&spans, "function", HighlightTag::Keyword));
+    assert!(has(&spans, "new", HighlightTag::Function));
+    assert!(has(&spans, "move", HighlightTag::Function));
+    assert!(has(&spans, "setmetatable", HighlightTag::Function));
+    assert!(has(&spans, "log", HighlightTag::Function));
+    assert!(has(&spans, "speed", HighlightTag::Property));
+    assert!(has(&spans, "name", HighlightTag::Property));
+}
+
+#[test]
+fn markup_extensions_map_to_xml_and_html() {
+    use std::path::Path;
+
+    for name in ["layout.xml", "icon.svg", "MainWindow.xaml"] {
+        assert_eq!(
+            LanguageId::from_path(Some(Path::new(name))),
+            LanguageId::Xml
+        );
+    }
+    for name in ["index.html", "legacy.htm"] {
+        assert_eq!(
+            LanguageId::from_path(Some(Path::new(name))),
+            LanguageId::Html
+        );
+    }
+}
+
+#[test]
+fn html_highlights_tags_attributes_and_embedded_code() {
+    let source = r#"<!DOCTYPE html>
+<html lang="en">
+  <!-- page header -->
+  <head>
+    <style>body { margin: 0; }</style>
+  </head>
+  <body class=main>
+    <p>Hello</p>
+    <script>console.log("hi");</script>
+  </body>
+</html>
+"#;
+    let spans = highlights(LanguageId::Html, source);
+    assert!(has(&spans, "<!DOCTYPE html>", HighlightTag::Keyword));
+    assert!(has(&spans, "<!-- page header -->", HighlightTag::Comment));
+    assert!(has(&spans, "html", HighlightTag::Type));
+    assert!(has(&spans, "p", HighlightTag::Type));
+    assert!(has(&spans, "lang", HighlightTag::Property));
+    assert!(has(&spans, "\"en\"", HighlightTag::String));
+    assert!(has(&spans, "main", HighlightTag::String));
+    assert!(has(&spans, "body { margin: 0; }", HighlightTag::String));
+    assert!(has(&spans, "console.log(\"hi\");", HighlightTag::String));
+    assert!(!spans.iter().any(|(text, _)| text == "Hello"));
+}
+
+#[test]
+fn shell_detected_from_extension_and_shebang() {
+    use std::path::Path;
+
+    for name in ["build.sh", "init.bash", ".zshrc.zsh"] {
+        assert_eq!(
+            LanguageId::from_path(Some(Path::new(name))),
+            LanguageId::Shell
+        );
+    }
+
+    assert_eq!(
+        LanguageId::from_shebang("#!/bin/sh"),
+        Some(LanguageId::Shell)
+    );
+    assert_eq!(
+        LanguageId::from_shebang("#!/usr/bin/env bash"),
+        Some(LanguageId::Shell)
+    );
+    assert_eq!(
+        LanguageId::from_shebang("#!/usr/bin/env -S zsh -f"),
+        Some(LanguageId::Shell)
+    );
+    assert_eq!(
+        LanguageId::from_shebang("#!/usr/bin/python3.12"),
+        Some(LanguageId::Python)
+    );
+    assert_eq!(LanguageId::from_shebang("#!/usr/bin/env perl"), None);
+    assert_eq!(LanguageId::from_shebang("echo hi"), None);
+
+    let script = Some(Path::new("scripts/deploy"));
+    assert_eq!(LanguageId::detect(script, "#!/bin/bash"), LanguageId::Shell);
+    assert_eq!(LanguageId::detect(None, "#!/bin/bash"), LanguageId::Shell);
+    assert_eq!(
+        LanguageId::detect(script, "deploy notes"),
+        LanguageId::PlainText
+    );
+    // An extension always wins over the shebang.
+    let rust = Some(Path::new("main.rs"));
+    assert_eq!(LanguageId::detect(rust, "#!/bin/bash"), LanguageId::Rust);
+}
+
+#[test]
+fn shell_highlights_commands_and_expansions() {
+    let source = r#"#!/usr/bin/env bash
+# deploy
+deploy() {
+  local target="${1:-prod}"
+  if [ -z "$target" ]; then
+    echo 'missing' >&2
+    return 1
+  fi
+  for f in *.tar; do rsync "$f" "$HOST:/srv"; done
+}
+"#;
+    let spans = highlights(LanguageId::Shell, source);
+    assert!(has(&spans, "# deploy", HighlightTag::Comment));
+    assert!(has(&spans, "deploy", HighlightTag::Function));
+    assert!(has(&spans, "echo", HighlightTag::Function));
+    assert!(has(&spans, "rsync", HighlightTag::Function));
+    assert!(has(&spans, "'missing'", HighlightTag::String));
+    assert!(has(&spans, "\"$target\"", HighlightTag::String));
+    assert!(has(&spans, "${1:-prod}", HighlightTag::Variable));
+    assert!(has(&spans, "$HOST", HighlightTag::Variable));
+    assert!(has(&spans, "if", HighlightTag::Keyword));
+    assert!(has(&spans, "for", HighlightTag::Keyword));
+    assert!(has(&spans, "local", HighlightTag::Keyword));
+}
+
+#[test]
+fn shebang_covers_common_interpreter_paths() {
+    let cases = [
+        ("#!/bin/bash", Some(LanguageId::Shell)),
+        ("#! /bin/sh -e", Some(LanguageId::Shell)),
+        ("#!/usr/local/bin/zsh", Some(LanguageId::Shell)),
+        ("#!/usr/bin/env python3", Some(LanguageId::Python)),
+        ("#!/usr/local/bin/python3.11 -u", Some(LanguageId::Python)),
+        ("#!/usr/bin/env node", Some(LanguageId::JavaScript)),
+        ("#!/usr/bin/env -S deno run", Some(LanguageId::JavaScript)),
+        ("#!/usr/bin/lua5.4", Some(LanguageId::Lua)),
+        ("#!/usr/bin/env ruby", None),
+        ("#!", None),
+    ];
+    for (line, expected) in cases {
+        assert_eq!(LanguageId::from_shebang(line), expected, "{line}");
+    }
+}
+
+#[test]
+fn content_detection_falls_back_for_plain_text_paths() {
+    use std::path::Path;
+
+    assert_eq!(
+        LanguageId::from_content("<?xml version=\"1.0\"?>"),
+        Some(LanguageId::Xml)
+    );
+    assert_eq!(
+        LanguageId::from_content("\u{feff}  <!DOCTYPE html>"),
+        Some(LanguageId::Html)
+    );
+    assert_eq!(
+        LanguageId::from_content("<html lang=\"en\">"),
+        Some(LanguageId::Html)
+    );
+    assert_eq!(LanguageId::from_content("plain notes"), None);
+
+    let makefile = Some(Path::new("Makefile"));
+    assert_eq!(
+        LanguageId::detect(makefile, "all: build"),
+        LanguageId::PlainText
+    );
+    let notes = Some(Path::new("run.txt"));
+    assert_eq!(
+        LanguageId::detect(notes, "#!/usr/bin/env bash"),
+        LanguageId::Shell
+    );
+    let page = Some(Path::new("page.tmpl"));
+    assert_eq!(
+        LanguageId::detect(page, "<!doctype html>"),
+        LanguageId::Html
+    );
+}
+
+#[test]
+fn default_query_matches_what_syntax_state_compiles() {
+    use crate::default_query;
+
+    assert_eq!(default_query(LanguageId::PlainText), "");
+    assert_eq!(default_query(LanguageId::Markdown), "");
+    assert!(default_query(LanguageId::Rust).contains("@macro"));
+    assert!(default_query(LanguageId::Cpp).contains("(preproc_directive) @keyword"));
+
+    // Composite queries are built once and then shared.
+    let typescript = default_query(LanguageId::TypeScript);
+    assert!(typescript.ends_with(default_query(LanguageId::JavaScript)));
+    assert!(std::ptr::eq(
+        typescript,
+        default_query(LanguageId::TypeScript)
+    ));
+}
+
+#[test]
+fn namespaces_get_their_own_tag() {
+    let cpp = highlights(
+        LanguageId::Cpp,
+        "namespace app { std::vector<int> items; }\nint n = std::max(1, 2);\n",
+    );
+    assert!(has(&cpp, "std", HighlightTag::Namespace));
+    assert!(has(&cpp, "app", HighlightTag::Namespace));
+
+    let rust = highlights(
+        LanguageId::Rust,
+        "use std::{fmt, io};\nfn f(x: &dyn std::fmt::Display) { io::stdin(); }\n",
+    );
+    assert!(has(&rust, "std", HighlightTag::Namespace));
+    assert!(has(&rust, "fmt", HighlightTag::Namespace));
+    assert!(has(&rust, "io", HighlightTag::Namespace));
+    assert!(has(&rust, "stdin", HighlightTag::Function));
+}
+
+#[test]
+fn javascript_regex_literals_are_not_plain_strings() {
+    let spans = highlights(
+        LanguageId::JavaScript,
+        "const words = text.match(/\\w+/g);\nconst s = \"/not a regex/\";\n",
+    );
+    assert!(has(&spans, "/\\w+/g", HighlightTag::Regex));
+    assert!(has(&spans, "\"/not a regex/\"", HighlightTag::String));
+}
+
+#[test]
+fn error_spans_report_error_and_missing_nodes() {
+    let error_spans = |language, source: &str| {
+        let rope = Rope::from_str(source);
+        let mut syntax = SyntaxState::new(language).unwrap();
+        syntax.set_text(&rope).unwrap();
+        syntax.error_spans().to_vec()
+    };
+
+    assert!(error_spans(LanguageId::Cpp, "int main() { return 0; }\n").is_empty());
+
+    // The parser recovers by inserting the missing `;` as a zero-width token.
+    let missing = error_spans(LanguageId::Cpp, "int main() { return 0 }\n");
+    assert!(!missing.is_empty());
+    assert!(missing.iter().all(|s| s.tag == HighlightTag::Error));
+    assert!(missing.iter().any(|s| s.byte_range.is_empty()));
+
+    let source = "fn main() { let x = ; }\n";
+    let errors = error_spans(LanguageId::Rust, source);
+    assert!(!errors.is_empty());
+    assert!(errors.iter().all(|s| s.byte_range.end <= source.len()));
+
+    assert!(error_spans(LanguageId::PlainText, "fn (").is_empty());
+}
+
+#[test]
+fn error_spans_are_cached_per_generation() {
+    let source = "fn a() { let x = ; }\nfn b() {}\nfn c() { let y = ; }\n";
+    let rope = Rope::from_str(source);
+    let mut syntax = SyntaxState::new(LanguageId::Rust).unwrap();
+    syntax.set_text(&rope).unwrap();
+    let generation = syntax.generation();
+    let all = syntax.error_spans().to_vec();
+    assert!(all.len() >= 2);
+    assert!(all
+        .windows(2)
+        .all(|w| w[0].byte_range.end <= w[1].byte_range.start));
+
+    // Lookups by range only return the spans on those bytes.
+    let line = |n: usize| {
+        let start = rope.line_to_byte(n);
+        start..rope.line_to_byte(n + 1) - 1
+    };
+    let on_line = |n: usize| {
+        let range = line(n);
+        all.iter()
+            .filter(|s| s.byte_range.start <= range.end && range.start <= s.byte_range.end)
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(syntax.error_spans_in(line(0)), on_line(0));
+    assert!(syntax.error_spans_in(line(1)).is_empty());
+    assert_eq!(syntax.error_spans_in(line(2)), on_line(2));
+    assert_eq!(syntax.generation(), generation);
+
+    // Any change to the tree starts a new generation.
+    let mut edited = rope.clone();
+    edited.remove(0..source.find('}').unwrap() + 1);
+    syntax.queue_edit(tree_sitter::InputEdit {
+        start_byte: 0,
+        old_end_byte: source.find('}').unwrap() + 1,
+        new_end_byte: 0,
+        start_position: tree_sitter::Point::new(0, 0),
+        old_end_position: tree_sitter::Point::new(0, source.find('}').unwrap() + 1),
+        new_end_position: tree_sitter::Point::new(0, 0),
+    });
+    assert_ne!(syntax.generation(), generation);
+    syntax.set_text(&edited).unwrap();
+    let reparsed = syntax.generation();
+    assert!(syntax.error_spans().len() < all.len());
+    syntax.reset(LanguageId::Rust, &edited).unwrap();
+    assert_ne!(syntax.generation(), reparsed);
+}
+
+#[test]
+fn fold_ranges_cover_multiline_bodies() {
+    let fold_ranges = |language, source: &str| {
+        let rope = Rope::from_str(source);
+        let mut syntax = SyntaxState::new(language).unwrap();
+        syntax.set_text(&rope).unwrap();
+        syntax
+            .fold_ranges()
+            .iter()
+            .map(|r| source[r.clone()].to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let cpp = fold_ranges(
+        LanguageId::Cpp,
+        "int main() {\n  if (x) { y(); }\n  return 0;\n}\n/* one\n   two */\n",
+    );
+    assert_eq!(
+        cpp,
+        ["{\n  if (x) { y(); }\n  return 0;\n}", "/* one\n   two */"]
+    );
+
+    // Python folds start on the header line and nested blocks get their own range.
+    let python = fold_ranges(
+        LanguageId::Python,
+        "def f():\n    if x:\n        a()\n        b()\n    return 1\n",
+    );
+    assert_eq!(python.len(), 2);
+    assert!(python[0].starts_with("def f():"));
+    assert!(python[1].starts_with("if x:"));
+
+    assert!(fold_ranges(LanguageId::Cpp, "int f() { return 0; }\n").is_empty());
+    assert!(fold_ranges(LanguageId::PlainText, "a\nb\n").is_empty());
+
+    // Cached ranges follow a queued edit before the reparse.
+    let rope = Rope::from_str("fn f() {\n}\n");
+    let mut syntax = SyntaxState::new(LanguageId::Rust).unwrap();
+    syntax.set_text(&rope).unwrap();
+    assert_eq!(syntax.fold_ranges().len(), 1);
+    assert_eq!(syntax.fold_ranges()[0], 7..10);
+    syntax.queue_edit(tree_sitter::InputEdit {
+        start_byte: 0,
+        old_end_byte: 0,
+        new_end_byte: 1,
+        start_position: tree_sitter::Point::new(0, 0),
+        old_end_position: tree_sitter::Point::new(0, 0),
+        new_end_position: tree_sitter::Point::new(1, 0),
+    });
+    assert_eq!(syntax.fold_ranges()[0], 8..11);
+}
+
+#[test]
+fn symbol_list_collects_declarations_in_order() {
+    use crate::{Symbol, SymbolKind};
+
+    let symbols = |language, source: &str| {
+        let rope = Rope::from_str(source);
+        let mut syntax = SyntaxState::new(language).unwrap();
+        syntax.set_text(&rope).unwrap();
+        syntax.symbol_list(&rope).to_vec()
+    };
+    let symbol = |name: &str, kind, line| Symbol {
+        name: name.to_string(),
+        kind,
+        line,
+    };
+
+    let python = symbols(
+        LanguageId::Python,
+        "LIMIT = 3\n\ndef load(path):\n    data = open(path)\n    def inner():\n        pass\n    return data\n\nclass Cache:\n    def get(self, key):\n        return key\n\n@cached\ndef save():\n    pass\n",
+    );
+    assert_eq!(
+        python,
+        [
+            symbol("LIMIT", SymbolKind::Variable, 0),
+            symbol("load", SymbolKind::Function, 2),
+            symbol("Cache", SymbolKind::Type, 8),
+            symbol("get", SymbolKind::Function, 9),
+            symbol("save", SymbolKind::Function, 13),
+        ]
+    );
+
+    let rust = symbols(
+        LanguageId::Rust,
+        "const MAX: u32 = 1;\nstruct Point;\nimpl Point {\n    fn new() -> Self { Point }\n}\n",
+    );
+    let names: Vec<_> = rust.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+    assert_eq!(
+        names,
+        [
+            ("MAX", SymbolKind::Constant),
+            ("Point", SymbolKind::Type),
+            ("new", SymbolKind::Function),
+        ]
+    );
+
+    let cpp = symbols(
+        LanguageId::Cpp,
+        "struct Foo { int bar() { return 1; } };\nstruct Foo make();\nint *Foo::baz() { return 0; }\n",
+    );
+    let names: Vec<_> = cpp.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["Foo", "bar", "Foo::baz"]);
+
+    assert!(symbols(LanguageId::PlainText, "fn main() {}").is_empty());
+}
+
+#[test]
+fn background_parse_catches_up_with_edits_without_blocking() {
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    // Polls until the background tree arrives; a deadlock fails the test instead of hanging it.
+    fn wait_for_tree(syntax: &mut SyntaxState) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while syntax.is_parsing() {
+            assert!(Instant::now() < deadline, "background parse never finished");
+            syntax.poll_result();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+    fn point(rope: &Rope, byte: usize) -> tree_sitter::Point {
+        let row = rope.byte_to_line(byte);
+        tree_sitter::Point::new(row, byte - rope.line_to_byte(row))
+    }
+    fn insert(rope: &mut Rope, syntax: &mut SyntaxState, at: usize, text: &str) {
+        let start_position = point(rope, at);
+        rope.insert(rope.byte_to_char(at), text);
+        syntax.queue_edit(tree_sitter::InputEdit {
+            start_byte: at,
+            old_end_byte: at,
+            new_end_byte: at + text.len(),
+            start_position,
+            old_end_position: start_position,
+            new_end_position: point(rope, at + text.len()),
+        });
+    }
+
+    let mut rope = Rope::from_str("fn a() {}\n");
+    let mut syntax = SyntaxState::new(LanguageId::Rust).unwrap();
+    syntax.set_text(&rope).unwrap();
+    assert!(syntax.poll_result().is_none());
+
+    insert(&mut rope, &mut syntax, 10, "fn b() {}\n");
+    syntax.queue_parse(Arc::new(rope.clone()));
+    assert!(syntax.is_parsing());
+    // A second request while one is running is ignored rather than queued behind it.
+    syntax.queue_parse(Arc::new(rope.clone()));
+    // Edited while the parse runs: replayed onto the returned tree.
+    insert(&mut rope, &mut syntax, 20, "fn c() {}\n");
+    wait_for_tree(&mut syntax);
+
+    syntax.queue_parse(Arc::new(rope.clone()));
+    wait_for_tree(&mut syntax);
+    let names: Vec<_> = syntax
+        .symbol_list(&rope)
+        .iter()
+        .map(|s| s.name.as_str())
+        .collect();
+    assert_eq!(names, ["a", "b", "c"]);
+
+    // Dropping the state with a parse in flight must not hang.
+    syntax.queue_edit(tree_sitter::InputEdit {
+        start_byte: 0,
+        old_end_byte: 0,
+        new_end_byte: 0,
+        start_position: tree_sitter::Point::new(0, 0),
+        old_end_position: tree_sitter::Point::new(0, 0),
+        new_end_position: tree_sitter::Point::new(0, 0),
+    });
+    syntax.queue_parse(Arc::new(rope));
+    drop(syntax);
+}
+
+#[test]
+fn reset_switches_language_and_reparses() {
+    let source = "def greet():\n    return 1\n";
+    let rope = Rope::from_str(source);
+    let mut syntax = SyntaxState::new(LanguageId::PlainText).unwrap();
+    syntax.set_text(&rope).unwrap();
+    assert!(syntax.symbol_list(&rope).is_empty());
+
+    syntax.reset(LanguageId::Python, &rope).unwrap();
+    assert_eq!(syntax.language(), LanguageId::Python);
+    let symbols = syntax.symbol_list(&rope);
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].name, "greet");
+    assert!(syntax.error_spans().is_empty());
+
+    // The same text is not valid Rust, so the new tree carries errors.
+    syntax.reset(LanguageId::Rust, &rope).unwrap();
+    assert!(!syntax.error_spans().is_empty());
+
+    syntax.reset(LanguageId::PlainText, &rope).unwrap();
+    assert!(syntax.error_spans().is_empty());
+    assert!(syntax
+        .highlight_spans(&rope, 0..source.len())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn every_language_has_a_distinct_display_name() {
+    let mut names: Vec<_> = LanguageId::ALL.iter().map(|l| l.display_name()).collect();
+    names.sort_unstable();
+    names.dedup();
+    assert_eq!(names.len(), LanguageId::ALL.len());
+    assert!(LanguageId::ALL.contains(&LanguageId::PlainText));
+}